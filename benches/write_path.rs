@@ -0,0 +1,64 @@
+use std::io::{self, BufWriter, Write};
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use csv::Writer;
+use tx_sim::{Accounting, ClientId, Decimal, Transaction, TxId, TxType};
+
+/// Number of accounts written, client ids 1 to `u16::MAX` whatever the `ClientId` width;
+const ACCOUNTS: u16 = u16::MAX;
+
+/// One account per client id;
+fn accounting() -> Accounting {
+    let mut accounting = Accounting::init();
+
+    for id in 1..=ACCOUNTS {
+        let deposit = Transaction {
+            r#type: TxType::Deposit,
+            client: ClientId::from(id),
+            tx: TxId::from(id),
+            amount: Some(Decimal::from(id) / Decimal::from(3)),
+            timestamp: None,
+            subaccount: None,
+            category: None,
+            source: None,
+        };
+        accounting
+            .process_transaction(deposit)
+            .expect("deposit is applied");
+    }
+
+    accounting
+}
+
+fn write_path(c: &mut Criterion) {
+    let mut accounting = accounting();
+
+    let mut group = c.benchmark_group("write_accounts");
+    group.throughput(Throughput::Elements(u64::from(ACCOUNTS)));
+
+    // The previous write path: serialize every record into a Vec, then write it out at once;
+    group.bench_function("vec_buffer", |b| {
+        b.iter(|| {
+            let mut wtr = Writer::from_writer(vec![]);
+            for account in accounting.accounts() {
+                let mut account = account.clone();
+                account.round_balances();
+                wtr.serialize(account).unwrap();
+            }
+            io::sink().write_all(&wtr.into_inner().unwrap()).unwrap();
+        })
+    });
+
+    group.bench_function("buf_writer", |b| {
+        b.iter(|| {
+            accounting
+                .write_accounts_csv(BufWriter::new(io::sink()))
+                .unwrap()
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, write_path);
+criterion_main!(benches);
@@ -1,17 +1,135 @@
-use std::collections::HashMap;
-use std::io::{self, Write};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+#[cfg(feature = "io")]
+use std::fs::File;
+#[cfg(feature = "io")]
+use std::io::{self, BufWriter, Read, Write};
+#[cfg(feature = "io")]
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
 
+#[cfg(feature = "io")]
 use csv::{Reader, Writer};
+#[cfg(feature = "io")]
+use flate2::write::GzEncoder;
+#[cfg(feature = "io")]
+use flate2::Compression;
+#[cfg(feature = "io")]
+use log::error;
+use log::{debug, info, warn};
+#[cfg(feature = "io")]
 use rand::distributions::{Distribution, Uniform};
+#[cfg(feature = "io")]
 use rand::thread_rng;
 
 use crate::*;
 
 #[derive(Debug, Default)]
 pub struct Accounting {
-    accounts: HashMap<ClientId, Account>,
-    transactions: HashMap<TxId, Transaction>,
+    pub(crate) config: AccountingConfig,
+    pub(crate) accounts: HashMap<ClientId, Account>,
+    /// Recorded deposits and withdrawals with their dispute state;
+    pub(crate) transactions: HashMap<TxId, RecordedTx>,
+    /// Ids of the recorded transactions of each client, in the order recorded;
+    pub(crate) client_tx_index: HashMap<ClientId, Vec<TxId>>,
+    /// End-of-day balances keyed by client and day number since the unix epoch;
+    pub(crate) daily_balances: BTreeMap<(ClientId, Timestamp), DailyBalance>,
+    /// Enrichment stages applied, in order, to each transaction read from csv;
+    pub(crate) enrichers: Vec<Box<dyn Enricher>>,
+    /// Client id remap table applied to every incoming transaction;
+    pub(crate) client_remapper: ClientIdRemapper,
+    /// Source of the current time; Defaults to the system clock when not set;
+    pub(crate) clock: Option<Arc<dyn Clock>>,
+    /// Source of transaction ids for `deposit` and `withdraw`;
+    pub(crate) id_allocator: Option<Arc<dyn TxIdAllocator>>,
+    /// Transactions whose amount exceeded four decimal places;
+    pub(crate) precision_adjustments: Vec<PrecisionAdjustment>,
+    /// Journal recording every accepted incoming transaction;
+    #[cfg(feature = "io")]
+    pub(crate) journal: Option<Journal>,
+    /// Change-data-capture stream of account state changes;
+    #[cfg(feature = "io")]
+    pub(crate) change_stream: Option<ChangeStream>,
+    /// Named sub-accounts per client; The client's account holds the rollup of all sub-accounts;
+    pub(crate) subaccounts: HashMap<ClientId, BTreeMap<SubAccountId, Account>>,
+    /// Deposits waiting to clear, keyed by clearing time and transaction id;
+    pub(crate) pending_deposits: BTreeMap<(Timestamp, TxId), PendingDeposit>,
+    /// Timestamp of each client's last transaction;
+    pub(crate) last_activity: HashMap<ClientId, Timestamp>,
+    /// Open disputes keyed by disputed transaction id;
+    pub(crate) disputes: BTreeMap<TxId, Dispute>,
+    /// Spending budgets keyed by client and category;
+    pub(crate) budgets: HashMap<(ClientId, String), Budget>,
+    /// Current budget period and amount withdrawn in it, keyed by client and category;
+    pub(crate) budget_spending: HashMap<(ClientId, String), (Timestamp, Amount)>,
+    /// Minimum available balance withdrawals may leave, keyed by client;
+    pub(crate) reserve_floors: BTreeMap<ClientId, Amount>,
+    /// Key-value attributes per client, readable by custom handlers;
+    pub(crate) attributes: BTreeMap<ClientId, BTreeMap<String, String>>,
+    /// Clients flagged for review;
+    pub(crate) flagged_accounts: BTreeSet<ClientId>,
+    /// Policy decisions and operator actions;
+    pub(crate) audit_log: Vec<AuditEvent>,
+    /// Every lock and unlock of an account, with its reason;
+    pub(crate) lock_events: Vec<LockEvent>,
+    /// Funds reserved for open orders, keyed by the reserve's tx id;
+    pub(crate) reservations: BTreeMap<TxId, Reservation>,
+    /// Operator holds placed independently of disputes, keyed by hold id;
+    pub(crate) holds: BTreeMap<HoldId, Hold>,
+    /// The id assigned to the most recently placed hold;
+    pub(crate) last_hold_id: HoldId,
+    /// Audit trail of debit adjustments such as fees and corrections;
+    pub(crate) debit_adjustments: Vec<DebitAdjustment>,
+    /// Closed accounts, keyed by client;
+    pub(crate) closures: BTreeMap<ClientId, AccountClosure>,
+    /// Day of the last interest accrual and interest accrued but not yet posted, per client;
+    pub(crate) interest_accrual: HashMap<ClientId, (Timestamp, Amount)>,
+    /// Deposits generated for interest postings;
+    pub(crate) interest_postings: Vec<Transaction>,
+    /// Customer notifications, recorded when `AccountingConfig::notifications` is set;
+    pub(crate) notifications: Vec<Notification>,
+    /// Client, kind and day of the recorded notifications, used for deduplication;
+    pub(crate) notified: HashSet<(ClientId, NotificationKind, Timestamp)>,
+    /// Most recent timestamp and transaction id per content hash, used for deduplication;
+    pub(crate) content_hashes: HashMap<u64, (Timestamp, TxId)>,
+    /// Transactions reported as content duplicates instead of being applied;
+    pub(crate) content_duplicates: Vec<ContentDuplicate>,
+    /// Deposits and withdrawals rejected for reusing a recorded transaction id;
+    pub(crate) duplicate_tx_ids: Vec<DuplicateTxId>,
+    /// Number of csv rows skipped per unknown transaction type name;
+    pub(crate) unknown_tx_types: BTreeMap<String, u64>,
+    /// Decision trace of the transactions selected by `AccountingConfig::trace`;
+    pub(crate) trace_steps: Vec<TraceStep>,
+    /// Source of the transaction being processed, if any;
+    pub(crate) source: Option<String>,
+    /// Per-source summaries of the transactions processed;
+    pub(crate) source_summaries: BTreeMap<String, SourceSummary>,
+    /// Ignored transactions, recorded when `AccountingConfig::track_rejected` is set;
+    pub(crate) rejected: Vec<RejectedTx>,
+    /// Why the transaction being processed was ignored, if it was;
+    pub(crate) rejection: Option<TransactionError>,
+    /// Circuit breaker aggregates, used when `AccountingConfig::circuit_breaker` is set;
+    pub(crate) breaker: BreakerState,
+    /// Transactions diverted pending review, see `quarantined_transactions`;
+    pub(crate) quarantined: Vec<Transaction>,
+    /// Processing latency histograms per transaction type;
+    pub(crate) latencies: HashMap<TxType, LatencyHistogram>,
+    /// Acknowledgements of transactions submitted with a receipt id;
+    pub(crate) acks: HashMap<ReceiptId, Ack>,
+    /// Durable log of acknowledgements;
+    #[cfg(feature = "io")]
+    pub(crate) ack_log: Option<AckLog>,
+    /// Invariant auto-check state, used when `invariant_check_interval` is set;
+    pub(crate) invariants: InvariantChecker,
+    /// Number of transactions passed to `process_transaction`;
+    pub(crate) processed: u64,
+    /// Processing function per transaction type;
+    pub(crate) handlers: TxHandlers,
+    /// Backend receiving the engine's metrics;
+    pub(crate) metrics: Option<Arc<dyn MetricsSink>>,
+    /// Span exporter for distributed tracing;
+    #[cfg(feature = "otlp")]
+    pub(crate) otlp: Option<OtlpExporter>,
 }
 
 impl Accounting {
@@ -20,151 +138,1228 @@ impl Accounting {
         Accounting::default()
     }
 
+    /// Create an Accounting struct with the provided configuration;
+    pub fn with_config(config: AccountingConfig) -> Self {
+        Accounting {
+            config,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the configuration used by the processor;
+    pub fn config(&self) -> &AccountingConfig {
+        &self.config
+    }
+
+    /// Swap in a new configuration, e.g. updated policies or limits, without losing the in-memory
+    /// state; The configuration is validated before the swap, and the change is recorded in the audit
+    /// log as `config_reloaded`. Returns the names of the changed fields.
+    pub fn reload_config(&mut self, config: AccountingConfig) -> Result<Vec<&'static str>, Error> {
+        config.validate()?;
+
+        let changed = self.config.changed_fields(&config);
+        let detail = if changed.is_empty() {
+            "unchanged".to_string()
+        } else {
+            format!("changed {}", changed.join(", "))
+        };
+
+        // Transactions processed from now on are journaled with the new configuration;
+        #[cfg(feature = "io")]
+        {
+            let now = self.now();
+            if let Some(journal) = self.journal.as_mut() {
+                journal.record_config(&config, now)?;
+            }
+        }
+
+        info!("Reloaded config: {}", detail);
+        self.config = config;
+        self.audit(None, None, "config_reloaded", detail);
+
+        Ok(changed)
+    }
+
+    /// Replace the clock used for time-based features, e.g. with a `MockClock` in tests;
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = Some(clock);
+    }
+
+    /// Returns the current unix timestamp from the configured clock;
+    pub fn now(&self) -> Timestamp {
+        match &self.clock {
+            Some(clock) => clock.now(),
+            None => SystemClock.now(),
+        }
+    }
+
+    /// Record every transaction accepted by `try_process_transaction` (and therefore every transaction
+    /// read from csv) to the journal before it is processed, along with the configuration and the
+    /// holds, closures, locks and review flags applied by the operator; See `JournalActionKind`.
+    /// Other operator changes, e.g. budgets, reserve floors, attributes, client remaps and preloaded
+    /// accounts, are not journaled.
+    #[cfg(feature = "io")]
+    pub fn attach_journal(&mut self, mut journal: Journal) -> Result<(), Error> {
+        journal.record_config(&self.config, self.now())?;
+        self.journal = Some(journal);
+        Ok(())
+    }
+
+    /// Stop journaling, returning the journal;
+    #[cfg(feature = "io")]
+    pub fn detach_journal(&mut self) -> Option<Journal> {
+        self.journal.take()
+    }
+
+    /// Returns the attached journal, if any;
+    #[cfg(feature = "io")]
+    pub fn journal_mut(&mut self) -> Option<&mut Journal> {
+        self.journal.as_mut()
+    }
+
+    /// Emit an `AccountEvent` to the change stream whenever an account changes;
+    #[cfg(feature = "io")]
+    pub fn attach_change_stream(&mut self, stream: ChangeStream) {
+        self.change_stream = Some(stream);
+    }
+
+    /// Stop emitting account changes, returning the change stream;
+    #[cfg(feature = "io")]
+    pub fn detach_change_stream(&mut self) -> Option<ChangeStream> {
+        self.change_stream.take()
+    }
+
+    /// Emit the account state to the change stream, if attached;
+    /// If the stream fails it is detached, so processing continues without replication.
+    #[cfg_attr(not(feature = "io"), allow(unused_variables))]
+    pub(crate) fn emit_change(&mut self, tx: Option<TxId>, account: &Account) {
+        #[cfg(feature = "io")]
+        if let Some(mut stream) = self.change_stream.take() {
+            match stream.emit_event(self.account_event(tx, account)) {
+                Ok(()) => self.change_stream = Some(stream),
+                Err(e) => error!("Detached change stream after failed write: {}", e),
+            }
+        }
+    }
+
+    /// Add an enrichment stage applied to each transaction read from csv before it is processed;
+    /// Enrichers are applied in the order they are added.
+    pub fn add_enricher(&mut self, enricher: Box<dyn Enricher>) {
+        self.enrichers.push(enricher);
+    }
+
+    /// Remap client ids, e.g. for bank mergers and migrations;
+    /// Existing accounts are moved to their new client id, merging balances into any account
+    /// already using that id, along with the client's recorded transactions, disputes, holds,
+    /// reservations, pending deposits, sub-accounts, daily balances, closure, reserve floor,
+    /// budgets, attributes and review flag. Recorded transactions are listed by
+    /// `client_transactions` after those of the new client id. Chained mappings (a -> b, b -> c)
+    /// resolve to the final client id; Cyclic mappings return `Error::InvalidArgument` before
+    /// anything is moved. The remap table is retained and applied to all subsequently processed
+    /// transactions.
+    pub fn remap_clients(&mut self, map: HashMap<ClientId, ClientId>) -> Result<(), Error> {
+        let map = resolve_client_map(&map)?;
+        let remap = |client: &mut ClientId| {
+            if let Some(new_client) = map.get(client) {
+                *client = *new_client;
+            }
+        };
+
+        for (&old_client, &new_client) in map.iter() {
+            let merging = self.accounts.contains_key(&new_client);
+
+            if let Some(mut old_account) = self.accounts.remove(&old_client) {
+                match self.accounts.get_mut(&new_client) {
+                    Some(account) => account.merge(&old_account),
+                    None => {
+                        old_account.client = new_client;
+                        self.accounts.insert(new_client, old_account);
+                    }
+                }
+            }
+
+            if let Some(ids) = self.client_tx_index.remove(&old_client) {
+                self.client_tx_index
+                    .entry(new_client)
+                    .or_default()
+                    .extend(ids);
+            }
+
+            if let Some(subaccounts) = self.subaccounts.remove(&old_client) {
+                let merged = self.subaccounts.entry(new_client).or_default();
+                for (name, mut subaccount) in subaccounts {
+                    match merged.get_mut(&name) {
+                        Some(existing) => existing.merge(&subaccount),
+                        None => {
+                            subaccount.client = new_client;
+                            merged.insert(name, subaccount);
+                        }
+                    }
+                }
+            }
+
+            if let Some(at) = self.last_activity.remove(&old_client) {
+                let last = self.last_activity.entry(new_client).or_insert(at);
+                *last = (*last).max(at);
+            }
+
+            if let Some((day, accrued)) = self.interest_accrual.remove(&old_client) {
+                let merged = self
+                    .interest_accrual
+                    .entry(new_client)
+                    .or_insert((day, Amount::ZERO));
+                merged.0 = merged.0.max(day);
+                merged.1 += accrued;
+            }
+
+            // The stricter floor applies to a merged account;
+            if let Some(floor) = self.reserve_floors.remove(&old_client) {
+                let merged = self.reserve_floors.entry(new_client).or_insert(floor);
+                *merged = (*merged).max(floor);
+            }
+
+            // Attributes already set on the new client id take precedence;
+            if let Some(attributes) = self.attributes.remove(&old_client) {
+                let merged = self.attributes.entry(new_client).or_default();
+                for (key, value) in attributes {
+                    merged.entry(key).or_insert(value);
+                }
+            }
+
+            if self.flagged_accounts.remove(&old_client) {
+                self.flagged_accounts.insert(new_client);
+            }
+
+            // A closed account merged into an open one does not close it;
+            if let Some(mut closure) = self.closures.remove(&old_client) {
+                if !merging {
+                    closure.client = new_client;
+                    self.closures.insert(new_client, closure);
+                }
+            }
+        }
+
+        for recorded in self.transactions.values_mut() {
+            remap(&mut recorded.transaction.client);
+        }
+
+        for dispute in self.disputes.values_mut() {
+            remap(&mut dispute.client);
+        }
+
+        for hold in self.holds.values_mut() {
+            remap(&mut hold.client);
+        }
+
+        for reservation in self.reservations.values_mut() {
+            remap(&mut reservation.client);
+        }
+
+        for deposit in self.pending_deposits.values_mut() {
+            remap(&mut deposit.client);
+        }
+
+        for closure in self.closures.values_mut() {
+            if let Some(target) = closure.transferred_to.as_mut() {
+                remap(target);
+            }
+        }
+
+        // End-of-day balances of merged clients are summed per day;
+        for ((client, day), mut balance) in std::mem::take(&mut self.daily_balances) {
+            let client = map.get(&client).copied().unwrap_or(client);
+            match self.daily_balances.get_mut(&(client, day)) {
+                Some(merged) => {
+                    merged.available += balance.available;
+                    merged.held += balance.held;
+                    merged.total += balance.total;
+                }
+                None => {
+                    balance.client = client;
+                    self.daily_balances.insert((client, day), balance);
+                }
+            }
+        }
+
+        // Budgets already set on the new client id take precedence, and spending in the same period
+        // is summed;
+        let (moved, kept) = std::mem::take(&mut self.budgets)
+            .into_iter()
+            .partition::<Vec<_>, _>(|((client, _), _)| map.contains_key(client));
+        self.budgets.extend(kept);
+        for ((client, category), mut budget) in moved {
+            budget.client = map[&client];
+            self.budgets
+                .entry((budget.client, category))
+                .or_insert(budget);
+        }
+
+        for ((client, category), (period, spent)) in std::mem::take(&mut self.budget_spending) {
+            let client = map.get(&client).copied().unwrap_or(client);
+            let merged = self
+                .budget_spending
+                .entry((client, category))
+                .or_insert((period, Amount::ZERO));
+            if merged.0 == period {
+                merged.1 += spent;
+            } else if merged.0 < period {
+                *merged = (period, spent);
+            }
+        }
+
+        self.client_remapper.extend(&map.into_iter().collect());
+
+        Ok(())
+    }
+
     /// This method is provided to manually lock the account;
     /// If an client account is locked after a chargeback, no transactions may be processed until it is
-    /// unlocked.
-    pub fn lock_account(&mut self, client: ClientId, is_locked: bool) -> () {
-        let mut account = self
-            .accounts
-            .remove(&client)
-            .unwrap_or_else(|| Account::new(client));
+    /// unlocked. The change is recorded as a manual lock event.
+    pub fn lock_account(&mut self, client: ClientId, is_locked: bool) {
+        self.lock_account_with_reason(client, is_locked, LockReason::Manual, "manual action");
+    }
+
+    /// Validate the transaction against the configured strictness, then process it;
+    /// In `Strictness::Strict` mode invalid transactions return an error and are not processed.
+    /// When `invariant_check_interval` is set, returns `Error::InvariantViolation` once a check fails.
+    pub fn try_process_transaction(&mut self, tx: Transaction) -> Result<(), Error> {
+        self.try_process(tx).map(|_| ())
+    }
+
+    /// Validate and process the transaction, returning its outcome; See `try_process_transaction`.
+    pub(crate) fn try_process(
+        &mut self,
+        tx: Transaction,
+    ) -> Result<Result<TxOutcome, TransactionError>, Error> {
+        let outcome = self.try_process_unhalted(tx)?;
 
-        // Update the locked status on the account;
-        account.locked = is_locked;
+        // Halt as soon as the transaction tripped the circuit breaker;
+        self.check_circuit_breaker()?;
 
-        self.accounts.insert(client, account);
+        Ok(outcome)
+    }
+
+    /// Validate and process the transaction like `try_process`, but return the outcome of a
+    /// transaction tripping the circuit breaker; Only later transactions are refused.
+    pub(crate) fn try_process_unhalted(
+        &mut self,
+        tx: Transaction,
+    ) -> Result<Result<TxOutcome, TransactionError>, Error> {
+        if self.config.strictness == Strictness::Strict {
+            let validated = self.validate_strict(&tx);
+            self.trace(&tx, "validate", || match &validated {
+                Ok(()) => "strict validation passed".to_string(),
+                Err(e) => format!("strict validation failed: [{}] {}", e.code(), e),
+            });
+            validated?;
+        }
+
+        // Refuse transactions once the circuit breaker halted processing;
+        self.check_circuit_breaker()?;
+
+        // Diverted transactions are not accepted, so are left out of the journal;
+        #[cfg(feature = "io")]
+        if !self.diverts(&tx) {
+            let now = self.now();
+            if let Some(journal) = self.journal.as_mut() {
+                journal.append(&tx, now)?;
+            }
+        }
+
+        let outcome = match self.config.invariant_check_interval {
+            Some(interval) => {
+                let check_due = self.invariants.observe(&tx, interval);
+                let outcome = self.process_transaction(tx);
+
+                if check_due {
+                    self.check_invariants()?;
+                }
+
+                outcome
+            }
+            None => self.process_transaction(tx),
+        };
+
+        Ok(outcome)
+    }
+
+    /// Check a transaction against all strict mode validations without processing it;
+    pub fn validate_strict(&self, tx: &Transaction) -> Result<(), Error> {
+        let client = self.client_remapper.map(tx.client);
+
+        match tx.r#type {
+            TxType::Deposit | TxType::Withdrawal => {
+                match tx.amount {
+                    None => return Err(Error::MissingAmount(tx.tx)),
+                    Some(amount) if amount <= Amount::ZERO => {
+                        return Err(Error::InvalidAmount { tx: tx.tx, amount })
+                    }
+                    Some(_) => {}
+                }
+
+                if self.transactions.contains_key(&tx.tx) {
+                    return Err(Error::DuplicateTx(tx.tx));
+                }
+            }
+            TxType::Dispute | TxType::Resolve | TxType::Chargeback | TxType::Reversal => {
+                if let Some(recorded) = self.transactions.get(&tx.tx) {
+                    if recorded.transaction.client != client {
+                        return Err(Error::ClientMismatch { client, tx: tx.tx });
+                    }
+
+                    if tx.r#type == TxType::Dispute && recorded.state == TxState::Disputed {
+                        return Err(Error::AlreadyDisputed(tx.tx));
+                    }
+                }
+            }
+            TxType::Adjustment => {
+                if tx.amount.is_none() {
+                    return Err(Error::MissingAmount(tx.tx));
+                }
+
+                if self.transactions.contains_key(&tx.tx) {
+                    return Err(Error::DuplicateTx(tx.tx));
+                }
+            }
+            TxType::Reserve => {
+                match tx.amount {
+                    None => return Err(Error::MissingAmount(tx.tx)),
+                    Some(amount) if amount <= Amount::ZERO => {
+                        return Err(Error::InvalidAmount { tx: tx.tx, amount })
+                    }
+                    Some(_) => {}
+                }
+
+                if self.transactions.contains_key(&tx.tx) || self.reservations.contains_key(&tx.tx)
+                {
+                    return Err(Error::DuplicateTx(tx.tx));
+                }
+            }
+            TxType::Release | TxType::Fill => {
+                if let Some(reservation) = self.reservations.get(&tx.tx) {
+                    if reservation.client != client {
+                        return Err(Error::ClientMismatch { client, tx: tx.tx });
+                    }
+                }
+            }
+            TxType::Unknown => return Err(Error::UnknownTxType(tx.tx)),
+            TxType::Custom(_) => {
+                if !self.has_handler(tx.r#type) {
+                    return Err(Error::UnknownTxType(tx.tx));
+                }
+            }
+        }
+
+        Ok(())
     }
 
     /// This is the main method for processing the transaction;
+    /// Returns why the transaction was ignored, e.g. `TransactionError::InsufficientFunds`, leaving the
+    /// account unchanged.
     /// NOTE: If the client does not already have an account, this transaction
     /// will also create an account for the client.
-    pub fn process_transaction(&mut self, tx: Transaction) -> () {
+    pub fn process_transaction(&mut self, tx: Transaction) -> Result<TxOutcome, TransactionError> {
+        let start = Instant::now();
+        let r#type = tx.r#type;
+
+        // Only keep the transaction context when slow transactions are logged;
+        let context = self.config.slow_transaction_threshold.map(|_| tx.clone());
+        let (client, id, amount) = (tx.client, tx.tx, tx.amount);
+
+        self.rejection = None;
+        self.source = tx.source.clone();
+        if self.breaker.trip.is_some() {
+            self.divert(tx);
+        } else if self.quarantines(self.client_remapper.map(client)) {
+            self.quarantine(tx);
+        } else {
+            let at = tx.timestamp.unwrap_or_else(|| self.now());
+            self.apply_transaction(tx);
+
+            let withdrawn =
+                amount.filter(|_| r#type == TxType::Withdrawal && self.rejection.is_none());
+            self.observe_circuit_breaker(client, id, at, withdrawn);
+        }
+        self.processed += 1;
+
+        let elapsed = start.elapsed();
+        self.latencies.entry(r#type).or_default().record(elapsed);
+
+        #[cfg(feature = "otlp")]
+        if let Some(otlp) = self.otlp.as_mut() {
+            otlp.observe();
+        }
+
+        if let Some(metrics) = self.metrics.as_ref() {
+            let labels = [("type", r#type.as_str())];
+            metrics.counter("tx_sim_transactions_total", 1, &labels);
+            if self.rejection.is_some() {
+                metrics.counter("tx_sim_transactions_ignored_total", 1, &labels);
+            }
+            metrics.histogram(
+                "tx_sim_transaction_duration_seconds",
+                elapsed.as_secs_f64(),
+                &labels,
+            );
+            metrics.gauge("tx_sim_accounts", self.accounts.len() as f64, &[]);
+            metrics.gauge("tx_sim_open_disputes", self.disputes.len() as f64, &[]);
+        }
+
+        if let (Some(threshold), Some(tx)) = (self.config.slow_transaction_threshold, context) {
+            if elapsed >= threshold {
+                warn!(
+                    "Slow {:?} tx {} took {:?}: {:?}; account: {:?}",
+                    tx.r#type,
+                    tx.tx,
+                    elapsed,
+                    tx,
+                    self.get_account(self.client_remapper.map(tx.client))
+                );
+            }
+        }
+
+        let source = self.source.take();
+        if let Some(source) = source.as_deref() {
+            self.observe_source(source, self.rejection.is_none());
+        }
+
+        match self.rejection.take() {
+            Some(rejection) => {
+                if self.config.track_rejected {
+                    self.rejected.push(RejectedTx {
+                        r#type,
+                        client,
+                        tx: id,
+                        amount,
+                        reason: rejection.to_string(),
+                        source,
+                    });
+                }
+                Err(rejection)
+            }
+            None if r#type == TxType::Deposit && self.config.clearing_delay.is_some() => {
+                Ok(TxOutcome::Pending)
+            }
+            None => Ok(TxOutcome::Applied),
+        }
+    }
+
+    /// Apply a transaction to its account; See `process_transaction`.
+    fn apply_transaction(&mut self, mut tx: Transaction) {
+        self.trace(&tx, "received", || format!("{:?}", tx));
+
+        // Apply any client id remapping;
+        if !self.client_remapper.is_empty() {
+            let client = tx.client;
+            tx.client = self.client_remapper.map(tx.client);
+            self.trace(&tx, "remap", || {
+                format!("client {} -> {}", client, tx.client)
+            });
+        }
+
+        if tx.timestamp.is_none() && self.config.stamp_missing_timestamps {
+            tx.timestamp = Some(self.now());
+            self.trace(&tx, "timestamp", || format!("stamped {:?}", tx.timestamp));
+        }
+
+        // Deposits and withdrawals must move a positive amount;
+        if let Some(amount) = tx.amount {
+            if matches!(tx.r#type, TxType::Deposit | TxType::Withdrawal) && amount <= Amount::ZERO {
+                warn!(
+                    "Ignored {:?} tx {} for client {}: invalid amount {}",
+                    tx.r#type, tx.tx, tx.client, amount
+                );
+                self.ignore(&tx, TransactionError::InvalidAmount(amount), || {
+                    format!("invalid amount {}", amount)
+                });
+                return;
+            }
+        }
+
+        // Report resent rows instead of applying them;
+        if self.dedup_content(&tx) {
+            self.ignore(&tx, TransactionError::DuplicateContent, || {
+                "duplicate content".to_string()
+            });
+            return;
+        }
+        let content = self.config.dedup.is_some().then(|| content_hash(&tx));
+
+        // Enforce the amount precision policy;
+        if let Some(amount) = tx.amount {
+            if decimal_places(amount) > AMOUNT_DECIMAL_PLACES {
+                let adjusted = self.config.precision_policy.apply(amount);
+
+                self.precision_adjustments.push(PrecisionAdjustment {
+                    client: tx.client,
+                    tx: tx.tx,
+                    original: amount,
+                    adjusted,
+                });
+
+                match adjusted {
+                    Some(adjusted) => {
+                        tx.amount = Some(adjusted);
+                        self.trace(&tx, "precision", || {
+                            format!("amount {} -> {}", amount, adjusted)
+                        });
+                    }
+                    None => {
+                        warn!(
+                            "Ignored {:?} tx {} for client {}: amount {} exceeds {} decimal places",
+                            tx.r#type, tx.tx, tx.client, amount, AMOUNT_DECIMAL_PLACES
+                        );
+                        self.ignore(&tx, TransactionError::ExcessPrecision(amount), || {
+                            format!(
+                                "amount {} exceeds {} decimal places",
+                                amount, AMOUNT_DECIMAL_PLACES
+                            )
+                        });
+                        return;
+                    }
+                }
+            }
+        }
+
+        let active_at = tx.timestamp.unwrap_or_else(|| self.now());
+        self.last_activity.insert(tx.client, active_at);
+
+        // Clear pending deposits up to the transaction's time, before it is processed;
+        if !self.pending_deposits.is_empty() {
+            self.settle_pending_deposits(active_at);
+        }
+
+        // Decide disputes expired by the transaction's time, before it is processed;
+        if self.config.dispute_expiry.is_some() && !self.disputes.is_empty() {
+            self.expire_disputes(active_at);
+        }
+
+        // Accrue interest up to the transaction's time, before it is processed;
+        if self.config.interest.is_some() {
+            self.accrue_interest(tx.client, active_at);
+        }
+
         // Find or create a new account;
-        let mut account = self
-            .accounts
-            .remove(&tx.client)
-            .unwrap_or_else(|| Account::new(tx.client));
+        let existing = self.accounts.remove(&tx.client);
+        let created = existing.is_none();
+        let mut account = existing.unwrap_or_else(|| Account::new(tx.client));
+        account.stats.transactions += 1;
+        let before = (
+            account.available,
+            account.held,
+            account.total,
+            account.locked,
+        );
+        let recorded_before = self.recorded_state(tx.tx);
 
-        // Only process the transaction if the account is unlocked;
+        // Only process the transaction if the account is unlocked, or it reverses a chargeback;
         // NOTE: Another method will need to be used to unlock an account
         // after a charge back;
-        if !account.locked {
-            // Process the transaction and account based on transaction type;
-            match tx.r#type {
-                TxType::Deposit => {
-                    self.process_deposit(&mut account, &tx);
-                    // NOTE: Only insert the transaction is it a deposit or withdrawal;
-                    // If it is part of dispute resolution, the tx id is the same as the deposit tx id;
-                    self.transactions.insert(tx.tx, tx.clone());
-                }
-                TxType::Withdrawal => {
-                    self.process_withdrawal(&mut account, &tx);
-                    // NOTE: Only insert the transaction is it a deposit or withdrawal;
-                    // If it is part of dispute resolution, the tx id is the same as the deposit tx id;
-                    self.transactions.insert(tx.tx, tx.clone());
+        if self.closures.contains_key(&tx.client) {
+            warn!(
+                "Ignored {:?} tx {} for client {}: account is closed",
+                tx.r#type, tx.tx, tx.client
+            );
+            self.ignore(&tx, TransactionError::AccountClosed, || {
+                "account is closed".to_string()
+            });
+        } else if !account.locked || tx.r#type == TxType::Reversal {
+            self.trace(&tx, "dispatch", || format!("{} handler", tx.r#type));
+
+            // Process the transaction against the targeted sub-account, if any;
+            if tx.subaccount.is_none() && !self.subaccounts.contains_key(&tx.client) {
+                self.dispatch(&mut account, &tx);
+            } else {
+                self.dispatch_subaccount(&mut account, &tx);
+            }
+
+            if let (Some(hash), None) = (content, self.rejection.as_ref()) {
+                self.register_content(&tx, hash);
+            }
+
+            debug!(
+                "Processed {:?} tx {} for client {}: available={} held={} total={} locked={}",
+                tx.r#type,
+                tx.tx,
+                tx.client,
+                account.available,
+                account.held,
+                account.total,
+                account.locked
+            );
+
+            // Track lifetime peaks and drawdown after the balances have been updated;
+            account.stats.observe(account.total, account.held);
+
+            // Record the latest balance for the transaction's day;
+            if let Some(timestamp) = tx.timestamp {
+                if self.config.track_daily_balances {
+                    self.daily_balances.insert(
+                        (account.client, timestamp / SECONDS_PER_DAY),
+                        DailyBalance {
+                            client: account.client,
+                            date: format_date(timestamp),
+                            available: account.available,
+                            held: account.held,
+                            total: account.total,
+                        },
+                    );
                 }
-                TxType::Dispute => self.process_dispute(&mut account, &tx),
-                TxType::Resolve => self.process_resolve(&mut account, &tx),
-                TxType::Chargeback => self.process_chargeback(&mut account, &tx),
-                TxType::Unknown => unreachable!(),
             }
+        } else {
+            warn!(
+                "Ignored {:?} tx {} for client {}: account is locked",
+                tx.r#type, tx.tx, tx.client
+            );
+            self.ignore(&tx, TransactionError::AccountLocked, || {
+                "account is locked".to_string()
+            });
+        }
+
+        // Record why the account became locked;
+        if account.locked && !before.3 {
+            let reason = match tx.r#type {
+                TxType::Chargeback => LockReason::Chargeback,
+                _ => LockReason::Rule,
+            };
+            let detail = format!("{} tx {}", tx.r#type, tx.tx);
+            self.record_lock(&mut account, reason, Some(tx.tx), active_at, detail);
+        }
+
+        self.notify_transaction(&tx, before.2, &account, active_at);
+
+        self.trace(&tx, "result", || {
+            format!(
+                "available {} -> {}, held {} -> {}, total {} -> {}, locked {} -> {}",
+                before.0.normalize(),
+                account.available.normalize(),
+                before.1.normalize(),
+                account.held.normalize(),
+                before.2.normalize(),
+                account.total.normalize(),
+                before.3,
+                account.locked
+            )
+        });
+
+        // Emit the account state if the transaction changed it, or its recorded transaction;
+        if created
+            || before
+                != (
+                    account.available,
+                    account.held,
+                    account.total,
+                    account.locked,
+                )
+            || recorded_before != self.recorded_state(tx.tx)
+        {
+            self.emit_change(Some(tx.tx), &account);
         }
 
         // update changes (if any) for account;
         self.accounts.insert(tx.client, account);
     }
 
-    /// This method is provided as a helper method and is exposed for convience, but is intended to be consumed by
-    /// `self.process_transaction`
-    pub fn process_deposit(&mut self, account: &mut Account, tx: &Transaction) -> () {
+    /// This method is provided as a helper method and is intended to be consumed by `self.process_transaction`;
+    /// Exposed through `internals::AccountingInternals` with the `internals` feature.
+    pub(crate) fn process_deposit(&mut self, account: &mut Account, tx: &Transaction) {
         if let Some(amount) = tx.amount {
             // Credit the client's account
             account.total += amount;
-            account.available += amount;
+
+            // Deposits subject to a clearing delay are held until they clear;
+            match self.config.clearing_delay {
+                Some(delay) => {
+                    account.held += amount;
+                    self.hold_pending_deposit(tx, account.client, delay);
+                }
+                None => account.available += amount,
+            }
+        } else {
+            self.ignore(tx, TransactionError::MissingAmount, || {
+                "missing amount".to_string()
+            });
         }
     }
-    /// This method is provided as a helper method and is exposed for convience, but is intended to be consumed by
-    /// `self.process_transaction`
-    pub fn process_withdrawal(&mut self, account: &mut Account, tx: &Transaction) -> () {
+    /// This method is provided as a helper method and is intended to be consumed by `self.process_transaction`;
+    /// Exposed through `internals::AccountingInternals` with the `internals` feature.
+    pub(crate) fn process_withdrawal(&mut self, account: &mut Account, tx: &Transaction) {
         if let Some(amount) = tx.amount {
             // Only if the account has sufficient funds will the account's values be updated;
-            if account.available - amount >= 0. {
-                // Debit the client's account;
-                account.total -= amount;
-                account.available -= amount;
+            if covers(account.available, amount, self.config.amount_epsilon) {
+                if let Some(rejection) =
+                    self.reserve_floor_rejection(account.client, account.available, amount)
+                {
+                    warn!(
+                        "Ignored withdrawal tx {} for client {}: below reserve floor",
+                        tx.tx, tx.client
+                    );
+                    self.ignore(tx, rejection, || "below reserve floor".to_string());
+                    return;
+                }
+
+                if !self.check_budget(account.client, tx, amount) {
+                    warn!(
+                        "Ignored withdrawal tx {} for client {}: exceeds category budget",
+                        tx.tx, tx.client
+                    );
+                    self.ignore(tx, TransactionError::BudgetExceeded, || {
+                        "exceeds category budget".to_string()
+                    });
+                    return;
+                }
+
+                // Debit the client's account, and only then count it against the category budget;
+                account.total = clear_dust(account.total - amount, self.config.amount_epsilon);
+                account.available =
+                    clear_dust(account.available - amount, self.config.amount_epsilon);
+                self.record_budget_spending(account.client, tx, amount);
+
+                account.stats.observe_withdrawal(amount);
+            } else {
+                warn!(
+                    "Ignored withdrawal tx {} for client {}: insufficient funds",
+                    tx.tx, tx.client
+                );
+                let available = account.available;
+                self.ignore(
+                    tx,
+                    TransactionError::InsufficientFunds { available, amount },
+                    || "insufficient funds".to_string(),
+                );
             }
+        } else {
+            self.ignore(tx, TransactionError::MissingAmount, || {
+                "missing amount".to_string()
+            });
         }
     }
-    /// This method is provided as a helper method and is exposed for convience, but is intended to be consumed by
-    /// `self.process_transaction`
-    pub fn process_dispute(&mut self, account: &mut Account, tx: &Transaction) -> () {
+    /// This method is provided as a helper method and is intended to be consumed by `self.process_transaction`;
+    /// Exposed through `internals::AccountingInternals` with the `internals` feature.
+    pub(crate) fn process_dispute(&mut self, account: &mut Account, tx: &Transaction) {
         // find the disputed transaction; If it does not exist, ignore.
-        if let Some(transaction) = self.transactions.get(&tx.tx) {
-            if let Some(amount) = transaction.amount {
-                // Only if the account has sufficient available funds for dispute can they be held;
-                // available funds cannot be negative;
-                if account.available - amount >= 0. {
-                    account.available -= amount;
+        if let Some(recorded) = self.transactions.get(&tx.tx) {
+            if let Some(amount) = recorded.transaction.amount {
+                let withdrawal = recorded.transaction.r#type == TxType::Withdrawal;
+                if !self.check_dispute_amount(tx) || !self.check_tx_owner(tx) {
+                    return;
+                }
+
+                let state = match self.check_tx_state(tx) {
+                    Some(state) => state,
+                    None => return,
+                };
+
+                if withdrawal && !self.check_withdrawal_dispute(tx) {
+                    return;
+                }
+
+                // Hold only the amount of the dispute row for partial disputes;
+                let amount = match tx.amount {
+                    Some(partial) if self.config.partial_disputes => {
+                        if partial <= Amount::ZERO || partial > amount + self.config.amount_epsilon
+                        {
+                            warn!(
+                                "Ignored dispute of tx {} for client {}: invalid partial amount {}",
+                                tx.tx, tx.client, partial
+                            );
+                            self.ignore(tx, TransactionError::InvalidAmount(partial), || {
+                                format!("invalid partial amount {}", partial)
+                            });
+                            return;
+                        }
+                        partial
+                    }
+                    _ => amount,
+                };
+
+                if !self.check_dispute_cap(account.client, tx.tx) {
+                    warn!(
+                        "Ignored dispute of tx {} for client {}: too many open disputes",
+                        tx.tx, tx.client
+                    );
+                    self.ignore(tx, TransactionError::TooManyOpenDisputes, || {
+                        "too many open disputes".to_string()
+                    });
+                    return;
+                }
+
+                // Disputed withdrawals are credited back to held funds until the dispute is decided;
+                // Disputed deposits are held from available funds, which cannot be negative unless
+                // the dispute funds policy holds the amount regardless;
+                if withdrawal
+                    || self.config.dispute_funds_policy == DisputeFundsPolicy::Hold
+                    || covers(account.available, amount, self.config.amount_epsilon)
+                {
+                    if withdrawal {
+                        account.total += amount;
+                    } else {
+                        account.available =
+                            clear_dust(account.available - amount, self.config.amount_epsilon);
+                    }
                     account.held += amount;
+
+                    self.set_tx_state(tx.tx, state);
+                    let opened_at = tx.timestamp.unwrap_or_else(|| self.now());
+                    self.disputes.insert(
+                        tx.tx,
+                        Dispute {
+                            tx: tx.tx,
+                            client: account.client,
+                            amount,
+                            opened_at,
+                            resolution: None,
+                        },
+                    );
+                } else {
+                    warn!(
+                        "Ignored dispute of tx {} for client {}: insufficient available funds",
+                        tx.tx, tx.client
+                    );
+                    let available = account.available;
+                    self.ignore(
+                        tx,
+                        TransactionError::InsufficientFunds { available, amount },
+                        || "insufficient available funds".to_string(),
+                    );
                 }
+            } else {
+                warn!(
+                    "Ignored dispute of tx {} for client {}: transaction has no amount",
+                    tx.tx, tx.client
+                );
+                self.ignore(tx, TransactionError::MissingAmount, || {
+                    "transaction has no amount".to_string()
+                });
             }
+        } else {
+            warn!(
+                "Ignored dispute of tx {} for client {}: unknown transaction",
+                tx.tx, tx.client
+            );
+            self.ignore(tx, TransactionError::UnknownTx, || {
+                "unknown transaction".to_string()
+            });
         }
     }
 
-    /// This method is provided as a helper method and is exposed for convience, but is intended to be consumed by
-    /// `self.process_transaction`
-    pub fn process_resolve(&mut self, account: &mut Account, tx: &Transaction) -> () {
+    /// This method is provided as a helper method and is intended to be consumed by `self.process_transaction`;
+    /// Exposed through `internals::AccountingInternals` with the `internals` feature.
+    pub(crate) fn process_resolve(&mut self, account: &mut Account, tx: &Transaction) {
         // find the transaction to resolve; If it does not exist, ignore.
-        if let Some(transaction) = self.transactions.get(&tx.tx) {
-            if let Some(amount) = transaction.amount {
+        if let Some(recorded) = self.transactions.get(&tx.tx) {
+            if let Some(amount) = recorded.transaction.amount {
+                let withdrawal = recorded.transaction.r#type == TxType::Withdrawal;
+                if !self.check_dispute_amount(tx) || !self.check_tx_owner(tx) {
+                    return;
+                }
+
+                let state = match self.check_tx_state(tx) {
+                    Some(state) => state,
+                    None => return,
+                };
+
+                // Release the amount held by the open dispute, which may be partial;
+                let amount = self
+                    .disputes
+                    .get(&tx.tx)
+                    .map_or(amount, |dispute| dispute.amount);
+
                 // Only if the account has previously disputed and held funds can the transaction be resolved;
-                if account.held - amount >= 0. {
-                    account.available += amount;
-                    account.held -= amount;
+                if covers(account.held, amount, self.config.amount_epsilon) {
+                    // A resolved withdrawal dispute stands, so the credit is removed again;
+                    if withdrawal {
+                        account.total =
+                            clear_dust(account.total - amount, self.config.amount_epsilon);
+                    } else {
+                        account.available += amount;
+                    }
+                    account.held = clear_dust(account.held - amount, self.config.amount_epsilon);
+                    self.set_tx_state(tx.tx, state);
+                    self.disputes.remove(&tx.tx);
+                } else {
+                    warn!(
+                        "Ignored resolve of tx {} for client {}: insufficient held funds",
+                        tx.tx, tx.client
+                    );
+                    let held = account.held;
+                    self.ignore(
+                        tx,
+                        TransactionError::InsufficientHeldFunds { held, amount },
+                        || "insufficient held funds".to_string(),
+                    );
                 }
+            } else {
+                warn!(
+                    "Ignored resolve of tx {} for client {}: transaction has no amount",
+                    tx.tx, tx.client
+                );
+                self.ignore(tx, TransactionError::MissingAmount, || {
+                    "transaction has no amount".to_string()
+                });
             }
+        } else {
+            warn!(
+                "Ignored resolve of tx {} for client {}: unknown transaction",
+                tx.tx, tx.client
+            );
+            self.ignore(tx, TransactionError::UnknownTx, || {
+                "unknown transaction".to_string()
+            });
         }
     }
 
-    /// This method is provided as a helper method and is exposed for convience, but is intended to be consumed by
-    /// `self.process_transaction`
-    pub fn process_chargeback(&mut self, account: &mut Account, tx: &Transaction) -> () {
+    /// This method is provided as a helper method and is intended to be consumed by `self.process_transaction`;
+    /// Exposed through `internals::AccountingInternals` with the `internals` feature.
+    pub(crate) fn process_chargeback(&mut self, account: &mut Account, tx: &Transaction) {
         // find the transaction to charge back; If it does not exist, ignore.
-        if let Some(transaction) = self.transactions.get(&tx.tx) {
-            if let Some(amount) = transaction.amount {
+        if let Some(recorded) = self.transactions.get(&tx.tx) {
+            if let Some(amount) = recorded.transaction.amount {
+                let withdrawal = recorded.transaction.r#type == TxType::Withdrawal;
+                if !self.check_dispute_amount(tx) || !self.check_tx_owner(tx) {
+                    return;
+                }
+
+                let state = match self.check_tx_state(tx) {
+                    Some(state) => state,
+                    None => return,
+                };
+
+                // Release the amount held by the open dispute, which may be partial;
+                let amount = self
+                    .disputes
+                    .get(&tx.tx)
+                    .map_or(amount, |dispute| dispute.amount);
+
                 // Only if the account has previously disputed and held funds can the transaction be charged back;
-                if account.held - amount >= 0. {
-                    // Decrease the total amount;
-                    account.total -= amount;
+                if covers(account.held, amount, self.config.amount_epsilon) {
+                    // Decrease the total amount; A charged back withdrawal is returned to the client instead;
+                    if withdrawal {
+                        account.available += amount;
+                    } else {
+                        account.total =
+                            clear_dust(account.total - amount, self.config.amount_epsilon);
+                    }
 
                     // Decrease the funds held by the charge back amount;
-                    account.held -= amount;
+                    account.held = clear_dust(account.held - amount, self.config.amount_epsilon);
 
                     // Lock the account once they have had a charge back;
                     account.locked = true;
+                    self.set_tx_state(tx.tx, state);
+                    self.set_charged_back(tx.tx, Some(amount));
+                    self.disputes.remove(&tx.tx);
+                } else {
+                    warn!(
+                        "Ignored chargeback of tx {} for client {}: insufficient held funds",
+                        tx.tx, tx.client
+                    );
+                    let held = account.held;
+                    self.ignore(
+                        tx,
+                        TransactionError::InsufficientHeldFunds { held, amount },
+                        || "insufficient held funds".to_string(),
+                    );
                 }
+            } else {
+                warn!(
+                    "Ignored chargeback of tx {} for client {}: transaction has no amount",
+                    tx.tx, tx.client
+                );
+                self.ignore(tx, TransactionError::MissingAmount, || {
+                    "transaction has no amount".to_string()
+                });
             }
+        } else {
+            warn!(
+                "Ignored chargeback of tx {} for client {}: unknown transaction",
+                tx.tx, tx.client
+            );
+            self.ignore(tx, TransactionError::UnknownTx, || {
+                "unknown transaction".to_string()
+            });
         }
     }
 
     /// Write accounts csv table to standard output
+    #[cfg(feature = "io")]
     pub fn write_accounts_csv_stdout(&mut self) -> Result<(), Error> {
-        let mut wtr = Writer::from_writer(vec![]);
+        self.write_accounts_csv(BufWriter::new(io::stdout().lock()))
+    }
+
+    /// Write accounts csv table to any writer, streaming records as they are serialized;
+    /// Wrap unbuffered writers in a `BufWriter`.
+    #[cfg(feature = "io")]
+    pub fn write_accounts_csv<W: Write>(&mut self, writer: W) -> Result<(), Error> {
+        let mut wtr = Writer::from_writer(writer);
 
         for account in self.accounts.values_mut() {
             // Round balances before serialization;
-            account.round_balances()?;
+            account.round_balances();
 
             wtr.serialize(account)?;
         }
 
-        // Write Accounts csv to stdout;
-        io::stdout().write_all(&wtr.into_inner()?)?;
+        wtr.flush()?;
+
+        Ok(())
+    }
+
+    /// Write accounts csv table to a file, with the configured output durability;
+    #[cfg(feature = "io")]
+    pub fn write_accounts_csv_file(&mut self, file_path: PathBuf) -> Result<(), Error> {
+        let mut sink = CsvFileSink::create(file_path, self.config.output_durability)?;
+
+        for account in self.accounts.values_mut() {
+            // Round balances before serialization;
+            account.round_balances();
+
+            sink.write(&*account)?;
+        }
+
+        sink.close()
+    }
+
+    /// Write accounts csv table, including lifetime peak and drawdown statistics, to standard output
+    #[cfg(feature = "io")]
+    pub fn write_accounts_extended_csv_stdout(&self) -> Result<(), Error> {
+        self.write_accounts_extended_csv(BufWriter::new(io::stdout().lock()))
+    }
+
+    /// Write accounts csv table, including lifetime peak and drawdown statistics, to any writer;
+    #[cfg(feature = "io")]
+    pub fn write_accounts_extended_csv<W: Write>(&self, writer: W) -> Result<(), Error> {
+        let mut wtr = Writer::from_writer(writer);
+
+        for account in self.accounts.values() {
+            let mut extended = ExtendedAccount::from(account);
+            extended.closed = self.closures.contains_key(&account.client);
+            extended.last_activity = self.last_activity(account.client);
+
+            // Round balances before serialization;
+            extended.round_balances();
+
+            wtr.serialize(extended)?;
+        }
+
+        wtr.flush()?;
+
+        Ok(())
+    }
+
+    /// Write the per-client end-of-day balance series to a csv file, ordered by client and date;
+    /// Only days on which a client had timestamped activity are included.
+    #[cfg(feature = "io")]
+    pub fn write_daily_balances_csv_file(&self, file_path: PathBuf) -> Result<(), Error> {
+        let mut sink = CsvFileSink::create(file_path, self.config.output_durability)?;
+
+        for balance in self.daily_balances.values() {
+            let mut balance = balance.clone();
+
+            // Round balances before serialization;
+            balance.round_balances();
+
+            sink.write(balance)?;
+        }
+
+        sink.close()
+    }
+
+    /// Write the per-client end-of-day balance series to a Parquet file, ordered by client and date;
+    /// Dates are stored as `DATE` and balances as exact `DECIMAL(38, 4)` columns.
+    /// Requires the `parquet` feature.
+    #[cfg(feature = "parquet")]
+    pub fn write_daily_balances_parquet_file(&self, file_path: PathBuf) -> Result<(), Error> {
+        use parquet::data_type::{FixedLenByteArray, FixedLenByteArrayType, Int32Type, Int64Type};
+        use parquet::file::properties::WriterProperties;
+        use parquet::file::writer::SerializedFileWriter;
+        use parquet::schema::parser::parse_message_type;
+        use std::sync::Arc;
+
+        let schema = Arc::new(parse_message_type(&format!(
+            "message daily_balance {{
+                REQUIRED {} client (INTEGER({}, false));
+                REQUIRED INT32 date (DATE);
+                REQUIRED FIXED_LEN_BYTE_ARRAY (16) available (DECIMAL(38, {places}));
+                REQUIRED FIXED_LEN_BYTE_ARRAY (16) held (DECIMAL(38, {places}));
+                REQUIRED FIXED_LEN_BYTE_ARRAY (16) total (DECIMAL(38, {places}));
+            }}",
+            if ClientId::BITS == 64 {
+                "INT64"
+            } else {
+                "INT32"
+            },
+            ClientId::BITS,
+            places = AMOUNT_DECIMAL_PLACES
+        ))?);
+        let props = Arc::new(WriterProperties::builder().build());
+        let mut writer = SerializedFileWriter::new(File::create(file_path)?, schema, props)?;
+
+        let mut balances = Vec::with_capacity(self.daily_balances.len());
+        for (&(client, day), balance) in self.daily_balances.iter() {
+            let mut balance = balance.clone();
+
+            // Round balances before serialization;
+            balance.round_balances();
+
+            balances.push((client, day as i32, balance));
+        }
+
+        // Balances are exact decimals, stored as big-endian 128-bit units of the fourth decimal place;
+        let decimals = |amount: fn(&DailyBalance) -> Amount| -> Vec<FixedLenByteArray> {
+            balances
+                .iter()
+                .map(|(_, _, balance)| {
+                    FixedLenByteArray::from(amount_units(amount(balance)).to_be_bytes().to_vec())
+                })
+                .collect()
+        };
+        let dates: Vec<i32> = balances.iter().map(|(_, day, _)| *day).collect();
+        let columns = [
+            decimals(|balance| balance.available),
+            decimals(|balance| balance.held),
+            decimals(|balance| balance.total),
+        ];
+
+        let mut row_group = writer.next_row_group()?;
+
+        if let Some(mut column) = row_group.next_column()? {
+            if ClientId::BITS == 64 {
+                let clients: Vec<i64> = balances.iter().map(|(c, _, _)| *c as i64).collect();
+                column
+                    .typed::<Int64Type>()
+                    .write_batch(&clients, None, None)?;
+            } else {
+                let clients: Vec<i32> = balances.iter().map(|(c, _, _)| *c as i32).collect();
+                column
+                    .typed::<Int32Type>()
+                    .write_batch(&clients, None, None)?;
+            }
+            column.close()?;
+        }
+        if let Some(mut column) = row_group.next_column()? {
+            column
+                .typed::<Int32Type>()
+                .write_batch(&dates, None, None)?;
+            column.close()?;
+        }
+        for values in columns.iter() {
+            if let Some(mut column) = row_group.next_column()? {
+                column
+                    .typed::<FixedLenByteArrayType>()
+                    .write_batch(values, None, None)?;
+                column.close()?;
+            }
+        }
+
+        row_group.close()?;
+        writer.close()?;
 
         Ok(())
     }
 
     /// Used as a helper method to create dummy transactions;
+    #[cfg(feature = "io")]
     pub fn write_transactions_csv_file(
         transactions: Vec<Transaction>,
         file_path: PathBuf,
@@ -180,22 +1375,380 @@ impl Accounting {
         Ok(())
     }
 
+    /// Used as a helper method to create large dummy transactions files as gzip-compressed csv;
+    #[cfg(feature = "io")]
+    pub fn write_transactions_gzip_csv_file(
+        transactions: Vec<Transaction>,
+        file_path: PathBuf,
+    ) -> Result<(), Error> {
+        let encoder = GzEncoder::new(File::create(file_path)?, Compression::default());
+        let mut wtr = Writer::from_writer(encoder);
+
+        for tx in transactions {
+            wtr.serialize(&tx)?;
+        }
+
+        // Flush the csv writer before finishing the gzip stream;
+        wtr.flush()?;
+        wtr.into_inner()
+            .map_err(|e| io::Error::new(e.error().kind(), e.error().to_string()))?
+            .finish()?;
+
+        Ok(())
+    }
+
+    /// Used as a helper method to create large dummy transactions files in Parquet format, with
+    /// amounts as `DECIMAL(38, 4)` and the columns of the csv format;
+    /// Requires the `parquet` feature.
+    #[cfg(feature = "parquet")]
+    pub fn write_transactions_parquet_file(
+        transactions: Vec<Transaction>,
+        file_path: PathBuf,
+    ) -> Result<(), Error> {
+        use parquet::data_type::{
+            ByteArray, ByteArrayType, FixedLenByteArray, FixedLenByteArrayType, Int32Type,
+            Int64Type,
+        };
+        use parquet::file::properties::WriterProperties;
+        use parquet::file::writer::SerializedFileWriter;
+        use parquet::schema::parser::parse_message_type;
+        use std::sync::Arc;
+
+        // Client and tx ids are stored as unsigned integers of their width, in INT64 for u64 ids;
+        let physical = |bits: u32| if bits == 64 { "INT64" } else { "INT32" };
+        let schema = Arc::new(parse_message_type(&format!(
+            "message transaction {{
+                REQUIRED BYTE_ARRAY type (UTF8);
+                REQUIRED {} client (INTEGER({}, false));
+                REQUIRED {} tx (INTEGER({}, false));
+                OPTIONAL FIXED_LEN_BYTE_ARRAY (16) amount (DECIMAL(38, {}));
+                OPTIONAL INT64 timestamp (INTEGER(64, false));
+                OPTIONAL BYTE_ARRAY subaccount (UTF8);
+                OPTIONAL BYTE_ARRAY category (UTF8);
+                OPTIONAL BYTE_ARRAY source (UTF8);
+            }}",
+            physical(ClientId::BITS),
+            ClientId::BITS,
+            physical(TxId::BITS),
+            TxId::BITS,
+            AMOUNT_DECIMAL_PLACES
+        ))?);
+        let props = Arc::new(WriterProperties::builder().build());
+        let mut writer = SerializedFileWriter::new(File::create(file_path)?, schema, props)?;
+
+        let types: Vec<ByteArray> = transactions
+            .iter()
+            .map(|tx| ByteArray::from(tx.r#type.as_str()))
+            .collect();
+
+        // Optional columns are written as the present values plus a definition level per row;
+        let amount_levels: Vec<i16> = transactions
+            .iter()
+            .map(|tx| tx.amount.is_some() as i16)
+            .collect();
+        // Amounts are exact decimals, stored as big-endian 128-bit units of the fourth decimal place;
+        let amounts: Vec<FixedLenByteArray> = transactions
+            .iter()
+            .filter_map(|tx| tx.amount)
+            .map(|amount| FixedLenByteArray::from(amount_units(amount).to_be_bytes().to_vec()))
+            .collect();
+        let timestamp_levels: Vec<i16> = transactions
+            .iter()
+            .map(|tx| tx.timestamp.is_some() as i16)
+            .collect();
+        let timestamps: Vec<i64> = transactions
+            .iter()
+            .filter_map(|tx| tx.timestamp.map(|t| t as i64))
+            .collect();
+        let strings = |field: fn(&Transaction) -> Option<&String>| -> (Vec<ByteArray>, Vec<i16>) {
+            let levels = transactions
+                .iter()
+                .map(|tx| field(tx).is_some() as i16)
+                .collect();
+            let values = transactions
+                .iter()
+                .filter_map(|tx| field(tx).map(|value| ByteArray::from(value.as_str())))
+                .collect();
+            (values, levels)
+        };
+
+        let mut row_group = writer.next_row_group()?;
+
+        if let Some(mut column) = row_group.next_column()? {
+            column
+                .typed::<ByteArrayType>()
+                .write_batch(&types, None, None)?;
+            column.close()?;
+        }
+        if let Some(mut column) = row_group.next_column()? {
+            if ClientId::BITS == 64 {
+                let clients: Vec<i64> = transactions.iter().map(|tx| tx.client as i64).collect();
+                column
+                    .typed::<Int64Type>()
+                    .write_batch(&clients, None, None)?;
+            } else {
+                let clients: Vec<i32> = transactions.iter().map(|tx| tx.client as i32).collect();
+                column
+                    .typed::<Int32Type>()
+                    .write_batch(&clients, None, None)?;
+            }
+            column.close()?;
+        }
+        if let Some(mut column) = row_group.next_column()? {
+            if TxId::BITS == 64 {
+                let txs: Vec<i64> = transactions.iter().map(|tx| tx.tx as i64).collect();
+                column.typed::<Int64Type>().write_batch(&txs, None, None)?;
+            } else {
+                let txs: Vec<i32> = transactions.iter().map(|tx| tx.tx as i32).collect();
+                column.typed::<Int32Type>().write_batch(&txs, None, None)?;
+            }
+            column.close()?;
+        }
+        if let Some(mut column) = row_group.next_column()? {
+            column.typed::<FixedLenByteArrayType>().write_batch(
+                &amounts,
+                Some(&amount_levels),
+                None,
+            )?;
+            column.close()?;
+        }
+        if let Some(mut column) = row_group.next_column()? {
+            column
+                .typed::<Int64Type>()
+                .write_batch(&timestamps, Some(&timestamp_levels), None)?;
+            column.close()?;
+        }
+        let fields: [fn(&Transaction) -> Option<&String>; 3] = [
+            |tx| tx.subaccount.as_ref(),
+            |tx| tx.category.as_ref(),
+            |tx| tx.source.as_ref(),
+        ];
+        for field in fields {
+            if let Some(mut column) = row_group.next_column()? {
+                let (values, levels) = strings(field);
+                column
+                    .typed::<ByteArrayType>()
+                    .write_batch(&values, Some(&levels), None)?;
+                column.close()?;
+            }
+        }
+
+        row_group.close()?;
+        writer.close()?;
+
+        Ok(())
+    }
+
     /// Read the CSV transactions file and process each transaction;
+    #[cfg(feature = "io")]
     pub fn read_transactions_csv_file(&mut self, file_path: PathBuf) -> Result<(), Error> {
-        let mut file = Reader::from_path(file_path)?;
+        let source = self.file_source(&file_path);
+        let file = self.open_transactions_csv(file_path)?;
+        self.process_transactions_csv(file, source.as_deref())
+    }
 
-        for row in file.deserialize::<Transaction>() {
-            // Process CSV Row;
-            let transaction = row?;
+    /// Read CSV transactions from any reader, e.g. stdin, a socket or an in-memory buffer, and
+    /// process each transaction; The input format and limits apply, except `max_file_size`.
+    #[cfg(feature = "io")]
+    pub fn read_transactions_csv<R: Read>(&mut self, reader: R) -> Result<(), Error> {
+        let reader = self.input_format().reader(reader)?;
+        self.process_transactions_csv(reader, None)
+    }
 
-            // Process Transaction as it is being read;
-            // Update client account from transaction;
-            self.process_transaction(transaction);
+    /// Process each row of a transactions csv reader, tagging transactions with the source;
+    #[cfg(feature = "io")]
+    fn process_transactions_csv<R: Read>(
+        &mut self,
+        reader: Reader<DecodedReader<R>>,
+        source: Option<&str>,
+    ) -> Result<(), Error> {
+        for row in self.transaction_rows(reader)? {
+            if let Some(transaction) = self.prepare_row(row.map_err(|row| row.error), source)? {
+                // Process Transaction as it is being read;
+                // Update client account from transaction;
+                self.try_process_transaction(transaction)?;
+            }
         }
 
         Ok(())
     }
 
+    /// Parse and enrich a csv row, tagging it with the source unless it has one; Lenient mode skips
+    /// rows that fail to parse, returning `None`, and rows of unknown transaction types are counted
+    /// and skipped unless in strict mode.
+    #[cfg(feature = "io")]
+    pub(crate) fn prepare_row(
+        &mut self,
+        row: Result<Transaction, Error>,
+        source: Option<&str>,
+    ) -> Result<Option<Transaction>, Error> {
+        let strict = self.config.strictness == Strictness::Strict;
+        let mut transaction = match row {
+            Ok(transaction) if transaction.r#type == TxType::Unknown && !strict => {
+                self.skip_unknown_tx_type(TxType::Unknown.as_str());
+                return Ok(None);
+            }
+            Ok(transaction) => transaction,
+            // Input limits abort processing in every mode;
+            Err(e @ Error::InputLimitExceeded { .. }) => return Err(e),
+            Err(Error::InvalidTxType(name)) if !strict => {
+                self.skip_unknown_tx_type(&name);
+                return Ok(None);
+            }
+            Err(e) if self.config.strictness == Strictness::Lenient => {
+                warn!("Skipped row that failed to parse: {}", e);
+                return Ok(None);
+            }
+            Err(e) => return Err(e),
+        };
+
+        if transaction.source.is_none() {
+            transaction.source = source.map(str::to_string);
+        }
+
+        // Enrich the transaction before it is processed;
+        for enricher in self.enrichers.iter() {
+            enricher.enrich(&mut transaction)?;
+        }
+
+        Ok(Some(transaction))
+    }
+
+    /// Count a csv row skipped for its unknown transaction type;
+    #[cfg(feature = "io")]
+    fn skip_unknown_tx_type(&mut self, name: &str) {
+        warn!("Skipped row with unknown transaction type '{}'", name);
+        *self.unknown_tx_types.entry(name.to_string()).or_default() += 1;
+    }
+
+    /// Returns the number of csv rows skipped per unknown transaction type name, e.g. typos like
+    /// `depositt`; Rows of unknown types fail processing in strict mode instead.
+    pub fn unknown_tx_types(&self) -> &BTreeMap<String, u64> {
+        &self.unknown_tx_types
+    }
+
+    /// Returns every transaction whose amount exceeded four decimal places, with the policy outcome;
+    pub fn precision_adjustments(&self) -> &[PrecisionAdjustment] {
+        &self.precision_adjustments
+    }
+
+    /// Write the precision adjustments report to a csv file;
+    #[cfg(feature = "io")]
+    pub fn write_precision_adjustments_csv_file(&self, file_path: PathBuf) -> Result<(), Error> {
+        let mut sink = CsvFileSink::create(file_path, self.config.output_durability)?;
+
+        for adjustment in self.precision_adjustments.iter() {
+            sink.write(adjustment)?;
+        }
+
+        sink.close()
+    }
+
+    /// Iterate over all accounts, in no particular order;
+    pub fn accounts(&self) -> impl Iterator<Item = &Account> {
+        self.accounts.values()
+    }
+
+    /// Iterate over all recorded deposit and withdrawal transactions, in no particular order;
+    /// Their dispute state is returned by `tx_state`.
+    pub fn transactions(&self) -> impl Iterator<Item = &Transaction> {
+        self.transactions
+            .values()
+            .map(|recorded| &recorded.transaction)
+    }
+
+    /// Returns the recorded deposit or withdrawal, e.g. to audit what a dispute refers to;
+    pub fn get_transaction(&self, tx: TxId) -> Option<&Transaction> {
+        self.transactions
+            .get(&tx)
+            .map(|recorded| &recorded.transaction)
+    }
+
+    /// Iterate over the recorded deposits and withdrawals of the client, in the order recorded;
+    /// Served from a per-client index, without scanning every recorded transaction.
+    pub fn client_transactions(&self, client: ClientId) -> impl Iterator<Item = &Transaction> {
+        self.client_tx_index
+            .get(&client)
+            .into_iter()
+            .flatten()
+            .filter_map(move |tx| self.get_transaction(*tx))
+    }
+
+    /// Insert or replace an account, e.g. when restoring persisted state;
+    pub(crate) fn insert_account(&mut self, account: Account) {
+        self.accounts.insert(account.client, account);
+    }
+
+    /// Insert or replace a recorded transaction, e.g. when restoring persisted state;
+    pub(crate) fn insert_transaction(&mut self, tx: Transaction, state: TxState) {
+        let (id, client) = (tx.tx, tx.client);
+        let replaced = self.transactions.insert(
+            id,
+            RecordedTx {
+                transaction: tx,
+                state,
+                charged_back: None,
+            },
+        );
+
+        // Keep the client index free of duplicates when a record is replaced;
+        match replaced {
+            Some(previous) if previous.transaction.client == client => {}
+            previous => {
+                if let Some(previous) = previous {
+                    if let Some(ids) = self.client_tx_index.get_mut(&previous.transaction.client) {
+                        ids.retain(|tx| *tx != id);
+                    }
+                }
+                self.client_tx_index.entry(client).or_default().push(id);
+            }
+        }
+    }
+
+    /// Reset all processing state, e.g. accounts, recorded transactions, holds, budgets and spending,
+    /// reserve floors, quarantined transactions, the audit log, acks and content hashes; Keeps the
+    /// configuration, enrichers, client remap table, clock, id allocator, handlers and the attached
+    /// journal, streams and sinks.
+    pub(crate) fn clear(&mut self) {
+        let Accounting {
+            config,
+            enrichers,
+            client_remapper,
+            clock,
+            id_allocator,
+            #[cfg(feature = "io")]
+            journal,
+            #[cfg(feature = "io")]
+            change_stream,
+            #[cfg(feature = "io")]
+            ack_log,
+            handlers,
+            metrics,
+            #[cfg(feature = "otlp")]
+            otlp,
+            ..
+        } = std::mem::take(self);
+
+        *self = Accounting {
+            config,
+            enrichers,
+            client_remapper,
+            clock,
+            id_allocator,
+            #[cfg(feature = "io")]
+            journal,
+            #[cfg(feature = "io")]
+            change_stream,
+            #[cfg(feature = "io")]
+            ack_log,
+            handlers,
+            metrics,
+            #[cfg(feature = "otlp")]
+            otlp,
+            ..Default::default()
+        };
+    }
+
     /// Convenience method for getting an account stored in the private accounts HashMap
     pub fn get_account(&self, client: ClientId) -> Option<&Account> {
         self.accounts.get(&client)
@@ -205,44 +1758,36 @@ impl Accounting {
     /// Generated data may contain erroneous transactions on purpose;
     /// Use generated data to write test cases to enforce correctness;
     /// Data may also be used for performance testing;
+    #[cfg(feature = "io")]
     pub fn generate_dummy_transactions(
-        num_transactions: u32,
-        num_accounts: u16,
+        num_transactions: TxId,
+        num_accounts: ClientId,
     ) -> Result<Vec<Transaction>, Error> {
         let mut rng = thread_rng();
         let mut transactions = Vec::new();
 
-        let txs: Vec<u32> = Uniform::new_inclusive(1, num_transactions)
+        let txs: Vec<TxId> = Uniform::new_inclusive(1, num_transactions)
             .sample_iter(&mut rng)
             .take(num_transactions as usize)
             .collect();
 
         for tx in txs {
             let mut rng = thread_rng();
-            let client: u16 = Uniform::new_inclusive(1, num_accounts)
+            let client: ClientId = Uniform::new_inclusive(1, num_accounts)
                 .sample_iter(&mut rng)
                 .take(1)
                 .sum();
 
-            let tx_type: i32 = Uniform::new_inclusive(1, 5)
-                .sample_iter(&mut rng)
-                .take(1)
-                .sum();
+            let r#type = TxType::PAYMENTS[Uniform::new(0, TxType::PAYMENTS.len()).sample(&mut rng)];
 
-            let r#type = TxType::from(tx_type);
-
-            let amount = match r#type {
-                TxType::Deposit | TxType::Withdrawal => Some(
-                    format!(
-                        "{:.4}",
-                        Uniform::new_inclusive(0.1, 500.)
-                            .sample_iter(&mut rng)
-                            .take(3)
-                            .sum::<f64>()
-                    )
-                    .parse::<f64>()?,
-                ),
-                _ => None,
+            let amount = if r#type.is_funds_movement() {
+                let amount = Uniform::new_inclusive(0.1, 500.)
+                    .sample_iter(&mut rng)
+                    .take(3)
+                    .sum::<f64>();
+                Some(Amount::new((amount * 10_000.).round() as i64, 4).normalize())
+            } else {
+                None
             };
 
             let transaction = Transaction {
@@ -250,6 +1795,10 @@ impl Accounting {
                 client,
                 r#type,
                 amount,
+                timestamp: None,
+                subaccount: None,
+                category: None,
+                source: None,
             };
 
             transactions.push(transaction)
@@ -258,3 +1807,33 @@ impl Accounting {
         Ok(transactions)
     }
 }
+
+/// Resolve chained client id mappings (a -> b, b -> c) to their final client id, dropping identity
+/// mappings; Returns `Error::InvalidArgument` for cyclic mappings.
+fn resolve_client_map(
+    map: &HashMap<ClientId, ClientId>,
+) -> Result<BTreeMap<ClientId, ClientId>, Error> {
+    let mut resolved = BTreeMap::new();
+
+    for (&old_client, &new_client) in map.iter() {
+        let mut target = new_client;
+        let mut hops = 0;
+
+        while let Some(&next) = map.get(&target).filter(|next| **next != target) {
+            hops += 1;
+            if hops > map.len() {
+                return Err(Error::InvalidArgument(format!(
+                    "cyclic client id mapping through client {}",
+                    old_client
+                )));
+            }
+            target = next;
+        }
+
+        if target != old_client {
+            resolved.insert(old_client, target);
+        }
+    }
+
+    Ok(resolved)
+}
@@ -0,0 +1,131 @@
+#[cfg(feature = "io")]
+use std::fmt;
+#[cfg(feature = "io")]
+use std::path::PathBuf;
+
+#[cfg(feature = "io")]
+use csv::Reader;
+use log::debug;
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/// Client-supplied id identifying a submission across retries;
+pub type ReceiptId = String;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+/// Outcome of a submitted transaction.
+pub enum AckStatus {
+    /// The transaction was validated and processed;
+    Accepted,
+    /// The transaction was processed but ignored, leaving the account unchanged, e.g. a withdrawal
+    /// exceeding the available funds;
+    Ignored,
+    /// The transaction was rejected with an error and not processed;
+    Rejected,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+/// Durable acknowledgement of a submitted transaction; Resubmitting the same receipt id returns the
+/// original acknowledgement without processing the transaction again.
+pub struct Ack {
+    #[serde(rename = "receipt")]
+    pub receipt: ReceiptId,
+    #[serde(rename = "client")]
+    pub client: ClientId,
+    #[serde(rename = "tx")]
+    pub tx: TxId,
+    #[serde(rename = "status")]
+    pub status: AckStatus,
+    /// Error code of a rejected transaction, see `Error::code`
+    #[serde(rename = "error")]
+    pub error: Option<String>,
+    /// Error message of a rejected transaction, or why it was ignored
+    #[serde(rename = "message")]
+    pub message: Option<String>,
+}
+
+/// Append-only csv log of acknowledgements, fsynced after every record so an acknowledgement is
+/// never returned before it is durable.
+#[cfg(feature = "io")]
+pub struct AckLog {
+    sink: CsvFileSink,
+}
+
+#[cfg(feature = "io")]
+impl fmt::Debug for AckLog {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("AckLog").finish()
+    }
+}
+
+impl Accounting {
+    /// Validate and process a transaction at most once per receipt id; See `try_process_transaction`.
+    /// Resubmitting a known receipt id is a no-op returning the original acknowledgement, so
+    /// retrying clients can resubmit until acknowledged. Transient errors, i.e. I/O errors such as a
+    /// failed journal write and `Error::CircuitOpen`, are returned without an acknowledgement, as the
+    /// transaction was not processed and may be resubmitted.
+    pub fn submit(&mut self, receipt: ReceiptId, tx: Transaction) -> Result<Ack, Error> {
+        if let Some(ack) = self.acks.get(&receipt) {
+            debug!("Replayed acknowledgement for receipt {}", receipt);
+            return Ok(ack.clone());
+        }
+
+        // Refuse while the circuit breaker is open; The transaction tripping it is still acknowledged;
+        self.check_circuit_breaker()?;
+
+        let (client, id) = (tx.client, tx.tx);
+        let (status, error, message) = match self.try_process_unhalted(tx) {
+            Ok(Ok(_)) => (AckStatus::Accepted, None, None),
+            Ok(Err(rejection)) => (AckStatus::Ignored, None, Some(rejection.to_string())),
+            Err(e) if e.kind() == ErrorKind::Io => return Err(e),
+            Err(e) => (
+                AckStatus::Rejected,
+                Some(e.code().to_string()),
+                Some(e.to_string()),
+            ),
+        };
+
+        let ack = Ack {
+            receipt: receipt.clone(),
+            client,
+            tx: id,
+            status,
+            error,
+            message,
+        };
+
+        #[cfg(feature = "io")]
+        if let Some(log) = self.ack_log.as_mut() {
+            log.sink.write(&ack)?;
+        }
+
+        self.acks.insert(receipt, ack.clone());
+
+        Ok(ack)
+    }
+
+    /// Returns the acknowledgement of a receipt id, if it was submitted;
+    pub fn get_ack(&self, receipt: &str) -> Option<&Ack> {
+        self.acks.get(receipt)
+    }
+
+    /// Load the acknowledgements recorded in the log file, if it exists, and append every new
+    /// acknowledgement to it; Use together with a journal or snapshot restoring the processed state.
+    #[cfg(feature = "io")]
+    pub fn open_ack_log(&mut self, file_path: PathBuf) -> Result<(), Error> {
+        if file_path.exists() {
+            let mut rdr = Reader::from_path(&file_path)?;
+            for result in rdr.deserialize() {
+                let ack: Ack = result?;
+                self.acks.insert(ack.receipt.clone(), ack);
+            }
+        }
+
+        let sink = CsvFileSink::append(file_path, Durability::FsyncEvery(1))?;
+        self.ack_log = Some(AckLog { sink });
+
+        Ok(())
+    }
+}
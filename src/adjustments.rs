@@ -0,0 +1,178 @@
+#[cfg(feature = "io")]
+use std::path::PathBuf;
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+/// How a debit adjustment was applied.
+pub enum DebitMode {
+    /// The requested amount was debited in full from available funds;
+    Full,
+    /// The debit was capped at the available funds;
+    Capped,
+    /// The override flag was set and the requested amount was debited, even below zero available;
+    Override,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+/// Audit trail record of a debit adjustment, e.g. a fee or correction.
+pub struct DebitAdjustment {
+    #[serde(rename = "client")]
+    pub client: ClientId,
+    /// Amount requested to be debited
+    #[serde(rename = "requested")]
+    pub requested: Amount,
+    /// Amount actually debited
+    #[serde(rename = "debited")]
+    pub debited: Amount,
+    #[serde(rename = "mode")]
+    pub mode: DebitMode,
+    /// Free-form reason recorded by the operator
+    #[serde(rename = "reason")]
+    pub reason: String,
+    /// Clock time at which the adjustment was applied
+    #[serde(rename = "applied_at")]
+    pub applied_at: Timestamp,
+}
+
+impl Accounting {
+    /// Debit a fee or correction from the client's available and total funds;
+    /// The debit is capped at the available funds, so available never drops below zero, unless
+    /// `allow_negative` is set. Processed as an `adjustment` transaction with a newly allocated tx
+    /// id, so it is journaled and refused for locked or closed accounts. Every adjustment is
+    /// recorded in the audit trail.
+    pub fn debit_adjustment(
+        &mut self,
+        client: ClientId,
+        amount: Amount,
+        reason: &str,
+        allow_negative: bool,
+    ) -> Result<DebitAdjustment, Error> {
+        if amount <= Amount::ZERO {
+            return Err(Error::InvalidArgument(format!(
+                "debit amount must be positive, got {}",
+                amount
+            )));
+        }
+
+        let epsilon = self.config.amount_epsilon;
+        let available = self
+            .get_account(self.client_remapper.map(client))
+            .map_or(Amount::ZERO, |account| account.available);
+        let (debited, mode) = if covers(available, amount, epsilon) {
+            (amount, DebitMode::Full)
+        } else if allow_negative {
+            (amount, DebitMode::Override)
+        } else {
+            (available.max(Amount::ZERO), DebitMode::Capped)
+        };
+
+        self.adjust(client, debited, reason)?;
+
+        // The transaction only carries the debited amount, record what was requested;
+        let adjustment = self
+            .debit_adjustments
+            .last_mut()
+            .ok_or_else(|| Error::InvalidArgument(format!("cannot debit client {}", client)))?;
+        adjustment.requested = amount;
+        adjustment.mode = mode;
+
+        if mode == DebitMode::Capped {
+            info!(
+                "Capped debit of client {} from {} at available funds",
+                client, amount
+            );
+        }
+
+        Ok(adjustment.clone())
+    }
+
+    /// Process an `adjustment` transaction debiting the amount from the client, or crediting a
+    /// negative amount, under a newly allocated tx id;
+    pub(crate) fn adjust(
+        &mut self,
+        client: ClientId,
+        amount: Amount,
+        reason: &str,
+    ) -> Result<(), Error> {
+        let tx = self.allocate_tx_id();
+        let outcome = self.try_process(Transaction {
+            r#type: TxType::Adjustment,
+            client,
+            tx,
+            amount: Some(amount),
+            timestamp: None,
+            subaccount: None,
+            category: Some(reason.to_string()),
+            source: None,
+        })?;
+
+        outcome.map(|_| ()).map_err(|rejection| {
+            Error::InvalidArgument(format!("cannot adjust client {}: {}", client, rejection))
+        })
+    }
+
+    /// This method is provided as a helper method and is intended to be consumed by `self.process_transaction`;
+    /// Adjustments are applied in full, even below zero available, as `debit_adjustment` already
+    /// capped them.
+    pub(crate) fn process_adjustment(&mut self, account: &mut Account, tx: &Transaction) {
+        let amount = match tx.amount {
+            Some(amount) => amount,
+            None => {
+                self.ignore(tx, TransactionError::MissingAmount, || {
+                    "missing amount".to_string()
+                });
+                return;
+            }
+        };
+
+        let epsilon = self.config.amount_epsilon;
+        let mode = if amount >= Amount::ZERO && covers(account.available, amount, epsilon) {
+            DebitMode::Full
+        } else {
+            DebitMode::Override
+        };
+
+        account.available = clear_dust(account.available - amount, epsilon);
+        account.total = clear_dust(account.total - amount, epsilon);
+
+        let reason = tx.category.clone().unwrap_or_default();
+        match mode {
+            DebitMode::Override => warn!(
+                "Debited {} from client {} by override, leaving {} available: {}",
+                amount, tx.client, account.available, reason
+            ),
+            _ => info!("Debited {} from client {}: {}", amount, tx.client, reason),
+        }
+
+        self.debit_adjustments.push(DebitAdjustment {
+            client: tx.client,
+            requested: amount,
+            debited: amount,
+            mode,
+            reason,
+            applied_at: tx.timestamp.unwrap_or_else(|| self.now()),
+        });
+    }
+
+    /// Returns the audit trail of debit adjustments, in the order applied;
+    pub fn debit_adjustments(&self) -> &[DebitAdjustment] {
+        &self.debit_adjustments
+    }
+
+    /// Write the audit trail of debit adjustments to a csv file;
+    #[cfg(feature = "io")]
+    pub fn write_debit_adjustments_csv_file(&self, file_path: PathBuf) -> Result<(), Error> {
+        let mut sink = CsvFileSink::create(file_path, self.config.output_durability)?;
+
+        for adjustment in self.debit_adjustments.iter() {
+            sink.write(adjustment)?;
+        }
+
+        sink.close()
+    }
+}
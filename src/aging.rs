@@ -0,0 +1,128 @@
+use std::fmt;
+#[cfg(feature = "io")]
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+/// Aging range of frozen funds, by whole days since the funds were held.
+pub enum AgingBucket {
+    #[serde(rename = "0-7")]
+    UpToWeek,
+    #[serde(rename = "8-30")]
+    UpToMonth,
+    #[serde(rename = "31+")]
+    OverMonth,
+}
+
+impl AgingBucket {
+    /// Returns the bucket of an age in whole days;
+    pub fn of(age_days: u64) -> AgingBucket {
+        match age_days {
+            0..=7 => AgingBucket::UpToWeek,
+            8..=30 => AgingBucket::UpToMonth,
+            _ => AgingBucket::OverMonth,
+        }
+    }
+}
+
+impl fmt::Display for AgingBucket {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AgingBucket::UpToWeek => f.write_str("0-7"),
+            AgingBucket::UpToMonth => f.write_str("8-30"),
+            AgingBucket::OverMonth => f.write_str("31+"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+/// What froze the funds of an aging entry.
+pub enum HeldKind {
+    /// An open dispute, identified by the disputed transaction id;
+    Dispute,
+    /// An operator hold, identified by the hold id;
+    Hold,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+/// Row of the held-funds aging report: open funds held for a client, and how long they have been held.
+pub struct HeldAging {
+    #[serde(rename = "kind")]
+    pub kind: HeldKind,
+    /// Disputed transaction id or hold id
+    #[serde(rename = "id")]
+    pub id: u64,
+    #[serde(rename = "client")]
+    pub client: ClientId,
+    #[serde(rename = "amount")]
+    pub amount: Amount,
+    /// Time the dispute was opened or the hold was placed
+    #[serde(rename = "held_since")]
+    pub held_since: Timestamp,
+    /// Whole days since the funds were held
+    #[serde(rename = "age_days")]
+    pub age_days: u64,
+    #[serde(rename = "bucket")]
+    pub bucket: AgingBucket,
+}
+
+impl Accounting {
+    /// Returns every open dispute and operator hold with its age as of the given time, oldest first;
+    // `TxId` is already a u64 with the `tx-id-u64` feature;
+    #[allow(clippy::useless_conversion)]
+    pub fn held_aging(&self, as_of: Timestamp) -> Vec<HeldAging> {
+        let entry = |kind, id, client, amount, held_since: Timestamp| {
+            let age_days = as_of.saturating_sub(held_since) / SECONDS_PER_DAY;
+            HeldAging {
+                kind,
+                id,
+                client,
+                amount,
+                held_since,
+                age_days,
+                bucket: AgingBucket::of(age_days),
+            }
+        };
+
+        let mut aging: Vec<HeldAging> = self
+            .disputes()
+            .map(|dispute| {
+                entry(
+                    HeldKind::Dispute,
+                    u64::from(dispute.tx),
+                    dispute.client,
+                    dispute.amount,
+                    dispute.opened_at,
+                )
+            })
+            .chain(self.holds().map(|hold| {
+                entry(
+                    HeldKind::Hold,
+                    hold.id,
+                    hold.client,
+                    hold.amount,
+                    hold.placed_at,
+                )
+            }))
+            .collect();
+
+        aging.sort_by_key(|entry| (entry.held_since, entry.client, entry.id));
+        aging
+    }
+
+    /// Write the held-funds aging report as of the clock time to a csv file;
+    #[cfg(feature = "io")]
+    pub fn write_held_aging_csv_file(&self, file_path: PathBuf) -> Result<(), Error> {
+        let mut sink = CsvFileSink::create(file_path, self.config.output_durability)?;
+
+        for entry in self.held_aging(self.now()) {
+            sink.write(entry)?;
+        }
+
+        sink.close()
+    }
+}
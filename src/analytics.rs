@@ -0,0 +1,174 @@
+use std::collections::BTreeMap;
+#[cfg(feature = "io")]
+use std::path::PathBuf;
+
+use rust_decimal::prelude::ToPrimitive;
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/// Default outlier threshold, in median absolute deviations from the median;
+pub const DEFAULT_ANOMALY_THRESHOLD: f64 = 3.0;
+
+/// Minimum number of transactions of a type a client needs before its amounts are screened;
+pub const MIN_ANOMALY_SAMPLES: usize = 3;
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+/// Number of recorded transactions of a type with an amount in `[min, max)`; Buckets are decades.
+pub struct AmountBucket {
+    #[serde(rename = "type")]
+    pub r#type: TxType,
+    #[serde(rename = "min")]
+    pub min: f64,
+    #[serde(rename = "max")]
+    pub max: f64,
+    #[serde(rename = "count")]
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+/// A transaction amount deviating from the client's median amount for the type by more than
+/// `k` median absolute deviations.
+pub struct Anomaly {
+    #[serde(rename = "client")]
+    pub client: ClientId,
+    #[serde(rename = "tx")]
+    pub tx: TxId,
+    #[serde(rename = "type")]
+    pub r#type: TxType,
+    #[serde(rename = "amount")]
+    pub amount: f64,
+    #[serde(rename = "median")]
+    pub median: f64,
+    #[serde(rename = "mad")]
+    pub mad: f64,
+    /// Deviation from the median, in median absolute deviations
+    #[serde(rename = "score")]
+    pub score: f64,
+}
+
+/// Returns the median of the values; The values are sorted in place.
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+    let mid = values.len() / 2;
+    if values.len() % 2 == 1 {
+        values[mid]
+    } else {
+        (values[mid - 1] + values[mid]) / 2.
+    }
+}
+
+impl Accounting {
+    /// Returns the distribution of recorded transaction amounts per type, in decade buckets
+    /// (`[0, 1)`, `[1, 10)`, `[10, 100)`, ...); Empty buckets are omitted.
+    pub fn amount_histogram(&self) -> Vec<AmountBucket> {
+        let mut counts: BTreeMap<(&str, i32), (TxType, u64)> = BTreeMap::new();
+
+        for tx in self.transactions() {
+            if let Some(amount) = tx.amount.and_then(|amount| amount.to_f64()) {
+                let decade = if amount < 1. {
+                    -1
+                } else {
+                    amount.log10().floor() as i32
+                };
+
+                counts
+                    .entry((tx.r#type.as_str(), decade))
+                    .or_insert((tx.r#type, 0))
+                    .1 += 1;
+            }
+        }
+
+        counts
+            .into_iter()
+            .map(|((_, decade), (r#type, count))| AmountBucket {
+                r#type,
+                min: if decade < 0 { 0. } else { 10f64.powi(decade) },
+                max: 10f64.powi(decade + 1),
+                count,
+            })
+            .collect()
+    }
+
+    /// Flag recorded transactions whose amount is more than `k` median absolute deviations from the
+    /// client's median amount for the type; Clients with fewer than `MIN_ANOMALY_SAMPLES` transactions
+    /// of a type, or identical amounts, are not screened. Ordered by client and transaction id.
+    pub fn amount_anomalies(&self, k: f64) -> Vec<Anomaly> {
+        let mut groups: BTreeMap<(ClientId, &str), Vec<&Transaction>> = BTreeMap::new();
+
+        for tx in self.transactions().filter(|tx| tx.amount.is_some()) {
+            groups
+                .entry((tx.client, tx.r#type.as_str()))
+                .or_default()
+                .push(tx);
+        }
+
+        let mut anomalies = Vec::new();
+
+        for txs in groups.values() {
+            if txs.len() < MIN_ANOMALY_SAMPLES {
+                continue;
+            }
+
+            let mut amounts: Vec<f64> = txs
+                .iter()
+                .filter_map(|tx| tx.amount.and_then(|amount| amount.to_f64()))
+                .collect();
+            let median = median(&mut amounts);
+
+            let mut deviations: Vec<f64> = amounts.iter().map(|a| (a - median).abs()).collect();
+            let mad = self::median(&mut deviations);
+            if mad == 0. {
+                continue;
+            }
+
+            for tx in txs.iter() {
+                let amount = tx
+                    .amount
+                    .and_then(|amount| amount.to_f64())
+                    .unwrap_or_default();
+                let score = (amount - median).abs() / mad;
+
+                if score > k {
+                    anomalies.push(Anomaly {
+                        client: tx.client,
+                        tx: tx.tx,
+                        r#type: tx.r#type,
+                        amount,
+                        median,
+                        mad,
+                        score,
+                    });
+                }
+            }
+        }
+
+        anomalies.sort_by_key(|anomaly| (anomaly.client, anomaly.tx));
+        anomalies
+    }
+
+    /// Write the amount distribution per transaction type to a csv file;
+    #[cfg(feature = "io")]
+    pub fn write_amount_histogram_csv_file(&self, file_path: PathBuf) -> Result<(), Error> {
+        let mut sink = CsvFileSink::create(file_path, self.config.output_durability)?;
+
+        for bucket in self.amount_histogram() {
+            sink.write(bucket)?;
+        }
+
+        sink.close()
+    }
+
+    /// Write the amount anomalies, see `amount_anomalies`, to a csv file for fraud review;
+    #[cfg(feature = "io")]
+    pub fn write_anomalies_csv_file(&self, file_path: PathBuf, k: f64) -> Result<(), Error> {
+        let mut sink = CsvFileSink::create(file_path, self.config.output_durability)?;
+
+        for anomaly in self.amount_anomalies(k) {
+            sink.write(anomaly)?;
+        }
+
+        sink.close()
+    }
+}
@@ -0,0 +1,96 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::path::PathBuf;
+
+use csv::WriterBuilder;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::*;
+
+/// Default relative tolerance within which `Anonymizer` perturbs amounts;
+pub const DEFAULT_ANONYMIZE_TOLERANCE: f64 = 0.05;
+
+#[derive(Debug, Clone, PartialEq)]
+/// Rewrites transactions files for sharing: client ids are replaced by random ids and amounts are
+/// perturbed within a relative tolerance, while types, transaction ids, and therefore dispute chains,
+/// are kept. Equal client ids and equal amounts are rewritten to equal values, so duplicates remain
+/// duplicates. Rows and fields that fail to parse are copied unchanged, preserving malformed input.
+pub struct Anonymizer {
+    /// Maximum relative change of an amount, e.g. `0.05` for +/-5%; `0` keeps amounts unchanged
+    pub tolerance: f64,
+    /// Seed of the random client ids and perturbations; The same seed produces the same output
+    pub seed: u64,
+    /// Layout of the input file
+    pub format: InputFormat,
+}
+
+impl Default for Anonymizer {
+    fn default() -> Self {
+        Anonymizer {
+            tolerance: DEFAULT_ANONYMIZE_TOLERANCE,
+            seed: 0,
+            format: InputFormat::default(),
+        }
+    }
+}
+
+impl Anonymizer {
+    /// Anonymize a transactions csv file, writing the result to the output path;
+    /// Returns the number of rows written.
+    pub fn anonymize_csv_file(&self, input: PathBuf, output: PathBuf) -> Result<usize, Error> {
+        let mut rdr = self.format.flexible_reader(File::open(input)?)?;
+        let headers = rdr.headers()?.clone();
+        let client_column = headers.iter().position(|header| header == "client");
+        let amount_column = headers.iter().position(|header| header == "amount");
+
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut clients: HashMap<ClientId, ClientId> = HashMap::new();
+        let mut used: HashSet<ClientId> = HashSet::new();
+        let mut amounts: HashMap<u64, String> = HashMap::new();
+
+        let mut wtr = WriterBuilder::new().flexible(true).from_path(output)?;
+        wtr.write_record(&headers)?;
+
+        let mut rows = 0;
+        for record in rdr.records() {
+            let record = record?;
+            let mut fields: Vec<String> = record.iter().map(str::to_string).collect();
+
+            if let Some(field) = client_column.and_then(|column| fields.get_mut(column)) {
+                if let Ok(client) = field.trim().parse::<ClientId>() {
+                    let anonymized = *clients.entry(client).or_insert_with(|| loop {
+                        let candidate = rng.gen::<ClientId>();
+                        if used.insert(candidate) {
+                            break candidate;
+                        }
+                    });
+                    *field = anonymized.to_string();
+                }
+            }
+
+            if let Some(field) = amount_column.and_then(|column| fields.get_mut(column)) {
+                match field.trim().parse::<f64>() {
+                    Ok(amount) if amount.is_finite() && self.tolerance > 0. => {
+                        let tolerance = self.tolerance;
+                        *field = amounts
+                            .entry(amount.to_bits())
+                            .or_insert_with(|| {
+                                let factor = 1. + rng.gen_range(-tolerance..=tolerance);
+                                format!("{:.4}", amount * factor)
+                            })
+                            .clone();
+                    }
+                    _ => {}
+                }
+            }
+
+            wtr.write_record(&fields)?;
+            rows += 1;
+        }
+
+        wtr.flush()?;
+
+        Ok(rows)
+    }
+}
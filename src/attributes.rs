@@ -0,0 +1,83 @@
+#[cfg(feature = "io")]
+use std::path::PathBuf;
+
+#[cfg(feature = "io")]
+use csv::Reader;
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+/// Arbitrary key-value attribute of a client's account, e.g. `vip` = `true`.
+pub struct AccountAttribute {
+    #[serde(rename = "client")]
+    pub client: ClientId,
+    #[serde(rename = "key")]
+    pub key: String,
+    #[serde(rename = "value")]
+    pub value: String,
+}
+
+impl Accounting {
+    /// Set an attribute of a client's account, replacing any existing value; Attributes are kept in
+    /// snapshots and readable by custom handlers through `Accounting::attribute`, so policy logic can
+    /// be driven by data, e.g. skipping velocity checks for `vip` accounts.
+    pub fn set_attribute(&mut self, client: ClientId, key: &str, value: &str) {
+        self.attributes
+            .entry(client)
+            .or_default()
+            .insert(key.to_string(), value.to_string());
+    }
+
+    /// Remove an attribute of a client's account, returning its value if it was set;
+    pub fn remove_attribute(&mut self, client: ClientId, key: &str) -> Option<String> {
+        let attributes = self.attributes.get_mut(&client)?;
+        let value = attributes.remove(key);
+        if attributes.is_empty() {
+            self.attributes.remove(&client);
+        }
+
+        value
+    }
+
+    /// Returns the value of an attribute of a client's account, if set;
+    pub fn attribute(&self, client: ClientId, key: &str) -> Option<&str> {
+        self.attributes
+            .get(&client)
+            .and_then(|attributes| attributes.get(key))
+            .map(String::as_str)
+    }
+
+    /// Returns the attributes of a client's account, ordered by key;
+    pub fn account_attributes(&self, client: ClientId) -> impl Iterator<Item = (&str, &str)> {
+        self.attributes
+            .get(&client)
+            .into_iter()
+            .flatten()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    /// Returns every attribute set, ordered by client and key;
+    pub fn attributes(&self) -> impl Iterator<Item = AccountAttribute> + '_ {
+        self.attributes.iter().flat_map(|(client, attributes)| {
+            attributes.iter().map(move |(key, value)| AccountAttribute {
+                client: *client,
+                key: key.clone(),
+                value: value.clone(),
+            })
+        })
+    }
+
+    /// Load attributes from a csv file with `client,key,value` columns;
+    #[cfg(feature = "io")]
+    pub fn read_attributes_csv_file(&mut self, file_path: PathBuf) -> Result<(), Error> {
+        let mut rdr = Reader::from_path(file_path)?;
+
+        for result in rdr.deserialize() {
+            let attribute: AccountAttribute = result?;
+            self.set_attribute(attribute.client, &attribute.key, &attribute.value);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,73 @@
+#[cfg(feature = "io")]
+use std::path::PathBuf;
+
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+/// Record of a policy decision or operator action affecting an account.
+pub struct AuditEvent {
+    /// Clock time of the event
+    #[serde(rename = "at")]
+    pub at: Timestamp,
+    /// The account affected; Empty for ledger-wide operator actions, e.g. `config_reloaded`
+    #[serde(rename = "client")]
+    pub client: Option<ClientId>,
+    /// The transaction that caused the event, if any
+    #[serde(rename = "tx")]
+    pub tx: Option<TxId>,
+    /// Short machine-readable event name, e.g. `dispute_cap_rejected`
+    #[serde(rename = "event")]
+    pub event: String,
+    /// Human-readable details
+    #[serde(rename = "detail")]
+    pub detail: String,
+    /// Source of the transaction being processed when the event was recorded, if any
+    #[serde(rename = "source")]
+    pub source: Option<String>,
+}
+
+impl Accounting {
+    /// Record an event in the audit log, for the client's account or, without one, the ledger;
+    pub(crate) fn audit(
+        &mut self,
+        client: Option<ClientId>,
+        tx: Option<TxId>,
+        event: &str,
+        detail: String,
+    ) {
+        match client {
+            Some(client) => info!("Audit {} for client {}: {}", event, client, detail),
+            None => info!("Audit {}: {}", event, detail),
+        }
+
+        let at = self.now();
+        self.audit_log.push(AuditEvent {
+            at,
+            client,
+            tx,
+            event: event.to_string(),
+            detail,
+            source: self.source.clone(),
+        });
+    }
+
+    /// Returns the audit log, in the order recorded;
+    pub fn audit_log(&self) -> &[AuditEvent] {
+        &self.audit_log
+    }
+
+    /// Write the audit log to a csv file;
+    #[cfg(feature = "io")]
+    pub fn write_audit_log_csv_file(&self, file_path: PathBuf) -> Result<(), Error> {
+        let mut sink = CsvFileSink::create(file_path, self.config.output_durability)?;
+
+        for event in self.audit_log.iter() {
+            sink.write(event)?;
+        }
+
+        sink.close()
+    }
+}
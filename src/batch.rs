@@ -0,0 +1,40 @@
+use crate::*;
+
+/// Aggregate counts of a batch processed by `Accounting::process_transactions`;
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BatchResult {
+    /// Transactions applied, including pending deposits
+    pub applied: usize,
+    /// Transactions ignored by a rule, e.g. `TransactionError::InsufficientFunds`
+    pub rejected: usize,
+    /// Transactions ignored for an unknown transaction type
+    pub unknown: usize,
+}
+
+impl BatchResult {
+    /// Returns the number of transactions in the batch;
+    pub fn total(&self) -> usize {
+        self.applied + self.rejected + self.unknown
+    }
+}
+
+impl Accounting {
+    /// Process many transactions in order, like `process_transaction`, returning the aggregate
+    /// counts instead of a result per transaction;
+    pub fn process_transactions(
+        &mut self,
+        txs: impl IntoIterator<Item = Transaction>,
+    ) -> BatchResult {
+        let mut result = BatchResult::default();
+
+        for tx in txs {
+            match self.process_transaction(tx) {
+                Ok(_) => result.applied += 1,
+                Err(TransactionError::UnknownTxType(_)) => result.unknown += 1,
+                Err(_) => result.rejected += 1,
+            }
+        }
+
+        result
+    }
+}
@@ -0,0 +1,208 @@
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+use log::error;
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/// Action taken once the circuit breaker trips;
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum BreakerAction {
+    /// Stop processing: `try_process_transaction` returns `Error::CircuitOpen`, and
+    /// `process_transaction` ignores every later transaction;
+    #[default]
+    Halt,
+    /// Keep reading, but divert every later transaction to the quarantine instead of applying it;
+    Quarantine,
+}
+
+impl FromStr for BreakerAction {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "halt" => Ok(BreakerAction::Halt),
+            "quarantine" => Ok(BreakerAction::Quarantine),
+            _ => Err(Error::InvalidArgument(format!(
+                "unknown circuit breaker action '{}'",
+                s
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+/// Trips on anomalous aggregate patterns mid-run, e.g. a corrupted feed; See
+/// `AccountingConfig::circuit_breaker`.
+pub struct CircuitBreaker {
+    /// Trip once more than this fraction of the last `rejection_window` transactions was ignored,
+    /// e.g. `0.5`
+    pub max_rejection_rate: Option<f64>,
+    /// Number of most recent transactions the rejection rate is computed over; The rate is not
+    /// evaluated until this many transactions were processed
+    pub rejection_window: usize,
+    /// Trip once the withdrawals applied within `outflow_window` seconds exceed this amount
+    pub max_outflow: Option<Amount>,
+    /// Seconds of the outflow window, judged by transaction timestamps (or the clock time)
+    pub outflow_window: Timestamp,
+    pub action: BreakerAction,
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        CircuitBreaker {
+            max_rejection_rate: None,
+            rejection_window: 100,
+            max_outflow: None,
+            outflow_window: SECONDS_PER_DAY,
+            action: BreakerAction::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// Why and when the circuit breaker tripped;
+pub struct BreakerTrip {
+    /// The transaction that tripped the breaker, which was processed
+    pub tx: TxId,
+    /// Timestamp of the transaction, or the clock time
+    pub at: Timestamp,
+    pub reason: String,
+}
+
+#[derive(Debug, Default, Clone)]
+/// Running aggregates evaluated by the circuit breaker;
+pub(crate) struct BreakerState {
+    /// Whether each of the most recent transactions was ignored
+    outcomes: VecDeque<bool>,
+    rejected: usize,
+    /// Timestamp and amount of the withdrawals applied within the outflow window
+    outflows: VecDeque<(Timestamp, Amount)>,
+    outflow: Amount,
+    pub(crate) trip: Option<BreakerTrip>,
+}
+
+impl BreakerState {
+    /// Record a processed transaction, returning why the breaker trips, if it does;
+    fn observe(
+        &mut self,
+        breaker: &CircuitBreaker,
+        at: Timestamp,
+        rejected: bool,
+        withdrawn: Option<Amount>,
+    ) -> Option<String> {
+        let window = breaker.rejection_window.max(1);
+        self.outcomes.push_back(rejected);
+        self.rejected += rejected as usize;
+        if self.outcomes.len() > window && self.outcomes.pop_front() == Some(true) {
+            self.rejected -= 1;
+        }
+
+        if let Some(amount) = withdrawn {
+            self.outflows.push_back((at, amount));
+            self.outflow += amount;
+        }
+        while let Some((since, amount)) = self.outflows.front().copied() {
+            if since.saturating_add(breaker.outflow_window) > at {
+                break;
+            }
+            self.outflows.pop_front();
+            self.outflow -= amount;
+        }
+
+        if let Some(max) = breaker.max_rejection_rate {
+            let rate = self.rejected as f64 / self.outcomes.len() as f64;
+            if self.outcomes.len() >= window && rate > max {
+                return Some(format!(
+                    "{} of the last {} transactions were ignored, exceeding the rate of {}",
+                    self.rejected,
+                    self.outcomes.len(),
+                    max
+                ));
+            }
+        }
+
+        match breaker.max_outflow {
+            Some(max) if self.outflow > max => Some(format!(
+                "withdrawals of {} within {} seconds exceed {}",
+                self.outflow.normalize(),
+                breaker.outflow_window,
+                max
+            )),
+            _ => None,
+        }
+    }
+}
+
+impl Accounting {
+    /// Returns why and when the circuit breaker tripped, if it has;
+    pub fn circuit_breaker_trip(&self) -> Option<&BreakerTrip> {
+        self.breaker.trip.as_ref()
+    }
+
+    /// Close the tripped circuit breaker, e.g. once the feed was checked, restarting its aggregates;
+    /// Quarantined transactions are kept, see `release_quarantined`.
+    pub fn reset_circuit_breaker(&mut self) {
+        self.breaker = BreakerState::default();
+    }
+
+    /// Returns `Error::CircuitOpen` if the circuit breaker tripped in `BreakerAction::Halt` mode;
+    pub(crate) fn check_circuit_breaker(&self) -> Result<(), Error> {
+        let halt = self
+            .config
+            .circuit_breaker
+            .as_ref()
+            .is_none_or(|breaker| breaker.action == BreakerAction::Halt);
+
+        match self.breaker.trip.as_ref() {
+            Some(trip) if halt => Err(Error::CircuitOpen(trip.reason.clone())),
+            _ => Ok(()),
+        }
+    }
+
+    /// Refuse a transaction received while the circuit breaker is open, keeping it in the
+    /// quarantine in `BreakerAction::Quarantine` mode;
+    pub(crate) fn divert(&mut self, tx: Transaction) {
+        let quarantine = self
+            .config
+            .circuit_breaker
+            .as_ref()
+            .is_some_and(|breaker| breaker.action == BreakerAction::Quarantine);
+
+        self.ignore(&tx, TransactionError::CircuitOpen, || {
+            "circuit breaker is open".to_string()
+        });
+
+        if quarantine {
+            self.quarantined.push(tx);
+        }
+    }
+
+    /// Update the circuit breaker aggregates with a processed transaction, tripping the breaker
+    /// and recording a `circuit_breaker_tripped` audit event on an anomaly;
+    pub(crate) fn observe_circuit_breaker(
+        &mut self,
+        client: ClientId,
+        tx: TxId,
+        at: Timestamp,
+        withdrawn: Option<Amount>,
+    ) {
+        let breaker = match self.config.circuit_breaker.as_ref() {
+            Some(breaker) if self.breaker.trip.is_none() => breaker,
+            _ => return,
+        };
+
+        let rejected = self.rejection.is_some();
+        if let Some(reason) = self.breaker.observe(breaker, at, rejected, withdrawn) {
+            error!("Circuit breaker tripped by tx {}: {}", tx, reason);
+            self.audit(
+                Some(client),
+                Some(tx),
+                "circuit_breaker_tripped",
+                reason.clone(),
+            );
+            self.breaker.trip = Some(BreakerTrip { tx, at, reason });
+        }
+    }
+}
@@ -0,0 +1,160 @@
+#[cfg(feature = "io")]
+use std::path::PathBuf;
+use std::str::FromStr;
+
+#[cfg(feature = "io")]
+use csv::Reader;
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+/// Spending limit for a client's withdrawals in a category, per period.
+pub struct Budget {
+    #[serde(rename = "client")]
+    pub client: ClientId,
+    #[serde(rename = "category")]
+    pub category: String,
+    /// Maximum total withdrawn in the category per period
+    #[serde(rename = "limit")]
+    pub limit: Amount,
+    /// Period length in seconds; Periods are aligned to the unix epoch, e.g. `86400` for calendar days (UTC)
+    #[serde(rename = "period")]
+    pub period: Timestamp,
+}
+
+/// Action taken when a withdrawal would exceed its category budget;
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum BudgetPolicy {
+    /// Ignore the withdrawal;
+    #[default]
+    Reject,
+    /// Apply the withdrawal and flag the account for review;
+    Flag,
+}
+
+impl FromStr for BudgetPolicy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "reject" => Ok(BudgetPolicy::Reject),
+            "flag" => Ok(BudgetPolicy::Flag),
+            _ => Err(Error::InvalidArgument(format!(
+                "unknown budget policy '{}'",
+                s
+            ))),
+        }
+    }
+}
+
+impl Accounting {
+    /// Set the budget of a client's category, replacing any existing budget;
+    pub fn set_budget(&mut self, budget: Budget) {
+        self.budgets
+            .insert((budget.client, budget.category.clone()), budget);
+    }
+
+    /// Load budgets from a csv file with `client,category,limit,period` columns;
+    #[cfg(feature = "io")]
+    pub fn read_budgets_csv_file(&mut self, file_path: PathBuf) -> Result<(), Error> {
+        let mut rdr = Reader::from_path(file_path)?;
+
+        for result in rdr.deserialize() {
+            let budget: Budget = result?;
+            if budget.period == 0 {
+                return Err(Error::InvalidArgument(format!(
+                    "budget period for client {} category '{}' must be positive",
+                    budget.client, budget.category
+                )));
+            }
+            self.set_budget(budget);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the amount withdrawn in the client's category during the budget period containing `at`;
+    pub fn budget_spent(&self, client: ClientId, category: &str, at: Timestamp) -> Amount {
+        let budget = match self.budgets.get(&(client, category.to_string())) {
+            Some(budget) => budget,
+            None => return Amount::ZERO,
+        };
+
+        match self.budget_spending.get(&(client, category.to_string())) {
+            Some((period, spent)) if *period == at / budget.period => *spent,
+            _ => Amount::ZERO,
+        }
+    }
+
+    /// Apply the category budget to a withdrawal about to be applied, recording the decision in the
+    /// audit log; Returns false if the withdrawal must be ignored. The spending is only recorded by
+    /// `record_budget_spending` once the withdrawal is applied.
+    pub(crate) fn check_budget(
+        &mut self,
+        client: ClientId,
+        tx: &Transaction,
+        amount: Amount,
+    ) -> bool {
+        let category = match tx.category.as_ref() {
+            Some(category) => category,
+            None => return true,
+        };
+        let key = (client, category.clone());
+        let (limit, period_len) = match self.budgets.get(&key) {
+            Some(budget) => (budget.limit, budget.period),
+            None => return true,
+        };
+
+        let period = tx.timestamp.unwrap_or_else(|| self.now()) / period_len;
+        let spent = match self.budget_spending.get(&key) {
+            Some((current, spent)) if *current == period => *spent,
+            _ => Amount::ZERO,
+        };
+
+        if spent + amount > limit + self.config.amount_epsilon {
+            let detail = format!(
+                "withdrawal of {} in '{}' exceeds budget: {} of {} spent",
+                amount, category, spent, limit
+            );
+
+            match self.config.budget_policy {
+                BudgetPolicy::Reject => {
+                    self.audit(Some(client), Some(tx.tx), "budget_rejected", detail);
+                    return false;
+                }
+                BudgetPolicy::Flag => {
+                    self.audit(Some(client), Some(tx.tx), "budget_flagged", detail);
+                    self.flagged_accounts.insert(client);
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Record an applied withdrawal against its category budget for the current period;
+    pub(crate) fn record_budget_spending(
+        &mut self,
+        client: ClientId,
+        tx: &Transaction,
+        amount: Amount,
+    ) {
+        let key = match tx.category.as_ref() {
+            Some(category) => (client, category.clone()),
+            None => return,
+        };
+        let period_len = match self.budgets.get(&key) {
+            Some(budget) => budget.period,
+            None => return,
+        };
+
+        let period = tx.timestamp.unwrap_or_else(|| self.now()) / period_len;
+        let spent = match self.budget_spending.get(&key) {
+            Some((current, spent)) if *current == period => *spent,
+            _ => Amount::ZERO,
+        };
+
+        self.budget_spending.insert(key, (period, spent + amount));
+    }
+}
@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+/// A deposit credited to held funds until its clearing delay has elapsed.
+pub struct PendingDeposit {
+    #[serde(rename = "tx")]
+    pub tx: TxId,
+    #[serde(rename = "client")]
+    pub client: ClientId,
+    #[serde(rename = "subaccount")]
+    pub subaccount: Option<SubAccountId>,
+    #[serde(rename = "amount")]
+    pub amount: Amount,
+    /// Time from which the deposit is available
+    #[serde(rename = "clears_at")]
+    pub clears_at: Timestamp,
+}
+
+impl Accounting {
+    /// Returns the deposits waiting to clear, ordered by clearing time;
+    pub fn pending_deposits(&self) -> impl Iterator<Item = &PendingDeposit> {
+        self.pending_deposits.values()
+    }
+
+    /// Record a deposit credited to held funds, clearing after `AccountingConfig::clearing_delay`;
+    pub(crate) fn hold_pending_deposit(
+        &mut self,
+        tx: &Transaction,
+        client: ClientId,
+        delay: Timestamp,
+    ) {
+        let amount = tx.amount.unwrap_or_default();
+        let clears_at = tx.timestamp.unwrap_or_else(|| self.now()) + delay;
+
+        self.pending_deposits.insert(
+            (clears_at, tx.tx),
+            PendingDeposit {
+                tx: tx.tx,
+                client,
+                subaccount: tx.subaccount.clone(),
+                amount,
+                clears_at,
+            },
+        );
+    }
+
+    /// Move every pending deposit whose clearing time is at or before `now` from held to available;
+    /// Called with each transaction's timestamp (or the clock time) before it is processed, and may be
+    /// called directly, e.g. to settle deposits before writing the accounts. Returns the number cleared.
+    pub fn settle_pending_deposits(&mut self, now: Timestamp) -> usize {
+        let mut cleared = 0;
+
+        while let Some((&(clears_at, tx), _)) = self.pending_deposits.iter().next() {
+            if clears_at > now {
+                break;
+            }
+
+            let deposit = match self.pending_deposits.remove(&(clears_at, tx)) {
+                Some(deposit) => deposit,
+                None => break,
+            };
+
+            if let Some(name) = deposit.subaccount.as_ref() {
+                if let Some(subaccount) = self
+                    .subaccounts
+                    .get_mut(&deposit.client)
+                    .and_then(|subaccounts| subaccounts.get_mut(name))
+                {
+                    subaccount.held -= deposit.amount;
+                    subaccount.available += deposit.amount;
+                }
+            }
+
+            if let Some(mut account) = self.accounts.remove(&deposit.client) {
+                account.held -= deposit.amount;
+                account.available += deposit.amount;
+
+                self.emit_change(Some(deposit.tx), &account);
+                self.accounts.insert(deposit.client, account);
+            }
+
+            cleared += 1;
+        }
+
+        cleared
+    }
+}
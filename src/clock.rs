@@ -0,0 +1,57 @@
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::Timestamp;
+
+/// Source of the current time for time-based features;
+/// Replace the default system clock with a `MockClock` to make time-dependent behavior deterministic.
+pub trait Clock: Debug + Send + Sync {
+    /// Returns the current unix timestamp in seconds;
+    fn now(&self) -> Timestamp;
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+/// Clock reading the system time; This is the default clock used by `Accounting`.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Timestamp {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+/// Manually controlled clock for tests; Clones share the same time, so a test can keep a clone
+/// and advance the time after handing the clock to `Accounting`.
+pub struct MockClock {
+    now: Arc<AtomicU64>,
+}
+
+impl MockClock {
+    pub fn new(now: Timestamp) -> Self {
+        MockClock {
+            now: Arc::new(AtomicU64::new(now)),
+        }
+    }
+
+    /// Set the current time;
+    pub fn set(&self, now: Timestamp) {
+        self.now.store(now, Ordering::SeqCst);
+    }
+
+    /// Advance the current time by the given number of seconds;
+    pub fn advance(&self, seconds: Timestamp) {
+        self.now.fetch_add(seconds, Ordering::SeqCst);
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Timestamp {
+        self.now.load(Ordering::SeqCst)
+    }
+}
@@ -0,0 +1,219 @@
+use std::fmt;
+#[cfg(feature = "io")]
+use std::path::PathBuf;
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/// What happens to the residual available funds of a closed account;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClosureDisposition {
+    /// Credit the residual funds to the available funds of another account;
+    Transfer(ClientId),
+    /// Debit the residual funds as an adjustment;
+    WriteOff,
+}
+
+impl fmt::Display for ClosureDisposition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ClosureDisposition::Transfer(client) => write!(f, "transfer to {}", client),
+            ClosureDisposition::WriteOff => f.write_str("write-off"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+/// Record of a closed account and how its residual funds were disposed of.
+pub struct AccountClosure {
+    #[serde(rename = "client")]
+    pub client: ClientId,
+    /// Residual available funds at closure
+    #[serde(rename = "residual")]
+    pub residual: Amount,
+    /// Account credited with the residual funds; Empty if they were written off
+    #[serde(rename = "transferred_to")]
+    pub transferred_to: Option<ClientId>,
+    /// Clock time at which the account was closed
+    #[serde(rename = "closed_at")]
+    pub closed_at: Timestamp,
+}
+
+impl Accounting {
+    /// Close an account, disposing of its residual available funds; Transactions of closed accounts are
+    /// ignored and closed accounts are flagged in the extended output schema.
+    /// Closure is blocked while the account is locked, or has open disputes, holds or reservations,
+    /// or any held funds. The residual funds are moved with `adjustment` transactions; The closure
+    /// is journaled in place of the adjustments, which replaying the closure recreates.
+    pub fn close_account(
+        &mut self,
+        client: ClientId,
+        disposition: ClosureDisposition,
+    ) -> Result<AccountClosure, Error> {
+        #[cfg(feature = "io")]
+        let journal = self.journal.take();
+        let closed = self.close(client, disposition);
+
+        #[cfg(feature = "io")]
+        {
+            self.journal = journal;
+            if let Ok(closure) = &closed {
+                self.journal_action(JournalAction {
+                    target: closure.transferred_to,
+                    ..JournalAction::new(JournalActionKind::Close, client)
+                });
+            }
+        }
+
+        closed
+    }
+
+    fn close(
+        &mut self,
+        client: ClientId,
+        disposition: ClosureDisposition,
+    ) -> Result<AccountClosure, Error> {
+        let account = self
+            .get_account(client)
+            .cloned()
+            .ok_or_else(|| Error::InvalidArgument(format!("unknown client {}", client)))?;
+
+        if self.closures.contains_key(&client) {
+            return Err(Error::InvalidArgument(format!(
+                "client {}: account already closed",
+                client
+            )));
+        }
+
+        if account.locked {
+            return Err(Error::InvalidArgument(format!(
+                "client {}: account is locked",
+                client
+            )));
+        }
+
+        if self.open_disputes(client) > 0 {
+            return Err(Error::InvalidArgument(format!(
+                "client {}: account has open disputes",
+                client
+            )));
+        }
+
+        if self.client_holds(client).next().is_some()
+            || self
+                .reservations()
+                .any(|reservation| reservation.client == client)
+            || account.held.abs() > self.config.amount_epsilon
+        {
+            return Err(Error::InvalidArgument(format!(
+                "client {}: account has held funds",
+                client
+            )));
+        }
+
+        let residual = account.available;
+        let transferred_to = match disposition {
+            ClosureDisposition::Transfer(target) => {
+                if target == client
+                    || self.closures.contains_key(&target)
+                    || self
+                        .get_account(target)
+                        .is_some_and(|account| account.locked)
+                {
+                    return Err(Error::InvalidArgument(format!(
+                        "cannot transfer the funds of client {} to client {}",
+                        client, target
+                    )));
+                }
+
+                if residual < Amount::ZERO {
+                    return Err(Error::InvalidArgument(format!(
+                        "client {}: cannot transfer negative funds {}",
+                        client, residual
+                    )));
+                }
+
+                if residual != Amount::ZERO {
+                    self.adjust(
+                        client,
+                        residual,
+                        &format!("account closure transfer to client {}", target),
+                    )?;
+                    self.adjust(
+                        target,
+                        -residual,
+                        &format!("account closure transfer from client {}", client),
+                    )?;
+                }
+
+                Some(target)
+            }
+            ClosureDisposition::WriteOff => {
+                if residual != Amount::ZERO {
+                    self.adjust(client, residual, "account closure write-off")?;
+                }
+
+                None
+            }
+        };
+
+        // Clear any dust left below the amount epsilon;
+        let mut account = self
+            .get_account(client)
+            .cloned()
+            .unwrap_or_else(|| Account::new(client));
+        account.available = Amount::ZERO;
+        account.total = Amount::ZERO;
+        account.held = Amount::ZERO;
+
+        let closure = AccountClosure {
+            client,
+            residual,
+            transferred_to,
+            closed_at: self.now(),
+        };
+
+        if residual < Amount::ZERO {
+            warn!(
+                "Closed client {} with negative residual {}, {}",
+                client, residual, disposition
+            );
+        } else {
+            info!(
+                "Closed client {} with residual {}, {}",
+                client, residual, disposition
+            );
+        }
+
+        // Record the closure first, so the change stream event carries it;
+        self.closures.insert(client, closure.clone());
+        self.emit_change(None, &account);
+        self.insert_account(account);
+
+        Ok(closure)
+    }
+
+    /// Returns true if the client's account was closed;
+    pub fn is_closed(&self, client: ClientId) -> bool {
+        self.closures.contains_key(&client)
+    }
+
+    /// Iterate over the closed accounts, ordered by client;
+    pub fn closures(&self) -> impl Iterator<Item = &AccountClosure> {
+        self.closures.values()
+    }
+
+    /// Write the closed accounts to a csv file;
+    #[cfg(feature = "io")]
+    pub fn write_closures_csv_file(&self, file_path: PathBuf) -> Result<(), Error> {
+        let mut sink = CsvFileSink::create(file_path, self.config.output_durability)?;
+
+        for closure in self.closures() {
+            sink.write(closure)?;
+        }
+
+        sink.close()
+    }
+}
@@ -0,0 +1,137 @@
+use std::sync::Arc;
+
+use arrow_array::{ArrayRef, BooleanArray, Decimal128Array, RecordBatch, StringArray, UInt64Array};
+use arrow_schema::{DataType, Field, Schema, DECIMAL128_MAX_PRECISION};
+
+use crate::*;
+
+/// Arrow array and data type of the client column, matching the `ClientId` width;
+#[cfg(not(any(feature = "client-id-u32", feature = "client-id-u64")))]
+type ClientArray = arrow_array::UInt16Array;
+#[cfg(not(any(feature = "client-id-u32", feature = "client-id-u64")))]
+const CLIENT_DATA_TYPE: DataType = DataType::UInt16;
+#[cfg(all(feature = "client-id-u32", not(feature = "client-id-u64")))]
+type ClientArray = arrow_array::UInt32Array;
+#[cfg(all(feature = "client-id-u32", not(feature = "client-id-u64")))]
+const CLIENT_DATA_TYPE: DataType = DataType::UInt32;
+#[cfg(feature = "client-id-u64")]
+type ClientArray = UInt64Array;
+#[cfg(feature = "client-id-u64")]
+const CLIENT_DATA_TYPE: DataType = DataType::UInt64;
+
+/// Arrow array and data type of the tx column, matching the `TxId` width;
+#[cfg(not(feature = "tx-id-u64"))]
+type TxIdArray = arrow_array::UInt32Array;
+#[cfg(not(feature = "tx-id-u64"))]
+const TX_ID_DATA_TYPE: DataType = DataType::UInt32;
+#[cfg(feature = "tx-id-u64")]
+type TxIdArray = UInt64Array;
+#[cfg(feature = "tx-id-u64")]
+const TX_ID_DATA_TYPE: DataType = DataType::UInt64;
+
+/// Arrow data type of amount columns: exact decimals with four decimal places;
+const AMOUNT_DATA_TYPE: DataType =
+    DataType::Decimal128(DECIMAL128_MAX_PRECISION, AMOUNT_DECIMAL_PLACES as i8);
+
+/// Returns a decimal array of the amounts, with the amount data type;
+fn amount_array(amounts: impl Iterator<Item = Option<Amount>>) -> Result<ArrayRef, Error> {
+    let array = amounts
+        .map(|amount| amount.map(amount_units))
+        .collect::<Decimal128Array>()
+        .with_precision_and_scale(DECIMAL128_MAX_PRECISION, AMOUNT_DECIMAL_PLACES as i8)?;
+
+    Ok(Arc::new(array))
+}
+
+impl Accounting {
+    /// Returns the accounts as an Arrow record batch, ordered by client id;
+    /// Columns: client, available, held, total, locked. Balances are exact `Decimal128` values with
+    /// four decimal places, rounding half-to-even beyond them. Requires the `arrow` feature.
+    pub fn to_record_batch(&self) -> Result<RecordBatch, Error> {
+        let mut accounts: Vec<&Account> = self.accounts().collect();
+        accounts.sort_by_key(|account| account.client);
+
+        let schema = Schema::new(vec![
+            Field::new("client", CLIENT_DATA_TYPE, false),
+            Field::new("available", AMOUNT_DATA_TYPE, false),
+            Field::new("held", AMOUNT_DATA_TYPE, false),
+            Field::new("total", AMOUNT_DATA_TYPE, false),
+            Field::new("locked", DataType::Boolean, false),
+        ]);
+
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(ClientArray::from_iter_values(
+                accounts.iter().map(|a| a.client),
+            )),
+            amount_array(accounts.iter().map(|a| Some(a.available)))?,
+            amount_array(accounts.iter().map(|a| Some(a.held)))?,
+            amount_array(accounts.iter().map(|a| Some(a.total)))?,
+            Arc::new(
+                accounts
+                    .iter()
+                    .map(|a| Some(a.locked))
+                    .collect::<BooleanArray>(),
+            ),
+        ];
+
+        Ok(RecordBatch::try_new(Arc::new(schema), columns)?)
+    }
+
+    /// Returns the recorded deposits and withdrawals as an Arrow record batch, ordered by tx id;
+    /// Columns: type, client, tx, amount, timestamp, category; amount, timestamp and category are
+    /// nullable, and amount is a `Decimal128` like the balances. Requires the `arrow` feature.
+    pub fn transactions_to_record_batch(&self) -> Result<RecordBatch, Error> {
+        self.transactions_record_batch(None)
+    }
+
+    /// Returns the recorded deposits and withdrawals as an Arrow record batch like
+    /// `transactions_to_record_batch`, with tx ids replaced by their dense ids; Requires the `arrow`
+    /// feature.
+    pub fn dense_transactions_to_record_batch(
+        &self,
+        dense: &DenseTxIds,
+    ) -> Result<RecordBatch, Error> {
+        self.transactions_record_batch(Some(dense))
+    }
+
+    fn transactions_record_batch(&self, dense: Option<&DenseTxIds>) -> Result<RecordBatch, Error> {
+        let mut transactions: Vec<&Transaction> = self.transactions().collect();
+        transactions.sort_by_key(|tx| tx.tx);
+
+        let schema = Schema::new(vec![
+            Field::new("type", DataType::Utf8, false),
+            Field::new("client", CLIENT_DATA_TYPE, false),
+            Field::new("tx", TX_ID_DATA_TYPE, false),
+            Field::new("amount", AMOUNT_DATA_TYPE, true),
+            Field::new("timestamp", DataType::UInt64, true),
+            Field::new("category", DataType::Utf8, true),
+        ]);
+
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(StringArray::from_iter_values(
+                transactions.iter().map(|tx| tx.r#type.as_str()),
+            )),
+            Arc::new(ClientArray::from_iter_values(
+                transactions.iter().map(|tx| tx.client),
+            )),
+            Arc::new(TxIdArray::from_iter_values(transactions.iter().map(|tx| {
+                dense.and_then(|dense| dense.get(tx.tx)).unwrap_or(tx.tx)
+            }))),
+            amount_array(transactions.iter().map(|tx| tx.amount))?,
+            Arc::new(
+                transactions
+                    .iter()
+                    .map(|tx| tx.timestamp)
+                    .collect::<UInt64Array>(),
+            ),
+            Arc::new(
+                transactions
+                    .iter()
+                    .map(|tx| tx.category.as_deref())
+                    .collect::<StringArray>(),
+            ),
+        ];
+
+        Ok(RecordBatch::try_new(Arc::new(schema), columns)?)
+    }
+}
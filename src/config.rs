@@ -0,0 +1,302 @@
+use crate::{
+    Amount, BudgetPolicy, CircuitBreaker, DedupConfig, DisputeAmountPolicy, DisputeCapPolicy,
+    DisputeExpiry, DisputeFundsPolicy, Durability, Error, InputFormat, InterestConfig,
+    NotificationConfig, Partition, PrecisionPolicy, Timestamp, TraceFilter,
+    WithdrawalDisputePolicy,
+};
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// Default tolerance for balance comparisons; Amounts are exact decimals, so comparisons are exact.
+pub const DEFAULT_AMOUNT_EPSILON: Amount = Amount::ZERO;
+
+/// Configuration options for the `Accounting` processor.
+/// All optional behaviors are disabled by default, preserving the standard processing rules.
+/// Options that only affect reading input or writing output are not serialized, e.g. into journals.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AccountingConfig {
+    /// Record each client's end-of-day balances for timestamped transactions;
+    /// Required for `Accounting::write_daily_balances_csv`.
+    pub track_daily_balances: bool,
+    /// Record every ignored transaction with its reason;
+    /// Required for `Accounting::write_rejected_csv_file`.
+    pub track_rejected: bool,
+    /// Tag transactions read from files without a `source` value with the file name;
+    pub tag_source: bool,
+    /// How strictly input transactions are validated;
+    pub strictness: Strictness,
+    /// Stamp transactions without a timestamp with the current time of the `Accounting` clock;
+    pub stamp_missing_timestamps: bool,
+    /// Behavior for transaction amounts with more than four decimal places;
+    pub precision_policy: PrecisionPolicy,
+    /// Durability guarantees for csv reports written to files;
+    #[serde(skip)]
+    pub output_durability: Durability,
+    /// Log transactions whose processing takes at least this long, with full context;
+    pub slow_transaction_threshold: Option<Duration>,
+    /// Tolerance for sufficient funds checks in withdrawals, disputes, resolves, chargebacks and holds;
+    /// Balances left within the tolerance of zero are cleared to zero.
+    pub amount_epsilon: Amount,
+    /// Debug mode: validate the invariants of every account after every N transactions processed via
+    /// `Accounting::try_process_transaction`, halting on the first violation; Expensive for large ledgers.
+    pub invariant_check_interval: Option<usize>,
+    /// Report deposits and withdrawals resent with new transaction ids instead of applying them;
+    pub dedup: Option<DedupConfig>,
+    /// Maximum number of simultaneously open disputes per account;
+    pub max_open_disputes: Option<usize>,
+    /// Action taken when a dispute would exceed `max_open_disputes`;
+    pub dispute_cap_policy: DisputeCapPolicy,
+    /// Action taken on dispute, resolve and chargeback rows that carry an amount;
+    pub dispute_amount_policy: DisputeAmountPolicy,
+    /// Action taken when a disputed deposit exceeds the client's available funds;
+    pub dispute_funds_policy: DisputeFundsPolicy,
+    /// Handling of disputes referencing a withdrawal;
+    pub withdrawal_dispute_policy: WithdrawalDisputePolicy,
+    /// Automatically resolve or charge back disputes still open after a number of days, judged by
+    /// the timestamps of later transactions (or the clock time); Each `Accounting` carries its own
+    /// configuration, so tenants may use different expiry policies.
+    pub dispute_expiry: Option<DisputeExpiry>,
+    /// Hold the amount of dispute rows that carry one, if positive and at most the disputed amount,
+    /// instead of the full disputed amount; Resolves and chargebacks release the amount held.
+    pub partial_disputes: bool,
+    /// Credit deposits to held funds, moving them to available once this many seconds have passed since
+    /// the deposit, judged by the timestamps of later transactions (or the clock time);
+    pub clearing_delay: Option<Timestamp>,
+    /// Action taken when a withdrawal would exceed its category budget;
+    pub budget_policy: BudgetPolicy,
+    /// Record a decision trace for the selected transactions; See `Accounting::trace_steps`.
+    #[serde(skip)]
+    pub trace: Option<TraceFilter>,
+    /// Layout of input transactions csv files;
+    #[serde(skip)]
+    pub input: InputFormat,
+    /// Accrue daily interest on available balances, posted as generated deposits;
+    pub interest: Option<InterestConfig>,
+    /// Record customer notifications for locks, chargebacks and large withdrawals;
+    pub notifications: Option<NotificationConfig>,
+    /// Only process rows of clients within the partition, skipping other rows before they are parsed;
+    #[serde(skip)]
+    pub partition: Option<Partition>,
+    /// Abort reading a transactions file after this many rows, e.g. on shared batch infrastructure;
+    pub max_rows: Option<u64>,
+    /// Refuse to read transactions files larger than this many bytes;
+    pub max_file_size: Option<u64>,
+    /// Halt processing, or quarantine later transactions, once too many transactions are ignored
+    /// or withdrawals exceed a bound within a window, e.g. on a corrupted feed;
+    pub circuit_breaker: Option<CircuitBreaker>,
+    /// Quarantine the transactions of flagged clients instead of applying them, pending manual
+    /// review; See `Accounting::release_quarantined`.
+    pub quarantine_flagged: bool,
+}
+
+impl Default for AccountingConfig {
+    fn default() -> Self {
+        AccountingConfig {
+            track_daily_balances: false,
+            track_rejected: false,
+            tag_source: false,
+            strictness: Strictness::default(),
+            stamp_missing_timestamps: false,
+            precision_policy: PrecisionPolicy::default(),
+            output_durability: Durability::default(),
+            slow_transaction_threshold: None,
+            amount_epsilon: DEFAULT_AMOUNT_EPSILON,
+            invariant_check_interval: None,
+            dedup: None,
+            max_open_disputes: None,
+            dispute_cap_policy: DisputeCapPolicy::default(),
+            dispute_amount_policy: DisputeAmountPolicy::default(),
+            dispute_funds_policy: DisputeFundsPolicy::default(),
+            withdrawal_dispute_policy: WithdrawalDisputePolicy::default(),
+            dispute_expiry: None,
+            partial_disputes: false,
+            clearing_delay: None,
+            budget_policy: BudgetPolicy::default(),
+            trace: None,
+            input: InputFormat::default(),
+            interest: None,
+            notifications: None,
+            partition: None,
+            max_rows: None,
+            max_file_size: None,
+            circuit_breaker: None,
+            quarantine_flagged: false,
+        }
+    }
+}
+
+impl AccountingConfig {
+    /// Check the configuration for nonsensical limits and conflicting options, e.g. a dispute cap of
+    /// zero or a comment character equal to the delimiter; Returns `Error::InvalidConfig` naming the
+    /// first offending field.
+    pub fn validate(&self) -> Result<(), Error> {
+        let invalid = |field, reason: &str| {
+            Err(Error::InvalidConfig {
+                field,
+                reason: reason.to_string(),
+            })
+        };
+
+        if self.amount_epsilon < Amount::ZERO {
+            return invalid("amount_epsilon", "must not be negative");
+        }
+
+        if self.invariant_check_interval == Some(0) {
+            return invalid("invariant_check_interval", "must be positive");
+        }
+
+        if self.max_rows == Some(0) {
+            return invalid("max_rows", "must be positive");
+        }
+
+        if self.max_file_size == Some(0) {
+            return invalid("max_file_size", "must be positive");
+        }
+
+        if self.max_open_disputes == Some(0) {
+            return invalid(
+                "max_open_disputes",
+                "must be positive; a cap of zero rejects every dispute",
+            );
+        }
+
+        if self.dispute_cap_policy == DisputeCapPolicy::Flag && self.max_open_disputes.is_none() {
+            return invalid(
+                "dispute_cap_policy",
+                "the flag policy has no effect without max_open_disputes",
+            );
+        }
+
+        if self.slow_transaction_threshold == Some(Duration::ZERO) {
+            return invalid(
+                "slow_transaction_threshold",
+                "must be positive; zero logs every transaction",
+            );
+        }
+
+        if let Some(interest) = self.interest.as_ref() {
+            if interest.daily_rate < Amount::ZERO {
+                return invalid("interest.daily_rate", "must not be negative");
+            }
+
+            if interest.compound_days == 0 {
+                return invalid("interest.compound_days", "must be positive");
+            }
+        }
+
+        if self
+            .dispute_expiry
+            .as_ref()
+            .is_some_and(|expiry| expiry.days == 0)
+        {
+            return invalid("dispute_expiry.days", "must be positive");
+        }
+
+        if let Some(threshold) = self
+            .notifications
+            .as_ref()
+            .and_then(|notifications| notifications.large_withdrawal)
+        {
+            if threshold <= Amount::ZERO {
+                return invalid("notifications.large_withdrawal", "must be positive");
+            }
+        }
+
+        if let Some(breaker) = self.circuit_breaker.as_ref() {
+            if breaker
+                .max_rejection_rate
+                .is_some_and(|rate| !(0.0..1.0).contains(&rate))
+            {
+                return invalid(
+                    "circuit_breaker.max_rejection_rate",
+                    "must be at least 0 and below 1",
+                );
+            }
+
+            if breaker.rejection_window == 0 {
+                return invalid("circuit_breaker.rejection_window", "must be positive");
+            }
+
+            if breaker.max_outflow.is_some_and(|max| max <= Amount::ZERO) {
+                return invalid("circuit_breaker.max_outflow", "must be positive");
+            }
+
+            if breaker.outflow_window == 0 {
+                return invalid("circuit_breaker.outflow_window", "must be positive");
+            }
+        }
+
+        if self.input.comment == Some(self.input.delimiter) {
+            return invalid("input.comment", "must differ from the delimiter");
+        }
+
+        Ok(())
+    }
+
+    /// Returns the names of the fields that differ in the other configuration, e.g. to record a
+    /// config reload;
+    pub fn changed_fields(&self, other: &AccountingConfig) -> Vec<&'static str> {
+        // Not every field type implements `PartialEq`, so fields are compared by their debug output;
+        macro_rules! changed {
+            ($($field:ident),*) => {
+                [$((
+                    stringify!($field),
+                    format!("{:?}", self.$field) != format!("{:?}", other.$field),
+                )),*]
+            };
+        }
+
+        changed!(
+            track_daily_balances,
+            track_rejected,
+            tag_source,
+            strictness,
+            stamp_missing_timestamps,
+            precision_policy,
+            output_durability,
+            slow_transaction_threshold,
+            amount_epsilon,
+            invariant_check_interval,
+            dedup,
+            max_open_disputes,
+            dispute_cap_policy,
+            dispute_amount_policy,
+            dispute_funds_policy,
+            withdrawal_dispute_policy,
+            dispute_expiry,
+            partial_disputes,
+            clearing_delay,
+            budget_policy,
+            trace,
+            input,
+            interest,
+            notifications,
+            partition,
+            max_rows,
+            max_file_size,
+            circuit_breaker,
+            quarantine_flagged
+        )
+        .iter()
+        .filter(|(_, changed)| *changed)
+        .map(|(field, _)| *field)
+        .collect()
+    }
+}
+
+/// Validation level applied to transactions read from csv or processed via `Accounting::try_process_transaction`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Strictness {
+    /// Skip rows that fail to parse and silently ignore invalid transactions;
+    Lenient,
+    /// Fail on rows that fail to parse, apart from rows of unknown transaction types, which are
+    /// counted and skipped, and silently ignore invalid transactions;
+    #[default]
+    Standard,
+    /// Fail on any parse error, unknown transaction type, duplicate transaction id,
+    /// deposit, withdrawal or reserve without a positive amount, or dispute referencing another client's
+    /// transaction;
+    Strict,
+}
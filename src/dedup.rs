@@ -0,0 +1,198 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+#[cfg(feature = "io")]
+use std::path::PathBuf;
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+/// Content-hash deduplication of deposits and withdrawals resent with new transaction ids;
+/// Transactions with the same client, type and amount within `window` seconds of each other are
+/// reported as duplicates rather than applied. Transactions without a timestamp use the clock time.
+pub struct DedupConfig {
+    /// Deduplication window in seconds; `0` disables deduplication;
+    pub window: Timestamp,
+    /// Per-client windows overriding `window`, e.g. for producers known to resend rows;
+    pub client_windows: HashMap<ClientId, Timestamp>,
+}
+
+impl DedupConfig {
+    /// Returns the deduplication window of the client;
+    pub fn window_for(&self, client: ClientId) -> Timestamp {
+        self.client_windows
+            .get(&client)
+            .copied()
+            .unwrap_or(self.window)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+/// A transaction matching the content of an earlier transaction, reported instead of applied.
+pub struct ContentDuplicate {
+    #[serde(rename = "type")]
+    pub r#type: TxType,
+    #[serde(rename = "client")]
+    pub client: ClientId,
+    #[serde(rename = "tx")]
+    pub tx: TxId,
+    #[serde(rename = "amount")]
+    pub amount: Option<Amount>,
+    #[serde(rename = "timestamp")]
+    pub timestamp: Timestamp,
+    /// Id of the earlier transaction with the same content
+    #[serde(rename = "original_tx")]
+    pub original_tx: TxId,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+/// A deposit or withdrawal reusing the id of a recorded transaction, rejected instead of replacing it.
+pub struct DuplicateTxId {
+    #[serde(rename = "type")]
+    pub r#type: TxType,
+    #[serde(rename = "client")]
+    pub client: ClientId,
+    #[serde(rename = "tx")]
+    pub tx: TxId,
+    #[serde(rename = "amount")]
+    pub amount: Option<Amount>,
+    /// Type of the recorded transaction with the same id
+    #[serde(rename = "original_type")]
+    pub original_type: TxType,
+    /// Client of the recorded transaction with the same id
+    #[serde(rename = "original_client")]
+    pub original_client: ClientId,
+}
+
+/// Returns the content hash of a transaction: client, type and amount;
+pub(crate) fn content_hash(tx: &Transaction) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    tx.client.hash(&mut hasher);
+    tx.r#type.hash(&mut hasher);
+    tx.amount.map(|amount| amount.normalize()).hash(&mut hasher);
+    hasher.finish()
+}
+
+impl Accounting {
+    /// Returns the deduplication window of a deposit or withdrawal, if it is deduplicated;
+    fn dedup_window(&self, tx: &Transaction) -> Option<Timestamp> {
+        match self.config.dedup.as_ref() {
+            Some(dedup) if matches!(tx.r#type, TxType::Deposit | TxType::Withdrawal) => {
+                Some(dedup.window_for(tx.client)).filter(|window| *window > 0)
+            }
+            _ => None,
+        }
+    }
+
+    /// Check a deposit or withdrawal against the content of recently applied transactions; Returns
+    /// true, recording the duplicate, if it matches one within the client's deduplication window.
+    pub(crate) fn dedup_content(&mut self, tx: &Transaction) -> bool {
+        let window = match self.dedup_window(tx) {
+            Some(window) => window,
+            None => return false,
+        };
+
+        let timestamp = tx.timestamp.unwrap_or_else(|| self.now());
+        let hash = content_hash(tx);
+
+        if let Some((seen_at, original_tx)) = self.content_hashes.get(&hash).copied() {
+            if original_tx != tx.tx && timestamp.abs_diff(seen_at) <= window {
+                warn!(
+                    "Ignored {:?} tx {} for client {}: duplicate of tx {}",
+                    tx.r#type, tx.tx, tx.client, original_tx
+                );
+
+                self.content_duplicates.push(ContentDuplicate {
+                    r#type: tx.r#type,
+                    client: tx.client,
+                    tx: tx.tx,
+                    amount: tx.amount,
+                    timestamp,
+                    original_tx,
+                });
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Register the content hash of an applied deposit or withdrawal, computed before any
+    /// precision adjustment; Ignored transactions are not registered, so they can be resent.
+    pub(crate) fn register_content(&mut self, tx: &Transaction, hash: u64) {
+        if self.dedup_window(tx).is_some() {
+            let timestamp = tx.timestamp.unwrap_or_else(|| self.now());
+            self.content_hashes.insert(hash, (timestamp, tx.tx));
+        }
+    }
+
+    /// Check a deposit or withdrawal for a transaction id already recorded or reserved; Returns true,
+    /// recording the duplicate, if it must be rejected so the earlier record is kept for disputes.
+    pub(crate) fn check_duplicate_tx_id(&mut self, tx: &Transaction) -> bool {
+        let (original_type, original_client) = match self.transactions.get(&tx.tx) {
+            Some(recorded) => (recorded.transaction.r#type, recorded.transaction.client),
+            None => match self.reservations.get(&tx.tx) {
+                Some(reservation) => (TxType::Reserve, reservation.client),
+                None => return false,
+            },
+        };
+
+        warn!(
+            "Ignored {:?} tx {} for client {}: duplicate transaction id",
+            tx.r#type, tx.tx, tx.client
+        );
+        self.duplicate_tx_ids.push(DuplicateTxId {
+            r#type: tx.r#type,
+            client: tx.client,
+            tx: tx.tx,
+            amount: tx.amount,
+            original_type,
+            original_client,
+        });
+        self.ignore(tx, TransactionError::DuplicateTx, || {
+            format!(
+                "duplicate of {} tx {} for client {}",
+                original_type, tx.tx, original_client
+            )
+        });
+        true
+    }
+
+    /// Returns the deposits and withdrawals rejected for reusing a transaction id, in the order
+    /// processed;
+    pub fn duplicate_tx_ids(&self) -> &[DuplicateTxId] {
+        &self.duplicate_tx_ids
+    }
+
+    /// Write the duplicate transaction ids report to a csv file;
+    #[cfg(feature = "io")]
+    pub fn write_duplicate_tx_ids_csv_file(&self, file_path: PathBuf) -> Result<(), Error> {
+        let mut sink = CsvFileSink::create(file_path, self.config.output_durability)?;
+
+        for duplicate in self.duplicate_tx_ids.iter() {
+            sink.write(duplicate)?;
+        }
+
+        sink.close()
+    }
+
+    /// Returns the transactions reported as content duplicates, in the order processed;
+    pub fn content_duplicates(&self) -> &[ContentDuplicate] {
+        &self.content_duplicates
+    }
+
+    /// Write the content duplicates report to a csv file;
+    #[cfg(feature = "io")]
+    pub fn write_content_duplicates_csv_file(&self, file_path: PathBuf) -> Result<(), Error> {
+        let mut sink = CsvFileSink::create(file_path, self.config.output_durability)?;
+
+        for duplicate in self.content_duplicates.iter() {
+            sink.write(duplicate)?;
+        }
+
+        sink.close()
+    }
+}
@@ -0,0 +1,386 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::PathBuf;
+
+use csv::StringRecord;
+
+use crate::*;
+
+/// Default number of rows sampled by `diagnose_csv_file`;
+pub const DEFAULT_DIAGNOSE_SAMPLE: usize = 1_000;
+
+/// Number of parse errors included in a `Diagnosis`;
+const DIAGNOSE_ERRORS: usize = 5;
+
+/// Delimiters tried when detecting the layout of a file;
+const DIAGNOSE_DELIMITERS: [u8; 4] = [b',', b';', b'\t', b'|'];
+
+/// Alternative header names recognized for each column;
+const COLUMN_SYNONYMS: [(&str, &[&str]); 6] = [
+    ("type", &["kind", "tx_type", "transaction_type", "txtype"]),
+    (
+        "client",
+        &["client_id", "clientid", "account", "account_id", "customer"],
+    ),
+    (
+        "tx",
+        &["tx_id", "txid", "transaction", "transaction_id", "id"],
+    ),
+    ("amount", &["value", "amt", "sum"]),
+    ("timestamp", &["time", "ts", "date", "created_at"]),
+    ("category", &["tag", "group"]),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+/// Value type inferred for a column from the sampled rows, from narrowest to widest.
+pub enum ColumnKind {
+    /// Every sampled value is empty;
+    Empty,
+    Integer,
+    Decimal,
+    Text,
+}
+
+impl ColumnKind {
+    fn of(value: &str) -> ColumnKind {
+        let value = value.trim();
+        if value.is_empty() {
+            ColumnKind::Empty
+        } else if value.parse::<i64>().is_ok() {
+            ColumnKind::Integer
+        } else if value.parse::<f64>().is_ok() {
+            ColumnKind::Decimal
+        } else {
+            ColumnKind::Text
+        }
+    }
+}
+
+impl fmt::Display for ColumnKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ColumnKind::Empty => f.write_str("empty"),
+            ColumnKind::Integer => f.write_str("integer"),
+            ColumnKind::Decimal => f.write_str("decimal"),
+            ColumnKind::Text => f.write_str("text"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A column of a diagnosed file.
+pub struct ColumnDiagnosis {
+    /// The header as found in the file
+    pub header: String,
+    /// The transactions column the header was recognized as, if any
+    pub column: Option<&'static str>,
+    pub kind: ColumnKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Result of `diagnose_csv_file`: the detected layout of a transactions file, how the sampled rows
+/// parse with it, and the command line options required to process the file.
+pub struct Diagnosis {
+    pub file_path: PathBuf,
+    /// The detected character encoding
+    pub encoding: Encoding,
+    /// The input format detected for the file
+    pub format: InputFormat,
+    /// False if the first row looks like data rather than headers
+    pub has_headers: bool,
+    pub columns: Vec<ColumnDiagnosis>,
+    /// Required columns that could not be recognized
+    pub missing: Vec<&'static str>,
+    pub rows_sampled: usize,
+    /// Sampled rows failing to parse with the detected format
+    pub rows_failed: usize,
+    /// The first parse errors, with their line numbers
+    pub errors: Vec<String>,
+}
+
+impl Diagnosis {
+    /// Returns true if the file can be processed, possibly with the suggested options;
+    pub fn is_parseable(&self) -> bool {
+        self.has_headers && self.missing.is_empty()
+    }
+
+    /// Returns the command line options required to process the file;
+    pub fn suggested_options(&self) -> Vec<String> {
+        let mut options = Vec::new();
+
+        if let Some(encoding) = self.format.encoding {
+            options.push(format!("--encoding {}", encoding));
+        }
+
+        if self.format.delimiter != b',' {
+            options.push(format!(
+                "--delimiter {}",
+                format_delimiter(self.format.delimiter)
+            ));
+        }
+
+        if self.format.trim {
+            options.push("--trim".to_string());
+        }
+
+        if self.format.decimal_comma {
+            options.push("--decimal-comma".to_string());
+        }
+
+        for (column, header) in self.format.column_map.iter() {
+            options.push(format!("--map '{}={}'", column, header));
+        }
+
+        if self.rows_failed > 0 {
+            options.push("--lenient".to_string());
+        }
+
+        options
+    }
+}
+
+/// Format a delimiter as accepted by `--delimiter`;
+fn format_delimiter(delimiter: u8) -> String {
+    match delimiter {
+        b'\t' => "tab".to_string(),
+        delimiter => format!("'{}'", delimiter as char),
+    }
+}
+
+impl fmt::Display for Diagnosis {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "File: {}", self.file_path.display())?;
+        writeln!(f, "Encoding: {}", self.encoding)?;
+        writeln!(f, "Delimiter: {}", format_delimiter(self.format.delimiter))?;
+        writeln!(
+            f,
+            "Headers: {}",
+            if self.has_headers { "yes" } else { "no" }
+        )?;
+
+        writeln!(f, "Columns:")?;
+        for (i, column) in self.columns.iter().enumerate() {
+            writeln!(
+                f,
+                "  {:>2}  {:<20} -> {:<10} ({})",
+                i + 1,
+                format!("{:?}", column.header),
+                column.column.unwrap_or("unused"),
+                column.kind
+            )?;
+        }
+
+        if !self.missing.is_empty() {
+            writeln!(f, "Missing required columns: {}", self.missing.join(", "))?;
+        }
+
+        writeln!(
+            f,
+            "Sampled {} rows: {} parsed, {} failed",
+            self.rows_sampled,
+            self.rows_sampled - self.rows_failed,
+            self.rows_failed
+        )?;
+        for error in self.errors.iter() {
+            writeln!(f, "  {}", error)?;
+        }
+
+        if !self.has_headers {
+            writeln!(
+                f,
+                "The file has no header row; Add `{}` as the first line.",
+                TRANSACTION_COLUMNS[..4].join(",")
+            )?;
+        } else if !self.missing.is_empty() {
+            writeln!(
+                f,
+                "The file cannot be processed without the missing columns."
+            )?;
+        } else {
+            let mut command = vec!["tx-sim".to_string()];
+            command.extend(self.suggested_options());
+            command.push(self.file_path.display().to_string());
+            writeln!(f, "Suggested command:\n  {}", command.join(" "))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns true for an amount with a decimal comma, e.g. `1,5` or `1.234,56`;
+fn is_decimal_comma(amount: &str) -> bool {
+    amount.contains(',') && decimal_comma_amount(amount).parse::<Amount>().is_ok()
+}
+
+/// Recognize a header as a transactions column, by name or synonym, ignoring case and whitespace;
+fn recognize_column(header: &str) -> Option<&'static str> {
+    let header = header.trim().to_lowercase();
+
+    COLUMN_SYNONYMS
+        .iter()
+        .find(|(column, synonyms)| *column == header || synonyms.contains(&header.as_str()))
+        .map(|(column, _)| *column)
+}
+
+/// Inspect the headers and up to `sample` rows of a transactions csv file, detecting the delimiter,
+/// columns and value types, and parse the sampled rows with the detected format;
+pub fn diagnose_csv_file(file_path: PathBuf, sample: usize) -> Result<Diagnosis, Error> {
+    let read_lines = |encoding| -> io::Result<Vec<String>> {
+        BufReader::new(DecodedReader::new(File::open(&file_path)?, encoding))
+            .lines()
+            .take(sample + 1)
+            .collect()
+    };
+
+    // Fall back to Latin-1 for files that are not valid UTF-8;
+    let mut encoding = DecodedReader::new(File::open(&file_path)?, None).encoding()?;
+    let lines = match read_lines(None) {
+        Err(e) if e.kind() == io::ErrorKind::InvalidData && encoding == Encoding::Utf8 => {
+            encoding = Encoding::Latin1;
+            read_lines(Some(encoding))?
+        }
+        lines => lines?,
+    };
+
+    // Pick the delimiter splitting the most lines into the same number of fields, preferring more fields;
+    let delimiter = DIAGNOSE_DELIMITERS
+        .iter()
+        .map(|delimiter| {
+            let counts: Vec<usize> = lines
+                .iter()
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| line.split(*delimiter as char).count())
+                .collect();
+            let first = counts.first().copied().unwrap_or(1);
+            let consistent = counts.iter().filter(|count| **count == first).count();
+            (*delimiter, first, consistent)
+        })
+        .filter(|(_, fields, _)| *fields > 1)
+        .max_by_key(|(delimiter, fields, consistent)| (*consistent, *fields, *delimiter == b','))
+        .map_or(b',', |(delimiter, _, _)| delimiter);
+
+    let headers: Vec<String> = lines
+        .first()
+        .map(|line| line.split(delimiter as char).map(str::to_string).collect())
+        .unwrap_or_default();
+    let recognized: Vec<Option<&'static str>> = headers
+        .iter()
+        .map(|header| recognize_column(header))
+        .collect();
+
+    // A first row of transaction types and ids is data rather than headers;
+    let has_headers = headers
+        .first()
+        .is_none_or(|first| first.trim().parse::<TxType>().is_err());
+
+    let mut format = InputFormat {
+        delimiter,
+        trim: headers.iter().any(|header| header.trim() != header),
+        encoding: Some(encoding).filter(|encoding| *encoding == Encoding::Latin1),
+        ..Default::default()
+    };
+
+    for (header, column) in headers.iter().zip(recognized.iter()) {
+        if let Some(column) = column {
+            let header = if format.trim { header.trim() } else { header };
+            if header != *column {
+                format
+                    .column_map
+                    .push((column.to_string(), header.to_string()));
+            }
+        }
+    }
+
+    let missing: Vec<&'static str> = TRANSACTION_COLUMNS[..3]
+        .iter()
+        .filter(|column| !recognized.contains(&Some(**column)))
+        .copied()
+        .collect();
+
+    let mut reader = InputFormat {
+        column_map: Vec::new(),
+        ..format.clone()
+    }
+    .reader(File::open(&file_path)?)?;
+    let mut records: Vec<StringRecord> = reader.records().take(sample).flatten().collect();
+
+    // Amounts with a decimal comma, e.g. `1,5`, and none with a decimal point are read with
+    // `--decimal-comma`;
+    let amount_column = recognized
+        .iter()
+        .position(|column| *column == Some("amount"));
+    if let Some(column) = amount_column {
+        let amounts = || {
+            records
+                .iter()
+                .filter_map(move |record| record.get(column))
+                .map(str::trim)
+        };
+        format.decimal_comma = amounts().any(is_decimal_comma)
+            && !amounts().any(|amount| amount.contains('.') && !amount.contains(','));
+    }
+    if format.decimal_comma {
+        records = records
+            .iter()
+            .map(|record| decimal_comma_record(record, amount_column))
+            .collect();
+    }
+
+    // Infer the value types from the fields;
+    let mut kinds = vec![ColumnKind::Empty; headers.len()];
+    for record in records.iter() {
+        for (kind, value) in kinds.iter_mut().zip(record.iter()) {
+            *kind = (*kind).max(ColumnKind::of(value));
+        }
+    }
+
+    let columns = headers
+        .iter()
+        .zip(recognized.iter())
+        .zip(kinds)
+        .map(|((header, column), kind)| ColumnDiagnosis {
+            header: header.clone(),
+            column: *column,
+            kind,
+        })
+        .collect();
+
+    // Parse the sampled rows with the detected format;
+    let mut rows_sampled = 0;
+    let mut rows_failed = 0;
+    let mut errors = Vec::new();
+    if has_headers && missing.is_empty() {
+        let mut reader = format.reader(File::open(&file_path)?)?;
+        let headers = reader.headers()?.clone();
+        for record in reader.records().take(sample) {
+            rows_sampled += 1;
+            let row = record.map_err(Error::from).and_then(|record| {
+                let record = if format.decimal_comma {
+                    decimal_comma_record(&record, headers.iter().position(|h| h == "amount"))
+                } else {
+                    record
+                };
+                Ok(record.deserialize::<Transaction>(Some(&headers))?)
+            });
+            if let Err(e) = row {
+                rows_failed += 1;
+                if errors.len() < DIAGNOSE_ERRORS {
+                    errors.push(e.to_string());
+                }
+            }
+        }
+    }
+
+    Ok(Diagnosis {
+        file_path,
+        encoding,
+        format,
+        has_headers,
+        columns,
+        missing,
+        rows_sampled,
+        rows_failed,
+        errors,
+    })
+}
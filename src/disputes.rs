@@ -0,0 +1,428 @@
+use std::fmt;
+#[cfg(feature = "io")]
+use std::path::PathBuf;
+use std::str::FromStr;
+
+#[cfg(feature = "io")]
+use csv::Reader;
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+/// An open dispute, holding the disputed transaction's amount until it is resolved or charged back.
+pub struct Dispute {
+    /// Id of the disputed transaction
+    #[serde(rename = "tx")]
+    pub tx: TxId,
+    #[serde(rename = "client")]
+    pub client: ClientId,
+    /// Amount held for the dispute
+    #[serde(rename = "amount")]
+    pub amount: Amount,
+    /// Timestamp of the dispute transaction, or the clock time if it had none
+    #[serde(rename = "opened_at")]
+    pub opened_at: Timestamp,
+    /// Outcome decided by a case-management system: `resolve` or `chargeback`; Empty while open.
+    #[serde(rename = "resolution", default)]
+    pub resolution: Option<TxType>,
+}
+
+/// Dispute state of a recorded transaction;
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum TxState {
+    /// Never disputed;
+    #[default]
+    Normal,
+    /// Under an open dispute, with its amount held;
+    Disputed,
+    /// The last dispute was resolved; The transaction may be disputed again.
+    Resolved,
+    /// Charged back; No further disputes are accepted.
+    ChargedBack,
+    /// Ignored when processed, e.g. a withdrawal beyond available funds; The id stays reserved, but
+    /// the transaction cannot be disputed.
+    Rejected,
+}
+
+impl TxState {
+    /// Returns the state after applying a dispute, resolve or chargeback, or `None` if the
+    /// transition is not allowed from this state, e.g. a resolve without a prior dispute;
+    pub fn next(self, r#type: TxType) -> Option<TxState> {
+        match (self, r#type) {
+            (TxState::Normal, TxType::Dispute) | (TxState::Resolved, TxType::Dispute) => {
+                Some(TxState::Disputed)
+            }
+            (TxState::Disputed, TxType::Resolve) => Some(TxState::Resolved),
+            (TxState::Disputed, TxType::Chargeback) => Some(TxState::ChargedBack),
+            (TxState::ChargedBack, TxType::Reversal) => Some(TxState::Resolved),
+            _ => None,
+        }
+    }
+
+    /// Returns why a dispute, resolve or chargeback cannot be applied from this state;
+    pub(crate) fn rejection(self, r#type: TxType) -> TransactionError {
+        match (self, r#type) {
+            (TxState::Disputed, TxType::Dispute) => TransactionError::AlreadyDisputed,
+            (state, r#type) => TransactionError::InvalidStateTransition { state, r#type },
+        }
+    }
+}
+
+impl fmt::Display for TxState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            TxState::Normal => "normal",
+            TxState::Disputed => "disputed",
+            TxState::Resolved => "resolved",
+            TxState::ChargedBack => "charged back",
+            TxState::Rejected => "rejected",
+        })
+    }
+}
+
+/// A recorded deposit or withdrawal and its dispute state;
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct RecordedTx {
+    pub(crate) transaction: Transaction,
+    pub(crate) state: TxState,
+    /// Amount held by the dispute that was charged back, which may be partial; Caps a reversal.
+    pub(crate) charged_back: Option<Amount>,
+}
+
+impl RecordedTx {
+    /// Returns the amount a reversal may credit back: the amount charged back, or the whole
+    /// transaction amount for records restored without one;
+    pub(crate) fn reversible(&self) -> Amount {
+        self.charged_back
+            .or(self.transaction.amount)
+            .unwrap_or_default()
+    }
+}
+
+/// Action taken when a dispute would exceed `AccountingConfig::max_open_disputes`;
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum DisputeCapPolicy {
+    /// Ignore the new dispute;
+    #[default]
+    Reject,
+    /// Apply the new dispute and flag the account for review;
+    Flag,
+}
+
+impl FromStr for DisputeCapPolicy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "reject" => Ok(DisputeCapPolicy::Reject),
+            "flag" => Ok(DisputeCapPolicy::Flag),
+            _ => Err(Error::InvalidArgument(format!(
+                "unknown dispute cap policy '{}'",
+                s
+            ))),
+        }
+    }
+}
+
+/// Handling of disputes referencing a withdrawal;
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum WithdrawalDisputePolicy {
+    /// Credit the amount back into held funds: a resolve removes the credit, and a chargeback
+    /// releases it to available funds, returning the withdrawal to the client;
+    Credit,
+    /// Ignore disputes of withdrawals;
+    #[default]
+    Ignore,
+}
+
+impl FromStr for WithdrawalDisputePolicy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "credit" => Ok(WithdrawalDisputePolicy::Credit),
+            "ignore" => Ok(WithdrawalDisputePolicy::Ignore),
+            _ => Err(Error::InvalidArgument(format!(
+                "unknown withdrawal dispute policy '{}'",
+                s
+            ))),
+        }
+    }
+}
+
+/// Action taken when a disputed deposit exceeds the client's available funds, e.g. because the
+/// client already withdrew the money;
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum DisputeFundsPolicy {
+    /// Ignore the dispute, so available funds never go negative;
+    #[default]
+    Ignore,
+    /// Hold the disputed amount, letting available funds go negative;
+    Hold,
+}
+
+impl FromStr for DisputeFundsPolicy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ignore" => Ok(DisputeFundsPolicy::Ignore),
+            "hold" => Ok(DisputeFundsPolicy::Hold),
+            _ => Err(Error::InvalidArgument(format!(
+                "unknown dispute funds policy '{}'",
+                s
+            ))),
+        }
+    }
+}
+
+/// Action taken on dispute, resolve and chargeback rows that carry an amount;
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum DisputeAmountPolicy {
+    /// Silently ignore the amount;
+    #[default]
+    Ignore,
+    /// Log a warning and ignore the amount;
+    Warn,
+    /// Ignore the row;
+    Reject,
+}
+
+impl FromStr for DisputeAmountPolicy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ignore" => Ok(DisputeAmountPolicy::Ignore),
+            "warn" => Ok(DisputeAmountPolicy::Warn),
+            "reject" => Ok(DisputeAmountPolicy::Reject),
+            _ => Err(Error::InvalidArgument(format!(
+                "unknown dispute amount policy '{}'",
+                s
+            ))),
+        }
+    }
+}
+
+impl Accounting {
+    /// Apply the dispute amount policy to a dispute, resolve or chargeback row carrying an amount;
+    /// Returns false if the row must be ignored. Amounts used for partial disputes are accepted.
+    pub(crate) fn check_dispute_amount(&mut self, tx: &Transaction) -> bool {
+        let amount = match tx.amount {
+            Some(amount) => amount,
+            None => return true,
+        };
+
+        if tx.r#type == TxType::Dispute && self.config.partial_disputes {
+            return true;
+        }
+
+        match self.config.dispute_amount_policy {
+            DisputeAmountPolicy::Ignore => true,
+            DisputeAmountPolicy::Warn => {
+                warn!(
+                    "Ignoring amount {} of {:?} tx {} for client {}",
+                    amount, tx.r#type, tx.tx, tx.client
+                );
+                true
+            }
+            DisputeAmountPolicy::Reject => {
+                warn!(
+                    "Ignored {:?} tx {} for client {}: unexpected amount {}",
+                    tx.r#type, tx.tx, tx.client, amount
+                );
+                self.ignore(tx, TransactionError::UnexpectedAmount(amount), || {
+                    format!("unexpected amount {}", amount)
+                });
+                false
+            }
+        }
+    }
+
+    /// Returns the open disputes, ordered by disputed transaction id;
+    pub fn disputes(&self) -> impl Iterator<Item = &Dispute> {
+        self.disputes.values()
+    }
+
+    /// Returns the dispute state of a recorded transaction;
+    pub fn tx_state(&self, tx: TxId) -> Option<TxState> {
+        self.transactions.get(&tx).map(|recorded| recorded.state)
+    }
+
+    /// Apply the withdrawal dispute policy to a dispute of a withdrawal; Returns false if the row must
+    /// be ignored.
+    pub(crate) fn check_withdrawal_dispute(&mut self, tx: &Transaction) -> bool {
+        match self.config.withdrawal_dispute_policy {
+            WithdrawalDisputePolicy::Credit => true,
+            WithdrawalDisputePolicy::Ignore => {
+                warn!(
+                    "Ignored dispute of tx {} for client {}: withdrawal disputes are disabled",
+                    tx.tx, tx.client
+                );
+                self.ignore(tx, TransactionError::WithdrawalDispute, || {
+                    "withdrawal disputes are disabled".to_string()
+                });
+                false
+            }
+        }
+    }
+
+    /// Check that a dispute, resolve or chargeback references a transaction of the same client;
+    /// Returns false, ignoring the row, for cross-client references.
+    pub(crate) fn check_tx_owner(&mut self, tx: &Transaction) -> bool {
+        let owner = match self.transactions.get(&tx.tx) {
+            Some(recorded) => recorded.transaction.client,
+            None => return true,
+        };
+
+        if owner == tx.client {
+            return true;
+        }
+
+        warn!(
+            "Ignored {} of tx {} for client {}: transaction belongs to client {}",
+            tx.r#type, tx.tx, tx.client, owner
+        );
+        self.ignore(tx, TransactionError::ClientMismatch(owner), || {
+            format!("transaction belongs to client {}", owner)
+        });
+        false
+    }
+
+    /// Check that a dispute, resolve or chargeback is a valid transition of the referenced
+    /// transaction's state; Returns the new state, or `None` if the row must be ignored.
+    pub(crate) fn check_tx_state(&mut self, tx: &Transaction) -> Option<TxState> {
+        let state = self.tx_state(tx.tx).unwrap_or_default();
+        let next = state.next(tx.r#type);
+        if next.is_none() {
+            warn!(
+                "Ignored {} of tx {} for client {}: transaction is {}",
+                tx.r#type, tx.tx, tx.client, state
+            );
+            self.ignore(tx, state.rejection(tx.r#type), || {
+                format!("transaction is {}", state)
+            });
+        }
+        next
+    }
+
+    /// Set the dispute state of a recorded transaction;
+    pub(crate) fn set_tx_state(&mut self, tx: TxId, state: TxState) {
+        if let Some(recorded) = self.transactions.get_mut(&tx) {
+            recorded.state = state;
+        }
+    }
+
+    /// Returns the dispute state and amount charged back of a recorded transaction, if any;
+    pub(crate) fn recorded_state(&self, tx: TxId) -> Option<(TxState, Option<Amount>)> {
+        self.transactions
+            .get(&tx)
+            .map(|recorded| (recorded.state, recorded.charged_back))
+    }
+
+    /// Set the amount charged back of a recorded transaction;
+    pub(crate) fn set_charged_back(&mut self, tx: TxId, amount: Option<Amount>) {
+        if let Some(recorded) = self.transactions.get_mut(&tx) {
+            recorded.charged_back = amount;
+        }
+    }
+
+    /// Returns the open dispute of a transaction, if any;
+    pub fn get_dispute(&self, tx: TxId) -> Option<&Dispute> {
+        self.disputes.get(&tx)
+    }
+
+    /// Returns the number of open disputes of the client;
+    pub fn open_disputes(&self, client: ClientId) -> usize {
+        self.disputes
+            .values()
+            .filter(|dispute| dispute.client == client)
+            .count()
+    }
+
+    /// Returns the clients flagged for review, e.g. by `DisputeCapPolicy::Flag`;
+    pub fn flagged_accounts(&self) -> impl Iterator<Item = &ClientId> {
+        self.flagged_accounts.iter()
+    }
+
+    /// Apply the open disputes cap to a new dispute, recording the decision in the audit log;
+    /// Returns false if the dispute must be ignored.
+    pub(crate) fn check_dispute_cap(&mut self, client: ClientId, tx: TxId) -> bool {
+        let cap = match self.config.max_open_disputes {
+            Some(cap) => cap,
+            None => return true,
+        };
+
+        let open = self.open_disputes(client);
+        if open < cap {
+            return true;
+        }
+
+        let detail = format!("{} open disputes, cap is {}", open, cap);
+        match self.config.dispute_cap_policy {
+            DisputeCapPolicy::Reject => {
+                self.audit(Some(client), Some(tx), "dispute_cap_rejected", detail);
+                false
+            }
+            DisputeCapPolicy::Flag => {
+                self.audit(Some(client), Some(tx), "dispute_cap_flagged", detail);
+                self.flagged_accounts.insert(client);
+                true
+            }
+        }
+    }
+
+    /// Write the open disputes to a csv file, e.g. to hand the backlog to a case-management system;
+    #[cfg(feature = "io")]
+    pub fn write_disputes_csv_file(&self, file_path: PathBuf) -> Result<(), Error> {
+        let mut sink = CsvFileSink::create(file_path, self.config.output_durability)?;
+
+        for dispute in self.disputes() {
+            sink.write(dispute)?;
+        }
+
+        sink.close()
+    }
+
+    /// Load a disputes csv file, in the format written by `write_disputes_csv_file`;
+    /// Rows with a `resolution` are resolved or charged back, rows without one are opened unless
+    /// already open. Returns the number of rows applied.
+    #[cfg(feature = "io")]
+    pub fn read_disputes_csv_file(&mut self, file_path: PathBuf) -> Result<usize, Error> {
+        let mut rdr = Reader::from_path(file_path)?;
+        let mut applied = 0;
+
+        for result in rdr.deserialize() {
+            let dispute: Dispute = result?;
+
+            let r#type = match dispute.resolution {
+                Some(r#type @ TxType::Resolve) | Some(r#type @ TxType::Chargeback) => r#type,
+                None if self.disputes.contains_key(&dispute.tx) => continue,
+                None => TxType::Dispute,
+                Some(other) => {
+                    warn!(
+                        "Ignored dispute of tx {} for client {}: invalid resolution '{}'",
+                        dispute.tx, dispute.client, other
+                    );
+                    continue;
+                }
+            };
+
+            self.try_process_transaction(Transaction {
+                r#type,
+                client: dispute.client,
+                tx: dispute.tx,
+                amount: None,
+                timestamp: Some(dispute.opened_at),
+                subaccount: None,
+                category: None,
+                source: None,
+            })?;
+            applied += 1;
+        }
+
+        Ok(applied)
+    }
+}
@@ -0,0 +1,199 @@
+use std::fmt;
+use std::io::{self, Read};
+use std::str::FromStr;
+
+use crate::*;
+
+/// Number of bytes read from the source per decoding step;
+const DECODE_CHUNK: usize = 8 * 1024;
+
+/// Character encoding of input files; Decoded transparently to UTF-8 by `DecodedReader`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    /// ISO-8859-1, mapping every byte to the code point of the same value;
+    Latin1,
+}
+
+impl Encoding {
+    /// Detect the encoding from the first bytes of a file: a byte order mark, or the NUL bytes of
+    /// ASCII text encoded as UTF-16; Returns the encoding and the length of its byte order mark, if
+    /// any. Latin-1 cannot be told apart from UTF-8 and must be set explicitly.
+    pub fn detect(prefix: &[u8]) -> Option<(Encoding, usize)> {
+        match prefix {
+            [0xEF, 0xBB, 0xBF, ..] => Some((Encoding::Utf8, 3)),
+            [0xFF, 0xFE, ..] => Some((Encoding::Utf16Le, 2)),
+            [0xFE, 0xFF, ..] => Some((Encoding::Utf16Be, 2)),
+            [a, 0, b, 0, ..] if *a != 0 && *b != 0 => Some((Encoding::Utf16Le, 0)),
+            [0, a, 0, b, ..] if *a != 0 && *b != 0 => Some((Encoding::Utf16Be, 0)),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "utf-8",
+            Encoding::Utf16Le => "utf-16le",
+            Encoding::Utf16Be => "utf-16be",
+            Encoding::Latin1 => "latin1",
+        }
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Encoding {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "utf-8" | "utf8" => Ok(Encoding::Utf8),
+            "utf-16le" | "utf16le" => Ok(Encoding::Utf16Le),
+            "utf-16be" | "utf16be" => Ok(Encoding::Utf16Be),
+            "latin1" | "latin-1" | "iso-8859-1" => Ok(Encoding::Latin1),
+            _ => Err(Error::InvalidArgument(format!(
+                "unknown encoding '{}'; expected utf-8, utf-16le, utf-16be or latin1",
+                s
+            ))),
+        }
+    }
+}
+
+/// Reader decoding a source in the given, or detected, encoding to UTF-8; A byte order mark is
+/// skipped, and invalid UTF-16 is replaced with U+FFFD. UTF-8 sources are passed through.
+pub struct DecodedReader<R> {
+    inner: R,
+    /// The encoding, detected from the first bytes if not set
+    encoding: Option<Encoding>,
+    /// Bytes read from the source but not decoded yet
+    pending: Vec<u8>,
+    /// Decoded bytes not returned yet, from `position`
+    decoded: Vec<u8>,
+    position: usize,
+    eof: bool,
+}
+
+impl<R: Read> DecodedReader<R> {
+    pub fn new(inner: R, encoding: Option<Encoding>) -> Self {
+        DecodedReader {
+            inner,
+            encoding,
+            pending: Vec::new(),
+            decoded: Vec::new(),
+            position: 0,
+            eof: false,
+        }
+    }
+
+    /// Returns the encoding, detecting it from the first bytes of the source if it was not set;
+    pub fn encoding(&mut self) -> io::Result<Encoding> {
+        if let Some(encoding) = self.encoding {
+            return Ok(encoding);
+        }
+
+        while self.pending.len() < 4 && self.fill()? > 0 {}
+
+        let (encoding, bom) = Encoding::detect(&self.pending).unwrap_or((Encoding::Utf8, 0));
+        self.pending.drain(..bom);
+        self.encoding = Some(encoding);
+
+        Ok(encoding)
+    }
+
+    /// Read the next chunk of the source into the pending bytes; Returns the number of bytes read.
+    fn fill(&mut self) -> io::Result<usize> {
+        let mut chunk = [0; DECODE_CHUNK];
+        let n = self.inner.read(&mut chunk)?;
+        self.pending.extend_from_slice(&chunk[..n]);
+        self.eof = n == 0;
+        Ok(n)
+    }
+
+    /// Decode the pending bytes, keeping an incomplete trailing code unit or surrogate pair pending
+    /// until more bytes are read or the source is exhausted;
+    fn decode(&mut self, encoding: Encoding) {
+        self.decoded.clear();
+        self.position = 0;
+
+        let mut decoded = String::new();
+        let consumed = match encoding {
+            Encoding::Utf8 => {
+                std::mem::swap(&mut self.decoded, &mut self.pending);
+                return;
+            }
+            Encoding::Latin1 => {
+                decoded.extend(self.pending.iter().map(|byte| *byte as char));
+                self.pending.len()
+            }
+            Encoding::Utf16Le | Encoding::Utf16Be => {
+                let mut units: Vec<u16> = self
+                    .pending
+                    .chunks_exact(2)
+                    .map(|pair| match encoding {
+                        Encoding::Utf16Le => u16::from_le_bytes([pair[0], pair[1]]),
+                        _ => u16::from_be_bytes([pair[0], pair[1]]),
+                    })
+                    .collect();
+
+                // Keep a trailing high surrogate for the rest of its pair;
+                if !self.eof
+                    && units
+                        .last()
+                        .is_some_and(|unit| (0xD800..0xDC00).contains(unit))
+                {
+                    units.pop();
+                }
+
+                decoded.extend(
+                    char::decode_utf16(units.iter().copied())
+                        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER)),
+                );
+
+                // A trailing odd byte at the end of the source is invalid;
+                if self.eof && self.pending.len() % 2 == 1 {
+                    decoded.push(char::REPLACEMENT_CHARACTER);
+                    self.pending.len()
+                } else {
+                    units.len() * 2
+                }
+            }
+        };
+
+        self.pending.drain(..consumed);
+        self.decoded = decoded.into_bytes();
+    }
+}
+
+impl<R: Read> Read for DecodedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let encoding = self.encoding()?;
+
+        while self.position >= self.decoded.len() {
+            if self.pending.is_empty() && self.eof {
+                return Ok(0);
+            }
+
+            // Pass UTF-8 sources through once the byte order mark is skipped;
+            if encoding == Encoding::Utf8 && self.pending.is_empty() {
+                return self.inner.read(buf);
+            }
+
+            if !self.eof {
+                self.fill()?;
+            }
+            self.decode(encoding);
+        }
+
+        let n = buf.len().min(self.decoded.len() - self.position);
+        buf[..n].copy_from_slice(&self.decoded[self.position..self.position + n]);
+        self.position += n;
+
+        Ok(n)
+    }
+}
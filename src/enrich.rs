@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+#[cfg(feature = "io")]
+use std::path::PathBuf;
+
+#[cfg(feature = "io")]
+use csv::Reader;
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/// An enrichment stage applied to each transaction after it is parsed and before it is processed.
+/// Enrichers may mutate or augment the transaction, e.g. normalize amounts or map legacy client ids.
+pub trait Enricher: Debug + Send {
+    /// Mutate the transaction in place; Returning an error aborts reading the transactions file.
+    fn enrich(&self, tx: &mut Transaction) -> Result<(), Error>;
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+/// A single row of a client id mapping file.
+pub struct ClientIdMapping {
+    #[serde(rename = "old_client")]
+    pub old_client: ClientId,
+    #[serde(rename = "new_client")]
+    pub new_client: ClientId,
+}
+
+#[derive(Debug, Default, Clone)]
+/// Built-in enricher that rewrites legacy client ids using a mapping table;
+/// Client ids without a mapping are left unchanged.
+pub struct ClientIdRemapper {
+    map: HashMap<ClientId, ClientId>,
+}
+
+impl ClientIdRemapper {
+    pub fn new(map: HashMap<ClientId, ClientId>) -> Self {
+        ClientIdRemapper { map }
+    }
+
+    /// Load the mapping table from a csv file with `old_client,new_client` columns;
+    #[cfg(feature = "io")]
+    pub fn from_csv_file(file_path: PathBuf) -> Result<Self, Error> {
+        let mut file = Reader::from_path(file_path)?;
+        let mut map = HashMap::new();
+
+        for row in file.deserialize::<ClientIdMapping>() {
+            let mapping = row?;
+            map.insert(mapping.old_client, mapping.new_client);
+        }
+
+        Ok(ClientIdRemapper { map })
+    }
+
+    /// Add mappings to the table; Existing mappings that point at a remapped client id are
+    /// updated so that chained remaps (a -> b, then b -> c) resolve to the final client id.
+    pub fn extend(&mut self, map: &HashMap<ClientId, ClientId>) {
+        for target in self.map.values_mut() {
+            if let Some(new_client) = map.get(target) {
+                *target = *new_client;
+            }
+        }
+
+        for (old_client, new_client) in map {
+            self.map.entry(*old_client).or_insert(*new_client);
+        }
+    }
+
+    /// Returns true if no client ids are mapped;
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Returns the mapped client id, or the original client id if it is not mapped;
+    pub fn map(&self, client: ClientId) -> ClientId {
+        self.map.get(&client).copied().unwrap_or(client)
+    }
+}
+
+impl Enricher for ClientIdRemapper {
+    fn enrich(&self, tx: &mut Transaction) -> Result<(), Error> {
+        tx.client = self.map(tx.client);
+        Ok(())
+    }
+}
@@ -1,15 +1,203 @@
+#[cfg(feature = "io")]
 use csv::{Error as CsvError, IntoInnerError, Writer};
-use std::num::ParseFloatError;
+use std::fmt;
+use std::path::PathBuf;
 use thiserror::Error as ThisError;
 
+use crate::{Amount, ClientId, HoldId, InvariantViolation, TxId};
+
+/// Error categories; The first digit of an error code identifies its category.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// `E1xxx`: The input could not be parsed or an argument is invalid;
+    Parse,
+    /// `E2xxx`: A transaction was rejected by validation;
+    Transaction,
+    /// `E3xxx`: Reading or writing data failed;
+    Io,
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ErrorKind::Parse => f.write_str("parse"),
+            ErrorKind::Transaction => f.write_str("transaction"),
+            ErrorKind::Io => f.write_str("io"),
+        }
+    }
+}
+
+/// Errors returned by the library; Each variant has a stable error code, returned by `Error::code()`.
+/// Codes are never reused or renumbered.
+///
+/// | Code  | Variant            |
+/// |-------|--------------------|
+/// | E1001 | `CsvError`         |
+/// | E1002 | Retired, formerly `ParseFloatError` |
+/// | E1003 | `InvalidTxType`    |
+/// | E1004 | `InvalidArgument`  |
+/// | E1005 | `UnequalFields`    |
+/// | E1006 | `InvalidConfig`    |
+/// | E1007 | `InputLimitExceeded` |
+/// | E1008 | `IdOutOfRange`     |
+/// | E2001 | `UnknownTxType`    |
+/// | E2002 | `InsufficientFunds`|
+/// | E2003 | `DuplicateTx`      |
+/// | E2004 | `MissingAmount`    |
+/// | E2005 | `ClientMismatch`   |
+/// | E2006 | `UnknownHold`      |
+/// | E2007 | `InvariantViolation` |
+/// | E2008 | `AlreadyDisputed`  |
+/// | E2009 | `InvalidAmount`    |
+/// | E2010 | `ScriptFailed`     |
+/// | E2011 | `CircuitOpen`      |
+/// | E2012 | `InsufficientHeldFunds` |
+/// | E3001 | `IoError`, or a `CsvError` caused by I/O |
+/// | E3002 | `CsvWriterError`   |
+/// | E3003 | `ParquetError`     |
+/// | E3004 | `SnapshotError`    |
+/// | E3005 | `ReplicationGap`   |
+/// | E3006 | `ArrowError`       |
+/// | E3007 | `AlreadyInUse`     |
+/// | E3008 | `UnreplicatedHeldFunds` |
 #[derive(ThisError, Debug)]
 pub enum Error {
-    #[error("I/O Error")]
+    #[error("I/O Error: {0}")]
     IoError(#[from] std::io::Error),
-    #[error("CSV Error")]
+    #[cfg(feature = "io")]
+    #[error("CSV Error: {0}")]
     CsvError(#[from] CsvError),
-    #[error("CSV Writer Error")]
-    CsvWriterError(#[from] IntoInnerError<Writer<Vec<u8>>>),
-    #[error("Failed to parse amount")]
-    ParseFloatError(#[from] ParseFloatError),
+    #[cfg(feature = "io")]
+    #[error("CSV Writer Error: {0}")]
+    CsvWriterError(#[from] Box<IntoInnerError<Writer<Vec<u8>>>>),
+    #[error("Snapshot Error: {0}")]
+    SnapshotError(#[from] bincode::Error),
+    #[error("Replication gap: expected event {expected}, received {received}")]
+    ReplicationGap { expected: u64, received: u64 },
+    #[error("{} is already in use by another process", .0.display())]
+    AlreadyInUse(PathBuf),
+    #[error("Held funds {held} of client {client} exceed the {replicated} held by replicated disputes, holds, reservations and pending deposits")]
+    UnreplicatedHeldFunds {
+        client: ClientId,
+        held: Amount,
+        replicated: Amount,
+    },
+    #[cfg(feature = "parquet")]
+    #[error("Parquet Error: {0}")]
+    ParquetError(#[from] parquet::errors::ParquetError),
+    #[cfg(feature = "arrow")]
+    #[error("Arrow Error: {0}")]
+    ArrowError(#[from] arrow_schema::ArrowError),
+    #[error("Invalid argument: {0}")]
+    InvalidArgument(String),
+    #[error("Record at line {line} has {len} fields, but the header has {expected} fields")]
+    UnequalFields {
+        line: u64,
+        len: usize,
+        expected: usize,
+    },
+    #[error("Invalid config `{field}`: {reason}")]
+    InvalidConfig { field: &'static str, reason: String },
+    #[error("Input exceeds the {limit} limit of {max}")]
+    InputLimitExceeded { limit: &'static str, max: u64 },
+    #[error("Record at line {line} has {column} id {value}, exceeding the maximum of {max}")]
+    IdOutOfRange {
+        line: u64,
+        column: &'static str,
+        value: String,
+        max: u64,
+    },
+    #[error("Invalid transaction type '{0}'")]
+    InvalidTxType(String),
+    #[error("Unknown transaction type for tx {0}")]
+    UnknownTxType(TxId),
+    #[error("Insufficient available funds for client {client} to cover {amount}")]
+    InsufficientFunds { client: ClientId, amount: Amount },
+    #[error("Duplicate transaction id {0}")]
+    DuplicateTx(TxId),
+    #[error("Missing amount for tx {0}")]
+    MissingAmount(TxId),
+    #[error("Client {client} does not own tx {tx}")]
+    ClientMismatch { client: ClientId, tx: TxId },
+    #[error("Unknown hold {0}")]
+    UnknownHold(HoldId),
+    #[error("{0}")]
+    InvariantViolation(Box<InvariantViolation>),
+    #[error("Tx {0} is already under dispute")]
+    AlreadyDisputed(TxId),
+    #[error("Invalid amount {amount} for tx {tx}; amounts must be positive")]
+    InvalidAmount { tx: TxId, amount: Amount },
+    #[error("{0} scenario expectations failed")]
+    ScriptFailed(usize),
+    #[error("Circuit breaker open: {0}")]
+    CircuitOpen(String),
+    #[error("Insufficient held funds for client {client} to cover {amount}")]
+    InsufficientHeldFunds { client: ClientId, amount: Amount },
+}
+
+impl Error {
+    /// Returns the stable error code, e.g. `E1001`;
+    pub fn code(&self) -> &'static str {
+        match self {
+            #[cfg(feature = "io")]
+            Error::CsvError(e) if e.is_io_error() => "E3001",
+            #[cfg(feature = "io")]
+            Error::CsvError(_) => "E1001",
+            Error::InvalidTxType(_) => "E1003",
+            Error::InvalidArgument(_) => "E1004",
+            Error::UnequalFields { .. } => "E1005",
+            Error::InvalidConfig { .. } => "E1006",
+            Error::InputLimitExceeded { .. } => "E1007",
+            Error::IdOutOfRange { .. } => "E1008",
+            Error::UnknownTxType(_) => "E2001",
+            Error::InsufficientFunds { .. } => "E2002",
+            Error::DuplicateTx(_) => "E2003",
+            Error::MissingAmount(_) => "E2004",
+            Error::ClientMismatch { .. } => "E2005",
+            Error::UnknownHold(_) => "E2006",
+            Error::InvariantViolation(_) => "E2007",
+            Error::AlreadyDisputed(_) => "E2008",
+            Error::InvalidAmount { .. } => "E2009",
+            Error::ScriptFailed(_) => "E2010",
+            Error::CircuitOpen(_) => "E2011",
+            Error::InsufficientHeldFunds { .. } => "E2012",
+            Error::IoError(_) => "E3001",
+            #[cfg(feature = "io")]
+            Error::CsvWriterError(_) => "E3002",
+            #[cfg(feature = "parquet")]
+            Error::ParquetError(_) => "E3003",
+            Error::SnapshotError(_) => "E3004",
+            Error::ReplicationGap { .. } => "E3005",
+            Error::AlreadyInUse(_) => "E3007",
+            Error::UnreplicatedHeldFunds { .. } => "E3008",
+            #[cfg(feature = "arrow")]
+            Error::ArrowError(_) => "E3006",
+        }
+    }
+
+    /// Returns the category of the error;
+    pub fn kind(&self) -> ErrorKind {
+        match &self.code()[1..2] {
+            "1" => ErrorKind::Parse,
+            "2" => ErrorKind::Transaction,
+            _ => ErrorKind::Io,
+        }
+    }
+}
+
+/// A transactions csv row that failed to parse, as returned by `Accounting::csv_rows`;
+#[derive(Debug, ThisError)]
+#[error("line {line}: {error}")]
+pub struct RowError {
+    /// Line of the row in the input, starting at 1 for the header; `0` if unknown
+    pub line: u64,
+    #[source]
+    pub error: Error,
+}
+
+impl RowError {
+    /// Returns the stable code of the underlying error;
+    pub fn code(&self) -> &'static str {
+        self.error.code()
+    }
 }
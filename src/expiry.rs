@@ -0,0 +1,141 @@
+use std::str::FromStr;
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/// Action taken on disputes still open after `DisputeExpiry::days`;
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum DisputeExpiryAction {
+    /// Resolve the dispute, releasing the held funds;
+    #[default]
+    Resolve,
+    /// Charge the dispute back, locking the account;
+    Chargeback,
+}
+
+impl DisputeExpiryAction {
+    /// Returns the transaction type applied to an expired dispute;
+    pub fn tx_type(&self) -> TxType {
+        match self {
+            DisputeExpiryAction::Resolve => TxType::Resolve,
+            DisputeExpiryAction::Chargeback => TxType::Chargeback,
+        }
+    }
+}
+
+impl FromStr for DisputeExpiryAction {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "resolve" => Ok(DisputeExpiryAction::Resolve),
+            "chargeback" => Ok(DisputeExpiryAction::Chargeback),
+            _ => Err(Error::InvalidArgument(format!(
+                "unknown dispute expiry action '{}'",
+                s
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+/// Automatic decision of disputes left open too long; See `AccountingConfig::dispute_expiry`.
+pub struct DisputeExpiry {
+    /// Whole days after the dispute was opened at which it expires
+    pub days: Timestamp,
+    pub action: DisputeExpiryAction,
+}
+
+impl Accounting {
+    /// Apply the expiry action to every open dispute opened at least `DisputeExpiry::days` before
+    /// `now`, recording each automatic decision in the audit log as `dispute_auto_resolved` or
+    /// `dispute_auto_charged_back`; Called with each transaction's timestamp (or the clock time)
+    /// before it is processed, when `AccountingConfig::dispute_expiry` is set, and may be called
+    /// directly, e.g. before writing the accounts. Returns the number of disputes decided.
+    pub fn expire_disputes(&mut self, now: Timestamp) -> usize {
+        let expiry = match self.config.dispute_expiry.as_ref() {
+            Some(expiry) => expiry.clone(),
+            None => return 0,
+        };
+        let after = expiry.days.saturating_mul(SECONDS_PER_DAY);
+
+        let expired: Vec<Dispute> = self
+            .disputes
+            .values()
+            .filter(|dispute| dispute.opened_at.saturating_add(after) <= now)
+            .cloned()
+            .collect();
+
+        // Keep the outcome and source of the transaction being processed, if any;
+        let rejection = self.rejection.take();
+        let source = self.source.take();
+        let mut decided = 0;
+
+        for dispute in expired {
+            let mut account = match self.accounts.remove(&dispute.client) {
+                Some(account) => account,
+                None => continue,
+            };
+            let subaccount = self
+                .transactions
+                .get(&dispute.tx)
+                .and_then(|recorded| recorded.transaction.subaccount.clone());
+            let tx = Transaction {
+                r#type: expiry.action.tx_type(),
+                client: dispute.client,
+                tx: dispute.tx,
+                amount: None,
+                timestamp: Some(now),
+                subaccount,
+                category: None,
+                source: None,
+            };
+            let locked = account.locked;
+
+            if tx.subaccount.is_none() && !self.subaccounts.contains_key(&tx.client) {
+                self.dispatch(&mut account, &tx);
+            } else {
+                self.dispatch_subaccount(&mut account, &tx);
+            }
+
+            if account.locked && !locked {
+                let detail = format!("expired dispute of tx {}", tx.tx);
+                self.record_lock(
+                    &mut account,
+                    LockReason::Chargeback,
+                    Some(tx.tx),
+                    now,
+                    detail,
+                );
+            }
+
+            self.emit_change(Some(tx.tx), &account);
+            self.accounts.insert(dispute.client, account);
+
+            match self.rejection.take() {
+                Some(rejection) => warn!(
+                    "Failed to {} expired dispute of tx {} for client {}: {}",
+                    tx.r#type, tx.tx, tx.client, rejection
+                ),
+                None => {
+                    let event = match expiry.action {
+                        DisputeExpiryAction::Resolve => "dispute_auto_resolved",
+                        DisputeExpiryAction::Chargeback => "dispute_auto_charged_back",
+                    };
+                    let detail = format!(
+                        "automatic {} of dispute opened at {}, open for more than {} days",
+                        tx.r#type, dispute.opened_at, expiry.days
+                    );
+                    self.audit(Some(dispute.client), Some(dispute.tx), event, detail);
+                    decided += 1;
+                }
+            }
+        }
+
+        self.rejection = rejection;
+        self.source = source;
+        decided
+    }
+}
@@ -0,0 +1,91 @@
+#[cfg(feature = "io")]
+use std::path::PathBuf;
+
+#[cfg(feature = "io")]
+use csv::Reader;
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+/// Regulatory reserve of a client: the amount of available funds withdrawals may not touch.
+pub struct ReserveFloor {
+    #[serde(rename = "client")]
+    pub client: ClientId,
+    /// Minimum available balance left after a withdrawal
+    #[serde(rename = "floor")]
+    pub floor: Amount,
+}
+
+impl Accounting {
+    /// Set the reserve floor of a client, replacing any existing floor; Withdrawals that would take
+    /// available funds below the floor are ignored with `TransactionError::BelowReserveFloor`. A floor of
+    /// zero removes the reserve.
+    pub fn set_reserve(&mut self, client: ClientId, amount: Amount) -> Result<(), Error> {
+        if amount < Amount::ZERO {
+            return Err(Error::InvalidArgument(format!(
+                "reserve floor for client {} must not be negative, got {}",
+                client, amount
+            )));
+        }
+
+        if amount == Amount::ZERO {
+            self.reserve_floors.remove(&client);
+        } else {
+            self.reserve_floors.insert(client, amount);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the reserve floor of a client, zero if none is set;
+    pub fn reserve_floor(&self, client: ClientId) -> Amount {
+        self.reserve_floors
+            .get(&client)
+            .copied()
+            .unwrap_or(Amount::ZERO)
+    }
+
+    /// Returns an iterator over the reserve floors set, in client order;
+    pub fn reserve_floors(&self) -> impl Iterator<Item = ReserveFloor> + '_ {
+        self.reserve_floors
+            .iter()
+            .map(|(client, floor)| ReserveFloor {
+                client: *client,
+                floor: *floor,
+            })
+    }
+
+    /// Load reserve floors from a csv file with `client,floor` columns;
+    #[cfg(feature = "io")]
+    pub fn read_reserve_floors_csv_file(&mut self, file_path: PathBuf) -> Result<(), Error> {
+        let mut rdr = Reader::from_path(file_path)?;
+
+        for result in rdr.deserialize() {
+            let reserve: ReserveFloor = result?;
+            self.set_reserve(reserve.client, reserve.floor)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the rejection of a withdrawal that would take available funds below the client's
+    /// reserve floor, if any;
+    pub(crate) fn reserve_floor_rejection(
+        &self,
+        client: ClientId,
+        available: Amount,
+        amount: Amount,
+    ) -> Option<TransactionError> {
+        let floor = *self.reserve_floors.get(&client)?;
+        if covers(available - floor, amount, self.config.amount_epsilon) {
+            None
+        } else {
+            Some(TransactionError::BelowReserveFloor {
+                available,
+                floor,
+                amount,
+            })
+        }
+    }
+}
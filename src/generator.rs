@@ -0,0 +1,314 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+
+use rand::distributions::{Distribution, Uniform, WeightedIndex};
+use rand::{thread_rng, Rng};
+
+use crate::*;
+
+/// The pathological input categories produced by the adversarial generator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdversarialCase {
+    /// A deposit reusing the transaction id of an earlier recorded deposit;
+    /// The first occurrence produces two rows, recording the deposit before colliding with it.
+    TxIdCollision,
+    /// A dispute row referencing a transaction id, followed by the deposit with that id;
+    /// This case produces two rows.
+    DisputeBeforeDeposit,
+    /// A deposit with an amount near `f64::MAX`, far beyond the range of `Amount`;
+    EnormousAmount,
+    /// A deposit with a `NaN` amount string;
+    NanAmount,
+    /// A deposit with a negative amount string;
+    NegativeAmount,
+    /// A deposit with leading whitespace in every field;
+    WhitespacePadded,
+    /// A deposit row missing the tx and amount columns;
+    MissingColumns,
+}
+
+impl AdversarialCase {
+    pub const ALL: [AdversarialCase; 7] = [
+        AdversarialCase::TxIdCollision,
+        AdversarialCase::DisputeBeforeDeposit,
+        AdversarialCase::EnormousAmount,
+        AdversarialCase::NanAmount,
+        AdversarialCase::NegativeAmount,
+        AdversarialCase::WhitespacePadded,
+        AdversarialCase::MissingColumns,
+    ];
+
+    /// Returns true if rows of this case are expected to fail csv deserialization;
+    pub fn is_malformed(&self) -> bool {
+        matches!(
+            self,
+            AdversarialCase::EnormousAmount
+                | AdversarialCase::NanAmount
+                | AdversarialCase::WhitespacePadded
+                | AdversarialCase::MissingColumns
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+/// A raw csv row produced by the adversarial generator, tagged with the case it exercises.
+pub struct AdversarialRow {
+    pub case: AdversarialCase,
+    /// The raw csv line, without a trailing newline;
+    pub line: String,
+}
+
+/// Generate a random amount with four decimal places of precision;
+pub fn random_amount<R: Rng>(rng: &mut R) -> Amount {
+    Amount::new(rng.gen_range(0..=1_000_000_000i64), 4).normalize()
+}
+
+/// Generate a random, well-formed transaction; Deposits and withdrawals carry an amount,
+/// all other transaction types do not. Useful for property tests in embedding crates.
+pub fn random_transaction<R: Rng>(rng: &mut R) -> Transaction {
+    let r#type = TxType::PAYMENTS[rng.gen_range(0..TxType::PAYMENTS.len())];
+
+    let amount = if r#type.is_funds_movement() {
+        Some(random_amount(rng))
+    } else {
+        None
+    };
+
+    Transaction {
+        r#type,
+        client: rng.gen(),
+        tx: rng.gen(),
+        amount,
+        timestamp: if rng.gen() { Some(rng.gen()) } else { None },
+        subaccount: None,
+        category: None,
+        source: None,
+    }
+}
+
+/// Generate a random account with consistent balances (total = available + held);
+/// Lifetime statistics are left at their defaults since they are not part of the output schema.
+pub fn random_account<R: Rng>(rng: &mut R) -> Account {
+    let available = random_amount(rng);
+    let held = random_amount(rng);
+
+    Account {
+        client: rng.gen(),
+        available,
+        held,
+        total: available + held,
+        locked: rng.gen(),
+        ..Default::default()
+    }
+}
+
+/// Parameters for generating a stochastic transaction stream, e.g. for scenario and stress testing;
+/// Unlike `generate_dummy_transactions`, disputes only reference earlier deposits and resolves and
+/// chargebacks only reference open disputes, so the generated stream exercises the dispute flow.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeneratorProfile {
+    /// Number of transactions to generate
+    pub transactions: u32,
+    /// Client ids are drawn uniformly from `1..=clients`
+    pub clients: ClientId,
+    /// Relative weight of each type, in `TxType::PAYMENTS` order
+    pub mix: [f64; 5],
+    /// Deposit and withdrawal amounts are drawn uniformly up to this amount
+    pub max_amount: f64,
+}
+
+impl Default for GeneratorProfile {
+    fn default() -> Self {
+        GeneratorProfile {
+            transactions: 1_000,
+            clients: 100,
+            mix: [0.5, 0.35, 0.08, 0.05, 0.02],
+            max_amount: 500.,
+        }
+    }
+}
+
+impl GeneratorProfile {
+    /// Returns the relative weight of the transaction type; Zero for types the profile never generates.
+    pub fn weight(&self, r#type: TxType) -> f64 {
+        TxType::PAYMENTS
+            .iter()
+            .position(|t| *t == r#type)
+            .map_or(0., |i| self.mix[i])
+    }
+
+    /// Returns the profile with the weight of the transaction type multiplied by the factor;
+    pub fn scale(mut self, r#type: TxType, factor: f64) -> Self {
+        if let Some(i) = TxType::PAYMENTS.iter().position(|t| *t == r#type) {
+            self.mix[i] *= factor;
+        }
+
+        self
+    }
+
+    /// Generate the transaction stream, allocating transaction ids sequentially from `first_tx`;
+    /// A dispute, resolve or chargeback drawn with nothing to reference is generated as a deposit.
+    pub fn generate<R: Rng>(&self, rng: &mut R, first_tx: TxId) -> Result<Vec<Transaction>, Error> {
+        let types = WeightedIndex::new(self.mix.iter()).map_err(|e| {
+            Error::InvalidArgument(format!("invalid transaction mix {:?}: {}", self.mix, e))
+        })?;
+        let clients = Uniform::new_inclusive(1, self.clients.max(1));
+
+        let mut transactions = Vec::with_capacity(self.transactions as usize);
+        let mut deposits: Vec<(ClientId, TxId)> = Vec::new();
+        let mut disputed: Vec<(ClientId, TxId)> = Vec::new();
+        let mut next_tx = first_tx;
+
+        for _ in 0..self.transactions {
+            let r#type = TxType::PAYMENTS[types.sample(rng)];
+
+            let referenced = match r#type {
+                TxType::Dispute if !deposits.is_empty() => {
+                    let (client, tx) = deposits.swap_remove(rng.gen_range(0..deposits.len()));
+                    disputed.push((client, tx));
+                    Some((r#type, client, tx))
+                }
+                TxType::Resolve | TxType::Chargeback if !disputed.is_empty() => {
+                    let (client, tx) = disputed.swap_remove(rng.gen_range(0..disputed.len()));
+                    Some((r#type, client, tx))
+                }
+                _ => None,
+            };
+
+            let transaction = match referenced {
+                Some((r#type, client, tx)) => Transaction {
+                    r#type,
+                    client,
+                    tx,
+                    amount: None,
+                    timestamp: None,
+                    subaccount: None,
+                    category: None,
+                    source: None,
+                },
+                None => {
+                    let r#type = if r#type == TxType::Withdrawal {
+                        TxType::Withdrawal
+                    } else {
+                        TxType::Deposit
+                    };
+                    let client = clients.sample(rng);
+                    let tx = next_tx;
+                    next_tx = next_tx.wrapping_add(1);
+
+                    if r#type == TxType::Deposit {
+                        deposits.push((client, tx));
+                    }
+
+                    Transaction {
+                        r#type,
+                        client,
+                        tx,
+                        amount: Some(
+                            Amount::new(
+                                (rng.gen_range(0.0..=self.max_amount) * 10_000.).round() as i64,
+                                4,
+                            )
+                            .normalize(),
+                        ),
+                        timestamp: None,
+                        subaccount: None,
+                        category: None,
+                        source: None,
+                    }
+                }
+            };
+
+            transactions.push(transaction);
+        }
+
+        Ok(transactions)
+    }
+}
+
+impl Accounting {
+    /// Generate pathological transaction rows for testing input handling;
+    /// Every `AdversarialCase` is produced in turn, so all cases appear once `num_rows` is at least
+    /// `AdversarialCase::ALL.len()`. Rows are raw csv lines because several cases cannot be
+    /// represented as a `Transaction`.
+    pub fn generate_adversarial_rows(num_rows: u32, num_accounts: ClientId) -> Vec<AdversarialRow> {
+        let mut rng = thread_rng();
+        let clients = Uniform::new_inclusive(1, num_accounts);
+        let amounts = Uniform::new_inclusive(0.1, 500.);
+        let mut rows = Vec::new();
+
+        // Transaction ids are allocated sequentially so that collisions only occur on purpose;
+        let mut next_tx: TxId = 0;
+        // Id of the last deposit known to be recorded, reused by collisions;
+        let mut recorded: Option<TxId> = None;
+
+        for case in AdversarialCase::ALL.iter().cycle().take(num_rows as usize) {
+            let client = clients.sample(&mut rng);
+            let amount = format!("{:.4}", amounts.sample(&mut rng));
+
+            let lines = match case {
+                AdversarialCase::TxIdCollision => match recorded {
+                    Some(tx) => vec![format!("deposit,{},{},{}", client, tx, amount)],
+                    // Record a deposit first if none is known yet;
+                    None => {
+                        next_tx += 1;
+                        recorded = Some(next_tx);
+                        vec![
+                            format!("deposit,{},{},{}", client, next_tx, amount),
+                            format!("deposit,{},{},{}", client, next_tx, amount),
+                        ]
+                    }
+                },
+                AdversarialCase::DisputeBeforeDeposit => {
+                    // Dispute an id that is only deposited by a later row of this case;
+                    next_tx += 1;
+                    recorded = Some(next_tx);
+                    vec![
+                        format!("dispute,{},{},", client, next_tx),
+                        format!("deposit,{},{},{}", client, next_tx, amount),
+                    ]
+                }
+                AdversarialCase::EnormousAmount => {
+                    next_tx += 1;
+                    vec![format!("deposit,{},{},{:.4}", client, next_tx, f64::MAX)]
+                }
+                AdversarialCase::NanAmount => {
+                    next_tx += 1;
+                    vec![format!("deposit,{},{},NaN", client, next_tx)]
+                }
+                AdversarialCase::NegativeAmount => {
+                    next_tx += 1;
+                    vec![format!("deposit,{},{},-{}", client, next_tx, amount)]
+                }
+                AdversarialCase::WhitespacePadded => {
+                    next_tx += 1;
+                    vec![format!(" deposit, {}, {}, {}", client, next_tx, amount)]
+                }
+                AdversarialCase::MissingColumns => vec![format!("deposit,{}", client)],
+            };
+
+            for line in lines {
+                rows.push(AdversarialRow { case: *case, line });
+            }
+        }
+
+        rows
+    }
+
+    /// Write adversarial rows to a csv file with the standard transactions header;
+    pub fn write_adversarial_csv_file(
+        rows: &[AdversarialRow],
+        file_path: PathBuf,
+    ) -> Result<(), Error> {
+        let mut file = File::create(file_path)?;
+
+        writeln!(file, "type,client,tx,amount")?;
+
+        for row in rows {
+            writeln!(file, "{}", row.line)?;
+        }
+
+        Ok(())
+    }
+}
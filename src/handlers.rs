@@ -0,0 +1,205 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::sync::Arc;
+
+use log::warn;
+
+use crate::*;
+
+/// Maximum length in bytes of a custom transaction type name;
+pub const CUSTOM_TX_TYPE_MAX_LEN: usize = 23;
+
+/// Name of a custom transaction type, stored inline so `TxType` stays `Copy`;
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CustomTxType {
+    len: u8,
+    bytes: [u8; CUSTOM_TX_TYPE_MAX_LEN],
+}
+
+impl CustomTxType {
+    /// Returns the name as a custom type, or `None` if it is empty or longer than
+    /// `CUSTOM_TX_TYPE_MAX_LEN` bytes;
+    pub fn new(name: &str) -> Option<Self> {
+        if name.is_empty() || name.len() > CUSTOM_TX_TYPE_MAX_LEN {
+            return None;
+        }
+
+        let mut bytes = [0; CUSTOM_TX_TYPE_MAX_LEN];
+        bytes[..name.len()].copy_from_slice(name.as_bytes());
+
+        Some(CustomTxType {
+            len: name.len() as u8,
+            bytes,
+        })
+    }
+
+    /// Returns the name of the custom type;
+    pub fn as_str(&self) -> &str {
+        // Copied from a `&str` in `CustomTxType::new`, so always valid UTF-8;
+        std::str::from_utf8(&self.bytes[..self.len as usize]).unwrap_or_default()
+    }
+}
+
+impl fmt::Debug for CustomTxType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl TxType {
+    /// Returns true for types registered with `Accounting::register_tx_type`;
+    pub fn is_custom(&self) -> bool {
+        matches!(self, TxType::Custom(_))
+    }
+}
+
+/// Processing function for a transaction type, applied to the client's account;
+/// Implemented for any `Fn(&mut Accounting, &mut Account, &Transaction)`.
+pub trait TxHandler: Send + Sync {
+    fn process(&self, accounting: &mut Accounting, account: &mut Account, tx: &Transaction);
+}
+
+impl<F> TxHandler for F
+where
+    F: Fn(&mut Accounting, &mut Account, &Transaction) + Send + Sync,
+{
+    fn process(&self, accounting: &mut Accounting, account: &mut Account, tx: &Transaction) {
+        self(accounting, account, tx)
+    }
+}
+
+/// Registry of the processing function per transaction type, pre-populated with the built-in types,
+/// and of the custom transaction type names parsed from input files;
+#[derive(Clone)]
+pub struct TxHandlers {
+    handlers: HashMap<TxType, Arc<dyn TxHandler>>,
+    custom: BTreeSet<String>,
+}
+
+impl Default for TxHandlers {
+    fn default() -> Self {
+        let mut handlers: HashMap<TxType, Arc<dyn TxHandler>> = HashMap::new();
+        handlers.insert(TxType::Deposit, Arc::new(process_recorded_deposit));
+        handlers.insert(TxType::Withdrawal, Arc::new(process_recorded_withdrawal));
+        handlers.insert(TxType::Dispute, Arc::new(Accounting::process_dispute));
+        handlers.insert(TxType::Resolve, Arc::new(Accounting::process_resolve));
+        handlers.insert(TxType::Chargeback, Arc::new(Accounting::process_chargeback));
+        handlers.insert(TxType::Reserve, Arc::new(Accounting::process_reserve));
+        handlers.insert(TxType::Release, Arc::new(Accounting::process_release));
+        handlers.insert(TxType::Fill, Arc::new(Accounting::process_fill));
+        handlers.insert(TxType::Reversal, Arc::new(Accounting::process_reversal));
+        handlers.insert(TxType::Adjustment, Arc::new(Accounting::process_adjustment));
+
+        TxHandlers {
+            handlers,
+            custom: BTreeSet::new(),
+        }
+    }
+}
+
+impl fmt::Debug for TxHandlers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set()
+            .entries(self.handlers.keys().map(TxType::as_str))
+            .finish()
+    }
+}
+
+// NOTE: Only deposits and withdrawals are recorded;
+// If it is part of dispute resolution, the tx id is the same as the deposit tx id;
+// Transaction ids are never reused, so a dispute always references the first record;
+// Ignored rows are recorded as rejected, reserving the id without making them disputable;
+fn process_recorded_deposit(accounting: &mut Accounting, account: &mut Account, tx: &Transaction) {
+    if accounting.check_duplicate_tx_id(tx) {
+        return;
+    }
+
+    accounting.process_deposit(account, tx);
+    accounting.insert_transaction(tx.clone(), recorded_state(accounting));
+}
+
+fn process_recorded_withdrawal(
+    accounting: &mut Accounting,
+    account: &mut Account,
+    tx: &Transaction,
+) {
+    if accounting.check_duplicate_tx_id(tx) {
+        return;
+    }
+
+    accounting.process_withdrawal(account, tx);
+    accounting.insert_transaction(tx.clone(), recorded_state(accounting));
+}
+
+/// Returns the state to record a processed deposit or withdrawal with;
+fn recorded_state(accounting: &Accounting) -> TxState {
+    match accounting.rejection {
+        Some(_) => TxState::Rejected,
+        None => TxState::Normal,
+    }
+}
+
+impl Accounting {
+    /// Register a custom transaction type, e.g. `bonus`, so rows of that type are parsed from input
+    /// files; Registering a name twice returns the same type. Built-in names are rejected, as are
+    /// names longer than `CUSTOM_TX_TYPE_MAX_LEN` bytes.
+    pub fn register_tx_type(&mut self, name: &str) -> Result<TxType, Error> {
+        let custom = match CustomTxType::new(name) {
+            Some(custom) if name.parse::<TxType>().is_err() => custom,
+            _ => {
+                return Err(Error::InvalidArgument(format!(
+                    "invalid custom transaction type '{}'",
+                    name
+                )))
+            }
+        };
+
+        self.handlers.custom.insert(name.to_string());
+
+        Ok(TxType::Custom(custom))
+    }
+
+    /// Returns the built-in or registered custom transaction type with the name;
+    pub fn tx_type(&self, name: &str) -> Result<TxType, Error> {
+        name.parse::<TxType>().or_else(|e| {
+            match CustomTxType::new(name).filter(|_| self.handlers.custom.contains(name)) {
+                Some(custom) => Ok(TxType::Custom(custom)),
+                None => Err(e),
+            }
+        })
+    }
+
+    /// Returns the names of the registered custom transaction types;
+    #[cfg(feature = "io")]
+    pub(crate) fn custom_tx_types(&self) -> BTreeSet<String> {
+        self.handlers.custom.clone()
+    }
+
+    /// Register the processing function for a transaction type, replacing any previous one;
+    /// Use with `Accounting::register_tx_type` to add custom types without forking the crate, e.g.
+    /// bonuses.
+    pub fn register_handler<H: TxHandler + 'static>(&mut self, r#type: TxType, handler: H) {
+        self.handlers.handlers.insert(r#type, Arc::new(handler));
+    }
+
+    /// Returns true if a processing function is registered for the transaction type;
+    pub fn has_handler(&self, r#type: TxType) -> bool {
+        self.handlers.handlers.contains_key(&r#type)
+    }
+
+    /// Process the transaction and account with the handler registered for its type;
+    pub(crate) fn dispatch(&mut self, account: &mut Account, tx: &Transaction) {
+        match self.handlers.handlers.get(&tx.r#type).cloned() {
+            Some(handler) => handler.process(self, account, tx),
+            None => {
+                warn!(
+                    "Ignored tx {} for client {}: no handler for transaction type '{}'",
+                    tx.tx, tx.client, tx.r#type
+                );
+                self.ignore(tx, TransactionError::UnknownTxType(tx.r#type), || {
+                    "no handler".to_string()
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,148 @@
+#[cfg(feature = "io")]
+use std::path::PathBuf;
+
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/// Unique id of an operator hold;
+pub type HoldId = u64;
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+/// Funds held by an operator independently of the dispute flow, e.g. to freeze funds preemptively.
+pub struct Hold {
+    #[serde(rename = "hold")]
+    pub id: HoldId,
+    #[serde(rename = "client")]
+    pub client: ClientId,
+    #[serde(rename = "amount")]
+    pub amount: Amount,
+    /// Free-form reason recorded by the operator
+    #[serde(rename = "reason")]
+    pub reason: String,
+    /// Clock time at which the hold was placed
+    #[serde(rename = "placed_at")]
+    pub placed_at: Timestamp,
+}
+
+impl Accounting {
+    /// Move funds from available to held for the client, independently of any dispute;
+    /// The account must have sufficient available funds. Returns the id used to release the hold.
+    pub fn place_hold(
+        &mut self,
+        client: ClientId,
+        amount: Amount,
+        reason: &str,
+    ) -> Result<HoldId, Error> {
+        let mut account = self
+            .get_account(client)
+            .cloned()
+            .unwrap_or_else(|| Account::new(client));
+
+        if amount <= Amount::ZERO {
+            return Err(Error::InvalidArgument(format!(
+                "hold amount must be positive, got {}",
+                amount
+            )));
+        }
+
+        if !covers(account.available, amount, self.config.amount_epsilon) {
+            return Err(Error::InsufficientFunds { client, amount });
+        }
+
+        account.available = clear_dust(account.available - amount, self.config.amount_epsilon);
+        account.held += amount;
+        account.stats.observe(account.total, account.held);
+
+        self.last_hold_id += 1;
+        let hold = Hold {
+            id: self.last_hold_id,
+            client,
+            amount,
+            reason: reason.to_string(),
+            placed_at: self.now(),
+        };
+
+        info!(
+            "Placed hold {} of {} on client {}: {}",
+            hold.id, amount, client, reason
+        );
+
+        #[cfg(feature = "io")]
+        self.journal_action(JournalAction {
+            hold: Some(hold.id),
+            amount: Some(amount),
+            reason: hold.reason.clone(),
+            ..JournalAction::new(JournalActionKind::PlaceHold, client)
+        });
+
+        self.emit_change(None, &account);
+        self.insert_account(account);
+        self.holds.insert(hold.id, hold);
+
+        Ok(self.last_hold_id)
+    }
+
+    /// Release a hold, moving its funds from held back to available;
+    /// The hold is kept if the held funds no longer cover it, e.g. after a chargeback took from them.
+    pub fn release_hold(&mut self, id: HoldId) -> Result<Hold, Error> {
+        let hold = self.holds.get(&id).cloned().ok_or(Error::UnknownHold(id))?;
+
+        let mut account = self
+            .get_account(hold.client)
+            .cloned()
+            .unwrap_or_else(|| Account::new(hold.client));
+
+        if !covers(account.held, hold.amount, self.config.amount_epsilon) {
+            return Err(Error::InsufficientHeldFunds {
+                client: hold.client,
+                amount: hold.amount,
+            });
+        }
+
+        account.available += hold.amount;
+        account.held = clear_dust(account.held - hold.amount, self.config.amount_epsilon);
+        self.holds.remove(&id);
+
+        info!(
+            "Released hold {} of {} on client {}",
+            hold.id, hold.amount, hold.client
+        );
+
+        #[cfg(feature = "io")]
+        self.journal_action(JournalAction {
+            hold: Some(id),
+            ..JournalAction::new(JournalActionKind::ReleaseHold, hold.client)
+        });
+
+        self.emit_change(None, &account);
+        self.insert_account(account);
+
+        Ok(hold)
+    }
+
+    /// Iterate over all open holds, ordered by hold id;
+    pub fn holds(&self) -> impl Iterator<Item = &Hold> {
+        self.holds.values()
+    }
+
+    /// Iterate over the open holds of a client, ordered by hold id;
+    pub fn client_holds(&self, client: ClientId) -> impl Iterator<Item = &Hold> {
+        self.holds
+            .values()
+            .filter(move |hold| hold.client == client)
+    }
+
+    /// Write all open holds to a csv file;
+    #[cfg(feature = "io")]
+    pub fn write_holds_csv_file(&self, file_path: PathBuf) -> Result<(), Error> {
+        let mut sink = CsvFileSink::create(file_path, self.config.output_durability)?;
+
+        for hold in self.holds() {
+            sink.write(hold)?;
+        }
+
+        sink.close()
+    }
+}
@@ -0,0 +1,230 @@
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+#[cfg(feature = "io")]
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use serde::Serialize;
+
+use crate::*;
+
+/// Atomic counter of the `TxId` width;
+#[cfg(not(feature = "tx-id-u64"))]
+type AtomicTxId = std::sync::atomic::AtomicU32;
+#[cfg(feature = "tx-id-u64")]
+type AtomicTxId = std::sync::atomic::AtomicU64;
+
+/// Source of unique transaction ids for programmatically created transactions;
+/// Implementations must never return the same id twice.
+pub trait TxIdAllocator: Debug + Send + Sync {
+    /// Returns the next unique transaction id;
+    fn allocate(&self) -> TxId;
+}
+
+#[derive(Debug, Default, Clone)]
+/// Allocates sequential ids from an atomic counter; Clones share the same counter.
+pub struct CounterAllocator {
+    next: Arc<AtomicTxId>,
+}
+
+impl CounterAllocator {
+    /// Create an allocator whose first id is `first`;
+    pub fn new(first: TxId) -> Self {
+        CounterAllocator {
+            next: Arc::new(AtomicTxId::new(first)),
+        }
+    }
+}
+
+impl TxIdAllocator for CounterAllocator {
+    fn allocate(&self) -> TxId {
+        self.next.fetch_add(1, Ordering::SeqCst)
+    }
+}
+
+/// Number of low bits of a `SnowflakeAllocator` id holding the per-node sequence;
+pub const SNOWFLAKE_SEQUENCE_BITS: u32 = 24;
+
+#[derive(Debug, Clone)]
+/// Snowflake-style allocator for multiple independent producers: the high bits of each id hold the
+/// node id and the low `SNOWFLAKE_SEQUENCE_BITS` bits a per-node sequence, so nodes never collide.
+pub struct SnowflakeAllocator {
+    node: u8,
+    sequence: Arc<AtomicTxId>,
+}
+
+impl SnowflakeAllocator {
+    /// Create an allocator for the given node id;
+    pub fn new(node: u8) -> Self {
+        SnowflakeAllocator {
+            node,
+            sequence: Arc::new(AtomicTxId::new(0)),
+        }
+    }
+}
+
+impl TxIdAllocator for SnowflakeAllocator {
+    /// NOTE: The sequence wraps after 2^24 ids per node.
+    fn allocate(&self) -> TxId {
+        let sequence =
+            self.sequence.fetch_add(1, Ordering::SeqCst) & ((1 << SNOWFLAKE_SEQUENCE_BITS) - 1);
+        ((self.node as TxId) << SNOWFLAKE_SEQUENCE_BITS) | sequence
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+/// Export-time mapping of sparse transaction ids to dense sequential ids, assigned from 1 in
+/// ascending id order, e.g. for analytics systems performing better with dense integer keys;
+pub struct DenseTxIds {
+    ids: BTreeMap<TxId, TxId>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// Row of a dense id mapping file;
+pub struct DenseTxIdMapping {
+    pub tx: TxId,
+    pub dense_tx: TxId,
+}
+
+impl DenseTxIds {
+    /// Assign dense ids to the transaction ids; Repeated ids share a dense id.
+    pub fn new(txs: impl IntoIterator<Item = TxId>) -> Self {
+        let mut ids: BTreeMap<TxId, TxId> = txs.into_iter().map(|tx| (tx, 0)).collect();
+        for (dense, id) in (1..).zip(ids.values_mut()) {
+            *id = dense;
+        }
+
+        DenseTxIds { ids }
+    }
+
+    /// Returns the dense id of a transaction id, if mapped;
+    pub fn get(&self, tx: TxId) -> Option<TxId> {
+        self.ids.get(&tx).copied()
+    }
+
+    /// Returns the number of mapped ids;
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// Returns true if no ids are mapped;
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Returns the mapping ordered by transaction id;
+    pub fn mappings(&self) -> impl Iterator<Item = DenseTxIdMapping> + '_ {
+        self.ids.iter().map(|(tx, dense_tx)| DenseTxIdMapping {
+            tx: *tx,
+            dense_tx: *dense_tx,
+        })
+    }
+
+    /// Write the mapping to a csv file with `tx,dense_tx` columns, ordered by transaction id;
+    #[cfg(feature = "io")]
+    pub fn write_csv_file(&self, file_path: PathBuf, durability: Durability) -> Result<(), Error> {
+        let mut sink = CsvFileSink::create(file_path, durability)?;
+
+        for mapping in self.mappings() {
+            sink.write(mapping)?;
+        }
+
+        sink.close()
+    }
+}
+
+impl Accounting {
+    /// Returns a dense id mapping of the recorded deposits and withdrawals;
+    pub fn dense_tx_ids(&self) -> DenseTxIds {
+        DenseTxIds::new(self.transactions.keys().copied())
+    }
+
+    /// Write the recorded deposits and withdrawals to a csv file in the transactions schema, ordered
+    /// by tx id; With a mapping path, tx ids are replaced by dense sequential ids, and the mapping to
+    /// the original ids is written alongside. See `DenseTxIds`.
+    #[cfg(feature = "io")]
+    pub fn write_recorded_transactions_csv_file(
+        &self,
+        file_path: PathBuf,
+        dense_mapping_path: Option<PathBuf>,
+    ) -> Result<(), Error> {
+        let dense = dense_mapping_path.as_ref().map(|_| self.dense_tx_ids());
+        let mut transactions: Vec<&Transaction> = self.transactions().collect();
+        transactions.sort_by_key(|tx| tx.tx);
+
+        let mut sink = CsvFileSink::create(file_path, self.config.output_durability)?;
+        for tx in transactions {
+            let mut tx = tx.clone();
+            if let Some(dense_tx) = dense.as_ref().and_then(|dense| dense.get(tx.tx)) {
+                tx.tx = dense_tx;
+            }
+
+            sink.write(tx)?;
+        }
+        sink.close()?;
+
+        match (dense, dense_mapping_path) {
+            (Some(dense), Some(path)) => dense.write_csv_file(path, self.config.output_durability),
+            _ => Ok(()),
+        }
+    }
+
+    /// Replace the allocator used for programmatically created transactions;
+    /// By default ids are allocated sequentially after the largest recorded transaction id.
+    pub fn set_id_allocator(&mut self, allocator: Arc<dyn TxIdAllocator>) {
+        self.id_allocator = Some(allocator);
+    }
+
+    /// Returns the next unused transaction id from the allocator;
+    pub fn allocate_tx_id(&mut self) -> TxId {
+        let transactions = &self.transactions;
+        let allocator = self.id_allocator.get_or_insert_with(|| {
+            let first = transactions.keys().max().map_or(1, |max| max + 1);
+            Arc::new(CounterAllocator::new(first))
+        });
+
+        // Skip ids already used by transactions created elsewhere;
+        loop {
+            let id = allocator.allocate();
+            if !transactions.contains_key(&id) {
+                return id;
+            }
+        }
+    }
+
+    /// Deposit to the client's account with a newly allocated transaction id;
+    /// See `try_process_transaction`. Returns the transaction id.
+    pub fn deposit(&mut self, client: ClientId, amount: Amount) -> Result<TxId, Error> {
+        self.submit_new(TxType::Deposit, client, amount)
+    }
+
+    /// Withdraw from the client's account with a newly allocated transaction id;
+    /// See `try_process_transaction`. Returns the transaction id.
+    pub fn withdraw(&mut self, client: ClientId, amount: Amount) -> Result<TxId, Error> {
+        self.submit_new(TxType::Withdrawal, client, amount)
+    }
+
+    /// Process a new deposit or withdrawal with an allocated transaction id;
+    fn submit_new(
+        &mut self,
+        r#type: TxType,
+        client: ClientId,
+        amount: Amount,
+    ) -> Result<TxId, Error> {
+        let tx = self.allocate_tx_id();
+
+        self.try_process_transaction(Transaction {
+            r#type,
+            client,
+            tx,
+            amount: Some(amount),
+            timestamp: None,
+            subaccount: None,
+            category: None,
+            source: None,
+        })?;
+
+        Ok(tx)
+    }
+}
@@ -0,0 +1,225 @@
+#[cfg(feature = "io")]
+use std::fs::File;
+#[cfg(feature = "io")]
+use std::io::Read;
+#[cfg(feature = "io")]
+use std::path::PathBuf;
+
+#[cfg(feature = "io")]
+use csv::{Reader, ReaderBuilder, StringRecord, Trim};
+
+use crate::*;
+
+/// The columns of the transactions csv schema; `type`, `client` and `tx` are required.
+pub const TRANSACTION_COLUMNS: [&str; 7] = [
+    "type",
+    "client",
+    "tx",
+    "amount",
+    "timestamp",
+    "category",
+    "source",
+];
+
+/// Layout of input transactions csv files, for producers that deviate from the standard schema;
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputFormat {
+    /// Field delimiter, `,` by default
+    pub delimiter: u8,
+    /// Trim whitespace around headers and fields
+    pub trim: bool,
+    /// Character encoding, detected from a byte order mark or UTF-16 NUL bytes if not set
+    pub encoding: Option<Encoding>,
+    /// Header renames as `(column, header)` pairs, e.g. `("type", "kind")` reads the `kind`
+    /// column as the transaction type
+    pub column_map: Vec<(String, String)>,
+    /// Skip lines starting with this character, e.g. `#`; Enabled with `#` in lenient mode
+    pub comment: Option<u8>,
+    /// Skip lines whose fields are all empty or whitespace; Enabled in lenient mode
+    pub skip_blank_lines: bool,
+    /// Read amounts with a decimal comma, e.g. `1.234,56`; Dots are read as thousands separators
+    pub decimal_comma: bool,
+}
+
+impl Default for InputFormat {
+    fn default() -> Self {
+        InputFormat {
+            delimiter: b',',
+            trim: false,
+            encoding: None,
+            column_map: Vec::new(),
+            comment: None,
+            skip_blank_lines: false,
+            decimal_comma: false,
+        }
+    }
+}
+
+impl InputFormat {
+    /// Parse a delimiter given as a single ascii character, or `tab`;
+    pub fn parse_delimiter(s: &str) -> Result<u8, Error> {
+        match s {
+            "tab" | "\\t" => Ok(b'\t'),
+            s if s.len() == 1 && s.is_ascii() => Ok(s.as_bytes()[0]),
+            _ => Err(Error::InvalidArgument(format!(
+                "invalid delimiter '{}'; expected a single character or 'tab'",
+                s
+            ))),
+        }
+    }
+
+    /// Returns the format with blank lines and `#` comment lines skipped, unless another comment
+    /// character is set; Applied to transactions files in `Strictness::Lenient` mode.
+    pub fn lenient(mut self) -> Self {
+        self.comment.get_or_insert(b'#');
+        self.skip_blank_lines = true;
+        self
+    }
+
+    /// Read the column from a differently named header, given as `column=header`;
+    pub fn map_column(&mut self, spec: &str) -> Result<(), Error> {
+        let (column, header) = spec
+            .split_once('=')
+            .filter(|(column, header)| TRANSACTION_COLUMNS.contains(column) && !header.is_empty())
+            .ok_or_else(|| {
+                Error::InvalidArgument(format!(
+                    "invalid column mapping '{}'; expected <column>=<header> with a column among {}",
+                    spec,
+                    TRANSACTION_COLUMNS.join(", ")
+                ))
+            })?;
+
+        self.column_map.retain(|(mapped, _)| mapped != column);
+        self.column_map
+            .push((column.to_string(), header.to_string()));
+        Ok(())
+    }
+
+    /// Create a csv reader applying the format, decoding the input to UTF-8 and renaming mapped
+    /// headers to their columns;
+    #[cfg(feature = "io")]
+    pub fn reader<R: Read>(&self, rdr: R) -> Result<Reader<DecodedReader<R>>, Error> {
+        self.build_reader(rdr, false)
+    }
+
+    /// Create a csv reader applying the format that also accepts rows with a different number of fields;
+    #[cfg(feature = "io")]
+    pub(crate) fn flexible_reader<R: Read>(
+        &self,
+        rdr: R,
+    ) -> Result<Reader<DecodedReader<R>>, Error> {
+        self.build_reader(rdr, true)
+    }
+
+    #[cfg(feature = "io")]
+    fn build_reader<R: Read>(
+        &self,
+        rdr: R,
+        flexible: bool,
+    ) -> Result<Reader<DecodedReader<R>>, Error> {
+        let mut reader = ReaderBuilder::new()
+            .delimiter(self.delimiter)
+            .trim(if self.trim { Trim::All } else { Trim::None })
+            .comment(self.comment)
+            // Blank lines have a single field, so are only skipped once read;
+            .flexible(flexible || self.skip_blank_lines)
+            .from_reader(DecodedReader::new(rdr, self.encoding));
+
+        if !self.column_map.is_empty() {
+            let headers: StringRecord = reader
+                .headers()?
+                .iter()
+                .map(|header| {
+                    self.column_map
+                        .iter()
+                        .find(|(_, mapped)| mapped == header)
+                        .map_or(header, |(column, _)| column.as_str())
+                })
+                .collect();
+            reader.set_headers(headers);
+        }
+
+        Ok(reader)
+    }
+}
+
+/// Returns the amount with a decimal comma rewritten to a decimal point, e.g. `1.234,56` to `1234.56`;
+#[cfg(feature = "io")]
+pub(crate) fn decimal_comma_amount(amount: &str) -> String {
+    amount
+        .chars()
+        .filter(|c| *c != '.')
+        .map(|c| if c == ',' { '.' } else { c })
+        .collect()
+}
+
+/// Returns the record with the amount field rewritten from a decimal comma, if the column exists;
+#[cfg(feature = "io")]
+pub(crate) fn decimal_comma_record(
+    record: &StringRecord,
+    amount_column: Option<usize>,
+) -> StringRecord {
+    let mut rewritten: StringRecord = record
+        .iter()
+        .enumerate()
+        .map(|(i, field)| match amount_column {
+            Some(column) if column == i => decimal_comma_amount(field),
+            _ => field.to_string(),
+        })
+        .collect();
+
+    // Keep the position, so parse errors report the line;
+    rewritten.set_position(record.position().cloned());
+    rewritten
+}
+
+/// Returns true if every field of the record is empty or whitespace;
+#[cfg(feature = "io")]
+pub(crate) fn is_blank_record(record: &StringRecord) -> bool {
+    record.iter().all(|field| field.trim().is_empty())
+}
+
+impl Accounting {
+    /// Returns the input format applied to transactions files, including the lenient mode defaults;
+    pub fn input_format(&self) -> InputFormat {
+        match self.config.strictness {
+            Strictness::Lenient => self.config.input.clone().lenient(),
+            _ => self.config.input.clone(),
+        }
+    }
+
+    /// Open a transactions csv file, applying the input format;
+    #[cfg(feature = "io")]
+    pub(crate) fn open_transactions_csv(
+        &self,
+        file_path: PathBuf,
+    ) -> Result<Reader<DecodedReader<File>>, Error> {
+        let file = File::open(file_path)?;
+
+        // Refuse oversized inputs before reading any row;
+        if let Some(max) = self.config.max_file_size {
+            let size = file.metadata()?.len();
+            if size > max {
+                return Err(Error::InputLimitExceeded {
+                    limit: "file size",
+                    max,
+                });
+            }
+        }
+
+        self.input_format().reader(file)
+    }
+
+    /// Iterate over the parsed rows of a transactions csv file without processing them, e.g. to filter
+    /// or enrich transactions before `process_transaction`; The input format, partition and limits
+    /// apply, but enrichers do not. Rows that fail to parse, including unknown transaction type
+    /// names, are returned as `RowError`s with their line.
+    #[cfg(feature = "io")]
+    pub fn csv_rows(
+        &self,
+        file_path: PathBuf,
+    ) -> Result<impl Iterator<Item = Result<Transaction, RowError>>, Error> {
+        let file = self.open_transactions_csv(file_path)?;
+        self.transaction_rows(file)
+    }
+}
@@ -0,0 +1,222 @@
+use std::fmt;
+#[cfg(feature = "io")]
+use std::io::Write;
+#[cfg(feature = "io")]
+use std::path::PathBuf;
+
+#[cfg(feature = "io")]
+use csv::Writer;
+
+use crate::*;
+
+#[derive(Debug)]
+/// Read-only view over persisted state, answering account and dispute queries without replaying
+/// transactions; See `Accounting::open_readonly`.
+pub struct ReadOnlyAccounting {
+    accounting: Accounting,
+}
+
+impl ReadOnlyAccounting {
+    /// Returns the account of a client, if any;
+    pub fn get_account(&self, client: ClientId) -> Option<&Account> {
+        self.accounting.get_account(client)
+    }
+
+    /// Iterate over all accounts, in no particular order;
+    pub fn accounts(&self) -> impl Iterator<Item = &Account> {
+        self.accounting.accounts()
+    }
+
+    /// Returns the open dispute of a transaction, if any;
+    pub fn get_dispute(&self, tx: TxId) -> Option<&Dispute> {
+        self.accounting.get_dispute(tx)
+    }
+
+    /// Iterate over the open disputes of a client, or all clients, ordered by transaction id;
+    pub fn disputes(&self, client: Option<ClientId>) -> impl Iterator<Item = &Dispute> {
+        self.accounting
+            .disputes()
+            .filter(move |dispute| client.is_none_or(|client| dispute.client == client))
+    }
+
+    /// Iterate over the open holds of a client, ordered by hold id;
+    pub fn client_holds(&self, client: ClientId) -> impl Iterator<Item = &Hold> {
+        self.accounting.client_holds(client)
+    }
+
+    /// Write the accounts of a client, or all clients, as csv to any writer;
+    #[cfg(feature = "io")]
+    pub fn write_accounts_csv<W: Write>(
+        &self,
+        writer: W,
+        client: Option<ClientId>,
+    ) -> Result<(), Error> {
+        let mut wtr = Writer::from_writer(writer);
+
+        for account in self.accounts() {
+            if client.is_none_or(|client| account.client == client) {
+                let mut account = account.clone();
+                account.round_balances();
+                wtr.serialize(account)?;
+            }
+        }
+
+        wtr.flush()?;
+        Ok(())
+    }
+
+    /// Returns the formatted single-account view of a client, if the client has an account;
+    pub fn account_report(&self, client: ClientId, recent: usize) -> Option<AccountReport> {
+        self.accounting.account_report(client, recent)
+    }
+
+    /// Write the open disputes of a client, or all clients, as csv to any writer;
+    #[cfg(feature = "io")]
+    pub fn write_disputes_csv<W: Write>(
+        &self,
+        writer: W,
+        client: Option<ClientId>,
+    ) -> Result<(), Error> {
+        let mut wtr = Writer::from_writer(writer);
+
+        for dispute in self.disputes(client) {
+            wtr.serialize(dispute)?;
+        }
+
+        wtr.flush()?;
+        Ok(())
+    }
+}
+
+/// Default number of recent transactions included in an `AccountReport`;
+pub const DEFAULT_REPORT_TRANSACTIONS: usize = 10;
+
+/// Format an amount with four decimal places and thousands separators, e.g. `-1,234.5000`;
+pub fn format_amount(amount: Amount) -> String {
+    let formatted = format!("{:.4}", amount.abs().round_dp(AMOUNT_DECIMAL_PLACES as u32));
+    let (whole, fraction) = formatted.split_at(formatted.len() - 5);
+
+    let mut grouped = String::new();
+    for (i, digit) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+
+    // Amounts rounding to zero are not signed;
+    let sign = if amount < Amount::ZERO && formatted != "0.0000" {
+        "-"
+    } else {
+        ""
+    };
+
+    format!("{}{}{}", sign, grouped, fraction)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Single-account view for support requests: balances, lock status, open disputes and holds, and
+/// the most recent deposits and withdrawals; `Display` renders it as a formatted summary.
+pub struct AccountReport {
+    pub account: Account,
+    pub disputes: Vec<Dispute>,
+    pub holds: Vec<Hold>,
+    /// Most recent deposits and withdrawals first, ordered by timestamp then transaction id
+    pub recent: Vec<Transaction>,
+}
+
+impl fmt::Display for AccountReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let account = &self.account;
+
+        write!(f, "Client {}", account.client)?;
+        match (account.locked, account.lock_reason) {
+            (true, Some(reason)) => writeln!(f, " (LOCKED: {})", reason)?,
+            (true, None) => writeln!(f, " (LOCKED)")?,
+            (false, _) => writeln!(f)?,
+        }
+
+        writeln!(f, "  Available  {:>20}", format_amount(account.available))?;
+        writeln!(f, "  Held       {:>20}", format_amount(account.held))?;
+        writeln!(f, "  Total      {:>20}", format_amount(account.total))?;
+
+        writeln!(f, "Open disputes ({})", self.disputes.len())?;
+        for dispute in self.disputes.iter() {
+            writeln!(
+                f,
+                "  tx {:<10} {:>20}  opened {}",
+                dispute.tx,
+                format_amount(dispute.amount),
+                format_date(dispute.opened_at)
+            )?;
+        }
+
+        if !self.holds.is_empty() {
+            writeln!(f, "Holds ({})", self.holds.len())?;
+            for hold in self.holds.iter() {
+                writeln!(
+                    f,
+                    "  hold {:<8} {:>20}  {}",
+                    hold.id,
+                    format_amount(hold.amount),
+                    hold.reason
+                )?;
+            }
+        }
+
+        writeln!(f, "Recent transactions ({})", self.recent.len())?;
+        for tx in self.recent.iter() {
+            writeln!(
+                f,
+                "  tx {:<10} {:<10} {:>20}  {}",
+                tx.tx,
+                tx.r#type.as_str(),
+                tx.amount.map(format_amount).unwrap_or_default(),
+                tx.timestamp
+                    .map(format_date)
+                    .unwrap_or_else(|| "-".to_string())
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Accounting {
+    /// Returns the single-account view of a client with up to `recent` recent transactions, if the
+    /// client has an account;
+    pub fn account_report(&self, client: ClientId, recent: usize) -> Option<AccountReport> {
+        let account = self.get_account(client)?.clone();
+
+        let mut transactions: Vec<&Transaction> = self
+            .transactions()
+            .filter(|tx| tx.client == client)
+            .collect();
+        transactions.sort_by_key(|tx| (tx.timestamp, tx.tx));
+
+        Some(AccountReport {
+            account,
+            disputes: self
+                .disputes()
+                .filter(|dispute| dispute.client == client)
+                .cloned()
+                .collect(),
+            holds: self.client_holds(client).cloned().collect(),
+            recent: transactions
+                .into_iter()
+                .rev()
+                .take(recent)
+                .cloned()
+                .collect(),
+        })
+    }
+
+    /// Load a snapshot file read-only, for queries without reprocessing transactions;
+    #[cfg(feature = "io")]
+    pub fn open_readonly(file_path: PathBuf) -> Result<ReadOnlyAccounting, Error> {
+        let mut accounting = Accounting::init();
+        accounting.read_snapshot_file(file_path)?;
+
+        Ok(ReadOnlyAccounting { accounting })
+    }
+}
@@ -0,0 +1,133 @@
+#[cfg(feature = "io")]
+use std::path::PathBuf;
+
+use log::debug;
+use rust_decimal::RoundingStrategy;
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/// Category tagging the deposits generated for interest postings;
+pub const INTEREST_CATEGORY: &str = "interest";
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+/// Daily interest accrual on available balances; See `AccountingConfig::interest`.
+pub struct InterestConfig {
+    /// Interest accrued per day, relative to the available balance, e.g. `0.0001` for 0.01%
+    pub daily_rate: Amount,
+    /// Post the accrued interest to the available balance every N days since the unix epoch, after
+    /// which it bears interest itself; `1` compounds daily.
+    pub compound_days: Timestamp,
+}
+
+impl Default for InterestConfig {
+    fn default() -> Self {
+        InterestConfig {
+            daily_rate: Amount::ZERO,
+            compound_days: 1,
+        }
+    }
+}
+
+impl Accounting {
+    /// Accrue the client's interest for every whole day since its last accrual, up to the given time,
+    /// posting it as `interest` deposits at the end of each compounding period;
+    /// Interest accrues on positive available balances of unlocked accounts. Called with the time of
+    /// each transaction before it is processed, when `AccountingConfig::interest` is set.
+    /// NOTE: Postings take transaction ids counting down from `TxId::MAX`, apart from input ids, and
+    /// cannot be disputed.
+    pub fn accrue_interest(&mut self, client: ClientId, now: Timestamp) {
+        let config = match self.config.interest.as_ref() {
+            Some(config) => config.clone(),
+            None => return,
+        };
+        let today = now / SECONDS_PER_DAY;
+
+        let (mut day, mut accrued) = match self.interest_accrual.get(&client) {
+            Some(state) => *state,
+            None => {
+                self.interest_accrual.insert(client, (today, Amount::ZERO));
+                return;
+            }
+        };
+
+        let mut account = match self.accounts.remove(&client) {
+            Some(account) if day < today => account,
+            Some(account) => {
+                self.accounts.insert(client, account);
+                return;
+            }
+            None => return,
+        };
+
+        let mut posted = false;
+        while day < today {
+            if !account.locked && account.available > Amount::ZERO {
+                accrued += account.available * config.daily_rate;
+            }
+            day += 1;
+
+            // Post whole ten-thousandths, carrying the remainder into the next period;
+            let amount = accrued
+                .round_dp_with_strategy(AMOUNT_DECIMAL_PLACES as u32, RoundingStrategy::ToZero)
+                .normalize();
+            if day % config.compound_days.max(1) == 0 && amount > Amount::ZERO {
+                account.available += amount;
+                account.total += amount;
+                accrued -= amount;
+                posted = true;
+
+                let tx = Transaction {
+                    r#type: TxType::Deposit,
+                    client,
+                    tx: TxId::MAX - self.interest_postings.len() as TxId,
+                    amount: Some(amount),
+                    timestamp: Some(day * SECONDS_PER_DAY),
+                    subaccount: None,
+                    category: Some(INTEREST_CATEGORY.to_string()),
+                    source: None,
+                };
+                debug!(
+                    "Posted interest of {} to client {} as tx {}",
+                    amount, client, tx.tx
+                );
+
+                self.interest_postings.push(tx);
+            }
+        }
+
+        if posted {
+            account.stats.observe(account.total, account.held);
+            self.emit_change(None, &account);
+        }
+
+        self.accounts.insert(client, account);
+        self.interest_accrual.insert(client, (today, accrued));
+    }
+
+    /// Accrue the interest of every account up to the given time, e.g. at the end of a run;
+    pub fn accrue_all_interest(&mut self, now: Timestamp) {
+        let clients: Vec<ClientId> = self.accounts.keys().copied().collect();
+
+        for client in clients {
+            self.accrue_interest(client, now);
+        }
+    }
+
+    /// Returns the generated interest deposits, in the order posted;
+    pub fn interest_postings(&self) -> &[Transaction] {
+        &self.interest_postings
+    }
+
+    /// Write the generated interest deposits to a csv file;
+    #[cfg(feature = "io")]
+    pub fn write_interest_postings_csv_file(&self, file_path: PathBuf) -> Result<(), Error> {
+        let mut sink = CsvFileSink::create(file_path, self.config.output_durability)?;
+
+        for tx in self.interest_postings.iter() {
+            sink.write(tx)?;
+        }
+
+        sink.close()
+    }
+}
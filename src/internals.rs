@@ -0,0 +1,50 @@
+use crate::*;
+
+/// Low-level processing helpers applying a single transaction type to an account, without the
+/// checks, recording and bookkeeping of `Accounting::process_transaction`; Intended for custom
+/// `TxHandler`s. The caller must pass the client's account, taken out of the engine, and the
+/// helpers are exempt from semver guarantees.
+pub trait AccountingInternals {
+    fn process_deposit(&mut self, account: &mut Account, tx: &Transaction);
+    fn process_withdrawal(&mut self, account: &mut Account, tx: &Transaction);
+    fn process_dispute(&mut self, account: &mut Account, tx: &Transaction);
+    fn process_resolve(&mut self, account: &mut Account, tx: &Transaction);
+    fn process_chargeback(&mut self, account: &mut Account, tx: &Transaction);
+    fn process_reserve(&mut self, account: &mut Account, tx: &Transaction);
+    fn process_release(&mut self, account: &mut Account, tx: &Transaction);
+    fn process_fill(&mut self, account: &mut Account, tx: &Transaction);
+}
+
+impl AccountingInternals for Accounting {
+    fn process_deposit(&mut self, account: &mut Account, tx: &Transaction) {
+        Accounting::process_deposit(self, account, tx)
+    }
+
+    fn process_withdrawal(&mut self, account: &mut Account, tx: &Transaction) {
+        Accounting::process_withdrawal(self, account, tx)
+    }
+
+    fn process_dispute(&mut self, account: &mut Account, tx: &Transaction) {
+        Accounting::process_dispute(self, account, tx)
+    }
+
+    fn process_resolve(&mut self, account: &mut Account, tx: &Transaction) {
+        Accounting::process_resolve(self, account, tx)
+    }
+
+    fn process_chargeback(&mut self, account: &mut Account, tx: &Transaction) {
+        Accounting::process_chargeback(self, account, tx)
+    }
+
+    fn process_reserve(&mut self, account: &mut Account, tx: &Transaction) {
+        Accounting::process_reserve(self, account, tx)
+    }
+
+    fn process_release(&mut self, account: &mut Account, tx: &Transaction) {
+        Accounting::process_release(self, account, tx)
+    }
+
+    fn process_fill(&mut self, account: &mut Account, tx: &Transaction) {
+        Accounting::process_fill(self, account, tx)
+    }
+}
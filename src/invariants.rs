@@ -0,0 +1,107 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+use crate::*;
+
+/// Number of recently processed transactions kept for invariant violation reports;
+pub const INVARIANT_HISTORY_LEN: usize = 32;
+
+#[derive(Debug, Clone, PartialEq)]
+/// Dump of an account failing an invariant check, with the transactions leading up to it.
+pub struct InvariantViolation {
+    /// Description of the violated invariant;
+    pub invariant: String,
+    /// The offending account, as of the check;
+    pub account: Account,
+    /// The transaction processed immediately before the check;
+    pub tx: Option<Transaction>,
+    /// Recently processed transactions of the offending client, oldest first;
+    pub history: Vec<Transaction>,
+}
+
+impl fmt::Display for InvariantViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", self.invariant)?;
+        writeln!(f, "  account: {:?}", self.account)?;
+        writeln!(f, "  last tx: {:?}", self.tx)?;
+        write!(f, "  recent client history:")?;
+        for tx in self.history.iter() {
+            write!(f, "\n    {:?}", tx)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+/// State of the invariant auto-check mode; See `AccountingConfig::invariant_check_interval`.
+pub(crate) struct InvariantChecker {
+    /// Transactions processed since the last check;
+    pub(crate) processed: usize,
+    /// The most recently processed transactions, oldest first;
+    pub(crate) recent: VecDeque<Transaction>,
+}
+
+impl InvariantChecker {
+    /// Record a processed transaction; Returns true if a check is due.
+    pub(crate) fn observe(&mut self, tx: &Transaction, interval: usize) -> bool {
+        if self.recent.len() == INVARIANT_HISTORY_LEN {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(tx.clone());
+
+        self.processed += 1;
+        if self.processed >= interval {
+            self.processed = 0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl Accounting {
+    /// Validate the invariants of every account:
+    /// `total == available + held`, non-negative available (unless disputes may hold more than the
+    /// available funds) and held funds,
+    /// and operator holds not exceeding the held funds (all within `amount_epsilon`);
+    pub fn check_invariants(&self) -> Result<(), Error> {
+        let epsilon = self.config.amount_epsilon;
+
+        for account in self.accounts.values() {
+            let client_holds: Amount = self.client_holds(account.client).map(|h| h.amount).sum();
+
+            let invariant = if !approx_eq(account.total, account.available + account.held, epsilon)
+            {
+                "total does not equal available + held"
+            } else if account.available < -epsilon
+                && self.config.dispute_funds_policy == DisputeFundsPolicy::Ignore
+            {
+                "available funds are negative"
+            } else if account.held < -epsilon {
+                "held funds are negative"
+            } else if !covers(account.held, client_holds, epsilon) {
+                "operator holds exceed held funds"
+            } else {
+                continue;
+            };
+
+            return Err(Error::InvariantViolation(Box::new(InvariantViolation {
+                invariant: format!(
+                    "Invariant violated for client {}: {}",
+                    account.client, invariant
+                ),
+                account: account.clone(),
+                tx: self.invariants.recent.back().cloned(),
+                history: self
+                    .invariants
+                    .recent
+                    .iter()
+                    .filter(|tx| self.client_remapper.map(tx.client) == account.client)
+                    .cloned()
+                    .collect(),
+            })));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,678 @@
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use csv::{Reader, WriterBuilder};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/// File name of the journal segment index within the journal directory;
+pub const JOURNAL_INDEX_FILE: &str = "journal.index";
+
+/// File name of the lock held by the process writing to the journal directory;
+pub const JOURNAL_LOCK_FILE: &str = "journal.lock";
+
+/// Rotation and durability options for a `Journal`.
+#[derive(Debug, Clone)]
+pub struct JournalConfig {
+    /// Rotate to a new segment once the current segment reaches this size in bytes;
+    pub max_segment_bytes: u64,
+    /// Rotate to a new segment once the current segment is older than this many seconds;
+    pub max_segment_age: Option<Timestamp>,
+    /// Durability guarantees for journal writes;
+    pub durability: Durability,
+    /// Open the journal without taking its lock, even if another process holds it;
+    pub force: bool,
+}
+
+impl Default for JournalConfig {
+    fn default() -> Self {
+        JournalConfig {
+            max_segment_bytes: 64 * 1024 * 1024,
+            max_segment_age: None,
+            durability: Durability::Buffered,
+            force: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+/// A row of the journal index, describing one segment file.
+pub struct JournalSegment {
+    /// Sequence number of the segment, starting at 1
+    #[serde(rename = "segment")]
+    pub segment: u32,
+    /// File name of the segment within the journal directory, e.g. `journal.0001.log`
+    #[serde(rename = "file")]
+    pub file: String,
+    /// Number of transactions written to the segment
+    #[serde(rename = "records")]
+    pub records: u64,
+    /// Clock time at which the segment was opened
+    #[serde(rename = "opened_at")]
+    pub opened_at: Timestamp,
+    /// Whether the segment has been rotated and will not be written to again
+    #[serde(rename = "closed")]
+    pub closed: bool,
+}
+
+impl JournalSegment {
+    /// Returns the file name of the segment with the given sequence number;
+    pub fn file_name(segment: u32) -> String {
+        format!("journal.{:04}.log", segment)
+    }
+
+    /// Returns the file name of the accounting configuration the segment was recorded with;
+    pub fn config_file_name(segment: u32) -> String {
+        format!("journal.{:04}.config", segment)
+    }
+
+    /// Returns the file name of the operator actions recorded in the segment;
+    pub fn actions_file_name(segment: u32) -> String {
+        format!("journal.{:04}.actions", segment)
+    }
+}
+
+/// Operator action recorded in a journal segment; See `JournalAction`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JournalActionKind {
+    /// `Accounting::place_hold`
+    PlaceHold,
+    /// `Accounting::release_hold`
+    ReleaseHold,
+    /// `Accounting::close_account`
+    Close,
+    /// `Accounting::lock_account_with_reason` locking the account
+    Lock,
+    /// `Accounting::lock_account_with_reason` unlocking the account
+    Unlock,
+    /// `Accounting::flag_account`
+    Flag,
+    /// `Accounting::clear_account_flag`, also called by `Accounting::release_quarantined`
+    ClearFlag,
+}
+
+impl fmt::Display for JournalActionKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            JournalActionKind::PlaceHold => "place_hold",
+            JournalActionKind::ReleaseHold => "release_hold",
+            JournalActionKind::Close => "close",
+            JournalActionKind::Lock => "lock",
+            JournalActionKind::Unlock => "unlock",
+            JournalActionKind::Flag => "flag",
+            JournalActionKind::ClearFlag => "clear_flag",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+/// A row of a segment's actions file, recording an operator action applied between transactions.
+pub struct JournalAction {
+    /// Number of transactions written to the segment before the action
+    #[serde(rename = "after")]
+    pub after: u64,
+    #[serde(rename = "action")]
+    pub action: JournalActionKind,
+    #[serde(rename = "client")]
+    pub client: ClientId,
+    /// Hold placed or released
+    #[serde(rename = "hold")]
+    pub hold: Option<HoldId>,
+    /// Amount of a placed hold
+    #[serde(rename = "amount")]
+    pub amount: Option<Amount>,
+    /// Account credited with the residual funds of a closed account; Empty if they were written off
+    #[serde(rename = "target")]
+    pub target: Option<ClientId>,
+    /// Why the account was locked or unlocked
+    #[serde(rename = "lock_reason")]
+    pub lock_reason: Option<LockReason>,
+    /// Reason of a hold, or detail of a lock
+    #[serde(rename = "reason")]
+    pub reason: String,
+    /// Clock time of the action
+    #[serde(rename = "at")]
+    pub at: Timestamp,
+}
+
+impl JournalAction {
+    /// Returns an action on the client with no other fields set;
+    pub fn new(action: JournalActionKind, client: ClientId) -> Self {
+        JournalAction {
+            after: 0,
+            action,
+            client,
+            hold: None,
+            amount: None,
+            target: None,
+            lock_reason: None,
+            reason: String::new(),
+            at: 0,
+        }
+    }
+}
+
+/// Append-only log of incoming transactions and operator actions, split into rotated segment files
+/// with an index; Each segment records the accounting configuration it was written with, so
+/// replaying the segments in order onto the state they were recorded against reproduces the state.
+#[derive(Debug)]
+pub struct Journal {
+    dir: PathBuf,
+    config: JournalConfig,
+    segments: Vec<JournalSegment>,
+    writer: BufWriter<File>,
+    segment_bytes: u64,
+    pending: usize,
+    /// Accounting configuration recorded for each new segment, once set by `record_config`
+    accounting_config: Option<AccountingConfig>,
+    /// Number of operator actions recorded in the current segment
+    segment_actions: u64,
+    /// Lock on the journal directory, unless opened with `JournalConfig::force`
+    _lock: Option<FileLock>,
+}
+
+impl Journal {
+    /// Open a journal in the directory, creating it if necessary;
+    /// A new segment is always started, continuing the numbering of any existing segments. Returns
+    /// `Error::AlreadyInUse` if another process has the journal open, unless `JournalConfig::force`.
+    pub fn open(dir: PathBuf, config: JournalConfig, now: Timestamp) -> Result<Self, Error> {
+        fs::create_dir_all(&dir)?;
+
+        let lock = match config.force {
+            true => None,
+            false => Some(FileLock::acquire(dir.join(JOURNAL_LOCK_FILE))?),
+        };
+
+        let mut segments = Journal::read_index(&dir)?;
+
+        // Any segment left open by a previous process is closed;
+        for segment in segments.iter_mut() {
+            segment.closed = true;
+        }
+
+        let next = segments.last().map(|s| s.segment + 1).unwrap_or(1);
+        let (writer, segment_bytes) = Journal::create_segment(&dir, next)?;
+
+        segments.push(JournalSegment {
+            segment: next,
+            file: JournalSegment::file_name(next),
+            records: 0,
+            opened_at: now,
+            closed: false,
+        });
+
+        let journal = Journal {
+            dir,
+            config,
+            segments,
+            writer,
+            segment_bytes,
+            pending: 0,
+            accounting_config: None,
+            segment_actions: 0,
+            _lock: lock,
+        };
+        journal.write_index()?;
+
+        Ok(journal)
+    }
+
+    /// Returns the journal directory;
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns the segments recorded in the index, oldest first;
+    pub fn segments(&self) -> &[JournalSegment] {
+        &self.segments
+    }
+
+    /// Append a transaction to the current segment, rotating first if the segment is full or expired;
+    pub fn append(&mut self, tx: &Transaction, now: Timestamp) -> Result<(), Error> {
+        if self.should_rotate(now) {
+            self.rotate(now)?;
+        }
+
+        let mut wtr = WriterBuilder::new().has_headers(false).from_writer(vec![]);
+        wtr.serialize(tx)?;
+        let record = wtr.into_inner().map_err(Box::new)?;
+
+        self.writer.write_all(&record)?;
+        self.segment_bytes += record.len() as u64;
+        self.pending += 1;
+
+        if let Some(segment) = self.segments.last_mut() {
+            segment.records += 1;
+        }
+
+        if let Durability::FsyncEvery(n) = self.config.durability {
+            if self.pending >= n {
+                self.sync()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Record an operator action applied after the transactions appended to the current segment so
+    /// far; `JournalAction::after` is set accordingly.
+    pub fn append_action(&mut self, action: JournalAction) -> Result<(), Error> {
+        let segment = match self.segments.last() {
+            Some(segment) => segment,
+            None => return Ok(()),
+        };
+        let action = JournalAction {
+            after: segment.records,
+            ..action
+        };
+        let path = self
+            .dir
+            .join(JournalSegment::actions_file_name(segment.segment));
+
+        // The transactions the action follows are written out first;
+        self.writer.flush()?;
+
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        let mut wtr = WriterBuilder::new()
+            .has_headers(file.metadata()?.len() == 0)
+            .from_writer(vec![]);
+        wtr.serialize(&action)?;
+        file.write_all(&wtr.into_inner().map_err(Box::new)?)?;
+
+        if self.config.durability != Durability::Buffered {
+            file.sync_data()?;
+        }
+
+        self.segment_actions += 1;
+
+        Ok(())
+    }
+
+    /// Record the accounting configuration transactions are processed with from now on;
+    /// Rotates to a new segment first unless the current segment is still empty, and records the
+    /// configuration for every later segment.
+    pub fn record_config(
+        &mut self,
+        config: &AccountingConfig,
+        now: Timestamp,
+    ) -> Result<(), Error> {
+        let empty = self
+            .segments
+            .last()
+            .is_none_or(|segment| segment.records == 0);
+
+        self.accounting_config = Some(config.clone());
+
+        if empty && self.segment_actions == 0 {
+            self.write_config()
+        } else {
+            self.rotate(now)
+        }
+    }
+
+    /// Write the recorded accounting configuration for the current segment;
+    fn write_config(&self) -> Result<(), Error> {
+        let (config, segment) = match (self.accounting_config.as_ref(), self.segments.last()) {
+            (Some(config), Some(segment)) => (config, segment),
+            _ => return Ok(()),
+        };
+
+        let file = File::create(
+            self.dir
+                .join(JournalSegment::config_file_name(segment.segment)),
+        )?;
+        let mut wtr = BufWriter::new(file);
+        bincode::serialize_into(&mut wtr, config)?;
+        wtr.flush()?;
+
+        if self.config.durability != Durability::Buffered {
+            wtr.get_ref().sync_all()?;
+        }
+
+        Ok(())
+    }
+
+    /// Flush buffered records and fsync the current segment;
+    pub fn sync(&mut self) -> Result<(), Error> {
+        self.writer.flush()?;
+        self.writer.get_ref().sync_data()?;
+        self.pending = 0;
+        Ok(())
+    }
+
+    /// Flush the current segment and update the index, syncing to disk unless the durability
+    /// is `Durability::Buffered`;
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.writer.flush()?;
+
+        if self.config.durability != Durability::Buffered {
+            self.writer.get_ref().sync_all()?;
+        }
+
+        self.pending = 0;
+        self.write_index()
+    }
+
+    /// Close the current segment and start a new one;
+    pub fn rotate(&mut self, now: Timestamp) -> Result<(), Error> {
+        self.flush()?;
+
+        let next = match self.segments.last_mut() {
+            Some(segment) => {
+                segment.closed = true;
+                segment.segment + 1
+            }
+            None => 1,
+        };
+
+        let (writer, segment_bytes) = Journal::create_segment(&self.dir, next)?;
+        self.writer = writer;
+        self.segment_bytes = segment_bytes;
+        self.segment_actions = 0;
+
+        self.segments.push(JournalSegment {
+            segment: next,
+            file: JournalSegment::file_name(next),
+            records: 0,
+            opened_at: now,
+            closed: false,
+        });
+
+        info!(
+            "Rotated journal to segment {}",
+            JournalSegment::file_name(next)
+        );
+
+        self.write_config()?;
+        self.write_index()
+    }
+
+    fn should_rotate(&self, now: Timestamp) -> bool {
+        let segment = match self.segments.last() {
+            Some(segment) => segment,
+            None => return false,
+        };
+
+        if segment.records == 0 {
+            return false;
+        }
+
+        let expired = self
+            .config
+            .max_segment_age
+            .map(|age| now.saturating_sub(segment.opened_at) >= age)
+            .unwrap_or(false);
+
+        self.segment_bytes >= self.config.max_segment_bytes || expired
+    }
+
+    /// Create a segment file and write the csv header;
+    fn create_segment(dir: &Path, segment: u32) -> Result<(BufWriter<File>, u64), Error> {
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(dir.join(JournalSegment::file_name(segment)))?;
+
+        let header = b"type,client,tx,amount,timestamp,category,source\n";
+        let mut writer = BufWriter::new(file);
+        writer.write_all(header)?;
+
+        Ok((writer, header.len() as u64))
+    }
+
+    /// Read the segment index of a journal directory; Returns no segments if there is no index.
+    pub fn read_index(dir: &Path) -> Result<Vec<JournalSegment>, Error> {
+        let path = dir.join(JOURNAL_INDEX_FILE);
+
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut rdr = Reader::from_path(path)?;
+        let segments = rdr
+            .deserialize::<JournalSegment>()
+            .collect::<Result<Vec<JournalSegment>, csv::Error>>()?;
+
+        Ok(segments)
+    }
+
+    fn write_index(&self) -> Result<(), Error> {
+        write_journal_index(&self.dir, &self.segments)
+    }
+
+    /// Read the transactions recorded in a segment file, in order;
+    pub fn read_segment(dir: &Path, segment: &JournalSegment) -> Result<Vec<Transaction>, Error> {
+        let mut rdr = Reader::from_path(dir.join(&segment.file))?;
+        let transactions = rdr
+            .deserialize::<Transaction>()
+            .collect::<Result<Vec<Transaction>, csv::Error>>()?;
+
+        Ok(transactions)
+    }
+
+    /// Read the accounting configuration a segment was recorded with; Returns `None` for segments
+    /// written before the journal was attached with a configuration.
+    pub fn read_config(
+        dir: &Path,
+        segment: &JournalSegment,
+    ) -> Result<Option<AccountingConfig>, Error> {
+        let path = dir.join(JournalSegment::config_file_name(segment.segment));
+
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let rdr = BufReader::new(File::open(path)?);
+        Ok(Some(bincode::deserialize_from(rdr)?))
+    }
+
+    /// Read the operator actions recorded in a segment, in order;
+    pub fn read_actions(dir: &Path, segment: &JournalSegment) -> Result<Vec<JournalAction>, Error> {
+        let path = dir.join(JournalSegment::actions_file_name(segment.segment));
+
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut rdr = Reader::from_path(path)?;
+        let actions = rdr
+            .deserialize::<JournalAction>()
+            .collect::<Result<Vec<JournalAction>, csv::Error>>()?;
+
+        Ok(actions)
+    }
+
+    /// Collapse the closed segments of a journal into a snapshot;
+    /// The existing snapshot (if any) is loaded, the closed segments are replayed onto it in order
+    /// with the configuration each segment was recorded with, interleaving the recorded operator
+    /// actions, the new snapshot is written, and the compacted segments are removed from the
+    /// directory and the index. Segments without a recorded configuration are replayed with
+    /// `config`; If it is `None`, such segments are refused with `Error::InvalidArgument` unless
+    /// empty. The open segment is only compacted if `include_open` is set. Returns
+    /// `Error::AlreadyInUse` while another process has the journal open or holds the snapshot lock,
+    /// unless `force` is set to override a stale lock.
+    pub fn compact(
+        dir: &Path,
+        snapshot_path: PathBuf,
+        config: Option<AccountingConfig>,
+        include_open: bool,
+        force: bool,
+    ) -> Result<CompactionSummary, Error> {
+        // Hold the journal lock, so no segment is appended while the segments are replayed and
+        // removed, and the snapshot lock from reading the snapshot until the new one is written;
+        let _locks = match force {
+            true => None,
+            false => Some((
+                FileLock::acquire(dir.join(JOURNAL_LOCK_FILE))?,
+                FileLock::acquire(Snapshot::lock_path(&snapshot_path))?,
+            )),
+        };
+        let mut accounting = Accounting::default();
+
+        if snapshot_path.exists() {
+            accounting.read_snapshot_file(snapshot_path.clone())?;
+        }
+
+        let segments = Journal::read_index(dir)?;
+        let (compacted, remaining): (Vec<JournalSegment>, Vec<JournalSegment>) = segments
+            .into_iter()
+            .partition(|segment| segment.closed || include_open);
+
+        let mut summary = CompactionSummary::default();
+
+        for segment in compacted.iter() {
+            let transactions = Journal::read_segment(dir, segment)?;
+            let mut actions = Journal::read_actions(dir, segment)?.into_iter().peekable();
+
+            accounting.config = match (Journal::read_config(dir, segment)?, config.as_ref()) {
+                (Some(recorded), _) => recorded,
+                (None, Some(config)) => config.clone(),
+                (None, None) if transactions.is_empty() && actions.peek().is_none() => {
+                    AccountingConfig::default()
+                }
+                (None, None) => {
+                    return Err(Error::InvalidArgument(format!(
+                        "journal segment {} has no recorded configuration",
+                        segment.file
+                    )))
+                }
+            };
+
+            for (records, tx) in transactions.into_iter().enumerate() {
+                while let Some(action) = actions.next_if(|action| action.after <= records as u64) {
+                    accounting.replay_action(&action)?;
+                }
+
+                // Replayed rejections were already ignored when first processed;
+                let _ = accounting.process_transaction(tx);
+                summary.transactions += 1;
+            }
+
+            for action in actions {
+                accounting.replay_action(&action)?;
+            }
+            summary.segments += 1;
+        }
+
+        accounting.snapshot().write_file_unlocked(snapshot_path)?;
+
+        // Only remove segments once the new snapshot is safely written;
+        write_journal_index(dir, &remaining)?;
+        for segment in compacted.iter() {
+            fs::remove_file(dir.join(&segment.file))?;
+
+            for file in [
+                JournalSegment::config_file_name(segment.segment),
+                JournalSegment::actions_file_name(segment.segment),
+            ] {
+                let path = dir.join(file);
+                if path.exists() {
+                    fs::remove_file(path)?;
+                }
+            }
+        }
+
+        Ok(summary)
+    }
+}
+
+impl Accounting {
+    /// Record an operator action to the attached journal, if any; A failed write is logged, as the
+    /// action was already applied.
+    pub(crate) fn journal_action(&mut self, action: JournalAction) {
+        let now = self.now();
+
+        if let Some(journal) = self.journal.as_mut() {
+            let (kind, client) = (action.action, action.client);
+            if let Err(e) = journal.append_action(JournalAction { at: now, ..action }) {
+                error!("Failed to journal {} of client {}: {}", kind, client, e);
+            }
+        }
+    }
+
+    /// Apply a journaled operator action at the clock time it was recorded;
+    fn replay_action(&mut self, action: &JournalAction) -> Result<(), Error> {
+        let clock = self.clock.replace(Arc::new(MockClock::new(action.at)));
+        let replayed = self.apply_action(action);
+        self.clock = clock;
+        replayed
+    }
+
+    fn apply_action(&mut self, action: &JournalAction) -> Result<(), Error> {
+        let client = action.client;
+        let missing = |field: &str| {
+            Error::InvalidArgument(format!(
+                "journaled {} of client {} has no {}",
+                action.action, client, field
+            ))
+        };
+
+        match action.action {
+            JournalActionKind::PlaceHold => {
+                let amount = action.amount.ok_or_else(|| missing("amount"))?;
+                let id = self.place_hold(client, amount, &action.reason)?;
+
+                if Some(id) != action.hold {
+                    return Err(Error::InvalidArgument(format!(
+                        "replayed hold {} of client {} was journaled as hold {:?}",
+                        id, client, action.hold
+                    )));
+                }
+            }
+            JournalActionKind::ReleaseHold => {
+                self.release_hold(action.hold.ok_or_else(|| missing("hold"))?)?;
+            }
+            JournalActionKind::Close => {
+                let disposition = action
+                    .target
+                    .map_or(ClosureDisposition::WriteOff, ClosureDisposition::Transfer);
+                self.close_account(client, disposition)?;
+            }
+            JournalActionKind::Lock | JournalActionKind::Unlock => {
+                let reason = action.lock_reason.ok_or_else(|| missing("lock_reason"))?;
+                let is_locked = action.action == JournalActionKind::Lock;
+                self.lock_account_with_reason(client, is_locked, reason, &action.reason);
+            }
+            JournalActionKind::Flag => self.flag_account(client),
+            JournalActionKind::ClearFlag => {
+                self.clear_account_flag(client);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for Journal {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+/// Counts of the segments and transactions collapsed by `Journal::compact`.
+pub struct CompactionSummary {
+    pub segments: usize,
+    pub transactions: usize,
+}
+
+fn write_journal_index(dir: &Path, segments: &[JournalSegment]) -> Result<(), Error> {
+    let tmp_path = dir.join(format!("{}.tmp", JOURNAL_INDEX_FILE));
+
+    let mut sink = CsvFileSink::create(tmp_path.clone(), Durability::FsyncOnClose)?;
+    for segment in segments {
+        sink.write(segment)?;
+    }
+    sink.close()?;
+
+    fs::rename(tmp_path, dir.join(JOURNAL_INDEX_FILE))?;
+
+    Ok(())
+}
@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+#[cfg(feature = "io")]
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/// Upper bounds of the latency histogram buckets, in microseconds; Latencies above the last bound
+/// are counted in an overflow bucket.
+pub const LATENCY_BUCKETS_US: [u64; 16] = [
+    1, 2, 5, 10, 20, 50, 100, 200, 500, 1_000, 2_000, 5_000, 10_000, 50_000, 100_000, 1_000_000,
+];
+
+#[derive(Debug, Default, Clone, PartialEq)]
+/// Fixed-bucket histogram of transaction processing latencies.
+pub struct LatencyHistogram {
+    /// Counts per bucket of `LATENCY_BUCKETS_US`, followed by the overflow bucket;
+    counts: [u64; LATENCY_BUCKETS_US.len() + 1],
+    total: Duration,
+    max: Duration,
+}
+
+impl LatencyHistogram {
+    /// Record a single latency;
+    pub fn record(&mut self, latency: Duration) {
+        let micros = latency.as_micros() as u64;
+        let bucket = LATENCY_BUCKETS_US
+            .iter()
+            .position(|bound| micros <= *bound)
+            .unwrap_or(LATENCY_BUCKETS_US.len());
+
+        self.counts[bucket] += 1;
+        self.total += latency;
+        self.max = self.max.max(latency);
+    }
+
+    /// Returns the number of recorded latencies;
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Returns the largest recorded latency;
+    pub fn max(&self) -> Duration {
+        self.max
+    }
+
+    /// Returns the mean recorded latency;
+    pub fn mean(&self) -> Duration {
+        match self.count() {
+            0 => Duration::default(),
+            count => self.total / count as u32,
+        }
+    }
+
+    /// Returns the upper bound of the bucket containing the given percentile (0.0 - 1.0);
+    /// Percentiles falling into the overflow bucket return the largest recorded latency.
+    pub fn percentile(&self, percentile: f64) -> Duration {
+        let rank = (self.count() as f64 * percentile).ceil() as u64;
+        let mut seen = 0;
+
+        for (bucket, count) in self.counts.iter().enumerate() {
+            seen += count;
+
+            if seen >= rank && *count > 0 {
+                return match LATENCY_BUCKETS_US.get(bucket) {
+                    Some(bound) => Duration::from_micros(*bound),
+                    None => self.max,
+                };
+            }
+        }
+
+        Duration::default()
+    }
+
+    /// Returns the bucket upper bounds in microseconds (`None` for the overflow bucket) with their counts;
+    pub fn buckets(&self) -> impl Iterator<Item = (Option<u64>, u64)> + '_ {
+        self.counts
+            .iter()
+            .enumerate()
+            .map(|(i, count)| (LATENCY_BUCKETS_US.get(i).copied(), *count))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+/// Summary row of the latency report, per transaction type.
+pub struct LatencySummary {
+    #[serde(rename = "type")]
+    pub r#type: TxType,
+    #[serde(rename = "count")]
+    pub count: u64,
+    #[serde(rename = "mean_us")]
+    pub mean_us: u64,
+    #[serde(rename = "p50_us")]
+    pub p50_us: u64,
+    #[serde(rename = "p99_us")]
+    pub p99_us: u64,
+    #[serde(rename = "max_us")]
+    pub max_us: u64,
+}
+
+impl Accounting {
+    /// Returns the processing latency histograms per transaction type;
+    pub fn latency_histograms(&self) -> &HashMap<TxType, LatencyHistogram> {
+        &self.latencies
+    }
+
+    /// Write a per-type latency summary (count, mean, p50, p99, max) to a csv file;
+    #[cfg(feature = "io")]
+    pub fn write_latency_csv_file(&self, file_path: PathBuf) -> Result<(), Error> {
+        let mut sink = CsvFileSink::create(file_path, self.config.output_durability)?;
+
+        for r#type in TxType::ALL.iter() {
+            if let Some(histogram) = self.latencies.get(r#type) {
+                sink.write(LatencySummary {
+                    r#type: *r#type,
+                    count: histogram.count(),
+                    mean_us: histogram.mean().as_micros() as u64,
+                    p50_us: histogram.percentile(0.5).as_micros() as u64,
+                    p99_us: histogram.percentile(0.99).as_micros() as u64,
+                    max_us: histogram.max().as_micros() as u64,
+                })?;
+            }
+        }
+
+        sink.close()
+    }
+}
@@ -0,0 +1,125 @@
+use std::sync::Arc;
+
+use crate::*;
+
+/// Outcome of a transaction applied by `Ledger::apply`;
+pub type Outcome = Result<TxOutcome, TransactionError>;
+
+/// Balances of an account as returned by `Ledger::account`;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccountView {
+    pub client: ClientId,
+    pub available: Amount,
+    pub held: Amount,
+    pub total: Amount,
+    pub locked: bool,
+}
+
+impl From<&Account> for AccountView {
+    fn from(account: &Account) -> Self {
+        AccountView {
+            client: account.client,
+            available: account.available,
+            held: account.held,
+            total: account.total,
+            locked: account.locked,
+        }
+    }
+}
+
+/// Minimal state machine applying the processing rules in memory, for embedding inside e.g. a
+/// matching engine; The ledger never reads files, the system clock or random numbers: time only
+/// moves with transaction timestamps or `Ledger::set_time`, starting at zero, so replicas applying
+/// the same transactions reach the same `state_hash`. Build without default features to leave out
+/// the `io` feature and its csv, compression and random number dependencies.
+#[derive(Debug)]
+pub struct Ledger {
+    accounting: Accounting,
+    clock: MockClock,
+}
+
+impl Default for Ledger {
+    fn default() -> Self {
+        Ledger::new(AccountingConfig::default())
+    }
+}
+
+impl Ledger {
+    /// Create a ledger applying the rules of the configuration;
+    pub fn new(config: AccountingConfig) -> Self {
+        let clock = MockClock::new(0);
+        let mut accounting = Accounting::with_config(config);
+        accounting.set_clock(Arc::new(clock.clone()));
+
+        Ledger { accounting, clock }
+    }
+
+    /// Apply a transaction; See `Accounting::process_transaction`.
+    pub fn apply(&mut self, tx: Transaction) -> Outcome {
+        self.accounting.process_transaction(tx)
+    }
+
+    /// Returns the balances of a client's account, if it exists;
+    pub fn account(&self, client: ClientId) -> Option<AccountView> {
+        self.accounting.get_account(client).map(AccountView::from)
+    }
+
+    /// Set the time used for transactions without a timestamp;
+    pub fn set_time(&mut self, now: Timestamp) {
+        self.clock.set(now);
+    }
+
+    /// Returns a hash of the balances, lock status and dispute state of every account and
+    /// recorded transaction, stable across processes and platforms, e.g. to compare replicas;
+    pub fn state_hash(&self) -> u64 {
+        let mut hash = StateHash::default();
+
+        let mut accounts: Vec<&Account> = self.accounting.accounts().collect();
+        accounts.sort_by_key(|account| account.client);
+        for account in accounts {
+            hash.write(&account.client.to_le_bytes());
+            hash.write(&account.available.normalize().serialize());
+            hash.write(&account.held.normalize().serialize());
+            hash.write(&account.total.normalize().serialize());
+            hash.write(&[account.locked as u8]);
+        }
+
+        let mut transactions: Vec<(TxId, TxState)> = self
+            .accounting
+            .transactions
+            .iter()
+            .map(|(tx, recorded)| (*tx, recorded.state))
+            .collect();
+        transactions.sort_by_key(|(tx, _)| *tx);
+        for (tx, state) in transactions {
+            hash.write(&tx.to_le_bytes());
+            hash.write(&[state as u8]);
+        }
+
+        hash.0
+    }
+
+    /// Returns the underlying processor, e.g. to read reports;
+    pub fn accounting(&self) -> &Accounting {
+        &self.accounting
+    }
+}
+
+/// 64-bit FNV-1a hash; Unlike `DefaultHasher`, its output is specified, so hashes can be compared
+/// across builds.
+struct StateHash(u64);
+
+impl Default for StateHash {
+    fn default() -> Self {
+        StateHash(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl StateHash {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
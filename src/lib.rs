@@ -13,6 +13,7 @@
 //! use tx_sim::{Accounting, Error};
 //!
 //!
+//! # #[cfg(feature = "io")]
 //! fn main() -> Result<(), Error> {
 //!     if let Some(file_path) = env::args().skip(1).collect::<Vec<String>>().first() {
 //!         let mut accounting = Accounting::init();
@@ -26,26 +27,35 @@
 //!
 //!     Ok(())
 //! }
-//!
+//! # #[cfg(not(feature = "io"))]
+//! # fn main() {}
 //! ```
 //!
 //! Using the library:
 //!
 //! ```no_run
-//! use tx_sim::{Accounting, Transaction, TxType, Error};
+//! use tx_sim::{Accounting, Decimal, Transaction, TxOutcome, TxType, Error};
 //!
 //! let mut accounting = Accounting::init();
 //! let client = 1;
-//! let deposit_amount = 100.0;
+//! // Amounts are exact decimals; 1000 with a scale of 1 is 100.0;
+//! let deposit_amount = Decimal::new(1000, 1);
 //!
 //! // Process a transaction for an account programmatically;
-//! accounting.process_transaction(Transaction {
+//! let outcome = accounting.process_transaction(Transaction {
 //!     client,
 //!     tx: 1,
 //!     r#type: TxType::Deposit,
 //!     amount: Some(deposit_amount),
+//!     timestamp: None,
+//!     subaccount: None,
+//!     category: None,
+//!     source: None,
 //! });
 //!
+//! // Assert the deposit is applied; Ignored transactions return why, e.g. insufficient funds;
+//! assert_eq!(outcome, Ok(TxOutcome::Applied));
+//!
 //! // Assert the account is created when making a deposit;
 //! assert_eq!(accounting.get_account(client).is_some(), true);
 //!
@@ -53,6 +63,15 @@
 //! assert_eq!(accounting.get_account(client).map(|c| c.total), Some(deposit_amount));
 //!
 //! ```
+//! # API Stability
+//!
+//! `tx_sim::prelude` is the stable API surface; Other crate root re-exports may change in minor
+//! releases. The low-level helpers applying a single transaction type to an account, e.g.
+//! `process_deposit`, are only public with the `internals` feature, via
+//! `internals::AccountingInternals`, and carry no semver guarantees. The crate root re-exports
+//! each public item by name, so new module items are not exported by accident; The test-support
+//! harness is only exported with the `testing` feature.
+//!
 //! # Errors & Trouble Shooting
 //!
 //! If the program fails to parse the CSV file, check to ensure there are no leading empty spaces in the client, tx or amount values.
@@ -60,12 +79,206 @@
 //!
 
 mod accounting;
+mod acks;
+mod adjustments;
+mod aging;
+mod analytics;
+#[cfg(feature = "io")]
+mod anonymize;
+mod attributes;
+mod audit;
+mod batch;
+mod breaker;
+mod budgets;
+mod clearing;
+mod clock;
+mod closures;
+#[cfg(feature = "arrow")]
+mod columnar;
+mod config;
+mod dedup;
+#[cfg(feature = "io")]
+mod diagnose;
+mod disputes;
+mod encoding;
+mod enrich;
 mod error;
+mod expiry;
+mod floors;
+#[cfg(feature = "io")]
+mod generator;
+mod handlers;
+mod holds;
+mod ids;
+mod input;
+mod inspect;
+mod interest;
+#[cfg(feature = "internals")]
+pub mod internals;
+mod invariants;
+#[cfg(feature = "io")]
+mod journal;
+mod latency;
+mod ledger;
+#[cfg(feature = "io")]
+mod lockfile;
+mod locks;
+#[cfg(feature = "io")]
+mod merge;
+mod metrics;
 mod models;
+#[cfg(feature = "io")]
+mod montecarlo;
+mod network;
+mod notifications;
+#[cfg(feature = "otlp")]
+mod otlp;
+mod partition;
+mod precision;
+#[cfg(feature = "io")]
+mod preload;
+pub mod prelude;
+mod prune;
+mod quarantine;
+mod receipts;
+mod rejected;
+#[cfg(feature = "io")]
+mod replication;
+mod reservations;
+mod resources;
+#[cfg(feature = "io")]
+mod scenarios;
+#[cfg(feature = "io")]
+mod scheduler;
+#[cfg(feature = "io")]
+mod script;
+mod sink;
+mod snapshot;
+mod sources;
+#[cfg(feature = "io")]
+mod standby;
+#[cfg(feature = "pdf")]
+mod statement;
+mod subaccounts;
+#[cfg(feature = "testing")]
+mod testing;
+mod trace;
+mod validation;
+mod views;
 
-#[cfg(test)]
+#[cfg(all(test, feature = "io"))]
 mod test;
 
-pub use accounting::*;
-pub use error::*;
-pub use models::*;
+pub use accounting::Accounting;
+#[cfg(feature = "io")]
+pub use acks::AckLog;
+pub use acks::{Ack, AckStatus, ReceiptId};
+pub use adjustments::{DebitAdjustment, DebitMode};
+pub use aging::{AgingBucket, HeldAging, HeldKind};
+pub use analytics::{AmountBucket, Anomaly, DEFAULT_ANOMALY_THRESHOLD, MIN_ANOMALY_SAMPLES};
+#[cfg(feature = "io")]
+pub use anonymize::{Anonymizer, DEFAULT_ANONYMIZE_TOLERANCE};
+pub use attributes::AccountAttribute;
+pub use audit::AuditEvent;
+pub use batch::BatchResult;
+pub(crate) use breaker::BreakerState;
+pub use breaker::{BreakerAction, BreakerTrip, CircuitBreaker};
+pub use budgets::{Budget, BudgetPolicy};
+pub use clearing::PendingDeposit;
+pub use clock::{Clock, MockClock, SystemClock};
+pub use closures::{AccountClosure, ClosureDisposition};
+pub use config::{AccountingConfig, Strictness, DEFAULT_AMOUNT_EPSILON};
+pub(crate) use dedup::content_hash;
+pub use dedup::{ContentDuplicate, DedupConfig, DuplicateTxId};
+#[cfg(feature = "io")]
+pub use diagnose::{
+    diagnose_csv_file, ColumnDiagnosis, ColumnKind, Diagnosis, DEFAULT_DIAGNOSE_SAMPLE,
+};
+pub(crate) use disputes::RecordedTx;
+pub use disputes::{
+    Dispute, DisputeAmountPolicy, DisputeCapPolicy, DisputeFundsPolicy, TxState,
+    WithdrawalDisputePolicy,
+};
+pub use encoding::{DecodedReader, Encoding};
+pub use enrich::{ClientIdMapping, ClientIdRemapper, Enricher};
+pub use error::{Error, ErrorKind, RowError};
+pub use expiry::{DisputeExpiry, DisputeExpiryAction};
+pub use floors::ReserveFloor;
+#[cfg(feature = "io")]
+pub use generator::{
+    random_account, random_amount, random_transaction, AdversarialCase, AdversarialRow,
+    GeneratorProfile,
+};
+pub use handlers::{CustomTxType, TxHandler, TxHandlers, CUSTOM_TX_TYPE_MAX_LEN};
+pub use holds::{Hold, HoldId};
+pub use ids::{
+    CounterAllocator, DenseTxIdMapping, DenseTxIds, SnowflakeAllocator, TxIdAllocator,
+    SNOWFLAKE_SEQUENCE_BITS,
+};
+#[cfg(feature = "io")]
+pub(crate) use input::{decimal_comma_amount, decimal_comma_record, is_blank_record};
+pub use input::{InputFormat, TRANSACTION_COLUMNS};
+pub use inspect::{format_amount, AccountReport, ReadOnlyAccounting, DEFAULT_REPORT_TRANSACTIONS};
+pub use interest::{InterestConfig, INTEREST_CATEGORY};
+pub(crate) use invariants::InvariantChecker;
+pub use invariants::{InvariantViolation, INVARIANT_HISTORY_LEN};
+#[cfg(feature = "io")]
+pub use journal::{
+    CompactionSummary, Journal, JournalAction, JournalActionKind, JournalConfig, JournalSegment,
+    JOURNAL_INDEX_FILE, JOURNAL_LOCK_FILE,
+};
+pub use latency::{LatencyHistogram, LatencySummary, LATENCY_BUCKETS_US};
+pub use ledger::{AccountView, Ledger, Outcome};
+#[cfg(feature = "io")]
+pub use lockfile::FileLock;
+pub use locks::{LockEvent, LockReason};
+#[cfg(feature = "io")]
+pub use merge::{merge_accounts_csv_files, merge_accounts_csv_stdout};
+pub use metrics::{
+    Labels, LogMetrics, MetricsSink, NoopMetrics, PrometheusMetrics, DEFAULT_METRICS_BUCKETS,
+};
+pub use models::{
+    approx_eq, clear_dust, covers, format_date, Account, AccountDelta, AccountStats, Amount,
+    ClientId, DailyBalance, ExtendedAccount, SubAccountId, Timestamp, Transaction, TxId, TxType,
+    SECONDS_PER_DAY,
+};
+#[cfg(feature = "io")]
+pub use montecarlo::{MetricDistribution, MonteCarloReport};
+pub use network::{NetworkAction, NetworkAdjustment};
+pub use notifications::{Notification, NotificationConfig, NotificationKind};
+#[cfg(feature = "otlp")]
+pub use otlp::{OtlpConfig, OtlpExporter, OTLP_EXPORT_SPANS, OTLP_MAX_SPANS};
+pub use partition::{client_bucket, Partition};
+#[cfg(any(feature = "arrow", feature = "parquet"))]
+pub(crate) use precision::amount_units;
+pub use precision::{decimal_places, PrecisionAdjustment, PrecisionPolicy, AMOUNT_DECIMAL_PLACES};
+pub use prune::PrunePolicy;
+pub use receipts::{TxReceipt, TxStatus};
+pub use rejected::RejectedTx;
+#[cfg(feature = "io")]
+pub use replication::{AccountEvent, ChangeStream};
+pub use reservations::Reservation;
+pub use resources::{format_bytes, parse_bytes, peak_rss, CollectionUsage, ResourceReport};
+#[cfg(feature = "io")]
+pub use scenarios::{Scenario, ScenarioRunner, ScenarioSummary};
+#[cfg(feature = "io")]
+pub use scheduler::{FairScheduler, DEFAULT_SCHEDULER_WINDOW};
+#[cfg(feature = "io")]
+pub use script::{ScenarioScript, ScriptFailure, ScriptReport, ScriptStep};
+#[cfg(feature = "io")]
+pub use sink::CsvFileSink;
+pub use sink::Durability;
+pub use snapshot::Snapshot;
+pub use sources::SourceSummary;
+#[cfg(feature = "io")]
+pub use standby::{Standby, StandbyStatus};
+#[cfg(feature = "pdf")]
+pub use statement::{Statement, StatementLine};
+pub use subaccounts::SubAccountBalance;
+#[cfg(feature = "testing")]
+pub use testing::{parse_accounts_csv, TestFixture};
+pub use trace::{TraceFilter, TraceStep};
+pub use validation::{TransactionError, TxOutcome};
+pub use views::ReadSnapshot;
+
+pub use rust_decimal::Decimal;
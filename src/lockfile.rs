@@ -0,0 +1,35 @@
+use std::fs::{File, OpenOptions, TryLockError};
+use std::path::{Path, PathBuf};
+
+use crate::*;
+
+/// Advisory exclusive lock on a file, held until dropped, guarding journals and snapshots against
+/// concurrent processes; Uses `flock` on Unix and `LockFileEx` on Windows.
+#[derive(Debug)]
+pub struct FileLock {
+    path: PathBuf,
+    _file: File,
+}
+
+impl FileLock {
+    /// Acquire the lock on the file, creating it if necessary; Returns `Error::AlreadyInUse` if
+    /// another process holds it.
+    pub fn acquire(path: PathBuf) -> Result<Self, Error> {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)?;
+
+        match file.try_lock() {
+            Ok(()) => Ok(FileLock { path, _file: file }),
+            Err(TryLockError::WouldBlock) => Err(Error::AlreadyInUse(path)),
+            Err(TryLockError::Error(e)) => Err(e.into()),
+        }
+    }
+
+    /// Returns the path of the lock file;
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
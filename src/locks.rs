@@ -0,0 +1,153 @@
+use std::fmt;
+#[cfg(feature = "io")]
+use std::path::PathBuf;
+
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+/// Why an account was locked or unlocked.
+pub enum LockReason {
+    /// Locked by a chargeback;
+    Chargeback,
+    /// Locked or unlocked manually by an operator;
+    Manual,
+    /// Locked by a fraud or compliance rule;
+    Rule,
+}
+
+impl LockReason {
+    /// Returns the csv name of the lock reason;
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LockReason::Chargeback => "chargeback",
+            LockReason::Manual => "manual",
+            LockReason::Rule => "rule",
+        }
+    }
+}
+
+impl fmt::Display for LockReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+/// Record of an account being locked or unlocked.
+pub struct LockEvent {
+    /// Transaction timestamp, or clock time, of the change
+    #[serde(rename = "at")]
+    pub at: Timestamp,
+    #[serde(rename = "client")]
+    pub client: ClientId,
+    /// The locked status after the change
+    #[serde(rename = "locked")]
+    pub locked: bool,
+    #[serde(rename = "reason")]
+    pub reason: LockReason,
+    /// The transaction causing the change, e.g. the chargeback
+    #[serde(rename = "tx")]
+    pub tx: Option<TxId>,
+    /// Free-form details, e.g. the rule name or operator note
+    #[serde(rename = "detail")]
+    pub detail: String,
+}
+
+impl Accounting {
+    /// Record a change of the account's locked status, setting its lock reason;
+    pub(crate) fn record_lock(
+        &mut self,
+        account: &mut Account,
+        reason: LockReason,
+        tx: Option<TxId>,
+        at: Timestamp,
+        detail: String,
+    ) {
+        info!(
+            "{} client {} ({}): {}",
+            if account.locked { "Locked" } else { "Unlocked" },
+            account.client,
+            reason,
+            detail
+        );
+
+        account.lock_reason = if account.locked { Some(reason) } else { None };
+
+        if account.locked {
+            self.notify(account.client, NotificationKind::Lock, at, tx, None);
+        }
+
+        if let (true, Some(metrics)) = (account.locked, self.metrics.as_ref()) {
+            let labels = [("reason", reason.as_str())];
+            metrics.counter("tx_sim_accounts_locked_total", 1, &labels);
+        }
+
+        self.lock_events.push(LockEvent {
+            at,
+            client: account.client,
+            locked: account.locked,
+            reason,
+            tx,
+            detail,
+        });
+    }
+
+    /// Lock or unlock the account for a fraud or compliance rule, or another non-manual reason;
+    /// See `lock_account`.
+    pub fn lock_account_with_reason(
+        &mut self,
+        client: ClientId,
+        is_locked: bool,
+        reason: LockReason,
+        detail: &str,
+    ) {
+        let mut account = self
+            .accounts
+            .remove(&client)
+            .unwrap_or_else(|| Account::new(client));
+
+        if account.locked != is_locked {
+            account.locked = is_locked;
+
+            let now = self.now();
+            self.record_lock(&mut account, reason, None, now, detail.to_string());
+            self.emit_change(None, &account);
+
+            #[cfg(feature = "io")]
+            self.journal_action(JournalAction {
+                lock_reason: Some(reason),
+                reason: detail.to_string(),
+                ..JournalAction::new(
+                    match is_locked {
+                        true => JournalActionKind::Lock,
+                        false => JournalActionKind::Unlock,
+                    },
+                    client,
+                )
+            });
+        }
+
+        self.accounts.insert(client, account);
+    }
+
+    /// Returns every lock and unlock, in the order recorded;
+    pub fn lock_events(&self) -> &[LockEvent] {
+        &self.lock_events
+    }
+
+    /// Write the lock events to a csv file;
+    #[cfg(feature = "io")]
+    pub fn write_lock_events_csv_file(&self, file_path: PathBuf) -> Result<(), Error> {
+        let mut sink = CsvFileSink::create(file_path, self.config.output_durability)?;
+
+        for event in self.lock_events.iter() {
+            sink.write(event)?;
+        }
+
+        sink.close()
+    }
+}
@@ -1,17 +1,1559 @@
 use std::env;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
 use std::path::PathBuf;
+use std::process;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
 
+use log::{error, warn, LevelFilter, Log, Metadata, Record};
 use tx_sim::*;
 
-fn main() -> Result<(), Error> {
-    if let Some(file_path) = env::args().skip(1).collect::<Vec<String>>().first() {
-        let mut accounting = Accounting::init();
+/// Minimal logger writing library log records to standard error;
+struct StderrLogger;
+
+impl Log for StderrLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            let _ = writeln!(io::stderr(), "[{}] {}", record.level(), record.args());
+        }
+    }
+
+    fn flush(&self) {
+        let _ = io::stderr().flush();
+    }
+}
+
+static LOGGER: StderrLogger = StderrLogger;
+
+fn main() {
+    // Install the logger before dispatching, so every subcommand logs errors to stderr; `process`
+    // adjusts the level with `-q`, `-v` and `-vv`;
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(LevelFilter::Error);
+    }
+
+    if let Err(e) = run() {
+        eprintln!("error[{}]: {}", e.code(), e);
+        process::exit(1);
+    }
+}
+
+fn run() -> Result<(), Error> {
+    let mut args = env::args().skip(1).peekable();
+
+    match args.peek().map(String::as_str) {
+        Some("compact") => {
+            args.next();
+            compact(args)
+        }
+        Some("standby") => {
+            args.next();
+            standby(args)
+        }
+        Some("inspect") => {
+            args.next();
+            inspect(args)
+        }
+        Some("account") => {
+            args.next();
+            account(args)
+        }
+        Some("monte-carlo") => {
+            args.next();
+            monte_carlo(args)
+        }
+        Some("diagnose") => {
+            args.next();
+            diagnose(args)
+        }
+        Some("merge") => {
+            args.next();
+            merge(args)
+        }
+        Some("anonymize") => {
+            args.next();
+            anonymize(args)
+        }
+        Some("scenario") => {
+            args.next();
+            scenario(args)
+        }
+        Some("release") => {
+            args.next();
+            release(args)
+        }
+        #[cfg(feature = "pdf")]
+        Some("statement") => {
+            args.next();
+            statement(args)
+        }
+        _ => process(args),
+    }
+}
+
+/// `tx-sim compact --snapshot snap.bin --journal dir/ [--all] [--force]`
+/// Replay journal segments onto the snapshot, write the new snapshot and remove the applied segments;
+/// Only rotated segments are compacted unless `--all` is provided. `--force` skips the journal and
+/// snapshot locks.
+fn compact(mut args: impl Iterator<Item = String>) -> Result<(), Error> {
+    let mut snapshot_path = None;
+    let mut journal_dir = None;
+    let mut include_open = false;
+    let mut force = false;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--snapshot" => snapshot_path = args.next().map(PathBuf::from),
+            "--journal" => journal_dir = args.next().map(PathBuf::from),
+            "--all" => include_open = true,
+            "--force" => force = true,
+            _ => return Err(unknown_option(arg)),
+        }
+    }
+
+    let snapshot_path = snapshot_path
+        .ok_or_else(|| Error::InvalidArgument("missing --snapshot <path>".to_string()))?;
+    let journal_dir =
+        journal_dir.ok_or_else(|| Error::InvalidArgument("missing --journal <dir>".to_string()))?;
+
+    let summary = Journal::compact(
+        &journal_dir,
+        snapshot_path.clone(),
+        None,
+        include_open,
+        force,
+    )?;
+
+    println!(
+        "Compacted {} segments ({} transactions) into {}",
+        summary.segments,
+        summary.transactions,
+        snapshot_path.display()
+    );
+
+    Ok(())
+}
+
+/// `tx-sim release --snapshot snap.bin --quarantine quarantine.csv --client 7 [--client 8] [--force]`
+/// Apply the quarantined transactions of the reviewed clients onto the snapshot, write the snapshot
+/// and keep the remaining transactions in the quarantine file. `--force` skips the snapshot lock.
+fn release(mut args: impl Iterator<Item = String>) -> Result<(), Error> {
+    let mut snapshot_path = None;
+    let mut quarantine_path = None;
+    let mut clients = Vec::new();
+    let mut force = false;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--snapshot" => snapshot_path = args.next().map(PathBuf::from),
+            "--quarantine" => quarantine_path = args.next().map(PathBuf::from),
+            "--force" => force = true,
+            "--client" => {
+                let value = args.next().unwrap_or_default();
+                clients.push(value.parse::<ClientId>().map_err(|_| {
+                    Error::InvalidArgument(format!("invalid --client '{}'", value))
+                })?);
+            }
+            _ => return Err(unknown_option(arg)),
+        }
+    }
+
+    let snapshot_path = snapshot_path
+        .ok_or_else(|| Error::InvalidArgument("missing --snapshot <path>".to_string()))?;
+    let quarantine_path = quarantine_path
+        .ok_or_else(|| Error::InvalidArgument("missing --quarantine <path>".to_string()))?;
+    if clients.is_empty() {
+        return Err(Error::InvalidArgument("missing --client <id>".to_string()));
+    }
+
+    // Hold the snapshot lock from reading the snapshot until the new one is written;
+    let _lock = match force {
+        true => None,
+        false => Some(FileLock::acquire(Snapshot::lock_path(&snapshot_path))?),
+    };
+
+    let mut accounting = Accounting::init();
+    accounting.read_snapshot_file(snapshot_path.clone())?;
+    accounting.read_quarantined_csv_file(quarantine_path.clone())?;
+
+    for client in clients {
+        let receipts = accounting.release_quarantined(client);
+        let applied = receipts
+            .iter()
+            .filter(|receipt| receipt.is_applied())
+            .count();
+        println!(
+            "Released {} transactions of client {} ({} applied)",
+            receipts.len(),
+            client,
+            applied
+        );
+    }
+
+    accounting.snapshot().write_file_unlocked(snapshot_path)?;
+    accounting.write_quarantined_csv_file(quarantine_path)?;
+
+    Ok(())
+}
+
+/// `tx-sim inspect --snapshot snap.bin [--client 7] [--disputes]`
+/// Query a snapshot without reprocessing transactions: write the accounts, or with `--disputes` the
+/// open disputes, of a client or all clients to stdout.
+fn inspect(mut args: impl Iterator<Item = String>) -> Result<(), Error> {
+    let mut snapshot_path = None;
+    let mut client = None;
+    let mut disputes = false;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--snapshot" => snapshot_path = args.next().map(PathBuf::from),
+            "--client" => {
+                let value = args.next().unwrap_or_default();
+                client = Some(value.parse::<ClientId>().map_err(|_| {
+                    Error::InvalidArgument(format!("invalid --client '{}'", value))
+                })?);
+            }
+            "--disputes" => disputes = true,
+            _ => return Err(unknown_option(arg)),
+        }
+    }
+
+    let snapshot_path = snapshot_path
+        .ok_or_else(|| Error::InvalidArgument("missing --snapshot <path>".to_string()))?;
+    let view = Accounting::open_readonly(snapshot_path)?;
+
+    if disputes {
+        view.write_disputes_csv(io::stdout(), client)
+    } else {
+        view.write_accounts_csv(io::stdout(), client)
+    }
+}
+
+/// `tx-sim account <client> <transactions.csv | snap.bin> [--last 10]`
+/// Print a formatted view of a single account: balances, lock status, open disputes and holds, and
+/// the last N deposits and withdrawals. Files with a `.csv` extension are processed as transactions,
+/// any other file is read as a snapshot.
+fn account(mut args: impl Iterator<Item = String>) -> Result<(), Error> {
+    let mut client = None;
+    let mut file_path = None;
+    let mut recent = DEFAULT_REPORT_TRANSACTIONS;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--last" => {
+                let value = args.next().unwrap_or_default();
+                recent = value
+                    .parse()
+                    .map_err(|_| Error::InvalidArgument(format!("invalid --last '{}'", value)))?;
+            }
+            _ if client.is_none() => {
+                let value = positional(arg)?;
+                client =
+                    Some(value.parse::<ClientId>().map_err(|_| {
+                        Error::InvalidArgument(format!("invalid client '{}'", value))
+                    })?);
+            }
+            _ => file_path = Some(PathBuf::from(positional(arg)?)),
+        }
+    }
+
+    let client = client.ok_or_else(|| Error::InvalidArgument("missing <client>".to_string()))?;
+    let file_path = file_path.ok_or_else(|| {
+        Error::InvalidArgument("missing <transactions.csv | snapshot> path".to_string())
+    })?;
+
+    let mut accounting = Accounting::init();
+    if file_path.extension().is_some_and(|ext| ext == "csv") {
+        accounting.read_transactions_csv_file(file_path)?;
+    } else {
+        accounting.read_snapshot_file(file_path)?;
+    }
+
+    let report = accounting
+        .account_report(client, recent)
+        .ok_or_else(|| Error::InvalidArgument(format!("no account for client {}", client)))?;
+    print!("{}", report);
+
+    Ok(())
+}
+
+/// `tx-sim statement <client> <transactions.csv | snap.bin> [--from <timestamp>] [--to <timestamp>] [-o statement.pdf]`
+/// Write the account statement of a client for the period, from inclusive to exclusive unix
+/// timestamps, as a PDF file, or as text to stdout without `-o`. Files with a `.csv` extension are
+/// processed as transactions, tracking ignored ones, any other file is read as a snapshot.
+/// Requires the `pdf` feature.
+#[cfg(feature = "pdf")]
+fn statement(mut args: impl Iterator<Item = String>) -> Result<(), Error> {
+    let mut client = None;
+    let mut file_path = None;
+    let mut from = None;
+    let mut to = None;
+    let mut output = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--from" | "--to" => {
+                let value = args.next().unwrap_or_default();
+                let timestamp = value
+                    .parse::<Timestamp>()
+                    .map_err(|_| Error::InvalidArgument(format!("invalid {} '{}'", arg, value)))?;
+                match arg.as_str() {
+                    "--from" => from = Some(timestamp),
+                    _ => to = Some(timestamp),
+                }
+            }
+            "-o" | "--output" => output = args.next().map(PathBuf::from),
+            _ if client.is_none() => {
+                let value = positional(arg)?;
+                client =
+                    Some(value.parse::<ClientId>().map_err(|_| {
+                        Error::InvalidArgument(format!("invalid client '{}'", value))
+                    })?);
+            }
+            _ => file_path = Some(PathBuf::from(positional(arg)?)),
+        }
+    }
+
+    let client = client.ok_or_else(|| Error::InvalidArgument("missing <client>".to_string()))?;
+    let file_path = file_path.ok_or_else(|| {
+        Error::InvalidArgument("missing <transactions.csv | snapshot> path".to_string())
+    })?;
+
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        track_rejected: true,
+        ..Default::default()
+    });
+    if file_path.extension().is_some_and(|ext| ext == "csv") {
+        accounting.read_transactions_csv_file(file_path)?;
+    } else {
+        accounting.read_snapshot_file(file_path)?;
+    }
+
+    match output {
+        Some(path) => accounting.write_statement_pdf_file(client, from, to, path),
+        None => {
+            let statement = accounting.statement(client, from, to).ok_or_else(|| {
+                Error::InvalidArgument(format!("no account for client {}", client))
+            })?;
+            print!("{}", statement);
+            Ok(())
+        }
+    }
+}
+
+/// `tx-sim anonymize <in.csv> <out.csv> [--tolerance 0.05] [--seed 0]`
+/// Rewrite a transactions file for sharing: replace client ids and perturb amounts within the relative
+/// tolerance, keeping types, transaction ids, dispute chains and duplicates.
+fn anonymize(mut args: impl Iterator<Item = String>) -> Result<(), Error> {
+    let mut anonymizer = Anonymizer::default();
+    let mut paths = Vec::new();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--tolerance" => {
+                let value = args.next().unwrap_or_default();
+                anonymizer.tolerance = value
+                    .parse()
+                    .ok()
+                    .filter(|tolerance: &f64| (0.0..1.0).contains(tolerance))
+                    .ok_or_else(|| {
+                        Error::InvalidArgument(format!("invalid --tolerance '{}'", value))
+                    })?;
+            }
+            "--seed" => {
+                let value = args.next().unwrap_or_default();
+                anonymizer.seed = value
+                    .parse()
+                    .map_err(|_| Error::InvalidArgument(format!("invalid --seed '{}'", value)))?;
+            }
+            _ => paths.push(PathBuf::from(positional(arg)?)),
+        }
+    }
+
+    match paths.as_slice() {
+        [input, output] => {
+            anonymizer.anonymize_csv_file(input.clone(), output.clone())?;
+            Ok(())
+        }
+        _ => Err(Error::InvalidArgument(
+            "expected <in.csv> and <out.csv> paths".to_string(),
+        )),
+    }
+}
+
+/// `tx-sim merge <accounts.csv>... [-o merged.csv]`
+/// Combine the accounts exports of `--partition` runs into a single export ordered by client, written
+/// to the output file or standard output; Fails if a client appears in more than one input.
+fn merge(mut args: impl Iterator<Item = String>) -> Result<(), Error> {
+    let mut inputs = Vec::new();
+    let mut output = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--output" => output = args.next().map(PathBuf::from),
+            _ => inputs.push(PathBuf::from(positional(arg)?)),
+        }
+    }
+
+    if inputs.is_empty() {
+        return Err(Error::InvalidArgument(
+            "missing <accounts.csv> inputs".to_string(),
+        ));
+    }
+
+    match output {
+        Some(output) => merge_accounts_csv_files(&inputs, output, Durability::default())?,
+        None => merge_accounts_csv_stdout(&inputs)?,
+    };
+
+    Ok(())
+}
+
+/// `tx-sim diagnose <transactions.csv> [--sample 1000]`
+/// Inspect the headers and a sample of rows of a malformed transactions file, report the detected
+/// delimiter, columns and value types, and suggest the `--delimiter`/`--trim`/`--map`/
+/// `--decimal-comma`/`--lenient` options required to process it.
+fn diagnose(mut args: impl Iterator<Item = String>) -> Result<(), Error> {
+    let mut file_path = None;
+    let mut sample = DEFAULT_DIAGNOSE_SAMPLE;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--sample" => {
+                let value = args.next().unwrap_or_default();
+                sample = value
+                    .parse()
+                    .map_err(|_| Error::InvalidArgument(format!("invalid --sample '{}'", value)))?;
+            }
+            _ => file_path = Some(PathBuf::from(positional(arg)?)),
+        }
+    }
+
+    let file_path = file_path
+        .ok_or_else(|| Error::InvalidArgument("missing <transactions.csv> path".to_string()))?;
+
+    print!("{}", diagnose_csv_file(file_path, sample)?);
+
+    Ok(())
+}
+
+/// `tx-sim scenario run script.csv [more.csv ...]`
+/// Run scenario scripts against fresh ledgers and write a summary per script to stdout; Fails if any
+/// expectation fails.
+fn scenario(mut args: impl Iterator<Item = String>) -> Result<(), Error> {
+    match args.next().as_deref() {
+        Some("run") => {}
+        _ => {
+            return Err(Error::InvalidArgument(
+                "expected 'scenario run'".to_string(),
+            ))
+        }
+    }
+
+    let file_paths = args
+        .map(|arg| positional(arg).map(PathBuf::from))
+        .collect::<Result<Vec<_>, Error>>()?;
+    if file_paths.is_empty() {
+        return Err(Error::InvalidArgument(
+            "missing <script.csv> path".to_string(),
+        ));
+    }
+
+    let mut failed = 0;
+    for file_path in file_paths {
+        let report = ScenarioScript::read_file(file_path.clone())?.run(AccountingConfig::default());
+        print!("{}: {}", file_path.display(), report);
+        failed += report.failures.len();
+    }
+
+    if failed > 0 {
+        return Err(Error::ScriptFailed(failed));
+    }
+
+    Ok(())
+}
+
+/// `tx-sim monte-carlo [--runs 100] [--seed 0] [--snapshot snap.bin] [--transactions 1000] [--clients 100]
+/// [--mix 0.5,0.35,0.08,0.05,0.02] [--max-amount 500] [--runs-out runs.csv]`
+/// Run seeded randomized simulations over the snapshot state, or an empty ledger, and write the
+/// distributions of the end balances, locked accounts and negative-balance exposure to stdout.
+fn monte_carlo(mut args: impl Iterator<Item = String>) -> Result<(), Error> {
+    let mut runs = 100;
+    let mut snapshot_path = None;
+    let mut runs_path = None;
+    let mut scenario = Scenario::new("monte-carlo", GeneratorProfile::default());
+
+    fn parse<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, Error> {
+        let value = value.unwrap_or_default();
+        value
+            .parse()
+            .map_err(|_| Error::InvalidArgument(format!("invalid {} '{}'", flag, value)))
+    }
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--runs" => runs = parse(&arg, args.next())?,
+            "--seed" => scenario.seed = parse(&arg, args.next())?,
+            "--snapshot" => snapshot_path = args.next().map(PathBuf::from),
+            "--transactions" => scenario.profile.transactions = parse(&arg, args.next())?,
+            "--clients" => scenario.profile.clients = parse(&arg, args.next())?,
+            "--max-amount" => scenario.profile.max_amount = parse(&arg, args.next())?,
+            "--mix" => {
+                let value = args.next().unwrap_or_default();
+                let weights = value
+                    .split(',')
+                    .map(|weight| parse::<f64>(&arg, Some(weight.to_string())))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                if weights.len() != scenario.profile.mix.len() {
+                    return Err(Error::InvalidArgument(format!(
+                        "invalid --mix '{}'; expected deposit,withdrawal,dispute,resolve,chargeback weights",
+                        value
+                    )));
+                }
+                scenario.profile.mix.copy_from_slice(&weights);
+            }
+            "--runs-out" => runs_path = args.next().map(PathBuf::from),
+            _ => return Err(unknown_option(arg)),
+        }
+    }
+
+    let base = match snapshot_path {
+        Some(path) => Snapshot::read_file(path)?,
+        None => Snapshot::default(),
+    };
+
+    let report = ScenarioRunner::new(base).monte_carlo(&scenario, runs)?;
+
+    if let Some(path) = runs_path {
+        report.write_runs_csv_file(path)?;
+    }
+
+    report.write_distributions_csv(io::stdout())
+}
+
+/// `tx-sim standby --listen <addr> --admin <addr> [--snapshot snap.bin] [policy options] [transactions.csv]`
+/// Replicate a leader's change stream (see `--replicate tcp://...`) until promoted through the admin
+/// endpoint, then continue as the leader: process the transactions file, if any, on top of the
+/// replicated state and write the accounts to stdout, then process the transactions csv sent by
+/// each connection to the `--listen` address.
+/// The admin endpoint accepts one line command per connection: `status`, `accounts`, `reload`,
+/// `promote` or `shutdown`. `accounts` writes a point-in-time csv export of the replicated accounts
+/// while replication continues. `reload [policy options]` applies the policy options, e.g.
+/// `reload --max-open-disputes 3`, on top of the current configuration without a restart, also
+/// once promoted. `promote` is refused while held funds are not accounted for by the replicated
+/// state, see `Standby::check_promotion`, unless sent as `promote --force`. The endpoint is served
+/// until `shutdown`; A failed command is logged and does not stop it.
+fn standby(mut args: impl Iterator<Item = String>) -> Result<(), Error> {
+    let mut listen_addr = None;
+    let mut admin_addr = None;
+    let mut snapshot_path = None;
+    let mut file_path = None;
+    let mut config = AccountingConfig::default();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--listen" => listen_addr = args.next(),
+            "--admin" => admin_addr = args.next(),
+            "--snapshot" => snapshot_path = args.next().map(PathBuf::from),
+            _ if policy_option(&mut config, &arg, &mut args)? => {}
+            _ => file_path = Some(PathBuf::from(positional(arg)?)),
+        }
+    }
+
+    config.validate()?;
+
+    let listen_addr =
+        listen_addr.ok_or_else(|| Error::InvalidArgument("missing --listen <addr>".to_string()))?;
+    let admin_addr =
+        admin_addr.ok_or_else(|| Error::InvalidArgument("missing --admin <addr>".to_string()))?;
+
+    let mut accounting = Accounting::with_config(config);
+    if let Some(path) = snapshot_path {
+        accounting.read_snapshot_file(path)?;
+    }
+
+    let node = Arc::new(Mutex::new(Node::Standby(Standby::new(accounting))));
+    let listener = TcpListener::bind(listen_addr)?;
+    let admin = TcpListener::bind(admin_addr)?;
+
+    // Replicate from the leader's change stream on a background thread, then once promoted process
+    // the transactions sent to the same address;
+    let replica = Arc::clone(&node);
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    error!("Failed to accept connection: {}", e);
+                    continue;
+                }
+            };
+
+            let promoted = matches!(
+                *replica.lock().unwrap_or_else(|e| e.into_inner()),
+                Node::Leader(_)
+            );
+            if promoted {
+                if let Err(e) = lead(stream, &replica) {
+                    error!("Failed to process transactions: {}", e);
+                }
+                continue;
+            }
+
+            for event in ChangeStream::read_events(stream) {
+                let mut guard = replica.lock().unwrap_or_else(|e| e.into_inner());
+
+                // Stop replicating once promoted;
+                let standby = match &mut *guard {
+                    Node::Standby(standby) => standby,
+                    Node::Leader(_) => break,
+                };
+
+                if let Err(e) = event.and_then(|event| standby.apply(&event)) {
+                    error!("Stopped replicating change stream: {}", e);
+                    break;
+                }
+            }
+        }
+    });
+
+    // Serve the admin endpoint, also once promoted, until shut down; A failed connection is logged
+    // and the endpoint keeps serving;
+    for conn in admin.incoming() {
+        match conn
+            .map_err(Error::from)
+            .and_then(|conn| admin_command(conn, &node, &mut file_path))
+        {
+            Ok(true) => break,
+            Ok(false) => {}
+            Err(e) => error!("Failed to serve admin connection: {}", e),
+        }
+    }
+
+    Ok(())
+}
+
+/// Process a transactions csv sent to a promoted `standby`, answering `processed` or the
+/// error; The transactions are read until the sender closes its side before the state is locked,
+/// so a slow sender does not hold up the admin endpoint.
+fn lead(mut stream: TcpStream, node: &Mutex<Node>) -> Result<(), Error> {
+    let mut input = Vec::new();
+    stream.read_to_end(&mut input)?;
+
+    let mut guard = node.lock().unwrap_or_else(|e| e.into_inner());
+    let accounting = match &mut *guard {
+        Node::Leader(accounting) => accounting,
+        Node::Standby(_) => return Err(Error::InvalidArgument("not promoted".to_string())),
+    };
+
+    match accounting.read_transactions_csv(&input[..]) {
+        Ok(()) => writeln!(stream, "processed")?,
+        Err(e) => writeln!(stream, "error[{}]: {}", e.code(), e)?,
+    }
+
+    Ok(())
+}
+
+/// Serve one command of the `standby` admin endpoint; Returns true on `shutdown`.
+fn admin_command(
+    mut conn: TcpStream,
+    node: &Mutex<Node>,
+    file_path: &mut Option<PathBuf>,
+) -> Result<bool, Error> {
+    let mut command = String::new();
+    BufReader::new(conn.try_clone()?).read_line(&mut command)?;
+
+    match command.trim() {
+        "status" => {
+            let guard = node.lock().unwrap_or_else(|e| e.into_inner());
+            match &*guard {
+                Node::Standby(standby) => {
+                    let status = standby.status();
+                    let seconds = |value: Option<Timestamp>| {
+                        value.map_or_else(|| "-".to_string(), |value| format!("{}s", value))
+                    };
+                    writeln!(
+                        conn,
+                        "stream={} last_seq={} lag={} idle={} accounts={}",
+                        status
+                            .stream
+                            .map_or_else(|| "-".to_string(), |stream| format!("{:x}", stream)),
+                        status.last_seq,
+                        seconds(status.lag),
+                        seconds(status.idle),
+                        status.accounts
+                    )?;
+                }
+                Node::Leader(accounting) => {
+                    writeln!(conn, "promoted accounts={}", accounting.accounts().count())?
+                }
+            }
+        }
+        "accounts" => {
+            // Only hold the lock while taking the view, not while writing it out;
+            let view = node
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .accounting()
+                .read_snapshot();
+
+            view.write_accounts_csv(&mut conn)?;
+        }
+        command @ ("promote" | "promote --force") => {
+            let mut guard = node.lock().unwrap_or_else(|e| e.into_inner());
+            let standby = match &mut *guard {
+                Node::Standby(standby) => standby,
+                Node::Leader(_) => {
+                    writeln!(conn, "already promoted")?;
+                    return Ok(false);
+                }
+            };
+
+            // Refuse before giving up the replica, so it keeps replicating;
+            let checked = match command {
+                "promote" => standby.check_promotion(),
+                _ => Ok(()),
+            };
+            let opened = file_path
+                .as_ref()
+                .map_or(Ok(()), |path| std::fs::File::open(path).map(drop));
+            if let Err(e) = checked.and(opened.map_err(Error::from)) {
+                writeln!(conn, "error[{}]: {}", e.code(), e)?;
+                return Ok(false);
+            }
+
+            // Nothing returns early until the promoted state is swapped in, so errors of the
+            // transactions file leave the state as processed so far;
+            let mut accounting = std::mem::take(standby).promote();
+            let processed = match file_path.take() {
+                Some(path) => accounting.read_transactions_csv_file(path),
+                None => Ok(()),
+            }
+            .and_then(|_| accounting.write_accounts_csv_stdout());
+            *guard = Node::Leader(accounting);
+
+            match processed {
+                Ok(()) => writeln!(conn, "promoted")?,
+                Err(e) => writeln!(conn, "promoted, error[{}]: {}", e.code(), e)?,
+            }
+        }
+        "shutdown" => {
+            writeln!(conn, "shutdown")?;
+            return Ok(true);
+        }
+        other if other.split_whitespace().next() == Some("reload") => {
+            let mut guard = node.lock().unwrap_or_else(|e| e.into_inner());
+
+            // Validated before the swap, so a bad reload keeps the running configuration;
+            let mut options = other.split_whitespace().skip(1).map(String::from);
+            let mut config = guard.accounting().config().clone();
+            let reloaded =
+                reload_options(&mut config, &mut options).and_then(|_| match &mut *guard {
+                    Node::Standby(standby) => standby.reload_config(config),
+                    Node::Leader(accounting) => accounting.reload_config(config),
+                });
+
+            match reloaded {
+                Ok(changed) if changed.is_empty() => writeln!(conn, "unchanged")?,
+                Ok(changed) => writeln!(conn, "reloaded {}", changed.join(","))?,
+                Err(e) => writeln!(conn, "error[{}]: {}", e.code(), e)?,
+            }
+        }
+        other => writeln!(conn, "unknown command '{}'", other)?,
+    }
+
+    Ok(false)
+}
+
+/// State served by the `standby` command: a replica until promoted, then the leader;
+enum Node {
+    Standby(Standby),
+    Leader(Accounting),
+}
+
+impl Node {
+    /// Returns the replicated or leading state;
+    fn accounting(&self) -> &Accounting {
+        match self {
+            Node::Standby(standby) => standby.accounting(),
+            Node::Leader(accounting) => accounting,
+        }
+    }
+}
+
+/// Returns the positional argument, e.g. an input path, rejecting options that no arm of the
+/// subcommand matched; Shared by every subcommand, so a typo'd option such as `--sampel` is never
+/// taken as a path. A lone `-` is positional.
+fn positional(arg: String) -> Result<String, Error> {
+    match arg.starts_with('-') && arg != "-" {
+        true => Err(unknown_option(arg)),
+        false => Ok(arg),
+    }
+}
+
+/// Returns the error for an option the subcommand does not accept;
+fn unknown_option(arg: String) -> Error {
+    Error::InvalidArgument(format!("unknown option '{}'", arg))
+}
+
+/// Apply a policy or limit option of the run to the configuration, e.g. `--max-open-disputes 3`;
+/// Returns false if the option is not a policy option. Policy options can also be reloaded through
+/// the `standby` admin endpoint.
+fn policy_option(
+    config: &mut AccountingConfig,
+    arg: &str,
+    args: &mut impl Iterator<Item = String>,
+) -> Result<bool, Error> {
+    match arg {
+        "--epsilon" => {
+            let epsilon = args.next().unwrap_or_default();
+            config.amount_epsilon = epsilon
+                .parse::<Amount>()
+                .map_err(|_| Error::InvalidArgument(format!("invalid --epsilon '{}'", epsilon)))?;
+        }
+        "--max-open-disputes" => {
+            let cap = args.next().unwrap_or_default();
+            config.max_open_disputes = Some(cap.parse().map_err(|_| {
+                Error::InvalidArgument(format!("invalid --max-open-disputes '{}'", cap))
+            })?);
+        }
+        "--dispute-cap-policy" => {
+            config.dispute_cap_policy = args.next().unwrap_or_default().parse()?;
+        }
+        "--dispute-amounts" => {
+            config.dispute_amount_policy = args.next().unwrap_or_default().parse()?
+        }
+        "--dispute-funds" => {
+            config.dispute_funds_policy = args.next().unwrap_or_default().parse()?
+        }
+        "--dispute-expiry-days" => {
+            let days = args.next().unwrap_or_default();
+            config
+                .dispute_expiry
+                .get_or_insert_with(Default::default)
+                .days = days.parse().map_err(|_| {
+                Error::InvalidArgument(format!("invalid --dispute-expiry-days '{}'", days))
+            })?;
+        }
+        "--dispute-expiry-action" => {
+            config
+                .dispute_expiry
+                .get_or_insert_with(Default::default)
+                .action = args.next().unwrap_or_default().parse()?;
+        }
+        "--breaker-rejection-rate" => {
+            let rate = args.next().unwrap_or_default();
+            config
+                .circuit_breaker
+                .get_or_insert_with(Default::default)
+                .max_rejection_rate = Some(rate.parse().map_err(|_| {
+                Error::InvalidArgument(format!("invalid --breaker-rejection-rate '{}'", rate))
+            })?);
+        }
+        "--breaker-window" => {
+            let window = args.next().unwrap_or_default();
+            config
+                .circuit_breaker
+                .get_or_insert_with(Default::default)
+                .rejection_window = window.parse().map_err(|_| {
+                Error::InvalidArgument(format!("invalid --breaker-window '{}'", window))
+            })?;
+        }
+        "--breaker-max-outflow" => {
+            let max = args.next().unwrap_or_default();
+            config
+                .circuit_breaker
+                .get_or_insert_with(Default::default)
+                .max_outflow = Some(max.parse().map_err(|_| {
+                Error::InvalidArgument(format!("invalid --breaker-max-outflow '{}'", max))
+            })?);
+        }
+        "--breaker-outflow-window" => {
+            let seconds = args.next().unwrap_or_default();
+            config
+                .circuit_breaker
+                .get_or_insert_with(Default::default)
+                .outflow_window = seconds.parse().map_err(|_| {
+                Error::InvalidArgument(format!("invalid --breaker-outflow-window '{}'", seconds))
+            })?;
+        }
+        "--breaker-action" => {
+            config
+                .circuit_breaker
+                .get_or_insert_with(Default::default)
+                .action = args.next().unwrap_or_default().parse()?;
+        }
+        "--quarantine-flagged" => config.quarantine_flagged = true,
+        "--partial-disputes" => config.partial_disputes = true,
+        "--withdrawal-disputes" => {
+            config.withdrawal_dispute_policy = args.next().unwrap_or_default().parse()?
+        }
+        "--notify-large-withdrawal" => {
+            let amount = args.next().unwrap_or_default();
+            config
+                .notifications
+                .get_or_insert_with(Default::default)
+                .large_withdrawal = Some(amount.parse().map_err(|_| {
+                Error::InvalidArgument(format!("invalid --notify-large-withdrawal '{}'", amount))
+            })?);
+        }
+        "--budget-policy" => config.budget_policy = args.next().unwrap_or_default().parse()?,
+        _ => return Ok(false),
+    }
+
+    Ok(true)
+}
+
+/// Apply the policy options of an admin `reload` command to the configuration;
+fn reload_options(
+    config: &mut AccountingConfig,
+    options: &mut impl Iterator<Item = String>,
+) -> Result<(), Error> {
+    while let Some(option) = options.next() {
+        if !policy_option(config, &option, options)? {
+            return Err(Error::InvalidArgument(format!(
+                "'{}' is not a policy option",
+                option
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// `tx-sim [options] transactions.csv`
+/// Process a transactions file and write the accounts to stdout;
+fn process(mut args: impl Iterator<Item = String>) -> Result<(), Error> {
+    // Log errors only by default; `-q` silences all output, `-v` adds warnings about
+    // ignored rows and `-vv` adds full per-row processing decisions;
+    let mut log_level = LevelFilter::Error;
+
+    let mut file_path = None;
+    let mut config = AccountingConfig::default();
+
+    // Use the extended output schema, including lifetime account statistics;
+    let mut extended = false;
+
+    // Write the end-of-day balance series to this file path;
+    let mut daily_balances_path = None;
+
+    // Remap legacy client ids using this mapping file;
+    let mut client_map_path = None;
+
+    // Write amounts exceeding four decimal places to this file path;
+    let mut precision_report_path = None;
+
+    // Journal incoming transactions to this directory;
+    let mut journal_dir = None;
+
+    // Open the journal even if another process holds its lock;
+    let mut force = false;
+
+    // Write per-type processing latency summaries to this file path;
+    let mut latency_report_path = None;
+
+    // Load open or resolved disputes from this file path after processing transactions;
+    let mut import_disputes_path = None;
+
+    // Apply a card-network adjustments file (`tx,action,amount`) after the transactions;
+    let mut network_adjustments_path = None;
+
+    // Write the open disputes to this file path;
+    let mut export_disputes_path = None;
+
+    // Write the recorded deposits and withdrawals to this file path, with dense tx ids if a mapping
+    // file path is provided;
+    let mut export_transactions_path = None;
+    let mut dense_tx_ids_path = None;
+
+    // Schedule clients fairly, queueing at most this many transactions per client and window;
+    let mut fair_queue_cap = None;
+
+    // Write the amount distribution per transaction type to this file path;
+    let mut amount_histogram_path = None;
+
+    // Write amount outliers to this file path, flagging amounts more than k MADs from the median;
+    let mut anomalies_path = None;
+    let mut anomaly_threshold = DEFAULT_ANOMALY_THRESHOLD;
+
+    // Write deposits and withdrawals reported as content duplicates to this file path;
+    let mut duplicates_report_path = None;
+
+    // Write deposits and withdrawals rejected for reusing a transaction id to this file path;
+    let mut duplicate_ids_path = None;
+
+    // Write ignored transactions and the reason they were skipped to this file path;
+    let mut rejected_path = None;
+
+    // Write the per-source summaries to this file path;
+    let mut source_summary_path = None;
+
+    // Append the quarantined transactions to this file path, see `tx-sim release`;
+    let mut quarantine_path = None;
+
+    // Flag these clients for review before processing;
+    let mut flagged_clients = Vec::new();
+
+    // Write policy decisions and operator actions to this file path;
+    let mut audit_log_path = None;
+
+    // Load a prior run's accounts csv as the opening state;
+    let mut preload_accounts_path = None;
+
+    // Export processing spans to this OTLP/HTTP endpoint, one span per batch of transactions;
+    let mut otlp_endpoint: Option<String> = None;
+    let mut otlp_batch = None;
+
+    // Write engine metrics in the Prometheus text format to this file path, or log them;
+    let mut metrics_path = None;
+    let mut log_metrics = false;
+
+    // Write the generated interest deposits to this file path;
+    let mut interest_postings_path = None;
+
+    // Write customer notifications for locks, chargebacks and large withdrawals to this file path;
+    let mut notifications_path = None;
+
+    // Write the open order reservations to this file path;
+    let mut reservations_path = None;
+
+    // Write the open disputes and holds, with their age in days, to this file path;
+    let mut held_aging_path = None;
+    // Print peak memory and collection sizes to stderr after processing;
+    let mut resource_report = false;
+
+    // Write account lock and unlock events, with their reasons, to this file path;
+    let mut lock_events_path = None;
+
+    // Write the decision trace to this file path, or stderr;
+    let mut trace_path = None;
+
+    // Prune zero-balance accounts inactive for this many seconds before writing the accounts;
+    let mut prune_policy: Option<PrunePolicy> = None;
+
+    // Archive pruned accounts to this file path;
+    let mut prune_archive_path = None;
+
+    // Write the balances of named sub-accounts to this file path;
+    let mut subaccounts_path = None;
+
+    // Load category budgets from this file path;
+    let mut budgets_path = None;
+
+    // Load account attributes from this file path;
+    let mut attributes_path = None;
+
+    // Load reserve floors from this file path;
+    let mut reserve_floors_path = None;
+
+    // Stream account changes to this file path, or `tcp://host:port`;
+    let mut replicate_to = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-q" | "--quiet" => log_level = LevelFilter::Off,
+            "-v" | "--verbose" => log_level = LevelFilter::Warn,
+            "-vv" => log_level = LevelFilter::Debug,
+            "--extended" => extended = true,
+            "--daily-balances" => {
+                config.track_daily_balances = true;
+                daily_balances_path = args.next().map(PathBuf::from);
+
+                #[cfg(not(feature = "parquet"))]
+                if daily_balances_path
+                    .as_ref()
+                    .and_then(|path| path.extension())
+                    .is_some_and(|extension| extension == "parquet")
+                {
+                    return Err(Error::InvalidArgument(
+                        "--daily-balances: writing Parquet requires the parquet feature"
+                            .to_string(),
+                    ));
+                }
+            }
+            "--rejected" => {
+                config.track_rejected = true;
+                rejected_path = args.next().map(PathBuf::from);
+            }
+            "--tag-source" => config.tag_source = true,
+            "--source-summary" => {
+                config.tag_source = true;
+                source_summary_path = args.next().map(PathBuf::from);
+            }
+            "--strict" => config.strictness = Strictness::Strict,
+            "--lenient" => config.strictness = Strictness::Lenient,
+            "--precision" => {
+                config.precision_policy = args.next().unwrap_or_default().parse()?;
+            }
+            "--fsync" => config.output_durability = args.next().unwrap_or_default().parse()?,
+            "--precision-report" => precision_report_path = args.next().map(PathBuf::from),
+            "--replicate" => replicate_to = args.next(),
+            "--journal" => journal_dir = args.next().map(PathBuf::from),
+            "--force" => force = true,
+            "--latency-report" => latency_report_path = args.next().map(PathBuf::from),
+            "--slow-threshold-us" => {
+                let micros = args.next().unwrap_or_default();
+                let micros = micros.parse::<u64>().map_err(|_| {
+                    Error::InvalidArgument(format!("invalid --slow-threshold-us '{}'", micros))
+                })?;
+                config.slow_transaction_threshold = Some(Duration::from_micros(micros));
+            }
+            "--check-invariants" => {
+                let interval = args.next().unwrap_or_default();
+                let interval = interval.parse::<usize>().ok().filter(|n| *n > 0);
+                config.invariant_check_interval = Some(interval.ok_or_else(|| {
+                    Error::InvalidArgument("--check-invariants expects a positive interval".into())
+                })?);
+            }
+            "--import-disputes" => import_disputes_path = args.next().map(PathBuf::from),
+            "--network-adjustments" => network_adjustments_path = args.next().map(PathBuf::from),
+            "--export-disputes" => export_disputes_path = args.next().map(PathBuf::from),
+            "--export-transactions" => export_transactions_path = args.next().map(PathBuf::from),
+            "--dense-tx-ids" => dense_tx_ids_path = args.next().map(PathBuf::from),
+            "--fair-queue-cap" => {
+                let cap = args.next().unwrap_or_default();
+                let cap = cap.parse::<usize>().ok().filter(|n| *n > 0);
+                fair_queue_cap = Some(cap.ok_or_else(|| {
+                    Error::InvalidArgument("--fair-queue-cap expects a positive size".into())
+                })?);
+            }
+            "--amount-histogram" => amount_histogram_path = args.next().map(PathBuf::from),
+            "--anomalies" => anomalies_path = args.next().map(PathBuf::from),
+            "--anomaly-k" => {
+                let k = args.next().unwrap_or_default();
+                anomaly_threshold = k
+                    .parse::<f64>()
+                    .map_err(|_| Error::InvalidArgument(format!("invalid --anomaly-k '{}'", k)))?;
+            }
+            "--dedup-window" => {
+                let window = args.next().unwrap_or_default();
+                config.dedup.get_or_insert_with(Default::default).window =
+                    window.parse().map_err(|_| {
+                        Error::InvalidArgument(format!("invalid --dedup-window '{}'", window))
+                    })?;
+            }
+            "--dedup-client" => {
+                let value = args.next().unwrap_or_default();
+                let (client, window) = value
+                    .split_once(':')
+                    .and_then(|(client, window)| Some((client.parse().ok()?, window.parse().ok()?)))
+                    .ok_or_else(|| {
+                        Error::InvalidArgument(format!(
+                            "invalid --dedup-client '{}', expected <client>:<seconds>",
+                            value
+                        ))
+                    })?;
+                config
+                    .dedup
+                    .get_or_insert_with(Default::default)
+                    .client_windows
+                    .insert(client, window);
+            }
+            "--duplicates-report" => duplicates_report_path = args.next().map(PathBuf::from),
+            "--duplicate-ids" => duplicate_ids_path = args.next().map(PathBuf::from),
+            "--quarantine" => quarantine_path = args.next().map(PathBuf::from),
+            "--flag" => {
+                let value = args.next().unwrap_or_default();
+                flagged_clients.push(
+                    value.parse::<ClientId>().map_err(|_| {
+                        Error::InvalidArgument(format!("invalid --flag '{}'", value))
+                    })?,
+                );
+            }
+            "--audit-log" => audit_log_path = args.next().map(PathBuf::from),
+            "--preload-accounts" => preload_accounts_path = args.next().map(PathBuf::from),
+            "--otlp-endpoint" => otlp_endpoint = args.next(),
+            "--otlp-batch" => {
+                let size = args.next().unwrap_or_default();
+                let size = size.parse::<usize>().ok().filter(|n| *n > 0);
+                otlp_batch = Some(size.ok_or_else(|| {
+                    Error::InvalidArgument("--otlp-batch expects a positive size".into())
+                })?);
+            }
+            "--metrics" => metrics_path = args.next().map(PathBuf::from),
+            "--log-metrics" => log_metrics = true,
+            "--reservations" => reservations_path = args.next().map(PathBuf::from),
+            "--interest-rate" => {
+                let rate = args.next().unwrap_or_default();
+                config
+                    .interest
+                    .get_or_insert_with(Default::default)
+                    .daily_rate = rate.parse().map_err(|_| {
+                    Error::InvalidArgument(format!("invalid --interest-rate '{}'", rate))
+                })?;
+            }
+            "--interest-compound-days" => {
+                let days = args.next().unwrap_or_default();
+                config
+                    .interest
+                    .get_or_insert_with(Default::default)
+                    .compound_days =
+                    days.parse().ok().filter(|days| *days > 0).ok_or_else(|| {
+                        Error::InvalidArgument(format!(
+                            "invalid --interest-compound-days '{}'",
+                            days
+                        ))
+                    })?;
+            }
+            "--interest-postings" => interest_postings_path = args.next().map(PathBuf::from),
+            "--notifications" => {
+                notifications_path = args.next().map(PathBuf::from);
+                config.notifications.get_or_insert_with(Default::default);
+            }
+            "--held-aging" => held_aging_path = args.next().map(PathBuf::from),
+            "--resource-report" => resource_report = true,
+            "--lock-events" => lock_events_path = args.next().map(PathBuf::from),
+            "--trace-tx" => {
+                let tx = args.next().unwrap_or_default();
+                let tx = tx
+                    .parse()
+                    .map_err(|_| Error::InvalidArgument(format!("invalid --trace-tx '{}'", tx)))?;
+                config
+                    .trace
+                    .get_or_insert_with(Default::default)
+                    .txs
+                    .insert(tx);
+            }
+            "--trace-client" => {
+                let client = args.next().unwrap_or_default();
+                let client = client.parse().map_err(|_| {
+                    Error::InvalidArgument(format!("invalid --trace-client '{}'", client))
+                })?;
+                config
+                    .trace
+                    .get_or_insert_with(Default::default)
+                    .clients
+                    .insert(client);
+            }
+            "--trace-out" => trace_path = args.next().map(PathBuf::from),
+            "--prune-inactive" => {
+                let seconds = args.next().unwrap_or_default();
+                prune_policy = Some(PrunePolicy {
+                    inactive_for: seconds.parse().map_err(|_| {
+                        Error::InvalidArgument(format!("invalid --prune-inactive '{}'", seconds))
+                    })?,
+                    archive: None,
+                });
+            }
+            "--prune-archive" => prune_archive_path = args.next().map(PathBuf::from),
+            "--subaccounts" => subaccounts_path = args.next().map(PathBuf::from),
+            "--clearing-delay" => {
+                let delay = args.next().unwrap_or_default();
+                config.clearing_delay = Some(delay.parse().map_err(|_| {
+                    Error::InvalidArgument(format!("invalid --clearing-delay '{}'", delay))
+                })?);
+            }
+            "--budgets" => budgets_path = args.next().map(PathBuf::from),
+            "--attributes" => attributes_path = args.next().map(PathBuf::from),
+            "--reserve-floors" => reserve_floors_path = args.next().map(PathBuf::from),
+            "--client-map" => client_map_path = args.next().map(PathBuf::from),
+            "--delimiter" => {
+                config.input.delimiter =
+                    InputFormat::parse_delimiter(&args.next().unwrap_or_default())?
+            }
+            "--trim" => config.input.trim = true,
+            "--decimal-comma" => config.input.decimal_comma = true,
+            "--comment" => {
+                let comment = args.next().unwrap_or_default();
+                config.input.comment = Some(match comment.as_bytes() {
+                    [c] if c.is_ascii() => *c,
+                    _ => {
+                        return Err(Error::InvalidArgument(format!(
+                            "invalid --comment '{}'; expected a single character",
+                            comment
+                        )))
+                    }
+                });
+            }
+            "--skip-blank-lines" => config.input.skip_blank_lines = true,
+            "--encoding" => config.input.encoding = Some(args.next().unwrap_or_default().parse()?),
+            "--partition" => config.partition = Some(args.next().unwrap_or_default().parse()?),
+            "--max-rows" => {
+                let rows = args.next().unwrap_or_default();
+                config.max_rows = Some(rows.parse().map_err(|_| {
+                    Error::InvalidArgument(format!("invalid --max-rows '{}'", rows))
+                })?);
+            }
+            "--max-file-size" => {
+                let size = args.next().unwrap_or_default();
+                config.max_file_size = Some(parse_bytes(&size).ok_or_else(|| {
+                    Error::InvalidArgument(format!(
+                        "invalid --max-file-size '{}', expected bytes with an optional K, M or G suffix",
+                        size
+                    ))
+                })?);
+            }
+            "--map" => config.input.map_column(&args.next().unwrap_or_default())?,
+            _ if policy_option(&mut config, &arg, &mut args)? => {}
+            _ => file_path = Some(PathBuf::from(positional(arg)?)),
+        }
+    }
+
+    config.validate()?;
+
+    if dense_tx_ids_path.is_some() && export_transactions_path.is_none() {
+        return Err(Error::InvalidArgument(
+            "--dense-tx-ids requires --export-transactions <path>".to_string(),
+        ));
+    }
+
+    log::set_max_level(log_level);
+
+    if let Some(file_path) = file_path {
+        let mut accounting = Accounting::with_config(config);
+
+        let prometheus = metrics_path.as_ref().map(|_| PrometheusMetrics::new());
+        if let Some(prometheus) = prometheus.clone() {
+            accounting.set_metrics_sink(Arc::new(prometheus));
+        } else if log_metrics {
+            accounting.set_metrics_sink(Arc::new(LogMetrics));
+        }
+
+        if let Some(path) = client_map_path {
+            accounting.add_enricher(Box::new(ClientIdRemapper::from_csv_file(path)?));
+        }
+
+        if let Some(path) = budgets_path {
+            accounting.read_budgets_csv_file(path)?;
+        }
+
+        if let Some(path) = attributes_path {
+            accounting.read_attributes_csv_file(path)?;
+        }
+
+        if let Some(path) = reserve_floors_path {
+            accounting.read_reserve_floors_csv_file(path)?;
+        }
+
+        if let Some(path) = preload_accounts_path {
+            accounting.preload_accounts_csv(path)?;
+        }
+
+        if let Some(dir) = journal_dir {
+            let journal_config = JournalConfig {
+                durability: accounting.config().output_durability,
+                force,
+                ..Default::default()
+            };
+            let journal = Journal::open(dir, journal_config, accounting.now())?;
+            accounting.attach_journal(journal)?;
+        }
+
+        // Flagged after the journal is attached, so the flags are journaled;
+        for client in flagged_clients {
+            accounting.flag_account(client);
+        }
+
+        if let Some(target) = replicate_to {
+            let stream = match target.strip_prefix("tcp://") {
+                Some(addr) => ChangeStream::connect(addr)?,
+                None => ChangeStream::create_file(PathBuf::from(target))?,
+            };
+            accounting.attach_change_stream(stream);
+        }
+
+        if let Some(endpoint) = otlp_endpoint {
+            #[cfg(feature = "otlp")]
+            {
+                // Join the upstream stage's trace, if any;
+                let mut otlp_config = OtlpConfig {
+                    endpoint,
+                    traceparent: env::var("TRACEPARENT").ok(),
+                    ..Default::default()
+                };
+                if let Some(size) = otlp_batch {
+                    otlp_config.batch_size = size;
+                }
+                accounting.attach_otlp_exporter(OtlpExporter::new(otlp_config)?);
+            }
+
+            #[cfg(not(feature = "otlp"))]
+            {
+                let _ = otlp_batch;
+                return Err(Error::InvalidArgument(format!(
+                    "--otlp-endpoint '{}' requires the otlp feature",
+                    endpoint
+                )));
+            }
+        }
 
         // Read the incoming transactions file;
-        accounting.read_transactions_csv_file(PathBuf::from(file_path))?;
+        match fair_queue_cap {
+            Some(cap) => {
+                let mut scheduler = FairScheduler::new(cap);
+                accounting.read_transactions_csv_file_fair(
+                    file_path,
+                    &mut scheduler,
+                    DEFAULT_SCHEDULER_WINDOW,
+                )?;
+
+                for (client, count) in scheduler.overflows() {
+                    warn!("Queue of client {} overflowed {} times", client, count);
+                }
+            }
+            None => accounting.read_transactions_csv_file(file_path)?,
+        }
+
+        if let Some(path) = import_disputes_path {
+            accounting.read_disputes_csv_file(path)?;
+        }
+
+        if let Some(path) = network_adjustments_path {
+            accounting.read_network_adjustments_csv_file(path)?;
+        }
+
+        // Tracing is best effort; A collector outage does not fail processing;
+        #[cfg(feature = "otlp")]
+        if let Some(otlp) = accounting.detach_otlp_exporter() {
+            if let Err(e) = otlp.finish() {
+                warn!("Failed to export spans: {}", e);
+            }
+        }
+
+        if let Some(journal) = accounting.journal_mut() {
+            journal.flush()?;
+        }
+
+        if let Some(mut policy) = prune_policy {
+            policy.archive = prune_archive_path;
+            accounting.prune(&policy)?;
+        }
 
         // Write the accounts statements after processing transactions;
-        accounting.write_accounts_csv_stdout()?;
+        if extended {
+            accounting.write_accounts_extended_csv_stdout()?;
+        } else {
+            accounting.write_accounts_csv_stdout()?;
+        }
+
+        // The daily balance series is written as Parquet for a `.parquet` path;
+        if let Some(path) = daily_balances_path {
+            match path.extension() {
+                #[cfg(feature = "parquet")]
+                Some(extension) if extension == "parquet" => {
+                    accounting.write_daily_balances_parquet_file(path)?
+                }
+                _ => accounting.write_daily_balances_csv_file(path)?,
+            }
+        }
+
+        if let Some(path) = precision_report_path {
+            accounting.write_precision_adjustments_csv_file(path)?;
+        }
+
+        if let Some(path) = latency_report_path {
+            accounting.write_latency_csv_file(path)?;
+        }
+
+        if let Some(path) = export_disputes_path {
+            accounting.write_disputes_csv_file(path)?;
+        }
+
+        if let Some(path) = export_transactions_path {
+            accounting.write_recorded_transactions_csv_file(path, dense_tx_ids_path)?;
+        }
+
+        if accounting.config().trace.is_some() {
+            match trace_path {
+                Some(path) => accounting.write_trace_csv_file(path)?,
+                None => accounting.write_trace_csv(io::stderr())?,
+            }
+        }
+
+        if let Some(path) = subaccounts_path {
+            accounting.write_subaccounts_csv_file(path)?;
+        }
+
+        if let Some(path) = audit_log_path {
+            accounting.write_audit_log_csv_file(path)?;
+        }
+
+        if let (Some(path), Some(prometheus)) = (metrics_path, prometheus) {
+            prometheus.write_file(path)?;
+        }
+
+        if let Some(path) = reservations_path {
+            accounting.write_reservations_csv_file(path)?;
+        }
+
+        if let Some(path) = lock_events_path {
+            accounting.write_lock_events_csv_file(path)?;
+        }
+
+        if let Some(path) = interest_postings_path {
+            accounting.write_interest_postings_csv_file(path)?;
+        }
+
+        if let Some(path) = notifications_path {
+            accounting.write_notifications_csv_file(path)?;
+        }
+
+        if let Some(path) = held_aging_path {
+            accounting.write_held_aging_csv_file(path)?;
+        }
+
+        if let Some(path) = duplicates_report_path {
+            accounting.write_content_duplicates_csv_file(path)?;
+        }
+
+        if let Some(path) = duplicate_ids_path {
+            accounting.write_duplicate_tx_ids_csv_file(path)?;
+        }
+
+        if let Some(path) = rejected_path {
+            accounting.write_rejected_csv_file(path)?;
+        }
+
+        if let Some(path) = source_summary_path {
+            accounting.write_source_summaries_csv_file(path)?;
+        }
+
+        if let Some(path) = quarantine_path {
+            accounting.append_quarantined_csv_file(path)?;
+        }
+
+        if let Some(path) = amount_histogram_path {
+            accounting.write_amount_histogram_csv_file(path)?;
+        }
+
+        if let Some(path) = anomalies_path {
+            accounting.write_anomalies_csv_file(path, anomaly_threshold)?;
+        }
+
+        if resource_report {
+            eprint!("{}", accounting.resource_report());
+        }
+
+        // Report a circuit breaker tripped in quarantine mode, unless quiet;
+        if let Some(trip) = accounting.circuit_breaker_trip() {
+            if log_level != LevelFilter::Off {
+                eprintln!(
+                    "warning: circuit breaker tripped by tx {}: {}; {} transactions quarantined",
+                    trip.tx,
+                    trip.reason,
+                    accounting.quarantined_transactions().len()
+                );
+            }
+        }
+
+        // Report rows skipped for unknown transaction types, unless quiet;
+        if !accounting.unknown_tx_types().is_empty() && log_level != LevelFilter::Off {
+            let skipped: Vec<String> = accounting
+                .unknown_tx_types()
+                .iter()
+                .map(|(name, rows)| format!("'{}' ({} rows)", name, rows))
+                .collect();
+            eprintln!(
+                "warning: skipped rows of unknown transaction types: {}",
+                skipped.join(", ")
+            );
+        }
     }
 
     Ok(())
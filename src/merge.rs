@@ -0,0 +1,101 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use csv::{Reader, StringRecord, Writer};
+
+use crate::*;
+
+/// Read the accounts exports of partitioned runs, asserting they share the same columns and that each
+/// client appears in exactly one input; Returns the headers and the records ordered by client.
+fn read_partitions(inputs: &[PathBuf]) -> Result<(StringRecord, Vec<StringRecord>), Error> {
+    let mut headers: Option<StringRecord> = None;
+    let mut records: BTreeMap<ClientId, (StringRecord, &Path)> = BTreeMap::new();
+
+    for input in inputs.iter() {
+        let mut rdr = Reader::from_path(input)?;
+        let input_headers = rdr.headers()?.clone();
+
+        match headers.as_ref() {
+            Some(headers) if *headers != input_headers => {
+                return Err(Error::InvalidArgument(format!(
+                    "{}: columns differ from the first input",
+                    input.display()
+                )));
+            }
+            Some(_) => {}
+            None => headers = Some(input_headers.clone()),
+        }
+
+        let client_column = input_headers
+            .iter()
+            .position(|header| header == "client")
+            .ok_or_else(|| {
+                Error::InvalidArgument(format!("{}: missing client column", input.display()))
+            })?;
+
+        for record in rdr.records() {
+            let record = record?;
+            let client = record
+                .get(client_column)
+                .unwrap_or_default()
+                .parse::<ClientId>()
+                .map_err(|_| {
+                    Error::InvalidArgument(format!(
+                        "{}: invalid client '{}'",
+                        input.display(),
+                        record.get(client_column).unwrap_or_default()
+                    ))
+                })?;
+
+            if let Some((_, other)) = records.get(&client) {
+                return Err(Error::InvalidArgument(format!(
+                    "client {} appears in both {} and {}",
+                    client,
+                    other.display(),
+                    input.display()
+                )));
+            }
+            records.insert(client, (record, input));
+        }
+    }
+
+    Ok((
+        headers.unwrap_or_default(),
+        records.into_values().map(|(record, _)| record).collect(),
+    ))
+}
+
+/// Combine the accounts exports of partitioned runs into a single export ordered by client;
+/// Fails if the inputs have different columns or a client appears in more than one input.
+/// Returns the number of accounts written.
+pub fn merge_accounts_csv_files(
+    inputs: &[PathBuf],
+    output: PathBuf,
+    durability: Durability,
+) -> Result<usize, Error> {
+    let (headers, records) = read_partitions(inputs)?;
+
+    let mut sink = CsvFileSink::create(output, durability)?;
+    sink.write(headers.iter().collect::<Vec<&str>>())?;
+    for record in records.iter() {
+        sink.write(record.iter().collect::<Vec<&str>>())?;
+    }
+    sink.close()?;
+
+    Ok(records.len())
+}
+
+/// Combine the accounts exports of partitioned runs, writing the single export ordered by client to
+/// standard output; Returns the number of accounts written.
+pub fn merge_accounts_csv_stdout(inputs: &[PathBuf]) -> Result<usize, Error> {
+    let (headers, records) = read_partitions(inputs)?;
+
+    let mut wtr = Writer::from_writer(std::io::stdout());
+    wtr.write_record(&headers)?;
+    for record in records.iter() {
+        wtr.write_record(record)?;
+    }
+    wtr.flush()?;
+
+    Ok(records.len())
+}
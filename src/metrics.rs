@@ -0,0 +1,226 @@
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Write as _};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use log::info;
+
+use crate::*;
+
+/// Metric labels as name and value pairs, e.g. `[("type", "deposit")]`;
+pub type Labels<'a> = &'a [(&'a str, &'a str)];
+
+/// Backend receiving the engine's metrics, e.g. to forward them to StatsD or OpenTelemetry;
+/// Set with `Accounting::set_metrics_sink`. Methods are called on the processing path, so
+/// implementations should be cheap and must not block.
+///
+/// | Metric                                 | Kind      | Labels |
+/// |----------------------------------------|-----------|--------|
+/// | `tx_sim_transactions_total`            | counter   | `type` |
+/// | `tx_sim_transactions_ignored_total`    | counter   | `type` |
+/// | `tx_sim_transaction_duration_seconds`  | histogram | `type` |
+/// | `tx_sim_accounts_locked_total`         | counter   | `reason` |
+/// | `tx_sim_scheduler_overflows_total`     | counter   | `client` |
+/// | `tx_sim_accounts`                      | gauge     |        |
+/// | `tx_sim_open_disputes`                 | gauge     |        |
+pub trait MetricsSink: Debug + Send + Sync {
+    /// Increment a counter by the value;
+    fn counter(&self, name: &str, value: u64, labels: Labels);
+    /// Set a gauge to the value;
+    fn gauge(&self, name: &str, value: f64, labels: Labels);
+    /// Record an observation of a histogram;
+    fn histogram(&self, name: &str, value: f64, labels: Labels);
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+/// Discards all metrics; The default when no sink is set.
+pub struct NoopMetrics;
+
+impl MetricsSink for NoopMetrics {
+    fn counter(&self, _name: &str, _value: u64, _labels: Labels) {}
+    fn gauge(&self, _name: &str, _value: f64, _labels: Labels) {}
+    fn histogram(&self, _name: &str, _value: f64, _labels: Labels) {}
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+/// Logs every metric at info level; Useful for debugging, too verbose for production.
+pub struct LogMetrics;
+
+impl MetricsSink for LogMetrics {
+    fn counter(&self, name: &str, value: u64, labels: Labels) {
+        info!("counter {}{} += {}", name, format_labels(labels), value);
+    }
+
+    fn gauge(&self, name: &str, value: f64, labels: Labels) {
+        info!("gauge {}{} = {}", name, format_labels(labels), value);
+    }
+
+    fn histogram(&self, name: &str, value: f64, labels: Labels) {
+        info!("histogram {}{} <- {}", name, format_labels(labels), value);
+    }
+}
+
+/// Format labels in the Prometheus text format, e.g. `{type="deposit"}`; Empty without labels.
+fn format_labels(labels: Labels) -> String {
+    if labels.is_empty() {
+        return String::new();
+    }
+
+    let labels: Vec<String> = labels
+        .iter()
+        .map(|(name, value)| format!("{}=\"{}\"", name, value.replace('"', "\\\"")))
+        .collect();
+
+    format!("{{{}}}", labels.join(","))
+}
+
+/// Default upper bounds, in seconds, of the `PrometheusMetrics` histogram buckets;
+pub const DEFAULT_METRICS_BUCKETS: [f64; 8] = [1e-6, 1e-5, 1e-4, 1e-3, 1e-2, 0.1, 1., 10.];
+
+#[derive(Debug, Clone)]
+enum PrometheusValue {
+    Counter(u64),
+    Gauge(f64),
+    /// Cumulative count per bucket bound, followed by the sum and count of all observations;
+    Histogram(Vec<u64>, f64, u64),
+}
+
+#[derive(Debug, Default, Clone)]
+/// Aggregates metrics in memory and renders them in the Prometheus text exposition format;
+/// Clones share the same registry, so one clone can be set on the processor and another rendered.
+pub struct PrometheusMetrics {
+    buckets: Vec<f64>,
+    /// Values keyed by metric name, then formatted labels;
+    values: Arc<Mutex<BTreeMap<String, BTreeMap<String, PrometheusValue>>>>,
+}
+
+impl PrometheusMetrics {
+    /// Create a registry using `DEFAULT_METRICS_BUCKETS` for histograms;
+    pub fn new() -> Self {
+        PrometheusMetrics::with_buckets(DEFAULT_METRICS_BUCKETS.to_vec())
+    }
+
+    /// Create a registry using the provided histogram bucket upper bounds, in ascending order;
+    pub fn with_buckets(buckets: Vec<f64>) -> Self {
+        PrometheusMetrics {
+            buckets,
+            ..Default::default()
+        }
+    }
+
+    fn update<F: FnOnce(&mut Option<PrometheusValue>)>(&self, name: &str, labels: Labels, f: F) {
+        let mut values = self.values.lock().unwrap_or_else(|e| e.into_inner());
+        let series = values.entry(name.to_string()).or_default();
+
+        let key = format_labels(labels);
+        let mut value = series.remove(&key);
+        f(&mut value);
+
+        if let Some(value) = value {
+            series.insert(key, value);
+        }
+    }
+
+    /// Render all metrics in the Prometheus text exposition format;
+    pub fn render(&self) -> String {
+        let values = self.values.lock().unwrap_or_else(|e| e.into_inner());
+        let mut out = String::new();
+
+        for (name, series) in values.iter() {
+            let kind = match series.values().next() {
+                Some(PrometheusValue::Counter(_)) => "counter",
+                Some(PrometheusValue::Gauge(_)) => "gauge",
+                Some(PrometheusValue::Histogram(..)) => "histogram",
+                None => continue,
+            };
+            let _ = writeln!(out, "# TYPE {} {}", name, kind);
+
+            for (labels, value) in series.iter() {
+                match value {
+                    PrometheusValue::Counter(n) => {
+                        let _ = writeln!(out, "{}{} {}", name, labels, n);
+                    }
+                    PrometheusValue::Gauge(v) => {
+                        let _ = writeln!(out, "{}{} {}", name, labels, v);
+                    }
+                    PrometheusValue::Histogram(counts, sum, count) => {
+                        // The bucket bound is appended to any existing labels;
+                        let prefix = labels.trim_end_matches('}');
+                        let separator = if labels.is_empty() { "{" } else { "," };
+
+                        for (bound, n) in self.buckets.iter().zip(counts.iter()) {
+                            let _ = writeln!(
+                                out,
+                                "{}_bucket{}{}le=\"{}\"}} {}",
+                                name, prefix, separator, bound, n
+                            );
+                        }
+                        let _ = writeln!(
+                            out,
+                            "{}_bucket{}{}le=\"+Inf\"}} {}",
+                            name, prefix, separator, count
+                        );
+                        let _ = writeln!(out, "{}_sum{} {}", name, labels, sum);
+                        let _ = writeln!(out, "{}_count{} {}", name, labels, count);
+                    }
+                }
+            }
+        }
+
+        out
+    }
+
+    /// Write all metrics in the Prometheus text exposition format, e.g. for the node exporter's
+    /// textfile collector;
+    pub fn write_file(&self, file_path: PathBuf) -> Result<(), Error> {
+        fs::write(file_path, self.render())?;
+        Ok(())
+    }
+}
+
+impl MetricsSink for PrometheusMetrics {
+    fn counter(&self, name: &str, value: u64, labels: Labels) {
+        self.update(name, labels, |current| match current {
+            Some(PrometheusValue::Counter(n)) => *n += value,
+            _ => *current = Some(PrometheusValue::Counter(value)),
+        });
+    }
+
+    fn gauge(&self, name: &str, value: f64, labels: Labels) {
+        self.update(name, labels, |current| {
+            *current = Some(PrometheusValue::Gauge(value))
+        });
+    }
+
+    fn histogram(&self, name: &str, value: f64, labels: Labels) {
+        let buckets = &self.buckets;
+        self.update(name, labels, |current| {
+            if !matches!(current, Some(PrometheusValue::Histogram(..))) {
+                *current = Some(PrometheusValue::Histogram(vec![0; buckets.len()], 0., 0));
+            }
+
+            if let Some(PrometheusValue::Histogram(counts, sum, count)) = current {
+                for (bound, n) in buckets.iter().zip(counts.iter_mut()) {
+                    if value <= *bound {
+                        *n += 1;
+                    }
+                }
+                *sum += value;
+                *count += 1;
+            }
+        });
+    }
+}
+
+impl Accounting {
+    /// Set the backend receiving the engine's metrics; See `MetricsSink`.
+    pub fn set_metrics_sink(&mut self, sink: Arc<dyn MetricsSink>) {
+        self.metrics = Some(sink);
+    }
+
+    /// Returns the backend receiving the engine's metrics, if any;
+    pub fn metrics_sink(&self) -> Option<&Arc<dyn MetricsSink>> {
+        self.metrics.as_ref()
+    }
+}
@@ -1,99 +1,515 @@
-use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
 
-use super::Error;
+use rust_decimal::Decimal;
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{CustomTxType, Error, LockReason, AMOUNT_DECIMAL_PLACES};
 
 /// Valid u16 client ID; Client IDs exceeding u16::MAX will be considered invalid;
+/// Widened to u32 or u64 by the `client-id-u32` and `client-id-u64` features.
+#[cfg(not(any(feature = "client-id-u32", feature = "client-id-u64")))]
 pub type ClientId = u16;
 
-/// Globally unique, unordered u32 transaction ID; Transaction IDs exceeding u32::MAX will be considered invalid;
+/// Valid u32 client ID; Client IDs exceeding u32::MAX will be considered invalid;
+#[cfg(all(feature = "client-id-u32", not(feature = "client-id-u64")))]
+pub type ClientId = u32;
+
+/// Valid u64 client ID;
+#[cfg(feature = "client-id-u64")]
+pub type ClientId = u64;
+
+/// Globally unique, unordered transaction ID; Transaction IDs exceeding `TxId::MAX` will be considered
+/// invalid; A u32 by default, widened to u64 by the `tx-id-u64` feature.
+#[cfg(not(feature = "tx-id-u64"))]
 pub type TxId = u32;
 
+/// Globally unique, unordered transaction ID, widened to u64 by the `tx-id-u64` feature;
+#[cfg(feature = "tx-id-u64")]
+pub type TxId = u64;
+
+/// Exact decimal amount of funds; Arithmetic on amounts never drifts, unlike binary floating-point;
+pub type Amount = Decimal;
+
+/// Unix timestamp in seconds;
+pub type Timestamp = u64;
+
+/// Name of a client-scoped sub-account, e.g. `savings`;
+pub type SubAccountId = String;
+
+/// Number of seconds in a day, used for bucketing timestamps by date;
+pub const SECONDS_PER_DAY: Timestamp = 86_400;
+
 /// The possible transactions types representing a transaction.
 /// This structure provides the match arm expressions for determining
 /// transactions processing logic.
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum TxType {
-    #[serde(rename = "deposit")]
     /// A deposit is a credit to the client’s asset account, meaning it should **increase the available and
     /// total funds** of the client account
     Deposit,
-    #[serde(rename = "withdrawal")]
     /// A withdraw is a debit to the client’s asset account, meaning it should **decrease the available and
     /// total funds** of the client account. If a client does not have sufficient available funds the withdrawal
     // should fail and the total amount of funds should not change
     Withdrawal,
-    #[serde(rename = "dispute")]
     /// A dispute represents a client’s claim that a transaction was erroneous and should be reverse. The
     /// transaction shouldn’t be reversed yet but the associated funds should be held. This means that the
     /// clients **available funds should decrease** by the amount disputed, their **held funds should increase**
     /// by the amount disputed, while their *total funds should remain the same*.
     Dispute,
-    #[serde(rename = "resolve")]
     /// A resolve represents a resolution to a dispute, releasing the associated held funds. Funds that were
     /// previously disputed are no longer disputed. This means that the clients **held funds should decrease**
     /// by the amount no longer disputed, their **available funds should increase** by the amount no longer
     /// disputed, and **their total funds should remain the same**.
     Resolve,
-    #[serde(rename = "chargeback")]
     /// A chargeback is the final state of a dispute and represents the client reversing a transaction. Funds
     /// that were held have now been withdrawn. This means that the clients **held funds and total funds
     /// should decrease by the amount** previously disputed. **If a chargeback occurs the client’s account
     /// should be immediately frozen**.
     Chargeback,
-    #[serde(rename = "unknown")]
+    /// A reserve sets aside available funds for an open order on a trading engine. The reserved funds are
+    /// moved from available to held and tracked as a reservation under the reserve's tx id.
+    Reserve,
+    /// A release returns reserved funds to available when an order is cancelled, referencing the reserve's
+    /// tx id. Without an amount, the whole remaining reservation is released.
+    Release,
+    /// A fill converts reserved funds into a withdrawal when an order executes, referencing the reserve's
+    /// tx id. Without an amount, the whole remaining reservation is filled.
+    Fill,
+    /// A reversal overturns a chargeback, e.g. after representment, referencing the charged back tx id.
+    /// The amount, or the whole charged back amount without one, is credited back to the client, or
+    /// debited again for a charged back withdrawal. Reversals apply to locked accounts, which stay locked.
+    Reversal,
+    /// An adjustment debits an operator fee or correction from the client's available and total funds,
+    /// even below zero available, under a newly allocated tx id; The category column carries the
+    /// reason, and a negative amount credits the client. Applied with `Accounting::debit_adjustment`,
+    /// which caps it at the available funds, and by `Accounting::close_account` to move residual funds.
+    Adjustment,
     /// an unknown transaction;
     Unknown,
+    /// A transaction type registered with `Accounting::register_tx_type`, processed by the handler
+    /// registered with `Accounting::register_handler`;
+    Custom(CustomTxType),
+}
+
+impl TxType {
+    /// Every built-in transaction type; `TxType::Unknown` and custom types are excluded.
+    pub const ALL: [TxType; 10] = [
+        TxType::Deposit,
+        TxType::Withdrawal,
+        TxType::Dispute,
+        TxType::Resolve,
+        TxType::Chargeback,
+        TxType::Reserve,
+        TxType::Release,
+        TxType::Fill,
+        TxType::Reversal,
+        TxType::Adjustment,
+    ];
+
+    /// The deposit, withdrawal and dispute flow types, as produced by the generators;
+    pub const PAYMENTS: [TxType; 5] = [
+        TxType::Deposit,
+        TxType::Withdrawal,
+        TxType::Dispute,
+        TxType::Resolve,
+        TxType::Chargeback,
+    ];
+
+    /// Returns true for transaction types that move funds into or out of the account (deposits,
+    /// withdrawals, fills, reversals and adjustments), as opposed to dispute resolution types and
+    /// reserves and releases, which only move funds between available and held;
+    pub fn is_funds_movement(&self) -> bool {
+        matches!(
+            self,
+            TxType::Deposit
+                | TxType::Withdrawal
+                | TxType::Fill
+                | TxType::Reversal
+                | TxType::Adjustment
+        )
+    }
+
+    /// Returns the csv name of the transaction type;
+    pub fn as_str(&self) -> &str {
+        match self {
+            TxType::Deposit => "deposit",
+            TxType::Withdrawal => "withdrawal",
+            TxType::Dispute => "dispute",
+            TxType::Resolve => "resolve",
+            TxType::Chargeback => "chargeback",
+            TxType::Reserve => "reserve",
+            TxType::Release => "release",
+            TxType::Fill => "fill",
+            TxType::Reversal => "reversal",
+            TxType::Adjustment => "adjustment",
+            TxType::Unknown => "unknown",
+            TxType::Custom(custom) => custom.as_str(),
+        }
+    }
+}
+
+impl fmt::Display for TxType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
-impl From<i32> for TxType {
-    fn from(num: i32) -> Self {
-        match num {
-            1 => TxType::Deposit,
-            2 => TxType::Withdrawal,
-            3 => TxType::Dispute,
-            4 => TxType::Resolve,
-            5 => TxType::Chargeback,
-            _ => TxType::Unknown,
+/// Parses the built-in transaction types; Custom types are parsed with `Accounting::tx_type`.
+impl FromStr for TxType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "deposit" => Ok(TxType::Deposit),
+            "withdrawal" => Ok(TxType::Withdrawal),
+            "dispute" => Ok(TxType::Dispute),
+            "resolve" => Ok(TxType::Resolve),
+            "chargeback" => Ok(TxType::Chargeback),
+            "reserve" => Ok(TxType::Reserve),
+            "release" => Ok(TxType::Release),
+            "fill" => Ok(TxType::Fill),
+            "reversal" => Ok(TxType::Reversal),
+            "adjustment" => Ok(TxType::Adjustment),
+            "unknown" => Ok(TxType::Unknown),
+            _ => Err(Error::InvalidTxType(s.to_string())),
         }
     }
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+impl Serialize for TxType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for TxType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct TxTypeVisitor;
+
+        impl<'de> Visitor<'de> for TxTypeVisitor {
+            type Value = TxType;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a transaction type")
+            }
+
+            // Other names are read as custom types, checked against the registered custom types
+            // when csv rows are read;
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<TxType, E> {
+                value
+                    .parse()
+                    .or_else(|e| CustomTxType::new(value).map(TxType::Custom).ok_or(e))
+                    .map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_str(TxTypeVisitor)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(from = "TransactionRecord", into = "TransactionRecord")]
 /// Structure representing the transaction details provided in the input for processing.
 pub struct Transaction {
-    #[serde(rename = "type")]
     /// Transaction Type
     pub r#type: TxType,
-    /// Globally unique, unordered u16 client ID
-    #[serde(rename = "client")]
+    /// Globally unique, unordered client ID
     pub client: ClientId,
-    /// Globally unique, unordered u32 transaction ID
-    #[serde(rename = "tx")]
+    /// Globally unique, unordered transaction ID, see `TxId`
     pub tx: TxId,
     /// Transaction amount, represented to four decimal places of precision
+    pub amount: Option<Amount>,
+    /// Optional unix timestamp (seconds) of the transaction; Required for time-based features
+    pub timestamp: Option<Timestamp>,
+    /// Optional sub-account of the client targeted by a deposit or withdrawal;
+    /// Written as `client:subaccount` in the client column.
+    pub subaccount: Option<SubAccountId>,
+    /// Optional spending category, e.g. `groceries`; Used for category budgets
+    pub category: Option<String>,
+    /// Optional producer of the transaction, e.g. a file name, topic or partner id; Kept in the
+    /// audit log and aggregated per source.
+    pub source: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+/// Serialized form of a `Transaction`, combining the client and sub-account in the client column.
+struct TransactionRecord {
+    #[serde(rename = "type")]
+    r#type: TxType,
+    #[serde(rename = "client")]
+    client: ClientRef,
+    #[serde(rename = "tx")]
+    tx: TxId,
     #[serde(rename = "amount")]
-    pub amount: Option<f64>,
+    amount: Option<Amount>,
+    #[serde(rename = "timestamp", default)]
+    timestamp: Option<Timestamp>,
+    #[serde(rename = "category", default)]
+    category: Option<String>,
+    #[serde(rename = "source", default)]
+    source: Option<String>,
 }
 
-#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+impl From<TransactionRecord> for Transaction {
+    fn from(record: TransactionRecord) -> Self {
+        Transaction {
+            r#type: record.r#type,
+            client: record.client.client,
+            tx: record.tx,
+            amount: record.amount,
+            timestamp: record.timestamp,
+            subaccount: record.client.subaccount,
+            category: record.category,
+            source: record.source,
+        }
+    }
+}
+
+impl From<Transaction> for TransactionRecord {
+    fn from(tx: Transaction) -> Self {
+        TransactionRecord {
+            r#type: tx.r#type,
+            client: ClientRef {
+                client: tx.client,
+                subaccount: tx.subaccount,
+            },
+            tx: tx.tx,
+            amount: tx.amount,
+            timestamp: tx.timestamp,
+            category: tx.category,
+            source: tx.source,
+        }
+    }
+}
+
+/// Client column value: a client id, optionally followed by `:subaccount`;
+struct ClientRef {
+    client: ClientId,
+    subaccount: Option<SubAccountId>,
+}
+
+impl Serialize for ClientRef {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match &self.subaccount {
+            Some(subaccount) => {
+                serializer.collect_str(&format_args!("{}:{}", self.client, subaccount))
+            }
+            None => serializer.collect_str(&self.client),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ClientRef {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ClientRefVisitor;
+
+        impl<'de> Visitor<'de> for ClientRefVisitor {
+            type Value = ClientRef;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a client id, optionally followed by `:subaccount`")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<ClientRef, E> {
+                let (client, subaccount) = match v.split_once(':') {
+                    Some((client, subaccount)) if !subaccount.is_empty() => {
+                        (client, Some(subaccount.to_string()))
+                    }
+                    Some(_) => return Err(E::custom("empty sub-account name")),
+                    None => (v, None),
+                };
+
+                Ok(ClientRef {
+                    client: client.parse().map_err(E::custom)?,
+                    subaccount,
+                })
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<ClientRef, E> {
+                Ok(ClientRef {
+                    client: ClientId::try_from(v).map_err(E::custom)?,
+                    subaccount: None,
+                })
+            }
+        }
+
+        deserializer.deserialize_str(ClientRefVisitor)
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
 /// Structure representing the account details provided in the output for processing.
 pub struct Account {
-    /// Valid u16 client ID; Client IDs exceeding u16::MAX will be considered invalid.
+    /// Valid client ID; Client IDs exceeding `ClientId::MAX` will be considered invalid.
     #[serde(rename = "client")]
     pub client: ClientId,
     /// The total funds that are available for trading, staking, withdrawal, etc.
     /// This should be equal to the total - held amounts
-    #[serde(rename = "available")]
-    pub available: f64,
+    #[serde(rename = "available", serialize_with = "serialize_balance")]
+    pub available: Amount,
     /// The total funds that are held for dispute. This should be equal to total - available amounts
-    #[serde(rename = "held")]
-    pub held: f64,
+    #[serde(rename = "held", serialize_with = "serialize_balance")]
+    pub held: Amount,
     /// The total funds that are available or held. This should be equal to available + held
-    #[serde(rename = "total")]
-    pub total: f64,
+    #[serde(rename = "total", serialize_with = "serialize_balance")]
+    pub total: Amount,
     /// Whether the account is locked. An account is locked if a charge back occurs
     #[serde(rename = "locked")]
     pub locked: bool,
+    /// Lifetime balance statistics, tracked incrementally while processing;
+    /// Only exported in the extended output schema.
+    #[serde(skip)]
+    pub stats: AccountStats,
+    /// Why the account is locked, if it is; Only exported in the extended output schema.
+    #[serde(skip)]
+    pub lock_reason: Option<LockReason>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+/// Lifetime peak and drawdown statistics for an account, updated after every processed transaction.
+pub struct AccountStats {
+    /// The maximum total balance the account has ever held
+    pub peak_total: Amount,
+    /// The maximum amount of funds the account has ever had held for dispute
+    pub peak_held: Amount,
+    /// The largest single successful withdrawal from the account
+    pub largest_withdrawal: Amount,
+    /// The largest drop of the total balance from its running peak
+    pub max_drawdown: Amount,
+    /// The number of transactions processed for the account, including ignored ones
+    pub transactions: u64,
+}
+
+impl AccountStats {
+    /// Update the peak and drawdown statistics from the current account balances;
+    pub fn observe(&mut self, total: Amount, held: Amount) {
+        if total > self.peak_total {
+            self.peak_total = total;
+        }
+
+        if held > self.peak_held {
+            self.peak_held = held;
+        }
+
+        let drawdown = self.peak_total - total;
+        if drawdown > self.max_drawdown {
+            self.max_drawdown = drawdown;
+        }
+    }
+
+    /// Record a successful withdrawal amount;
+    pub fn observe_withdrawal(&mut self, amount: Amount) {
+        if amount > self.largest_withdrawal {
+            self.largest_withdrawal = amount;
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+/// The difference between two accounts, as returned by `Account::diff`.
+pub struct AccountDelta {
+    /// Client ID of the account the delta is computed from
+    #[serde(rename = "client")]
+    pub client: ClientId,
+    /// Client ID of the account the delta is computed to
+    #[serde(rename = "other_client")]
+    pub other_client: ClientId,
+    #[serde(rename = "available")]
+    pub available: Amount,
+    #[serde(rename = "held")]
+    pub held: Amount,
+    #[serde(rename = "total")]
+    pub total: Amount,
+    /// The locked status of the other account, if it differs
+    #[serde(rename = "locked")]
+    pub locked: Option<bool>,
+}
+
+impl AccountDelta {
+    /// Returns true if the locked status is unchanged and every balance delta is within the tolerance;
+    pub fn is_empty(&self, tolerance: Amount) -> bool {
+        self.locked.is_none()
+            && approx_eq(self.available, Amount::ZERO, tolerance)
+            && approx_eq(self.held, Amount::ZERO, tolerance)
+            && approx_eq(self.total, Amount::ZERO, tolerance)
+    }
+}
+
+/// Returns true if two amounts are within the given tolerance of each other;
+pub fn approx_eq(a: Amount, b: Amount, tolerance: Amount) -> bool {
+    (a - b).abs() <= tolerance
+}
+
+/// Returns true if the balance covers the amount, ignoring a shortfall within the given tolerance;
+/// Used for all sufficient funds checks; The tolerance is `AccountingConfig::amount_epsilon`, zero by default.
+pub fn covers(balance: Amount, amount: Amount, tolerance: Amount) -> bool {
+    balance - amount >= -tolerance
+}
+
+/// Returns zero for balances within the given tolerance of zero, otherwise the balance;
+pub fn clear_dust(balance: Amount, tolerance: Amount) -> Amount {
+    if approx_eq(balance, Amount::ZERO, tolerance) {
+        Amount::ZERO
+    } else {
+        balance
+    }
+}
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+/// Structure representing the extended account output schema, including lifetime statistics.
+pub struct ExtendedAccount {
+    #[serde(rename = "client")]
+    pub client: ClientId,
+    #[serde(rename = "available", serialize_with = "serialize_balance")]
+    pub available: Amount,
+    #[serde(rename = "held", serialize_with = "serialize_balance")]
+    pub held: Amount,
+    #[serde(rename = "total", serialize_with = "serialize_balance")]
+    pub total: Amount,
+    #[serde(rename = "locked")]
+    pub locked: bool,
+    #[serde(rename = "peak_total", serialize_with = "serialize_balance")]
+    pub peak_total: Amount,
+    #[serde(rename = "peak_held", serialize_with = "serialize_balance")]
+    pub peak_held: Amount,
+    #[serde(rename = "largest_withdrawal", serialize_with = "serialize_balance")]
+    pub largest_withdrawal: Amount,
+    #[serde(rename = "max_drawdown", serialize_with = "serialize_balance")]
+    pub max_drawdown: Amount,
+    #[serde(rename = "lock_reason")]
+    pub lock_reason: Option<LockReason>,
+    /// Whether the account was closed with `Accounting::close_account`
+    #[serde(rename = "closed")]
+    pub closed: bool,
+    /// Timestamp of the account's last transaction, see `Accounting::last_activity`
+    #[serde(rename = "last_activity")]
+    pub last_activity: Option<Timestamp>,
+    /// Number of transactions processed for the account, including ignored ones
+    #[serde(rename = "transactions")]
+    pub transactions: u64,
+}
+
+impl From<&Account> for ExtendedAccount {
+    fn from(account: &Account) -> Self {
+        ExtendedAccount {
+            client: account.client,
+            available: account.available,
+            held: account.held,
+            total: account.total,
+            locked: account.locked,
+            peak_total: account.stats.peak_total,
+            peak_held: account.stats.peak_held,
+            largest_withdrawal: account.stats.largest_withdrawal,
+            max_drawdown: account.stats.max_drawdown,
+            lock_reason: account.lock_reason,
+            closed: false,
+            last_activity: None,
+            transactions: account.stats.transactions,
+        }
+    }
 }
 
 impl Account {
@@ -104,13 +520,120 @@ impl Account {
         }
     }
 
+    /// Merge the balances of another account into this account, e.g. when two client ids are
+    /// combined; The merged account is locked if either account is locked.
+    pub fn merge(&mut self, other: &Account) {
+        self.available += other.available;
+        self.held += other.held;
+        self.total += other.total;
+        self.locked = self.locked || other.locked;
+
+        self.stats.peak_held = self.stats.peak_held.max(other.stats.peak_held);
+        self.stats.largest_withdrawal = self
+            .stats
+            .largest_withdrawal
+            .max(other.stats.largest_withdrawal);
+        self.stats.max_drawdown = self.stats.max_drawdown.max(other.stats.max_drawdown);
+        self.stats.observe(self.total, self.held);
+    }
+
+    /// Returns the balance changes from this account to another account;
+    /// Deltas are computed as `other - self`.
+    pub fn diff(&self, other: &Account) -> AccountDelta {
+        AccountDelta {
+            client: self.client,
+            other_client: other.client,
+            available: other.available - self.available,
+            held: other.held - self.held,
+            total: other.total - self.total,
+            locked: if self.locked != other.locked {
+                Some(other.locked)
+            } else {
+                None
+            },
+        }
+    }
+
+    /// Returns true if both accounts belong to the same client, have the same locked status, and all
+    /// balances are within the given tolerance of each other;
+    pub fn approx_eq(&self, other: &Account, tolerance: Amount) -> bool {
+        self.client == other.client && self.diff(other).is_empty(tolerance)
+    }
+
     /// Helper method for rounding account balances to four decimal places;
     /// NOTE: This method would be better suited as an implemented Trait,
     /// reusable for other models.
-    pub fn round_balances(&mut self) -> Result<(), Error> {
-        self.total = format!("{:.4}", self.total).parse::<f64>()?;
-        self.held = format!("{:.4}", self.held).parse::<f64>()?;
-        self.available = format!("{:.4}", self.available).parse::<f64>()?;
-        Ok(())
+    pub fn round_balances(&mut self) {
+        self.total = round(self.total);
+        self.held = round(self.held);
+        self.available = round(self.available);
+    }
+}
+
+impl ExtendedAccount {
+    /// Helper method for rounding extended account balances and statistics to four decimal places;
+    pub fn round_balances(&mut self) {
+        self.total = round(self.total);
+        self.held = round(self.held);
+        self.available = round(self.available);
+        self.peak_total = round(self.peak_total);
+        self.peak_held = round(self.peak_held);
+        self.largest_withdrawal = round(self.largest_withdrawal);
+        self.max_drawdown = round(self.max_drawdown);
+    }
+}
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+/// Structure representing a client's end-of-day balances in the daily balance series output.
+pub struct DailyBalance {
+    #[serde(rename = "client")]
+    pub client: ClientId,
+    /// Calendar date (UTC) formatted as `YYYY-MM-DD`
+    #[serde(rename = "date")]
+    pub date: String,
+    #[serde(rename = "available", serialize_with = "serialize_balance")]
+    pub available: Amount,
+    #[serde(rename = "held", serialize_with = "serialize_balance")]
+    pub held: Amount,
+    #[serde(rename = "total", serialize_with = "serialize_balance")]
+    pub total: Amount,
+}
+
+impl DailyBalance {
+    /// Helper method for rounding daily balances to four decimal places;
+    pub fn round_balances(&mut self) {
+        self.total = round(self.total);
+        self.held = round(self.held);
+        self.available = round(self.available);
+    }
+}
+
+/// Format a unix timestamp (seconds) as a UTC `YYYY-MM-DD` date;
+pub fn format_date(timestamp: Timestamp) -> String {
+    // Civil-from-days conversion; see http://howardhinnant.github.io/date_algorithms.html
+    let days = (timestamp / SECONDS_PER_DAY) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let doe = days.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Round a value to four decimal places;
+fn round(value: Amount) -> Amount {
+    value.round_dp(AMOUNT_DECIMAL_PLACES as u32)
+}
+
+/// Serialize a balance without trailing zeros but with at least one decimal place, e.g. `2.5` or `0.0`;
+fn serialize_balance<S: Serializer>(balance: &Amount, serializer: S) -> Result<S::Ok, S::Error> {
+    let mut balance = balance.normalize();
+    if balance.scale() == 0 {
+        balance.rescale(1);
     }
+    serializer.collect_str(&balance)
 }
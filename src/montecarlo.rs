@@ -0,0 +1,123 @@
+use std::io::Write;
+use std::path::PathBuf;
+
+use csv::Writer;
+use rust_decimal::prelude::ToPrimitive;
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+/// Distribution of a summary metric across Monte Carlo runs.
+pub struct MetricDistribution {
+    #[serde(rename = "metric")]
+    pub metric: String,
+    #[serde(rename = "min")]
+    pub min: f64,
+    #[serde(rename = "mean")]
+    pub mean: f64,
+    #[serde(rename = "p5")]
+    pub p5: f64,
+    #[serde(rename = "p50")]
+    pub p50: f64,
+    #[serde(rename = "p95")]
+    pub p95: f64,
+    #[serde(rename = "max")]
+    pub max: f64,
+}
+
+impl MetricDistribution {
+    /// Summarize the values of a metric; Percentiles use the nearest rank.
+    pub fn from_values(metric: &str, mut values: Vec<f64>) -> Self {
+        values.sort_by(|a, b| a.total_cmp(b));
+
+        let percentile = |p: f64| {
+            if values.is_empty() {
+                return 0.;
+            }
+            let rank = (p * values.len() as f64).ceil() as usize;
+            values[rank.clamp(1, values.len()) - 1]
+        };
+
+        MetricDistribution {
+            metric: metric.to_string(),
+            min: values.first().copied().unwrap_or_default(),
+            mean: values.iter().sum::<f64>() / values.len().max(1) as f64,
+            p5: percentile(0.05),
+            p50: percentile(0.5),
+            p95: percentile(0.95),
+            max: values.last().copied().unwrap_or_default(),
+        }
+    }
+}
+
+/// Extracts a metric from a run summary;
+type SummaryMetric = fn(&ScenarioSummary) -> f64;
+
+/// The metrics aggregated by `MonteCarloReport::distributions`;
+const MONTE_CARLO_METRICS: [(&str, SummaryMetric); 7] = [
+    ("available", |s| s.available.to_f64().unwrap_or_default()),
+    ("held", |s| s.held.to_f64().unwrap_or_default()),
+    ("total", |s| s.total.to_f64().unwrap_or_default()),
+    ("total_change", |s| {
+        s.total_change.to_f64().unwrap_or_default()
+    }),
+    ("locked_accounts", |s| s.locked_accounts as f64),
+    ("negative_accounts", |s| s.negative_accounts as f64),
+    ("negative_exposure", |s| {
+        s.negative_exposure.to_f64().unwrap_or_default()
+    }),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+/// The summaries of every Monte Carlo run, in seed order.
+pub struct MonteCarloReport {
+    pub runs: Vec<ScenarioSummary>,
+}
+
+impl MonteCarloReport {
+    /// Returns the distribution of every aggregated metric across the runs;
+    pub fn distributions(&self) -> Vec<MetricDistribution> {
+        MONTE_CARLO_METRICS
+            .iter()
+            .map(|(metric, value)| {
+                MetricDistribution::from_values(metric, self.runs.iter().map(value).collect())
+            })
+            .collect()
+    }
+
+    /// Write the metric distributions as csv to any writer;
+    pub fn write_distributions_csv<W: Write>(&self, writer: W) -> Result<(), Error> {
+        let mut wtr = Writer::from_writer(writer);
+
+        for distribution in self.distributions() {
+            wtr.serialize(distribution)?;
+        }
+
+        wtr.flush()?;
+        Ok(())
+    }
+
+    /// Write the summary of every run to a csv file;
+    pub fn write_runs_csv_file(&self, file_path: PathBuf) -> Result<(), Error> {
+        ScenarioRunner::write_summaries_csv_file(&self.runs, file_path)
+    }
+}
+
+impl ScenarioRunner {
+    /// Run the scenario `runs` times over the base ledger, seeding run `i` with `scenario.seed + i`;
+    /// Runs are independent, so a report is reproducible from the scenario and run count.
+    pub fn monte_carlo(&self, scenario: &Scenario, runs: usize) -> Result<MonteCarloReport, Error> {
+        let mut scenario = scenario.clone();
+        let seed = scenario.seed;
+
+        let runs = (0..runs as u64)
+            .map(|i| {
+                scenario.seed = seed.wrapping_add(i);
+                self.run_scenario(&scenario).map(|(summary, _)| summary)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        Ok(MonteCarloReport { runs })
+    }
+}
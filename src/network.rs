@@ -0,0 +1,192 @@
+#[cfg(feature = "io")]
+use std::path::PathBuf;
+
+#[cfg(feature = "io")]
+use csv::{ReaderBuilder, Trim};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+/// Card-network outcome of a transaction.
+pub enum NetworkAction {
+    /// The cardholder opened a dispute; Processed as a `dispute`.
+    Dispute,
+    /// The dispute was decided for the client; Processed as a `resolve`.
+    Resolve,
+    /// The dispute was decided against the client; Processed as a `chargeback`.
+    Chargeback,
+    /// A chargeback was overturned, e.g. after representment; See `Accounting::reverse_chargeback`.
+    Reversal,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+/// A row of a card-network adjustments file, referencing a recorded transaction by id.
+pub struct NetworkAdjustment {
+    #[serde(rename = "tx")]
+    pub tx: TxId,
+    #[serde(rename = "action")]
+    pub action: NetworkAction,
+    /// Partial dispute or reversal amount; The whole transaction amount when empty.
+    #[serde(rename = "amount", default)]
+    pub amount: Option<Amount>,
+}
+
+impl Accounting {
+    /// Reverse the chargeback of a transaction, crediting the amount, or the whole charged back amount,
+    /// back to the client's available and total funds, or debiting it for a charged back withdrawal;
+    /// Processed as a `reversal` transaction, so it is journaled, and applies to locked accounts, which
+    /// stay locked until unlocked by an operator. The transaction state moves to `TxState::Resolved`.
+    pub fn reverse_chargeback(
+        &mut self,
+        tx: TxId,
+        amount: Option<Amount>,
+    ) -> Result<Amount, Error> {
+        let recorded = self
+            .transactions
+            .get(&tx)
+            .ok_or_else(|| Error::InvalidArgument(format!("cannot reverse unknown tx {}", tx)))?;
+        let client = recorded.transaction.client;
+        let reversed = amount.unwrap_or_else(|| recorded.reversible());
+
+        let outcome = self.try_process(Transaction {
+            r#type: TxType::Reversal,
+            client,
+            tx,
+            amount,
+            timestamp: None,
+            subaccount: None,
+            category: None,
+            source: None,
+        })?;
+
+        match outcome {
+            Ok(_) => Ok(reversed),
+            Err(rejection) => Err(Error::InvalidArgument(format!(
+                "cannot reverse tx {}: {}",
+                tx, rejection
+            ))),
+        }
+    }
+
+    /// This method is provided as a helper method and is intended to be consumed by `self.process_transaction`;
+    /// Reversals are capped at the amount charged back, which may be a partial dispute amount.
+    pub(crate) fn process_reversal(&mut self, account: &mut Account, tx: &Transaction) {
+        let (withdrawal, reversible) = match self.transactions.get(&tx.tx) {
+            Some(recorded) => (
+                recorded.transaction.r#type == TxType::Withdrawal,
+                recorded.reversible(),
+            ),
+            None => {
+                warn!(
+                    "Ignored reversal of tx {} for client {}: unknown transaction",
+                    tx.tx, tx.client
+                );
+                self.ignore(tx, TransactionError::UnknownTx, || {
+                    "unknown transaction".to_string()
+                });
+                return;
+            }
+        };
+
+        if !self.check_tx_owner(tx) {
+            return;
+        }
+
+        let state = match self.check_tx_state(tx) {
+            Some(state) => state,
+            None => return,
+        };
+
+        let amount = tx.amount.unwrap_or(reversible);
+        if amount <= Amount::ZERO || !covers(reversible, amount, self.config.amount_epsilon) {
+            warn!(
+                "Ignored reversal of tx {} for client {}: invalid amount {}",
+                tx.tx, tx.client, amount
+            );
+            self.ignore(tx, TransactionError::InvalidAmount(amount), || {
+                format!("invalid reversal amount {}", amount)
+            });
+            return;
+        }
+
+        if withdrawal {
+            account.available -= amount;
+            account.total -= amount;
+        } else {
+            account.available += amount;
+            account.total += amount;
+        }
+
+        info!(
+            "Reversed chargeback of tx {} for client {}",
+            tx.tx, tx.client
+        );
+        self.audit(
+            Some(tx.client),
+            Some(tx.tx),
+            "chargeback_reversed",
+            amount.to_string(),
+        );
+
+        self.set_tx_state(tx.tx, state);
+        self.set_charged_back(tx.tx, None);
+    }
+
+    /// Apply a card-network adjustments file (`tx,action,amount`) to the recorded transactions;
+    /// Disputes, resolves and chargebacks are processed as transactions of the client of the
+    /// referenced transaction, reversals via `reverse_chargeback`. Rows referencing unknown
+    /// transactions or invalid reversals are logged and skipped. Returns the number of rows applied.
+    #[cfg(feature = "io")]
+    pub fn read_network_adjustments_csv_file(
+        &mut self,
+        file_path: PathBuf,
+    ) -> Result<usize, Error> {
+        let mut rdr = ReaderBuilder::new().trim(Trim::All).from_path(file_path)?;
+        let mut applied = 0;
+
+        for result in rdr.deserialize() {
+            let adjustment: NetworkAdjustment = result?;
+
+            let client = match self.transactions.get(&adjustment.tx) {
+                Some(recorded) => recorded.transaction.client,
+                None => {
+                    warn!(
+                        "Ignored network {:?} of tx {}: unknown transaction",
+                        adjustment.action, adjustment.tx
+                    );
+                    continue;
+                }
+            };
+
+            let r#type = match adjustment.action {
+                NetworkAction::Dispute => TxType::Dispute,
+                NetworkAction::Resolve => TxType::Resolve,
+                NetworkAction::Chargeback => TxType::Chargeback,
+                NetworkAction::Reversal => {
+                    match self.reverse_chargeback(adjustment.tx, adjustment.amount) {
+                        Ok(_) => applied += 1,
+                        Err(e) => warn!("Ignored network reversal: {}", e),
+                    }
+                    continue;
+                }
+            };
+
+            self.try_process_transaction(Transaction {
+                r#type,
+                client,
+                tx: adjustment.tx,
+                amount: adjustment.amount,
+                timestamp: None,
+                subaccount: None,
+                category: None,
+                source: None,
+            })?;
+            applied += 1;
+        }
+
+        Ok(applied)
+    }
+}
@@ -0,0 +1,134 @@
+#[cfg(feature = "io")]
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+/// Account events that trigger a customer notification; See `AccountingConfig::notifications`.
+pub struct NotificationConfig {
+    /// Notify applied withdrawals of at least this amount;
+    pub large_withdrawal: Option<Amount>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+/// Account event a customer is notified of.
+pub enum NotificationKind {
+    /// The account was locked, for any reason;
+    Lock,
+    /// A chargeback was applied;
+    Chargeback,
+    /// A withdrawal of at least `NotificationConfig::large_withdrawal` was applied;
+    LargeWithdrawal,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+/// Customer notification for downstream messaging systems; At most one per client, kind and day.
+pub struct Notification {
+    #[serde(rename = "client")]
+    pub client: ClientId,
+    #[serde(rename = "kind")]
+    pub kind: NotificationKind,
+    /// Calendar date (UTC) of the event, formatted as `YYYY-MM-DD`
+    #[serde(rename = "date")]
+    pub date: String,
+    /// Time of the event
+    #[serde(rename = "at")]
+    pub at: Timestamp,
+    /// The transaction that triggered the event, if any
+    #[serde(rename = "tx")]
+    pub tx: Option<TxId>,
+    /// Amount involved in the event, if any
+    #[serde(rename = "amount")]
+    pub amount: Option<Amount>,
+}
+
+impl Accounting {
+    /// Record a notification, unless notifications are disabled or the client was already notified of
+    /// the same kind of event on the same day;
+    pub(crate) fn notify(
+        &mut self,
+        client: ClientId,
+        kind: NotificationKind,
+        at: Timestamp,
+        tx: Option<TxId>,
+        amount: Option<Amount>,
+    ) {
+        if self.config.notifications.is_none()
+            || !self.notified.insert((client, kind, at / SECONDS_PER_DAY))
+        {
+            return;
+        }
+
+        self.notifications.push(Notification {
+            client,
+            kind,
+            date: format_date(at),
+            at,
+            tx,
+            amount,
+        });
+    }
+
+    /// Record the notifications triggered by a processed transaction;
+    pub(crate) fn notify_transaction(
+        &mut self,
+        tx: &Transaction,
+        total_before: Amount,
+        account: &Account,
+        at: Timestamp,
+    ) {
+        let large_withdrawal = match self.config.notifications.as_ref() {
+            Some(config) => config.large_withdrawal,
+            None => return,
+        };
+
+        // Only applied chargebacks and withdrawals decrease the total;
+        if account.total >= total_before {
+            return;
+        }
+
+        match tx.r#type {
+            TxType::Chargeback => self.notify(
+                account.client,
+                NotificationKind::Chargeback,
+                at,
+                Some(tx.tx),
+                Some(total_before - account.total),
+            ),
+            TxType::Withdrawal => {
+                if let (Some(amount), Some(threshold)) = (tx.amount, large_withdrawal) {
+                    if amount >= threshold {
+                        self.notify(
+                            account.client,
+                            NotificationKind::LargeWithdrawal,
+                            at,
+                            Some(tx.tx),
+                            Some(amount),
+                        );
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns the recorded customer notifications, in the order of the events;
+    pub fn notifications(&self) -> &[Notification] {
+        &self.notifications
+    }
+
+    /// Write the recorded customer notifications to a csv file;
+    #[cfg(feature = "io")]
+    pub fn write_notifications_csv_file(&self, file_path: PathBuf) -> Result<(), Error> {
+        let mut sink = CsvFileSink::create(file_path, self.config.output_durability)?;
+
+        for notification in self.notifications.iter() {
+            sink.write(notification)?;
+        }
+
+        sink.close()
+    }
+}
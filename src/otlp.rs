@@ -0,0 +1,380 @@
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use log::{debug, warn};
+use rand::{thread_rng, Rng};
+use serde_json::{json, Value};
+
+use crate::*;
+
+/// Number of finished spans buffered before they are exported mid-run;
+pub const OTLP_EXPORT_SPANS: usize = 512;
+
+/// Maximum number of finished spans buffered while the collector is unreachable; The oldest spans
+/// are dropped beyond it.
+pub const OTLP_MAX_SPANS: usize = 8 * OTLP_EXPORT_SPANS;
+
+#[derive(Debug, Clone, PartialEq)]
+/// Configuration of the OTLP span exporter; Requires the `otlp` feature.
+pub struct OtlpConfig {
+    /// Base url of the OTLP/HTTP collector, e.g. `http://localhost:4318`; Spans are posted as json
+    /// to its path followed by `/v1/traces`. Only plain `http://` endpoints are supported.
+    pub endpoint: String,
+    /// The `service.name` resource attribute
+    pub service_name: String,
+    /// Number of transactions covered by each batch span
+    pub batch_size: usize,
+    /// W3C `traceparent` of the upstream pipeline stage, e.g. from the `TRACEPARENT` environment
+    /// variable; Spans join its trace, otherwise a new trace is started.
+    pub traceparent: Option<String>,
+    /// Timeout of connecting to the collector, and of each write and read of an export
+    pub timeout: Duration,
+}
+
+impl Default for OtlpConfig {
+    fn default() -> Self {
+        OtlpConfig {
+            endpoint: "http://localhost:4318".to_string(),
+            service_name: "tx-sim".to_string(),
+            batch_size: 10_000,
+            traceparent: None,
+            timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct OtlpSpan {
+    span_id: String,
+    parent_span_id: Option<String>,
+    name: &'static str,
+    start: u64,
+    end: u64,
+    attributes: Vec<(&'static str, u64)>,
+}
+
+/// Exports a root span for the processing run and a child span per batch of transactions to an
+/// OTLP/HTTP collector, so the stage's latency is visible in the pipeline's trace;
+/// Attach with `Accounting::attach_otlp_exporter` and call `finish` once processing completes.
+#[derive(Debug)]
+pub struct OtlpExporter {
+    config: OtlpConfig,
+    /// `host:port` of the collector, from the endpoint
+    host: String,
+    /// Request path of the traces export, from the endpoint
+    path: String,
+    trace_id: String,
+    parent_span_id: Option<String>,
+    root_span_id: String,
+    root_start: u64,
+    processed: u64,
+    batch: u64,
+    batch_start: Option<u64>,
+    batch_transactions: u64,
+    spans: Vec<OtlpSpan>,
+    /// Number of buffered spans at which the next mid-run export is attempted
+    export_at: usize,
+    dropped: u64,
+}
+
+/// Returns the current time in unix nanoseconds;
+fn unix_nanos() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos() as u64)
+}
+
+/// Returns a random, non-zero id of `bytes` bytes as lowercase hex;
+fn random_id(bytes: usize) -> String {
+    let mut rng = thread_rng();
+    loop {
+        let id: String = (0..bytes)
+            .map(|_| format!("{:02x}", rng.gen::<u8>()))
+            .collect();
+        if id.bytes().any(|b| b != b'0') {
+            return id;
+        }
+    }
+}
+
+/// Split an `http://host[:port][/path]` endpoint into the host and the traces export path;
+fn parse_endpoint(endpoint: &str) -> Option<(String, String)> {
+    let rest = endpoint.strip_prefix("http://")?;
+    let (host, path) = match rest.find(['/', '?', '#']) {
+        Some(at) => rest.split_at(at),
+        None => (rest, ""),
+    };
+    if host.is_empty() || host.contains('@') || path.contains(['?', '#']) {
+        return None;
+    }
+
+    Some((
+        host.to_string(),
+        format!("{}/v1/traces", path.trim_end_matches('/')),
+    ))
+}
+
+/// Parse a W3C `traceparent`, e.g. `00-<32 hex trace id>-<16 hex span id>-01`;
+fn parse_traceparent(traceparent: &str) -> Option<(String, String)> {
+    let parts: Vec<&str> = traceparent.trim().split('-').collect();
+    let is_hex = |s: &str, len: usize| {
+        s.len() == len && s.bytes().all(|b| b.is_ascii_hexdigit()) && s.bytes().any(|b| b != b'0')
+    };
+
+    match parts.as_slice() {
+        [_, trace_id, span_id, _] if is_hex(trace_id, 32) && is_hex(span_id, 16) => {
+            Some((trace_id.to_lowercase(), span_id.to_lowercase()))
+        }
+        _ => None,
+    }
+}
+
+impl OtlpExporter {
+    /// Create an exporter, starting the root span; An invalid `traceparent` is an error.
+    pub fn new(config: OtlpConfig) -> Result<Self, Error> {
+        let (host, path) = parse_endpoint(&config.endpoint).ok_or_else(|| {
+            Error::InvalidArgument(format!(
+                "unsupported OTLP endpoint '{}', expected http://host:port[/path]",
+                config.endpoint
+            ))
+        })?;
+
+        let (trace_id, parent_span_id) = match config.traceparent.as_deref() {
+            Some(traceparent) => {
+                let (trace_id, span_id) = parse_traceparent(traceparent).ok_or_else(|| {
+                    Error::InvalidArgument(format!("invalid traceparent '{}'", traceparent))
+                })?;
+                (trace_id, Some(span_id))
+            }
+            None => (random_id(16), None),
+        };
+
+        Ok(OtlpExporter {
+            config,
+            host,
+            path,
+            trace_id,
+            parent_span_id,
+            root_span_id: random_id(8),
+            root_start: unix_nanos(),
+            processed: 0,
+            batch: 0,
+            batch_start: None,
+            batch_transactions: 0,
+            spans: Vec::new(),
+            export_at: OTLP_EXPORT_SPANS,
+            dropped: 0,
+        })
+    }
+
+    /// Returns the id of the trace the spans belong to;
+    pub fn trace_id(&self) -> &str {
+        &self.trace_id
+    }
+
+    /// Returns the W3C `traceparent` of the root span, to propagate to downstream stages;
+    pub fn traceparent(&self) -> String {
+        format!("00-{}-{}-01", self.trace_id, self.root_span_id)
+    }
+
+    /// Record a processed transaction, closing the batch span every `batch_size` transactions;
+    pub(crate) fn observe(&mut self) {
+        if self.batch_start.is_none() {
+            self.batch_start = Some(unix_nanos());
+        }
+
+        self.processed += 1;
+        self.batch_transactions += 1;
+
+        if self.batch_transactions as usize >= self.config.batch_size.max(1) {
+            self.close_batch();
+
+            if self.spans.len() >= self.export_at {
+                match self.export() {
+                    Ok(()) => self.export_at = OTLP_EXPORT_SPANS,
+                    Err(e) => {
+                        warn!("Failed to export spans to {}: {}", self.config.endpoint, e);
+                        self.drop_excess_spans();
+
+                        // Back off until another batch of spans is buffered;
+                        self.export_at = self.spans.len() + OTLP_EXPORT_SPANS;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Drop the oldest spans beyond `OTLP_MAX_SPANS`, so an unreachable collector cannot exhaust memory;
+    fn drop_excess_spans(&mut self) {
+        let excess = self.spans.len().saturating_sub(OTLP_MAX_SPANS);
+        if excess > 0 {
+            self.spans.drain(..excess);
+            self.dropped += excess as u64;
+            warn!(
+                "Dropped {} spans buffered for {} ({} in total)",
+                excess, self.config.endpoint, self.dropped
+            );
+        }
+    }
+
+    /// Returns the number of spans dropped while the collector was unreachable;
+    pub fn dropped_spans(&self) -> u64 {
+        self.dropped
+    }
+
+    fn close_batch(&mut self) {
+        if let Some(start) = self.batch_start.take() {
+            self.batch += 1;
+            self.spans.push(OtlpSpan {
+                span_id: random_id(8),
+                parent_span_id: Some(self.root_span_id.clone()),
+                name: "tx-sim.batch",
+                start,
+                end: unix_nanos(),
+                attributes: vec![
+                    ("tx_sim.batch", self.batch),
+                    ("tx_sim.transactions", self.batch_transactions),
+                ],
+            });
+            self.batch_transactions = 0;
+        }
+    }
+
+    /// Close the open batch span and the root span, and export all remaining spans;
+    pub fn finish(mut self) -> Result<(), Error> {
+        self.close_batch();
+        self.spans.push(OtlpSpan {
+            span_id: self.root_span_id.clone(),
+            parent_span_id: self.parent_span_id.clone(),
+            name: "tx-sim.process",
+            start: self.root_start,
+            end: unix_nanos(),
+            attributes: vec![
+                ("tx_sim.transactions", self.processed),
+                ("tx_sim.batches", self.batch),
+            ],
+        });
+
+        self.export()
+    }
+
+    /// Returns the OTLP/HTTP json request body for the buffered spans;
+    fn request_body(&self) -> Value {
+        let spans: Vec<Value> = self
+            .spans
+            .iter()
+            .map(|span| {
+                let attributes: Vec<Value> = span
+                    .attributes
+                    .iter()
+                    .map(|(key, value)| json!({"key": key, "value": {"intValue": value.to_string()}}))
+                    .collect();
+
+                let mut value = json!({
+                    "traceId": self.trace_id,
+                    "spanId": span.span_id,
+                    "name": span.name,
+                    "kind": 1,
+                    "startTimeUnixNano": span.start.to_string(),
+                    "endTimeUnixNano": span.end.to_string(),
+                    "attributes": attributes,
+                });
+                if let Some(parent) = span.parent_span_id.as_ref() {
+                    value["parentSpanId"] = json!(parent);
+                }
+                value
+            })
+            .collect();
+
+        json!({
+            "resourceSpans": [{
+                "resource": {
+                    "attributes": [{
+                        "key": "service.name",
+                        "value": {"stringValue": self.config.service_name},
+                    }],
+                },
+                "scopeSpans": [{
+                    "scope": {"name": "tx-sim"},
+                    "spans": spans,
+                }],
+            }],
+        })
+    }
+
+    /// Connect to the collector within the configured timeout, trying each resolved address;
+    fn connect(&self) -> io::Result<TcpStream> {
+        // The port defaults to 80 like any `http://` url;
+        let addrs = match self.host.rsplit_once(':') {
+            Some((_, port)) if !port.contains(']') => self.host.to_socket_addrs()?,
+            _ => (self.host.as_str(), 80).to_socket_addrs()?,
+        };
+
+        let mut last_error = None;
+        for addr in addrs {
+            match TcpStream::connect_timeout(&addr, self.config.timeout) {
+                Ok(stream) => {
+                    stream.set_read_timeout(Some(self.config.timeout))?;
+                    stream.set_write_timeout(Some(self.config.timeout))?;
+                    return Ok(stream);
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+
+        Err(last_error
+            .unwrap_or_else(|| io::Error::other(format!("no address found for '{}'", self.host))))
+    }
+
+    /// Post the buffered spans to the collector's traces endpoint;
+    fn export(&mut self) -> Result<(), Error> {
+        if self.spans.is_empty() {
+            return Ok(());
+        }
+
+        let body = self.request_body().to_string();
+
+        let mut stream = self.connect()?;
+        write!(
+            stream,
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.path,
+            self.host,
+            body.len(),
+            body
+        )?;
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response)?;
+
+        let status = response.split_whitespace().nth(1).unwrap_or_default();
+        if !status.starts_with('2') {
+            return Err(Error::IoError(io::Error::other(format!(
+                "OTLP collector responded with status '{}'",
+                status
+            ))));
+        }
+
+        debug!(
+            "Exported {} spans to {}",
+            self.spans.len(),
+            self.config.endpoint
+        );
+        self.spans.clear();
+
+        Ok(())
+    }
+}
+
+impl Accounting {
+    /// Attach an OTLP span exporter, observing every processed transaction; Requires the `otlp` feature.
+    pub fn attach_otlp_exporter(&mut self, exporter: OtlpExporter) {
+        self.otlp = Some(exporter);
+    }
+
+    /// Detach the OTLP span exporter, e.g. to `finish` it once processing completes;
+    pub fn detach_otlp_exporter(&mut self) -> Option<OtlpExporter> {
+        self.otlp.take()
+    }
+}
@@ -0,0 +1,226 @@
+use std::fmt;
+#[cfg(feature = "io")]
+use std::io::Read;
+use std::str::FromStr;
+
+#[cfg(feature = "io")]
+use csv::{Reader, StringRecord};
+
+use crate::*;
+
+/// Subset of clients processed by one invocation of a distributed run;
+/// Every client belongs to exactly one bucket of a `Bucket` partitioning.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Partition {
+    /// Clients hashing to bucket `index` (1-based) of `count`, given as `index/count`;
+    Bucket { index: u32, count: u32 },
+    /// Clients within an inclusive id range, given as `first-last`;
+    Range { first: ClientId, last: ClientId },
+}
+
+impl Partition {
+    /// Returns true if the client belongs to the partition;
+    pub fn contains(&self, client: ClientId) -> bool {
+        match *self {
+            Partition::Bucket { index, count } => client_bucket(client, count) == index,
+            Partition::Range { first, last } => (first..=last).contains(&client),
+        }
+    }
+}
+
+/// Returns the 1-based bucket of the client among `count` buckets;
+/// The hash is fixed so that buckets are stable across runs, machines and versions.
+// `ClientId` is already a u64 with the `client-id-u64` feature;
+#[allow(clippy::useless_conversion)]
+pub fn client_bucket(client: ClientId, count: u32) -> u32 {
+    let hash = u64::from(client).wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 32;
+    (hash % count.max(1) as u64) as u32 + 1
+}
+
+impl FromStr for Partition {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            Error::InvalidArgument(format!(
+                "invalid partition '{}'; expected <index>/<count> with 1 <= index <= count, or <first>-<last>",
+                s
+            ))
+        };
+
+        if let Some((index, count)) = s.split_once('/') {
+            let index = index.parse::<u32>().map_err(|_| invalid())?;
+            let count = count.parse::<u32>().map_err(|_| invalid())?;
+            if index == 0 || index > count {
+                return Err(invalid());
+            }
+            Ok(Partition::Bucket { index, count })
+        } else if let Some((first, last)) = s.split_once('-') {
+            let first = first.parse::<ClientId>().map_err(|_| invalid())?;
+            let last = last.parse::<ClientId>().map_err(|_| invalid())?;
+            if first > last {
+                return Err(invalid());
+            }
+            Ok(Partition::Range { first, last })
+        } else {
+            Err(invalid())
+        }
+    }
+}
+
+impl fmt::Display for Partition {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Partition::Bucket { index, count } => write!(f, "{}/{}", index, count),
+            Partition::Range { first, last } => write!(f, "{}-{}", first, last),
+        }
+    }
+}
+
+#[cfg(feature = "io")]
+impl Accounting {
+    /// Iterate over the transaction rows of a csv reader, skipping blank lines if the input format does,
+    /// and rows of clients outside the configured partition before they are deserialized;
+    /// Rows whose client fails to parse are passed through.
+    pub(crate) fn transaction_rows<R: Read>(
+        &self,
+        mut reader: Reader<DecodedReader<R>>,
+    ) -> Result<impl Iterator<Item = Result<Transaction, RowError>>, Error> {
+        let headers = reader.headers()?.clone();
+        let partition = self.config.partition;
+        let client_column = headers.iter().position(|header| header == "client");
+        let type_column = headers.iter().position(|header| header == "type");
+        let tx_column = headers.iter().position(|header| header == "tx");
+        let custom_tx_types = self.custom_tx_types();
+
+        // Partition by the client id, ignoring the `:subaccount` suffix of the client column;
+        let keep = move |record: &StringRecord| match (partition, client_column) {
+            (Some(partition), Some(column)) => record
+                .get(column)
+                .and_then(|client| client.trim().split(':').next())
+                .and_then(|client| client.parse::<ClientId>().ok())
+                .is_none_or(|client| partition.contains(client)),
+            _ => true,
+        };
+
+        // Blank lines are read with a flexible reader, so other rows are checked for missing fields;
+        let skip_blank_lines = self.input_format().skip_blank_lines;
+        let amount_column = headers.iter().position(|header| header == "amount");
+        let decimal_comma = self.config.input.decimal_comma;
+
+        // Abort once more than `max_rows` rows are read, including rows skipped by the partition;
+        let max_rows = self.config.max_rows;
+        let mut rows = 0;
+
+        Ok(reader.into_records().filter_map(move |record| {
+            let record = match record {
+                Ok(record) => record,
+                Err(e) => {
+                    let line = e.position().map_or(0, |position| position.line());
+                    return Some(Err(RowError {
+                        line,
+                        error: e.into(),
+                    }));
+                }
+            };
+            let line = record.position().map_or(0, |position| position.line());
+            let row_error = |error| Some(Err(RowError { line, error }));
+
+            if skip_blank_lines && is_blank_record(&record) {
+                return None;
+            }
+
+            rows += 1;
+            if let Some(max) = max_rows.filter(|max| rows > *max) {
+                return row_error(Error::InputLimitExceeded {
+                    limit: "row count",
+                    max,
+                });
+            }
+
+            if skip_blank_lines && record.len() != headers.len() {
+                return row_error(Error::UnequalFields {
+                    line,
+                    len: record.len(),
+                    expected: headers.len(),
+                });
+            }
+
+            if !keep(&record) {
+                return None;
+            }
+
+            // Report ids too large for the `ClientId` and `TxId` widths apart from other parse errors;
+            if let Some(e) = id_out_of_range(&record, client_column, tx_column) {
+                return row_error(e);
+            }
+
+            // Report unknown transaction types apart from other parse errors, so they can be skipped;
+            if let Some(name) = type_column.and_then(|column| record.get(column)) {
+                let name = name.trim();
+                if name.parse::<TxType>().is_err() && !custom_tx_types.contains(name) {
+                    return row_error(Error::InvalidTxType(name.to_string()));
+                }
+            }
+
+            let record = if decimal_comma {
+                decimal_comma_record(&record, amount_column)
+            } else {
+                record
+            };
+
+            Some(
+                record
+                    .deserialize::<Transaction>(Some(&headers))
+                    .map_err(|e| RowError {
+                        line,
+                        error: e.into(),
+                    }),
+            )
+        }))
+    }
+}
+
+/// Returns an `IdOutOfRange` error if the client or tx column of the record holds an integer that
+/// does not fit the id width selected by the `client-id-*` and `tx-id-u64` features;
+// The ids are already u64 with the widest features;
+#[cfg(feature = "io")]
+#[allow(clippy::unnecessary_cast)]
+fn id_out_of_range(
+    record: &StringRecord,
+    client_column: Option<usize>,
+    tx_column: Option<usize>,
+) -> Option<Error> {
+    let line = record.position().map_or(0, |position| position.line());
+    // Only digit strings that fail to parse overflow, other values are reported as parse errors;
+    let overflows = |value: &str, fits: bool| {
+        !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) && !fits
+    };
+
+    if let Some(client) = client_column.and_then(|column| record.get(column)) {
+        // Ignore the `:subaccount` suffix of the client column;
+        let client = client.trim().split(':').next().unwrap_or_default();
+        if overflows(client, client.parse::<ClientId>().is_ok()) {
+            return Some(Error::IdOutOfRange {
+                line,
+                column: "client",
+                value: client.to_string(),
+                max: ClientId::MAX as u64,
+            });
+        }
+    }
+
+    if let Some(tx) = tx_column.and_then(|column| record.get(column)) {
+        let tx = tx.trim();
+        if overflows(tx, tx.parse::<TxId>().is_ok()) {
+            return Some(Error::IdOutOfRange {
+                line,
+                column: "tx",
+                value: tx.to_string(),
+                max: TxId::MAX as u64,
+            });
+        }
+    }
+
+    None
+}
@@ -0,0 +1,96 @@
+use std::str::FromStr;
+
+use rust_decimal::RoundingStrategy;
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/// Maximum number of decimal places supported for transaction amounts;
+pub const AMOUNT_DECIMAL_PLACES: usize = 4;
+
+/// Behavior for transaction amounts with more than four decimal places.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum PrecisionPolicy {
+    /// Process the amount as provided;
+    #[default]
+    Allow,
+    /// Do not process the transaction;
+    Reject,
+    /// Round the amount to four decimal places, rounding ties to the nearest even digit;
+    RoundHalfEven,
+    /// Truncate the amount to four decimal places;
+    Truncate,
+}
+
+impl PrecisionPolicy {
+    /// Apply the policy to an amount; Returns `None` if the amount is rejected.
+    pub fn apply(&self, amount: Amount) -> Option<Amount> {
+        if decimal_places(amount) <= AMOUNT_DECIMAL_PLACES {
+            return Some(amount);
+        }
+
+        match self {
+            PrecisionPolicy::Allow => Some(amount),
+            PrecisionPolicy::Reject => None,
+            PrecisionPolicy::RoundHalfEven => Some(scale_amount(amount, true)),
+            PrecisionPolicy::Truncate => Some(scale_amount(amount, false)),
+        }
+    }
+}
+
+impl FromStr for PrecisionPolicy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "allow" => Ok(PrecisionPolicy::Allow),
+            "reject" => Ok(PrecisionPolicy::Reject),
+            "round" | "round-half-even" => Ok(PrecisionPolicy::RoundHalfEven),
+            "truncate" => Ok(PrecisionPolicy::Truncate),
+            _ => Err(Error::InvalidArgument(format!(
+                "unknown precision policy '{}'",
+                s
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+/// A transaction whose amount exceeded four decimal places, reported with the policy outcome.
+pub struct PrecisionAdjustment {
+    #[serde(rename = "client")]
+    pub client: ClientId,
+    #[serde(rename = "tx")]
+    pub tx: TxId,
+    /// The amount as provided in the input
+    #[serde(rename = "original")]
+    pub original: Amount,
+    /// The amount that was processed; Empty if the transaction was rejected
+    #[serde(rename = "adjusted")]
+    pub adjusted: Option<Amount>,
+}
+
+/// Returns the number of decimal places in the shortest representation of the amount;
+pub fn decimal_places(amount: Amount) -> usize {
+    amount.normalize().scale() as usize
+}
+
+/// Returns the amount in units of the fourth decimal place, rounding half-to-even beyond it; The
+/// unscaled value of a `DECIMAL(_, 4)` column in the Arrow and Parquet exports.
+#[cfg(any(feature = "arrow", feature = "parquet"))]
+pub(crate) fn amount_units(amount: Amount) -> i128 {
+    let mut amount = scale_amount(amount, true);
+    amount.rescale(AMOUNT_DECIMAL_PLACES as u32);
+    amount.mantissa()
+}
+
+/// Reduce an amount to four decimal places, either rounding half-to-even or truncating;
+fn scale_amount(amount: Amount, round: bool) -> Amount {
+    let strategy = if round {
+        RoundingStrategy::MidpointNearestEven
+    } else {
+        RoundingStrategy::ToZero
+    };
+
+    amount.round_dp_with_strategy(AMOUNT_DECIMAL_PLACES as u32, strategy)
+}
@@ -0,0 +1,83 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use csv::Reader;
+use log::info;
+use serde::Deserialize;
+
+use crate::*;
+
+/// A row of a prior run's accounts csv; Columns beyond the account schema, such as the extended
+/// statistics, are ignored, except for the lock reason.
+#[derive(Debug, Deserialize)]
+struct PreloadRecord {
+    client: ClientId,
+    available: Amount,
+    held: Amount,
+    total: Amount,
+    locked: bool,
+    #[serde(default)]
+    lock_reason: Option<LockReason>,
+}
+
+impl Accounting {
+    /// Load a prior run's accounts csv as the opening state, warm-starting processing from its output;
+    /// Unlike restoring a snapshot, only balances and lock status are required. Locked accounts stay
+    /// locked and held funds are carried over, but as the disputes behind them are unknown, they can
+    /// only be released by an operator. The file is validated before any account is loaded: every
+    /// total must equal available plus held, held funds must not be negative, and a client may
+    /// neither repeat nor already have an account. Returns the number of accounts loaded.
+    pub fn preload_accounts_csv(&mut self, file_path: PathBuf) -> Result<usize, Error> {
+        let epsilon = self.config.amount_epsilon;
+        let mut accounts = BTreeMap::new();
+
+        for record in Reader::from_path(file_path)?.deserialize::<PreloadRecord>() {
+            let record = record?;
+            let client = record.client;
+
+            if (record.total - (record.available + record.held)).abs() > epsilon {
+                return Err(Error::InvalidArgument(format!(
+                    "client {}: total {} does not equal available {} plus held {}",
+                    client, record.total, record.available, record.held
+                )));
+            }
+
+            if record.held < -epsilon {
+                return Err(Error::InvalidArgument(format!(
+                    "client {}: negative held funds {}",
+                    client, record.held
+                )));
+            }
+
+            if self.accounts.contains_key(&client) || accounts.contains_key(&client) {
+                return Err(Error::InvalidArgument(format!(
+                    "client {}: account already loaded",
+                    client
+                )));
+            }
+
+            let mut account = Account {
+                client,
+                available: record.available,
+                held: record.held,
+                total: record.total,
+                locked: record.locked,
+                ..Default::default()
+            };
+            account.lock_reason = record.lock_reason.filter(|_| account.locked);
+            account.stats.observe(account.total, account.held);
+
+            accounts.insert(client, account);
+        }
+
+        let count = accounts.len();
+        for (_, account) in accounts {
+            self.emit_change(None, &account);
+            self.insert_account(account);
+        }
+
+        info!("Preloaded {} accounts", count);
+
+        Ok(count)
+    }
+}
@@ -0,0 +1,9 @@
+//! The stable API surface: importing `tx_sim::prelude::*` brings in the types needed to process
+//! transactions and read accounts. Items in the prelude only change in breaking (major) releases;
+//! Other re-exports at the crate root may change in minor releases.
+
+pub use crate::{
+    Account, Accounting, AccountingConfig, Amount, ClientId, Decimal, Error, ErrorKind, Strictness,
+    Timestamp, Transaction, TransactionError, TxId, TxOutcome, TxReceipt, TxState, TxStatus,
+    TxType,
+};
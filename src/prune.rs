@@ -0,0 +1,74 @@
+#[cfg(feature = "io")]
+use std::path::PathBuf;
+
+use log::info;
+
+use crate::*;
+
+#[derive(Debug, Default, Clone, PartialEq)]
+/// Selects accounts removed by `Accounting::prune`.
+pub struct PrunePolicy {
+    /// Minimum number of seconds since the account's last transaction;
+    pub inactive_for: Timestamp,
+    /// Append the pruned accounts to this csv file before removing them;
+    #[cfg(feature = "io")]
+    pub archive: Option<PathBuf>,
+}
+
+impl Accounting {
+    /// Returns the timestamp of the client's last transaction, or the clock time at which it was
+    /// processed if it had none;
+    pub fn last_activity(&self, client: ClientId) -> Option<Timestamp> {
+        self.last_activity.get(&client).copied()
+    }
+
+    /// Remove unlocked accounts with zero balances, no open disputes or holds, and no transactions
+    /// for at least `inactive_for` seconds, optionally archiving them first; Returns the pruned accounts.
+    /// Accounts without recorded activity are treated as inactive.
+    /// NOTE: Recorded transactions are retained, so later disputes of them are still recognised.
+    pub fn prune(&mut self, policy: &PrunePolicy) -> Result<Vec<Account>, Error> {
+        let now = self.now();
+        let epsilon = self.config.amount_epsilon;
+
+        let mut clients: Vec<ClientId> = self
+            .accounts
+            .values()
+            .filter(|account| {
+                !account.locked
+                    && approx_eq(account.available, Amount::ZERO, epsilon)
+                    && approx_eq(account.held, Amount::ZERO, epsilon)
+                    && approx_eq(account.total, Amount::ZERO, epsilon)
+                    && self.open_disputes(account.client) == 0
+                    && self.client_holds(account.client).next().is_none()
+                    && self
+                        .last_activity(account.client)
+                        .is_none_or(|last| now.saturating_sub(last) >= policy.inactive_for)
+            })
+            .map(|account| account.client)
+            .collect();
+        clients.sort_unstable();
+
+        let pruned: Vec<Account> = clients
+            .iter()
+            .filter_map(|client| self.accounts.get(client).cloned())
+            .collect();
+
+        #[cfg(feature = "io")]
+        if let Some(path) = policy.archive.as_ref() {
+            let mut sink = CsvFileSink::append(path.clone(), self.config.output_durability)?;
+            for account in pruned.iter() {
+                sink.write(account)?;
+            }
+            sink.close()?;
+        }
+
+        for client in clients.iter() {
+            self.accounts.remove(client);
+            self.last_activity.remove(client);
+        }
+
+        info!("Pruned {} inactive accounts", pruned.len());
+
+        Ok(pruned)
+    }
+}
@@ -0,0 +1,122 @@
+#[cfg(feature = "io")]
+use std::path::PathBuf;
+
+#[cfg(feature = "io")]
+use csv::Reader;
+
+use crate::*;
+
+impl Accounting {
+    /// Returns the transactions diverted instead of applied, in the order received: transactions of
+    /// flagged clients when `AccountingConfig::quarantine_flagged` is set, and transactions received
+    /// while the circuit breaker was open in `BreakerAction::Quarantine` mode;
+    pub fn quarantined_transactions(&self) -> &[Transaction] {
+        &self.quarantined
+    }
+
+    /// Flag the client for manual review, e.g. on a fraud score; With
+    /// `AccountingConfig::quarantine_flagged` set, its later transactions are quarantined.
+    pub fn flag_account(&mut self, client: ClientId) {
+        if self.flagged_accounts.insert(client) {
+            self.audit(
+                Some(client),
+                None,
+                "account_flagged",
+                "manual review".to_string(),
+            );
+
+            #[cfg(feature = "io")]
+            self.journal_action(JournalAction::new(JournalActionKind::Flag, client));
+        }
+    }
+
+    /// Clear the client's review flag; Returns false if the client was not flagged.
+    pub fn clear_account_flag(&mut self, client: ClientId) -> bool {
+        let cleared = self.flagged_accounts.remove(&client);
+        if cleared {
+            self.audit(
+                Some(client),
+                None,
+                "account_flag_cleared",
+                "review cleared".to_string(),
+            );
+
+            #[cfg(feature = "io")]
+            self.journal_action(JournalAction::new(JournalActionKind::ClearFlag, client));
+        }
+
+        cleared
+    }
+
+    /// Clear the client's review flag and process its quarantined transactions in the order
+    /// received, e.g. after manual review; Returns a receipt per released transaction.
+    pub fn release_quarantined(&mut self, client: ClientId) -> Vec<TxReceipt> {
+        self.clear_account_flag(client);
+
+        let quarantined = std::mem::take(&mut self.quarantined);
+        let (released, kept): (Vec<Transaction>, Vec<Transaction>) = quarantined
+            .into_iter()
+            .partition(|tx| self.client_remapper.map(tx.client) == client);
+        self.quarantined = kept;
+
+        let detail = format!("{} transactions released", released.len());
+        self.audit(Some(client), None, "quarantine_released", detail);
+
+        released.into_iter().map(|tx| self.apply(tx)).collect()
+    }
+
+    /// Returns true if the transaction is diverted instead of applied; See `quarantined_transactions`.
+    #[cfg(feature = "io")]
+    pub(crate) fn diverts(&self, tx: &Transaction) -> bool {
+        self.breaker.trip.is_some() || self.quarantines(self.client_remapper.map(tx.client))
+    }
+
+    /// Returns true if the client's transactions are quarantined pending review;
+    pub(crate) fn quarantines(&self, client: ClientId) -> bool {
+        self.config.quarantine_flagged && self.flagged_accounts.contains(&client)
+    }
+
+    /// Keep a transaction of a flagged client in the quarantine instead of applying it;
+    pub(crate) fn quarantine(&mut self, tx: Transaction) {
+        self.ignore(&tx, TransactionError::Quarantined, || {
+            "client is flagged for review".to_string()
+        });
+        self.quarantined.push(tx);
+    }
+
+    /// Write the quarantined transactions to a csv file in the transactions schema, so they can be
+    /// reviewed and processed again;
+    #[cfg(feature = "io")]
+    pub fn write_quarantined_csv_file(&self, file_path: PathBuf) -> Result<(), Error> {
+        let mut sink = CsvFileSink::create(file_path, self.config.output_durability)?;
+
+        for tx in self.quarantined.iter() {
+            sink.write(tx)?;
+        }
+
+        sink.close()
+    }
+
+    /// Append the quarantined transactions to a quarantine journal csv file, creating it if missing;
+    #[cfg(feature = "io")]
+    pub fn append_quarantined_csv_file(&self, file_path: PathBuf) -> Result<(), Error> {
+        let mut sink = CsvFileSink::append(file_path, self.config.output_durability)?;
+
+        for tx in self.quarantined.iter() {
+            sink.write(tx)?;
+        }
+
+        sink.close()
+    }
+
+    /// Add the transactions of a quarantine journal csv file to the quarantine, e.g. to release
+    /// them in a later run;
+    #[cfg(feature = "io")]
+    pub fn read_quarantined_csv_file(&mut self, file_path: PathBuf) -> Result<(), Error> {
+        for tx in Reader::from_path(file_path)?.deserialize() {
+            self.quarantined.push(tx?);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,74 @@
+use crate::*;
+
+/// What `Accounting::apply` did with a transaction;
+#[derive(Debug, Clone, PartialEq)]
+pub enum TxStatus {
+    /// The transaction was applied to the account;
+    Applied,
+    /// The deposit was applied to the held funds until its clearing delay has passed;
+    Pending,
+    /// The transaction was ignored, leaving the account unchanged;
+    Ignored(TransactionError),
+    /// The transaction failed with an error, e.g. strict validation; See `Error::code`.
+    Rejected { code: &'static str, message: String },
+}
+
+/// Receipt of a transaction processed by `Accounting::apply`: its status and the resulting
+/// balances of the client's account, for downstream reporting;
+#[derive(Debug, Clone, PartialEq)]
+pub struct TxReceipt {
+    /// Client of the account, after any client id remapping
+    pub client: ClientId,
+    pub tx: TxId,
+    pub r#type: TxType,
+    pub status: TxStatus,
+    pub available: Amount,
+    pub held: Amount,
+    pub total: Amount,
+    pub locked: bool,
+    /// The transaction locked the account, e.g. a chargeback
+    pub became_locked: bool,
+}
+
+impl TxReceipt {
+    /// Returns true if the transaction was applied, including pending deposits;
+    pub fn is_applied(&self) -> bool {
+        matches!(self.status, TxStatus::Applied | TxStatus::Pending)
+    }
+}
+
+impl Accounting {
+    /// Validate and process a transaction like `try_process_transaction`, returning a receipt of
+    /// what it did and the resulting balances instead of an error;
+    pub fn apply(&mut self, tx: Transaction) -> TxReceipt {
+        let client = self.client_remapper.map(tx.client);
+        let (id, r#type) = (tx.tx, tx.r#type);
+        let was_locked = self
+            .get_account(client)
+            .is_some_and(|account| account.locked);
+
+        let status = match self.try_process(tx) {
+            Ok(Ok(TxOutcome::Applied)) => TxStatus::Applied,
+            Ok(Ok(TxOutcome::Pending)) => TxStatus::Pending,
+            Ok(Err(rejection)) => TxStatus::Ignored(rejection),
+            Err(e) => TxStatus::Rejected {
+                code: e.code(),
+                message: e.to_string(),
+            },
+        };
+
+        let account = self.get_account(client);
+        let locked = account.is_some_and(|account| account.locked);
+        TxReceipt {
+            client,
+            tx: id,
+            r#type,
+            status,
+            available: account.map_or(Amount::ZERO, |account| account.available),
+            held: account.map_or(Amount::ZERO, |account| account.held),
+            total: account.map_or(Amount::ZERO, |account| account.total),
+            locked,
+            became_locked: locked && !was_locked,
+        }
+    }
+}
@@ -0,0 +1,44 @@
+#[cfg(feature = "io")]
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+/// A transaction ignored by `Accounting::process_transaction`, with the reason it was skipped.
+pub struct RejectedTx {
+    #[serde(rename = "type")]
+    pub r#type: TxType,
+    #[serde(rename = "client")]
+    pub client: ClientId,
+    #[serde(rename = "tx")]
+    pub tx: TxId,
+    #[serde(rename = "amount")]
+    pub amount: Option<Amount>,
+    /// Why the transaction was ignored, see `TransactionError`
+    #[serde(rename = "reason")]
+    pub reason: String,
+    #[serde(rename = "source")]
+    pub source: Option<String>,
+}
+
+impl Accounting {
+    /// Returns the ignored transactions, in the order processed; Only recorded when
+    /// `AccountingConfig::track_rejected` is set.
+    pub fn rejected_transactions(&self) -> &[RejectedTx] {
+        &self.rejected
+    }
+
+    /// Write the ignored transactions report to a csv file;
+    #[cfg(feature = "io")]
+    pub fn write_rejected_csv_file(&self, file_path: PathBuf) -> Result<(), Error> {
+        let mut sink = CsvFileSink::create(file_path, self.config.output_durability)?;
+
+        for rejected in self.rejected.iter() {
+            sink.write(rejected)?;
+        }
+
+        sink.close()
+    }
+}
@@ -0,0 +1,267 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::net::TcpStream;
+use std::path::PathBuf;
+
+use csv::{Reader, Writer};
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+/// A change-data-capture record carrying the full state of an account after it changed;
+/// Applying events in sequence order reproduces the account state on a replica, along with the
+/// recorded deposits and withdrawals and their open disputes, so a promoted replica accepts
+/// disputes of transactions processed by the leader.
+/// NOTE: Operator holds, reservations, pending deposits and sub-accounts are not replicated; See
+/// `Standby::check_promotion`.
+pub struct AccountEvent {
+    /// Monotonically increasing sequence number of the event within the stream
+    #[serde(rename = "seq")]
+    pub seq: u64,
+    /// The transaction that caused the change; Empty for manual changes such as unlocking
+    #[serde(rename = "tx")]
+    pub tx: Option<TxId>,
+    #[serde(rename = "client")]
+    pub client: ClientId,
+    #[serde(rename = "available")]
+    pub available: Amount,
+    #[serde(rename = "held")]
+    pub held: Amount,
+    #[serde(rename = "total")]
+    pub total: Amount,
+    #[serde(rename = "locked")]
+    pub locked: bool,
+    /// Leader clock time at which the event was emitted
+    #[serde(rename = "at", default)]
+    pub at: Option<Timestamp>,
+    /// Type of the recorded transaction `tx`; Empty if the event changed no recorded transaction
+    #[serde(rename = "type", default)]
+    pub r#type: Option<TxType>,
+    /// Amount of the recorded transaction
+    #[serde(rename = "amount", default)]
+    pub amount: Option<Amount>,
+    /// Timestamp of the recorded transaction
+    #[serde(rename = "timestamp", default)]
+    pub timestamp: Option<Timestamp>,
+    /// Dispute state of the recorded transaction after the change
+    #[serde(rename = "state", default)]
+    pub state: Option<TxState>,
+    /// Amount charged back of the recorded transaction
+    #[serde(rename = "charged_back", default)]
+    pub charged_back: Option<Amount>,
+    /// Amount held by the open dispute of the recorded transaction
+    #[serde(rename = "disputed", default)]
+    pub disputed: Option<Amount>,
+    /// Time the open dispute was opened
+    #[serde(rename = "opened_at", default)]
+    pub opened_at: Option<Timestamp>,
+    /// Random id of the stream, drawn by the leader when it starts streaming; A new id restarts the
+    /// sequence numbers, e.g. when the leader reconnects
+    #[serde(rename = "stream", default)]
+    pub stream: Option<u64>,
+    /// Why the account is locked, if it is
+    #[serde(rename = "lock_reason", default)]
+    pub lock_reason: Option<LockReason>,
+    /// Clock time at which the account was closed; Empty unless closed
+    #[serde(rename = "closed_at", default)]
+    pub closed_at: Option<Timestamp>,
+    /// Residual available funds at closure
+    #[serde(rename = "residual", default)]
+    pub residual: Option<Amount>,
+    /// Account credited with the residual funds at closure
+    #[serde(rename = "transferred_to", default)]
+    pub transferred_to: Option<ClientId>,
+}
+
+/// Writes `AccountEvent` records as csv to a file, socket or any other writer as transactions apply.
+pub struct ChangeStream {
+    writer: Writer<Box<dyn Write + Send>>,
+    stream: u64,
+    seq: u64,
+}
+
+impl fmt::Debug for ChangeStream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ChangeStream")
+            .field("stream", &self.stream)
+            .field("seq", &self.seq)
+            .finish()
+    }
+}
+
+impl ChangeStream {
+    /// Stream events to any writer under a new random stream id; Events are flushed after every
+    /// record.
+    pub fn new(writer: Box<dyn Write + Send>) -> Self {
+        ChangeStream {
+            writer: Writer::from_writer(writer),
+            stream: rand::random(),
+            seq: 0,
+        }
+    }
+
+    /// Stream events to a file, creating or truncating it;
+    pub fn create_file(file_path: PathBuf) -> Result<Self, Error> {
+        Ok(ChangeStream::new(Box::new(BufWriter::new(File::create(
+            file_path,
+        )?))))
+    }
+
+    /// Stream events to a TCP socket, e.g. a standby instance;
+    pub fn connect(addr: &str) -> Result<Self, Error> {
+        Ok(ChangeStream::new(Box::new(TcpStream::connect(addr)?)))
+    }
+
+    /// Returns the id of the stream, carried by every event;
+    pub fn stream_id(&self) -> u64 {
+        self.stream
+    }
+
+    /// Returns the sequence number of the last emitted event;
+    pub fn seq(&self) -> u64 {
+        self.seq
+    }
+
+    /// Emit the current state of the account;
+    pub fn emit(&mut self, tx: Option<TxId>, account: &Account) -> Result<(), Error> {
+        self.emit_event(AccountEvent {
+            tx,
+            client: account.client,
+            available: account.available,
+            held: account.held,
+            total: account.total,
+            locked: account.locked,
+            ..Default::default()
+        })
+    }
+
+    /// Emit the event under the next sequence number;
+    pub(crate) fn emit_event(&mut self, mut event: AccountEvent) -> Result<(), Error> {
+        self.seq += 1;
+        event.seq = self.seq;
+        event.stream = Some(self.stream);
+
+        self.writer.serialize(event)?;
+        self.writer.flush()?;
+
+        Ok(())
+    }
+
+    /// Read events from a change stream, e.g. a file or a socket accepted by a standby;
+    pub fn read_events<R: Read>(reader: R) -> impl Iterator<Item = Result<AccountEvent, Error>> {
+        Reader::from_reader(reader)
+            .into_deserialize::<AccountEvent>()
+            .map(|row| row.map_err(Error::from))
+    }
+}
+
+impl Accounting {
+    /// Returns the change stream event for the account, carrying its lock reason and closure, and
+    /// the recorded transaction `tx` and its open dispute, if any;
+    pub(crate) fn account_event(&self, tx: Option<TxId>, account: &Account) -> AccountEvent {
+        let mut event = AccountEvent {
+            tx,
+            client: account.client,
+            available: account.available,
+            held: account.held,
+            total: account.total,
+            locked: account.locked,
+            at: Some(self.now()),
+            lock_reason: account.lock_reason,
+            ..Default::default()
+        };
+
+        if let Some(closure) = self.closures.get(&account.client) {
+            event.closed_at = Some(closure.closed_at);
+            event.residual = Some(closure.residual);
+            event.transferred_to = closure.transferred_to;
+        }
+
+        let recorded = tx
+            .and_then(|tx| self.transactions.get(&tx))
+            .filter(|recorded| recorded.transaction.client == account.client);
+        if let Some(recorded) = recorded {
+            event.r#type = Some(recorded.transaction.r#type);
+            event.amount = recorded.transaction.amount;
+            event.timestamp = recorded.transaction.timestamp;
+            event.state = Some(recorded.state);
+            event.charged_back = recorded.charged_back;
+
+            if let Some(dispute) = self.disputes.get(&recorded.transaction.tx) {
+                event.disputed = Some(dispute.amount);
+                event.opened_at = Some(dispute.opened_at);
+            }
+        }
+
+        event
+    }
+
+    /// Apply a change-data-capture event, replacing the balances, lock status and closure of the
+    /// account, and the recorded transaction and open dispute it carries;
+    /// NOTE: Holds, reservations, pending deposits, sub-accounts, and categories and sources of
+    /// recorded transactions are not replicated.
+    pub fn apply_account_event(&mut self, event: &AccountEvent) {
+        let mut account = self
+            .get_account(event.client)
+            .cloned()
+            .unwrap_or_else(|| Account::new(event.client));
+
+        account.available = event.available;
+        account.held = event.held;
+        account.total = event.total;
+        account.locked = event.locked;
+        account.lock_reason = event.lock_reason;
+        account.stats.observe(account.total, account.held);
+
+        self.insert_account(account);
+
+        if let Some(closed_at) = event.closed_at {
+            self.closures.insert(
+                event.client,
+                AccountClosure {
+                    client: event.client,
+                    residual: event.residual.unwrap_or_default(),
+                    transferred_to: event.transferred_to,
+                    closed_at,
+                },
+            );
+        }
+
+        if let (Some(tx), Some(r#type), Some(state)) = (event.tx, event.r#type, event.state) {
+            self.insert_transaction(
+                Transaction {
+                    r#type,
+                    client: event.client,
+                    tx,
+                    amount: event.amount,
+                    timestamp: event.timestamp,
+                    subaccount: None,
+                    category: None,
+                    source: None,
+                },
+                state,
+            );
+            self.set_charged_back(tx, event.charged_back);
+
+            match (event.disputed, event.opened_at) {
+                (Some(amount), Some(opened_at)) => {
+                    self.disputes.insert(
+                        tx,
+                        Dispute {
+                            tx,
+                            client: event.client,
+                            amount,
+                            opened_at,
+                            resolution: None,
+                        },
+                    );
+                }
+                _ => {
+                    self.disputes.remove(&tx);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,171 @@
+#[cfg(feature = "io")]
+use std::path::PathBuf;
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+/// Funds reserved for an open order by a `reserve` transaction, tracked separately from disputes;
+/// The reserved funds are held until released on cancel or converted into a withdrawal on fill.
+pub struct Reservation {
+    /// The id of the reserve transaction, referenced by releases and fills
+    #[serde(rename = "tx")]
+    pub tx: TxId,
+    #[serde(rename = "client")]
+    pub client: ClientId,
+    /// The amount still reserved, after partial releases and fills
+    #[serde(rename = "amount")]
+    pub amount: Amount,
+    /// The amount converted into withdrawals by fills
+    #[serde(rename = "filled")]
+    pub filled: Amount,
+    #[serde(rename = "reserved_at")]
+    pub reserved_at: Timestamp,
+}
+
+impl Accounting {
+    /// This method is provided as a helper method and is intended to be consumed by `self.process_transaction`;
+    /// Exposed through `internals::AccountingInternals` with the `internals` feature.
+    pub(crate) fn process_reserve(&mut self, account: &mut Account, tx: &Transaction) {
+        let amount = match tx.amount {
+            Some(amount) if amount > Amount::ZERO => amount,
+            _ => {
+                warn!(
+                    "Ignored reserve tx {} for client {}: missing amount",
+                    tx.tx, tx.client
+                );
+                let rejection = match tx.amount {
+                    Some(amount) => TransactionError::InvalidAmount(amount),
+                    None => TransactionError::MissingAmount,
+                };
+                self.ignore(tx, rejection, || "missing amount".to_string());
+                return;
+            }
+        };
+
+        if self.reservations.contains_key(&tx.tx) || self.transactions.contains_key(&tx.tx) {
+            warn!(
+                "Ignored reserve tx {} for client {}: duplicate transaction id",
+                tx.tx, tx.client
+            );
+            self.ignore(tx, TransactionError::DuplicateTx, || {
+                "duplicate transaction id".to_string()
+            });
+            return;
+        }
+
+        if !covers(account.available, amount, self.config.amount_epsilon) {
+            warn!(
+                "Ignored reserve tx {} for client {}: insufficient available funds",
+                tx.tx, tx.client
+            );
+            let available = account.available;
+            self.ignore(
+                tx,
+                TransactionError::InsufficientFunds { available, amount },
+                || "insufficient available funds".to_string(),
+            );
+            return;
+        }
+
+        account.available = clear_dust(account.available - amount, self.config.amount_epsilon);
+        account.held += amount;
+
+        let reserved_at = tx.timestamp.unwrap_or_else(|| self.now());
+        self.reservations.insert(
+            tx.tx,
+            Reservation {
+                tx: tx.tx,
+                client: account.client,
+                amount,
+                filled: Amount::ZERO,
+                reserved_at,
+            },
+        );
+    }
+
+    /// This method is provided as a helper method and is intended to be consumed by `self.process_transaction`;
+    /// Exposed through `internals::AccountingInternals` with the `internals` feature.
+    pub(crate) fn process_release(&mut self, account: &mut Account, tx: &Transaction) {
+        if let Some(amount) = self.take_reservation(account, tx) {
+            account.held = clear_dust(account.held - amount, self.config.amount_epsilon);
+            account.available += amount;
+        }
+    }
+
+    /// This method is provided as a helper method and is intended to be consumed by `self.process_transaction`;
+    /// Exposed through `internals::AccountingInternals` with the `internals` feature.
+    pub(crate) fn process_fill(&mut self, account: &mut Account, tx: &Transaction) {
+        if let Some(amount) = self.take_reservation(account, tx) {
+            account.held = clear_dust(account.held - amount, self.config.amount_epsilon);
+            account.total = clear_dust(account.total - amount, self.config.amount_epsilon);
+
+            if let Some(reservation) = self.reservations.get_mut(&tx.tx) {
+                reservation.filled += amount;
+            }
+        }
+    }
+
+    /// Take the amount of a release or fill from the referenced reservation, defaulting to the
+    /// whole remaining amount; Fully consumed reservations are closed.
+    fn take_reservation(&mut self, account: &Account, tx: &Transaction) -> Option<Amount> {
+        let epsilon = self.config.amount_epsilon;
+
+        let reservation = match self.reservations.get_mut(&tx.tx) {
+            Some(reservation) if reservation.client == account.client => reservation,
+            _ => {
+                warn!(
+                    "Ignored {} of tx {} for client {}: unknown reservation",
+                    tx.r#type, tx.tx, tx.client
+                );
+                self.ignore(tx, TransactionError::UnknownReservation, || {
+                    "unknown reservation".to_string()
+                });
+                return None;
+            }
+        };
+
+        let amount = tx.amount.unwrap_or(reservation.amount);
+        if amount <= Amount::ZERO || !covers(reservation.amount, amount, epsilon) {
+            warn!(
+                "Ignored {} of tx {} for client {}: exceeds reserved amount",
+                tx.r#type, tx.tx, tx.client
+            );
+            self.ignore(tx, TransactionError::InvalidAmount(amount), || {
+                "exceeds reserved amount".to_string()
+            });
+            return None;
+        }
+
+        reservation.amount = clear_dust(reservation.amount - amount, epsilon);
+        if reservation.amount == Amount::ZERO {
+            self.reservations.remove(&tx.tx);
+        }
+
+        Some(amount)
+    }
+
+    /// Returns the open reservation of a reserve transaction, if any;
+    pub fn get_reservation(&self, tx: TxId) -> Option<&Reservation> {
+        self.reservations.get(&tx)
+    }
+
+    /// Iterate over all open reservations, ordered by transaction id;
+    pub fn reservations(&self) -> impl Iterator<Item = &Reservation> {
+        self.reservations.values()
+    }
+
+    /// Write all open reservations to a csv file;
+    #[cfg(feature = "io")]
+    pub fn write_reservations_csv_file(&self, file_path: PathBuf) -> Result<(), Error> {
+        let mut sink = CsvFileSink::create(file_path, self.config.output_durability)?;
+
+        for reservation in self.reservations() {
+            sink.write(reservation)?;
+        }
+
+        sink.close()
+    }
+}
@@ -0,0 +1,184 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::mem::size_of;
+
+use crate::*;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Estimated memory retained by one of the engine's collections.
+pub struct CollectionUsage {
+    pub name: &'static str,
+    pub entries: usize,
+    /// Shallow estimate from the entry size and allocated capacity; Heap data owned by entries, e.g.
+    /// category strings, is not included.
+    pub bytes: usize,
+}
+
+impl CollectionUsage {
+    fn hash_map<K, V>(name: &'static str, map: &HashMap<K, V>) -> Self {
+        // Hash tables allocate one control byte per bucket;
+        CollectionUsage {
+            name,
+            entries: map.len(),
+            bytes: map.capacity() * (size_of::<(K, V)>() + 1),
+        }
+    }
+
+    fn btree_map<K, V>(name: &'static str, map: &BTreeMap<K, V>) -> Self {
+        CollectionUsage {
+            name,
+            entries: map.len(),
+            bytes: map.len() * size_of::<(K, V)>(),
+        }
+    }
+
+    fn vec<T>(name: &'static str, vec: &[T]) -> Self {
+        CollectionUsage {
+            name,
+            entries: vec.len(),
+            bytes: std::mem::size_of_val(vec),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// End-of-run resource usage, for capacity planning without an external profiler; `Display` renders
+/// it as a formatted summary.
+pub struct ResourceReport {
+    /// Peak resident set size of the process in bytes; Only available on Linux
+    pub peak_rss: Option<u64>,
+    pub transactions_processed: u64,
+    pub transactions_retained: usize,
+    pub accounts: usize,
+    /// Collections with at least one entry, largest first
+    pub collections: Vec<CollectionUsage>,
+}
+
+impl ResourceReport {
+    /// Returns the estimated memory retained by all collections, in bytes;
+    pub fn estimated_bytes(&self) -> usize {
+        self.collections.iter().map(|usage| usage.bytes).sum()
+    }
+}
+
+impl fmt::Display for ResourceReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Resource report")?;
+        match self.peak_rss {
+            Some(peak_rss) => {
+                writeln!(f, "  Peak RSS               {:>12}", format_bytes(peak_rss))?
+            }
+            None => writeln!(f, "  Peak RSS               {:>12}", "unavailable")?,
+        }
+        writeln!(
+            f,
+            "  Transactions processed {:>12}",
+            self.transactions_processed
+        )?;
+        writeln!(
+            f,
+            "  Transactions retained  {:>12}",
+            self.transactions_retained
+        )?;
+        writeln!(f, "  Accounts               {:>12}", self.accounts)?;
+
+        writeln!(
+            f,
+            "Estimated collection memory ({})",
+            format_bytes(self.estimated_bytes() as u64)
+        )?;
+        for usage in self.collections.iter() {
+            writeln!(
+                f,
+                "  {:<22} {:>12} entries {:>12}",
+                usage.name,
+                usage.entries,
+                format_bytes(usage.bytes as u64)
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Format a number of bytes with a binary unit, e.g. `1.5 MiB`;
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+
+    let mut value = bytes as f64 / 1024.;
+    let mut unit = 0;
+    while value >= 1024. && unit < UNITS.len() - 1 {
+        value /= 1024.;
+        unit += 1;
+    }
+
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// Parse a number of bytes with an optional binary unit suffix, e.g. `512`, `64K`, `10M` or `2G`;
+pub fn parse_bytes(s: &str) -> Option<u64> {
+    let s = s.trim();
+    let (number, shift) = match s.char_indices().last()? {
+        (i, 'K') | (i, 'k') => (&s[..i], 10),
+        (i, 'M') | (i, 'm') => (&s[..i], 20),
+        (i, 'G') | (i, 'g') => (&s[..i], 30),
+        (i, 'T') | (i, 't') => (&s[..i], 40),
+        _ => (s, 0),
+    };
+
+    number.parse::<u64>().ok()?.checked_mul(1 << shift)
+}
+
+/// Returns the peak resident set size of the process in bytes, read from `/proc/self/status`;
+/// Returns `None` on platforms without procfs.
+pub fn peak_rss() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    let kib: u64 = line
+        .trim_start_matches("VmHWM:")
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse()
+        .ok()?;
+
+    Some(kib * 1024)
+}
+
+impl Accounting {
+    /// Returns the process's peak memory and the retained entries and estimated memory of the
+    /// engine's collections;
+    pub fn resource_report(&self) -> ResourceReport {
+        let mut collections = vec![
+            CollectionUsage::hash_map("accounts", &self.accounts),
+            CollectionUsage::hash_map("transactions", &self.transactions),
+            CollectionUsage::hash_map("client_tx_index", &self.client_tx_index),
+            CollectionUsage::btree_map("disputes", &self.disputes),
+            CollectionUsage::hash_map("last_activity", &self.last_activity),
+            CollectionUsage::btree_map("daily_balances", &self.daily_balances),
+            CollectionUsage::btree_map("pending_deposits", &self.pending_deposits),
+            CollectionUsage::btree_map("reservations", &self.reservations),
+            CollectionUsage::btree_map("holds", &self.holds),
+            CollectionUsage::hash_map("subaccounts", &self.subaccounts),
+            CollectionUsage::hash_map("content_hashes", &self.content_hashes),
+            CollectionUsage::vec("audit_log", &self.audit_log),
+            CollectionUsage::vec("lock_events", &self.lock_events),
+            CollectionUsage::vec("notifications", &self.notifications),
+            CollectionUsage::vec("trace_steps", &self.trace_steps),
+        ];
+        collections.retain(|usage| usage.entries > 0);
+        collections.sort_by(|a, b| b.bytes.cmp(&a.bytes).then(a.name.cmp(b.name)));
+
+        ResourceReport {
+            peak_rss: peak_rss(),
+            transactions_processed: self.processed,
+            transactions_retained: self.transactions.len(),
+            accounts: self.accounts.len(),
+            collections,
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use std::path::PathBuf;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/// A parameterized what-if scenario, applied on top of a base ledger by a `ScenarioRunner`;
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scenario {
+    pub name: String,
+    /// The generated transaction stream applied on top of the base ledger
+    pub profile: GeneratorProfile,
+    /// Flat fee debited from available funds after every applied withdrawal, capped at the available funds
+    pub withdrawal_fee: Amount,
+    /// Multiplier applied to the profile's dispute weight, e.g. `3.0` for a dispute surge
+    pub dispute_multiplier: f64,
+    /// Multiplier applied to the profile's withdrawal weight, e.g. `2.0` for a withdrawal spike
+    pub withdrawal_multiplier: f64,
+    /// Seed of the transaction generator; Scenarios with the same seed and profile are reproducible
+    pub seed: u64,
+}
+
+impl Scenario {
+    /// Create a scenario with no fee and unscaled weights;
+    pub fn new(name: &str, profile: GeneratorProfile) -> Self {
+        Scenario {
+            name: name.to_string(),
+            profile,
+            withdrawal_fee: Amount::ZERO,
+            dispute_multiplier: 1.,
+            withdrawal_multiplier: 1.,
+            seed: 0,
+        }
+    }
+
+    /// Returns the profile with the scenario's multipliers applied;
+    pub fn effective_profile(&self) -> GeneratorProfile {
+        self.profile
+            .clone()
+            .scale(TxType::Dispute, self.dispute_multiplier)
+            .scale(TxType::Withdrawal, self.withdrawal_multiplier)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+/// End state of a scenario run, compared against the base ledger.
+pub struct ScenarioSummary {
+    #[serde(rename = "scenario")]
+    pub scenario: String,
+    /// Number of generated transactions
+    #[serde(rename = "transactions")]
+    pub transactions: usize,
+    #[serde(rename = "accounts")]
+    pub accounts: usize,
+    #[serde(rename = "available")]
+    pub available: Amount,
+    #[serde(rename = "held")]
+    pub held: Amount,
+    #[serde(rename = "total")]
+    pub total: Amount,
+    /// Change of the total funds across all accounts relative to the base ledger
+    #[serde(rename = "total_change")]
+    pub total_change: Amount,
+    #[serde(rename = "locked_accounts")]
+    pub locked_accounts: usize,
+    /// Accounts with negative available or total funds
+    #[serde(rename = "negative_accounts")]
+    pub negative_accounts: usize,
+    /// Sum of the negative available funds across all accounts, as a positive amount
+    #[serde(rename = "negative_exposure")]
+    pub negative_exposure: Amount,
+    /// Withdrawal fees debited
+    #[serde(rename = "fees")]
+    pub fees: Amount,
+}
+
+/// Runs scenarios against the same base ledger, each on an independent copy of it;
+#[derive(Debug, Clone, Default)]
+pub struct ScenarioRunner {
+    base: Snapshot,
+    config: AccountingConfig,
+    scenarios: Vec<Scenario>,
+}
+
+impl ScenarioRunner {
+    /// Create a runner over a base ledger, e.g. `Accounting::snapshot()` or a snapshot file;
+    pub fn new(base: Snapshot) -> Self {
+        ScenarioRunner {
+            base,
+            ..Default::default()
+        }
+    }
+
+    /// Set the configuration used to process every scenario;
+    pub fn with_config(mut self, config: AccountingConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Add a scenario to the runner;
+    pub fn add_scenario(&mut self, scenario: Scenario) {
+        self.scenarios.push(scenario);
+    }
+
+    /// Returns the base ledger restored into a new processor;
+    pub fn base_accounting(&self) -> Accounting {
+        let mut accounting = Accounting::with_config(self.config.clone());
+        accounting.restore_snapshot(self.base.clone());
+        accounting
+    }
+
+    /// Run every scenario, returning a summary per scenario in the order added;
+    pub fn run(&self) -> Result<Vec<ScenarioSummary>, Error> {
+        self.scenarios
+            .iter()
+            .map(|scenario| self.run_scenario(scenario).map(|(summary, _)| summary))
+            .collect()
+    }
+
+    /// Run a scenario on a copy of the base ledger, returning its summary and end state;
+    pub fn run_scenario(
+        &self,
+        scenario: &Scenario,
+    ) -> Result<(ScenarioSummary, Accounting), Error> {
+        let mut accounting = self.base_accounting();
+        let base_total: Amount = self
+            .base
+            .accounts
+            .iter()
+            .map(|(account, _)| account.total)
+            .sum();
+
+        // Allocate generated ids after the base ledger's;
+        let first_tx = self
+            .base
+            .transactions
+            .iter()
+            .map(|(tx, _)| tx.tx)
+            .max()
+            .map_or(1, |tx| tx.saturating_add(1));
+
+        let mut rng = StdRng::seed_from_u64(scenario.seed);
+        let transactions = scenario.effective_profile().generate(&mut rng, first_tx)?;
+        let count = transactions.len();
+        let mut fees = Amount::ZERO;
+
+        for tx in transactions {
+            let (r#type, client) = (tx.r#type, tx.client);
+            let before = accounting.get_account(client).map(|account| account.total);
+
+            let _ = accounting.process_transaction(tx);
+
+            let after = accounting.get_account(client).map(|account| account.total);
+            if r#type == TxType::Withdrawal
+                && scenario.withdrawal_fee > Amount::ZERO
+                && after < before
+            {
+                fees += accounting
+                    .debit_adjustment(client, scenario.withdrawal_fee, "withdrawal fee", false)?
+                    .debited;
+            }
+        }
+
+        let mut summary = ScenarioSummary {
+            scenario: scenario.name.clone(),
+            transactions: count,
+            accounts: 0,
+            available: Amount::ZERO,
+            held: Amount::ZERO,
+            total: Amount::ZERO,
+            total_change: Amount::ZERO,
+            locked_accounts: 0,
+            negative_accounts: 0,
+            negative_exposure: Amount::ZERO,
+            fees,
+        };
+
+        // Sum in client order, so summaries of the same scenario are identical;
+        let mut accounts: Vec<&Account> = accounting.accounts().collect();
+        accounts.sort_by_key(|account| account.client);
+
+        for account in accounts {
+            summary.accounts += 1;
+            summary.available += account.available;
+            summary.held += account.held;
+            summary.total += account.total;
+            summary.locked_accounts += account.locked as usize;
+            summary.negative_accounts +=
+                (account.available < Amount::ZERO || account.total < Amount::ZERO) as usize;
+            summary.negative_exposure -= account.available.min(Amount::ZERO);
+        }
+        summary.total_change = summary.total - base_total;
+
+        Ok((summary, accounting))
+    }
+
+    /// Write scenario summaries to a csv file, one row per scenario;
+    pub fn write_summaries_csv_file(
+        summaries: &[ScenarioSummary],
+        file_path: PathBuf,
+    ) -> Result<(), Error> {
+        let mut sink = CsvFileSink::create(file_path, Durability::Buffered)?;
+
+        for summary in summaries {
+            sink.write(summary)?;
+        }
+
+        sink.close()
+    }
+}
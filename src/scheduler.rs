@@ -0,0 +1,136 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::path::PathBuf;
+
+use crate::*;
+
+/// Default number of rows read per scheduling window by `read_transactions_csv_file_fair`;
+pub const DEFAULT_SCHEDULER_WINDOW: usize = 1024;
+
+#[derive(Debug, Default)]
+/// Per-client transaction queues drained round-robin, so a client flooding transactions cannot
+/// starve others; A full client queue applies backpressure: the transaction is handed back, to be
+/// queued again once the queues are drained. The order of each client's transactions is preserved.
+pub struct FairScheduler {
+    queue_cap: usize,
+    queues: HashMap<ClientId, VecDeque<Transaction>>,
+    /// Clients with queued transactions, in round-robin order;
+    ready: VecDeque<ClientId>,
+    queued: usize,
+    overflows: BTreeMap<ClientId, u64>,
+}
+
+impl FairScheduler {
+    /// Create a scheduler queueing at most `queue_cap` transactions per client;
+    pub fn new(queue_cap: usize) -> Self {
+        FairScheduler {
+            queue_cap,
+            ..Default::default()
+        }
+    }
+
+    /// Queue a transaction; Returns the transaction back if the client's queue is full, counting an
+    /// overflow for the client.
+    pub fn enqueue(&mut self, tx: Transaction) -> Option<Transaction> {
+        let queue = self.queues.entry(tx.client).or_default();
+
+        if queue.len() >= self.queue_cap.max(1) {
+            *self.overflows.entry(tx.client).or_default() += 1;
+            return Some(tx);
+        }
+
+        if queue.is_empty() {
+            self.ready.push_back(tx.client);
+        }
+        queue.push_back(tx);
+        self.queued += 1;
+
+        None
+    }
+
+    /// Take the next transaction of the next client in round-robin order;
+    pub fn next_transaction(&mut self) -> Option<Transaction> {
+        let client = self.ready.pop_front()?;
+        let queue = self.queues.get_mut(&client)?;
+        let tx = queue.pop_front();
+
+        if !queue.is_empty() {
+            self.ready.push_back(client);
+        }
+        self.queued -= 1;
+
+        tx
+    }
+
+    /// Process every queued transaction in round-robin order; Returns the number processed.
+    pub fn drain_into(&mut self, accounting: &mut Accounting) -> Result<usize, Error> {
+        let mut processed = 0;
+
+        while let Some(tx) = self.next_transaction() {
+            accounting.try_process_transaction(tx)?;
+            processed += 1;
+        }
+
+        Ok(processed)
+    }
+
+    /// Returns the number of queued transactions;
+    pub fn len(&self) -> usize {
+        self.queued
+    }
+
+    /// Returns true if no transactions are queued;
+    pub fn is_empty(&self) -> bool {
+        self.queued == 0
+    }
+
+    /// Returns the number of transactions that found their client's queue full, per client;
+    pub fn overflows(&self) -> &BTreeMap<ClientId, u64> {
+        &self.overflows
+    }
+}
+
+impl Accounting {
+    /// Read the CSV transactions file through a fair scheduler: rows are read in windows of `window`
+    /// rows, and each window is drained round-robin across clients before the next is read.
+    /// A row finding its client's queue full drains the queues early, so no row is dropped; Each
+    /// overflow increments the `tx_sim_scheduler_overflows_total` counter.
+    pub fn read_transactions_csv_file_fair(
+        &mut self,
+        file_path: PathBuf,
+        scheduler: &mut FairScheduler,
+        window: usize,
+    ) -> Result<(), Error> {
+        let source = self.file_source(&file_path);
+        let file = self.open_transactions_csv(file_path)?;
+        let mut rows = 0;
+
+        for row in self.transaction_rows(file)? {
+            let row = row.map_err(|row| row.error);
+            if let Some(mut transaction) = self.prepare_row(row, source.as_deref())? {
+                // Backpressure; Drain the queues before reading further;
+                while let Some(overflow) = scheduler.enqueue(transaction) {
+                    if let Some(metrics) = self.metrics.as_ref() {
+                        let client = overflow.client.to_string();
+                        metrics.counter(
+                            "tx_sim_scheduler_overflows_total",
+                            1,
+                            &[("client", &client)],
+                        );
+                    }
+
+                    scheduler.drain_into(self)?;
+                    transaction = overflow;
+                }
+            }
+
+            rows += 1;
+            if rows % window.max(1) == 0 {
+                scheduler.drain_into(self)?;
+            }
+        }
+
+        scheduler.drain_into(self)?;
+
+        Ok(())
+    }
+}
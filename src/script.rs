@@ -0,0 +1,246 @@
+use std::fmt;
+use std::io::Read;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use csv::{ReaderBuilder, StringRecord, Trim};
+
+use crate::*;
+
+/// A step of a scenario script: a transaction or a control directive;
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScriptStep {
+    /// Process a transaction, stamped with the script clock time;
+    Transaction(Transaction),
+    /// `set-clock, <timestamp>`: set the script clock;
+    SetClock(Timestamp),
+    /// `advance-clock, <seconds>`: advance the script clock;
+    AdvanceClock(Timestamp),
+    /// `expect-balance, <client>, <available>, <held>, <total>`: assert the client's balances;
+    ExpectBalance {
+        client: ClientId,
+        available: Amount,
+        held: Amount,
+        total: Amount,
+    },
+    /// `expect-locked, <client>, <true|false>`: assert the client's lock status;
+    ExpectLocked { client: ClientId, locked: bool },
+    /// `expect-rejected`: assert the previous transaction was ignored;
+    ExpectRejected,
+}
+
+/// A failed expectation of a scenario script;
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptFailure {
+    /// Line of the expectation in the script
+    pub line: u64,
+    pub message: String,
+}
+
+/// Outcome of running a scenario script;
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScriptReport {
+    /// Number of steps run
+    pub steps: usize,
+    /// Number of expectations checked
+    pub expectations: usize,
+    pub failures: Vec<ScriptFailure>,
+}
+
+impl ScriptReport {
+    /// Returns true if every expectation held;
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl fmt::Display for ScriptReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{} steps, {} expectations, {} failed",
+            self.steps,
+            self.expectations,
+            self.failures.len()
+        )?;
+        for failure in self.failures.iter() {
+            writeln!(f, "  line {}: {}", failure.line, failure.message)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Behavioral test suite mixing transactions with clock and expectation directives, run against a
+/// fresh ledger driven by a `MockClock` starting at zero; Written as csv without a header, one step
+/// per row, e.g. `deposit, 1, 1, 10.0`, `advance-clock, 86400` or `expect-locked, 1, true`. Lines
+/// starting with `#` are comments.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScenarioScript {
+    /// Steps and the line they were read from
+    pub steps: Vec<(u64, ScriptStep)>,
+}
+
+/// Parse the field of a script row, naming the line and field in the error;
+fn field<T: FromStr>(
+    record: &StringRecord,
+    line: u64,
+    index: usize,
+    name: &str,
+) -> Result<T, Error> {
+    let value = record.get(index).unwrap_or_default();
+    value
+        .parse()
+        .map_err(|_| Error::InvalidArgument(format!("line {}: invalid {} '{}'", line, name, value)))
+}
+
+impl ScenarioScript {
+    /// Read a scenario script file;
+    pub fn read_file(file_path: PathBuf) -> Result<Self, Error> {
+        Self::from_reader(std::fs::File::open(file_path)?)
+    }
+
+    /// Read a scenario script from any reader;
+    pub fn from_reader<R: Read>(rdr: R) -> Result<Self, Error> {
+        let mut rdr = ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .trim(Trim::All)
+            .comment(Some(b'#'))
+            .from_reader(rdr);
+        let mut steps = Vec::new();
+
+        for result in rdr.records() {
+            let record = result?;
+            let line = record.position().map_or(0, |position| position.line());
+
+            let step = match record.get(0).unwrap_or_default() {
+                "" => continue,
+                "set-clock" => ScriptStep::SetClock(field(&record, line, 1, "timestamp")?),
+                "advance-clock" => ScriptStep::AdvanceClock(field(&record, line, 1, "seconds")?),
+                "expect-balance" => ScriptStep::ExpectBalance {
+                    client: field(&record, line, 1, "client")?,
+                    available: field(&record, line, 2, "available")?,
+                    held: field(&record, line, 3, "held")?,
+                    total: field(&record, line, 4, "total")?,
+                },
+                "expect-locked" => ScriptStep::ExpectLocked {
+                    client: field(&record, line, 1, "client")?,
+                    locked: field(&record, line, 2, "locked")?,
+                },
+                "expect-rejected" => ScriptStep::ExpectRejected,
+                action => ScriptStep::Transaction(Transaction {
+                    r#type: action.parse().map_err(|_| {
+                        Error::InvalidArgument(format!(
+                            "line {}: unknown action '{}'",
+                            line, action
+                        ))
+                    })?,
+                    client: field(&record, line, 1, "client")?,
+                    tx: field(&record, line, 2, "tx")?,
+                    amount: match record.get(3).unwrap_or_default() {
+                        "" => None,
+                        _ => Some(field(&record, line, 3, "amount")?),
+                    },
+                    timestamp: None,
+                    subaccount: None,
+                    category: None,
+                    source: None,
+                }),
+            };
+
+            steps.push((line, step));
+        }
+
+        Ok(ScenarioScript { steps })
+    }
+
+    /// Run the script against a fresh ledger with the configuration;
+    pub fn run(&self, config: AccountingConfig) -> ScriptReport {
+        let clock = Arc::new(MockClock::new(0));
+        let mut accounting = Accounting::with_config(config);
+        accounting.set_clock(clock.clone());
+
+        let mut report = ScriptReport::default();
+        let mut last_outcome = None;
+
+        for (line, step) in self.steps.iter() {
+            report.steps += 1;
+
+            let failure = match step {
+                ScriptStep::Transaction(tx) => {
+                    let mut tx = tx.clone();
+                    tx.timestamp = Some(clock.now());
+                    last_outcome = Some(accounting.process_transaction(tx));
+                    continue;
+                }
+                ScriptStep::SetClock(now) => {
+                    clock.set(*now);
+                    continue;
+                }
+                ScriptStep::AdvanceClock(seconds) => {
+                    clock.advance(*seconds);
+                    continue;
+                }
+                ScriptStep::ExpectBalance {
+                    client,
+                    available,
+                    held,
+                    total,
+                } => {
+                    let actual = accounting
+                        .get_account(*client)
+                        .map_or((Amount::ZERO, Amount::ZERO, Amount::ZERO), |account| {
+                            (account.available, account.held, account.total)
+                        });
+                    if actual == (*available, *held, *total) {
+                        None
+                    } else {
+                        Some(format!(
+                            "expected client {} available {}, held {}, total {}, got {}, {}, {}",
+                            client,
+                            available,
+                            held,
+                            total,
+                            actual.0.normalize(),
+                            actual.1.normalize(),
+                            actual.2.normalize()
+                        ))
+                    }
+                }
+                ScriptStep::ExpectLocked { client, locked } => {
+                    let actual = accounting
+                        .get_account(*client)
+                        .is_some_and(|account| account.locked);
+                    if actual == *locked {
+                        None
+                    } else {
+                        Some(format!(
+                            "expected client {} locked {}, got {}",
+                            client, locked, actual
+                        ))
+                    }
+                }
+                ScriptStep::ExpectRejected => match last_outcome.as_ref() {
+                    Some(Err(_)) => None,
+                    Some(Ok(outcome)) => Some(format!(
+                        "expected the previous transaction to be rejected, got {:?}",
+                        outcome
+                    )),
+                    None => Some("expected a previous transaction".to_string()),
+                },
+            };
+
+            report.expectations += 1;
+            if let Some(message) = failure {
+                report.failures.push(ScriptFailure {
+                    line: *line,
+                    message,
+                });
+            }
+        }
+
+        report
+    }
+}
@@ -0,0 +1,120 @@
+#[cfg(feature = "io")]
+use std::fs::{File, OpenOptions};
+#[cfg(feature = "io")]
+use std::path::PathBuf;
+use std::str::FromStr;
+
+#[cfg(feature = "io")]
+use csv::{Writer, WriterBuilder};
+#[cfg(feature = "io")]
+use serde::Serialize;
+
+use crate::*;
+
+/// Durability guarantees for csv file outputs, trading throughput for crash-durability.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Durability {
+    /// Buffer writes and leave flushing to disk to the operating system;
+    #[default]
+    Buffered,
+    /// Fsync the file once all records are written;
+    FsyncOnClose,
+    /// Fsync the file after every N records, and once all records are written;
+    FsyncEvery(usize),
+}
+
+impl FromStr for Durability {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "buffered" => Ok(Durability::Buffered),
+            "close" => Ok(Durability::FsyncOnClose),
+            n => match n.parse::<usize>() {
+                Ok(n) if n > 0 => Ok(Durability::FsyncEvery(n)),
+                _ => Err(Error::InvalidArgument(format!(
+                    "unknown durability '{}'; expected 'buffered', 'close' or a record count",
+                    s
+                ))),
+            },
+        }
+    }
+}
+
+/// Buffered csv file writer applying a `Durability` policy;
+/// Call `close` to flush and, depending on the policy, fsync the remaining records.
+#[cfg(feature = "io")]
+pub struct CsvFileSink {
+    writer: Writer<File>,
+    /// Handle to the same file, used to fsync after the csv writer is flushed;
+    file: File,
+    durability: Durability,
+    pending: usize,
+}
+
+#[cfg(feature = "io")]
+impl CsvFileSink {
+    /// Create or truncate the file at the path;
+    pub fn create(file_path: PathBuf, durability: Durability) -> Result<Self, Error> {
+        let file = File::create(file_path)?;
+
+        Ok(CsvFileSink {
+            writer: Writer::from_writer(file.try_clone()?),
+            file,
+            durability,
+            pending: 0,
+        })
+    }
+
+    /// Open the file at the path for appending, creating it if missing;
+    /// The csv header is only written if the file is empty.
+    pub fn append(file_path: PathBuf, durability: Durability) -> Result<Self, Error> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(file_path)?;
+        let has_headers = file.metadata()?.len() == 0;
+
+        Ok(CsvFileSink {
+            writer: WriterBuilder::new()
+                .has_headers(has_headers)
+                .from_writer(file.try_clone()?),
+            file,
+            durability,
+            pending: 0,
+        })
+    }
+
+    /// Serialize a record, syncing to disk if the policy requires it;
+    pub fn write<T: Serialize>(&mut self, record: T) -> Result<(), Error> {
+        self.writer.serialize(record)?;
+        self.pending += 1;
+
+        if let Durability::FsyncEvery(n) = self.durability {
+            if self.pending >= n {
+                self.sync()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Flush buffered records and fsync the file;
+    pub fn sync(&mut self) -> Result<(), Error> {
+        self.writer.flush()?;
+        self.file.sync_data()?;
+        self.pending = 0;
+        Ok(())
+    }
+
+    /// Flush buffered records, syncing to disk unless the policy is `Durability::Buffered`;
+    pub fn close(mut self) -> Result<(), Error> {
+        self.writer.flush()?;
+
+        if self.durability != Durability::Buffered {
+            self.file.sync_all()?;
+        }
+
+        Ok(())
+    }
+}
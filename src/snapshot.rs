@@ -0,0 +1,212 @@
+#[cfg(feature = "io")]
+use std::fs::{self, File};
+#[cfg(feature = "io")]
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+/// Persisted processing state: accounts, including lifetime statistics, last activity and
+/// sub-accounts, recorded transactions and their dispute state, operator holds, reservations, open disputes, pending deposits, lock events, account closures, interest accrual state, account attributes and
+/// charged back amounts.
+pub struct Snapshot {
+    pub accounts: Vec<(Account, AccountStats)>,
+    pub transactions: Vec<(Transaction, TxState)>,
+    pub holds: Vec<Hold>,
+    pub reservations: Vec<Reservation>,
+    pub disputes: Vec<Dispute>,
+    pub last_activity: Vec<(ClientId, Timestamp)>,
+    pub subaccounts: Vec<(SubAccountId, Account)>,
+    pub pending_deposits: Vec<PendingDeposit>,
+    pub lock_events: Vec<LockEvent>,
+    pub closures: Vec<AccountClosure>,
+    pub interest_accrual: Vec<(ClientId, Timestamp, Amount)>,
+    pub interest_postings: Vec<Transaction>,
+    pub attributes: Vec<AccountAttribute>,
+    /// Amounts charged back per transaction, capping reversals;
+    pub charged_back: Vec<(TxId, Amount)>,
+    /// Clients flagged for manual review, whose transactions are quarantined;
+    pub flagged_accounts: Vec<ClientId>,
+    /// Audit trail of debit adjustments;
+    pub debit_adjustments: Vec<DebitAdjustment>,
+}
+
+impl Snapshot {
+    /// Write the snapshot to a binary file; The snapshot is written to a temporary file first and
+    /// renamed into place, so an existing snapshot is never left partially written. Returns
+    /// `Error::AlreadyInUse` if another process is writing or compacting the snapshot.
+    #[cfg(feature = "io")]
+    pub fn write_file(&self, file_path: PathBuf) -> Result<(), Error> {
+        let _lock = FileLock::acquire(Snapshot::lock_path(&file_path))?;
+        self.write_file_unlocked(file_path)
+    }
+
+    /// Returns the path of the lock guarding writes to a snapshot file, e.g. `snap.lock`;
+    pub fn lock_path(file_path: &Path) -> PathBuf {
+        file_path.with_extension("lock")
+    }
+
+    /// Write the snapshot to a binary file without taking its lock, e.g. while already holding it or
+    /// to override a lock known to be stale; See `Snapshot::write_file`.
+    #[cfg(feature = "io")]
+    pub fn write_file_unlocked(&self, file_path: PathBuf) -> Result<(), Error> {
+        let tmp_path = file_path.with_extension("tmp");
+
+        let mut wtr = BufWriter::new(File::create(&tmp_path)?);
+        bincode::serialize_into(&mut wtr, self)?;
+        wtr.flush()?;
+        wtr.get_ref().sync_all()?;
+
+        fs::rename(tmp_path, file_path)?;
+
+        Ok(())
+    }
+
+    /// Read a snapshot from a binary file;
+    #[cfg(feature = "io")]
+    pub fn read_file(file_path: PathBuf) -> Result<Self, Error> {
+        let rdr = BufReader::new(File::open(file_path)?);
+        Ok(bincode::deserialize_from(rdr)?)
+    }
+}
+
+impl Accounting {
+    /// Capture the current accounts and recorded transactions;
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            accounts: self
+                .accounts()
+                .map(|account| (account.clone(), account.stats.clone()))
+                .collect(),
+            transactions: self
+                .transactions
+                .values()
+                .map(|recorded| (recorded.transaction.clone(), recorded.state))
+                .collect(),
+            holds: self.holds().cloned().collect(),
+            reservations: self.reservations().cloned().collect(),
+            disputes: self.disputes().cloned().collect(),
+            last_activity: self
+                .last_activity
+                .iter()
+                .map(|(client, at)| (*client, *at))
+                .collect(),
+            subaccounts: self
+                .subaccounts
+                .values()
+                .flatten()
+                .map(|(name, account)| (name.clone(), account.clone()))
+                .collect(),
+            pending_deposits: self.pending_deposits().cloned().collect(),
+            lock_events: self.lock_events().to_vec(),
+            closures: self.closures().cloned().collect(),
+            interest_accrual: self
+                .interest_accrual
+                .iter()
+                .map(|(client, (day, accrued))| (*client, *day, *accrued))
+                .collect(),
+            interest_postings: self.interest_postings().to_vec(),
+            attributes: self.attributes().collect(),
+            charged_back: self
+                .transactions
+                .values()
+                .filter_map(|recorded| {
+                    recorded
+                        .charged_back
+                        .map(|amount| (recorded.transaction.tx, amount))
+                })
+                .collect(),
+            flagged_accounts: self.flagged_accounts().copied().collect(),
+            debit_adjustments: self.debit_adjustments().to_vec(),
+        }
+    }
+
+    /// Replace the current accounts and recorded transactions with the snapshot state;
+    pub fn restore_snapshot(&mut self, snapshot: Snapshot) {
+        self.clear();
+
+        for (mut account, stats) in snapshot.accounts {
+            account.stats = stats;
+            self.insert_account(account);
+        }
+
+        for (tx, state) in snapshot.transactions {
+            self.insert_transaction(tx, state);
+        }
+
+        for hold in snapshot.holds {
+            self.last_hold_id = self.last_hold_id.max(hold.id);
+            self.holds.insert(hold.id, hold);
+        }
+
+        for reservation in snapshot.reservations {
+            self.reservations.insert(reservation.tx, reservation);
+        }
+
+        for dispute in snapshot.disputes {
+            self.disputes.insert(dispute.tx, dispute);
+        }
+
+        self.last_activity.extend(snapshot.last_activity);
+
+        for (name, account) in snapshot.subaccounts {
+            self.subaccounts
+                .entry(account.client)
+                .or_default()
+                .insert(name, account);
+        }
+
+        // Lock reasons are not part of the account encoding, restore them from the lock events;
+        for event in snapshot.lock_events.iter() {
+            if let Some(account) = self.accounts.get_mut(&event.client) {
+                account.lock_reason = if event.locked {
+                    Some(event.reason)
+                } else {
+                    None
+                };
+            }
+        }
+        self.lock_events = snapshot.lock_events;
+
+        for deposit in snapshot.pending_deposits {
+            self.pending_deposits
+                .insert((deposit.clears_at, deposit.tx), deposit);
+        }
+
+        for closure in snapshot.closures {
+            self.closures.insert(closure.client, closure);
+        }
+
+        for (client, day, accrued) in snapshot.interest_accrual {
+            self.interest_accrual.insert(client, (day, accrued));
+        }
+        self.interest_postings = snapshot.interest_postings;
+
+        for attribute in snapshot.attributes {
+            self.set_attribute(attribute.client, &attribute.key, &attribute.value);
+        }
+
+        for (tx, amount) in snapshot.charged_back {
+            self.set_charged_back(tx, Some(amount));
+        }
+
+        self.flagged_accounts.extend(snapshot.flagged_accounts);
+        self.debit_adjustments = snapshot.debit_adjustments;
+    }
+
+    /// Write a snapshot of the current state to a binary file;
+    #[cfg(feature = "io")]
+    pub fn write_snapshot_file(&self, file_path: PathBuf) -> Result<(), Error> {
+        self.snapshot().write_file(file_path)
+    }
+
+    /// Replace the current state with a snapshot read from a binary file;
+    #[cfg(feature = "io")]
+    pub fn read_snapshot_file(&mut self, file_path: PathBuf) -> Result<(), Error> {
+        self.restore_snapshot(Snapshot::read_file(file_path)?);
+        Ok(())
+    }
+}
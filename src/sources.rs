@@ -0,0 +1,71 @@
+use std::collections::BTreeMap;
+#[cfg(feature = "io")]
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+/// Number of transactions processed from a source, to trace bad data back to its producer.
+pub struct SourceSummary {
+    #[serde(rename = "source")]
+    pub source: String,
+    #[serde(rename = "transactions")]
+    pub transactions: u64,
+    #[serde(rename = "applied")]
+    pub applied: u64,
+    /// Transactions ignored, e.g. for insufficient funds or referencing unknown transactions
+    #[serde(rename = "ignored")]
+    pub ignored: u64,
+}
+
+impl Accounting {
+    /// Returns the source transactions read from the file are tagged with, if
+    /// `AccountingConfig::tag_source` is set: the file name;
+    #[cfg(feature = "io")]
+    pub(crate) fn file_source(&self, file_path: &Path) -> Option<String> {
+        if !self.config.tag_source {
+            return None;
+        }
+
+        file_path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+    }
+
+    /// Count a processed transaction in the summary of its source, if it has one;
+    pub(crate) fn observe_source(&mut self, source: &str, applied: bool) {
+        let summary = self
+            .source_summaries
+            .entry(source.to_string())
+            .or_insert_with(|| SourceSummary {
+                source: source.to_string(),
+                ..Default::default()
+            });
+
+        summary.transactions += 1;
+        if applied {
+            summary.applied += 1;
+        } else {
+            summary.ignored += 1;
+        }
+    }
+
+    /// Returns the per-source summaries of the transactions processed, keyed by source;
+    pub fn source_summaries(&self) -> &BTreeMap<String, SourceSummary> {
+        &self.source_summaries
+    }
+
+    /// Write the per-source summaries to a csv file;
+    #[cfg(feature = "io")]
+    pub fn write_source_summaries_csv_file(&self, file_path: PathBuf) -> Result<(), Error> {
+        let mut sink = CsvFileSink::create(file_path, self.config.output_durability)?;
+
+        for summary in self.source_summaries.values() {
+            sink.write(summary)?;
+        }
+
+        sink.close()
+    }
+}
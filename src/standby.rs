@@ -0,0 +1,149 @@
+use log::{info, warn};
+
+use crate::*;
+
+#[derive(Debug, Clone, PartialEq)]
+/// Replication status of a standby, as reported by its admin endpoint.
+pub struct StandbyStatus {
+    /// Id of the change stream being replicated; `None` if no event has been applied yet
+    pub stream: Option<u64>,
+    /// Sequence number of the last applied event
+    pub last_seq: u64,
+    /// Seconds between the leader emitting the last applied event and the standby applying it;
+    /// `None` if no timed event has been applied yet
+    pub lag: Option<Timestamp>,
+    /// Seconds since the last event was applied; `None` if no event has been applied yet
+    pub idle: Option<Timestamp>,
+    /// Number of replicated accounts
+    pub accounts: usize,
+}
+
+/// A warm replica of a leader's account state, maintained from its change stream;
+/// Promoting the standby returns the replicated state so it can continue processing as the leader.
+#[derive(Debug, Default)]
+pub struct Standby {
+    accounting: Accounting,
+    stream: Option<u64>,
+    last_seq: u64,
+    last_event_at: Option<Timestamp>,
+    lag: Option<Timestamp>,
+}
+
+impl Standby {
+    /// Create a standby replicating onto the provided state, e.g. a restored snapshot;
+    pub fn new(accounting: Accounting) -> Self {
+        Standby {
+            accounting,
+            ..Default::default()
+        }
+    }
+
+    /// Apply the next change stream event; Events already applied are ignored, and a gap in the
+    /// sequence numbers returns an error since the replica can no longer be trusted.
+    /// An event of a new stream id, e.g. once the leader reconnects, restarts the sequence numbers.
+    pub fn apply(&mut self, event: &AccountEvent) -> Result<(), Error> {
+        if event.stream.is_some() && event.stream != self.stream {
+            if let Some(stream) = self.stream {
+                info!(
+                    "Switched from change stream {:x} at event {} to stream {:x}",
+                    stream,
+                    self.last_seq,
+                    event.stream.unwrap_or_default()
+                );
+            }
+            self.stream = event.stream;
+            self.last_seq = 0;
+        }
+
+        if event.seq <= self.last_seq {
+            warn!("Ignored replayed change stream event {}", event.seq);
+            return Ok(());
+        }
+
+        if event.seq != self.last_seq + 1 {
+            return Err(Error::ReplicationGap {
+                expected: self.last_seq + 1,
+                received: event.seq,
+            });
+        }
+
+        let now = self.accounting.now();
+        self.accounting.apply_account_event(event);
+        self.last_seq = event.seq;
+        self.last_event_at = Some(now);
+        self.lag = event.at.map(|at| now.saturating_sub(at));
+
+        Ok(())
+    }
+
+    /// Returns the replication status;
+    pub fn status(&self) -> StandbyStatus {
+        StandbyStatus {
+            stream: self.stream,
+            last_seq: self.last_seq,
+            lag: self.lag,
+            idle: self
+                .last_event_at
+                .map(|at| self.accounting.now().saturating_sub(at)),
+            accounts: self.accounting.accounts().count(),
+        }
+    }
+
+    /// Returns the replicated state;
+    pub fn accounting(&self) -> &Accounting {
+        &self.accounting
+    }
+
+    /// Swap in a new configuration for the replicated state, kept once promoted; See
+    /// `Accounting::reload_config`.
+    pub fn reload_config(&mut self, config: AccountingConfig) -> Result<Vec<&'static str>, Error> {
+        self.accounting.reload_config(config)
+    }
+
+    /// Check that the replicated state can continue as the leader; Holds, reservations and pending
+    /// deposits are not replicated, so held funds that the replicated disputes, and the holds,
+    /// reservations and pending deposits of the initial state, do not account for would stay held
+    /// forever once promoted. Returns an error for the first such client.
+    pub fn check_promotion(&self) -> Result<(), Error> {
+        let accounting = &self.accounting;
+
+        for account in accounting.accounts() {
+            let client = account.client;
+            let replicated = accounting
+                .disputes
+                .values()
+                .filter(|dispute| dispute.client == client)
+                .map(|dispute| dispute.amount)
+                .chain(accounting.client_holds(client).map(|hold| hold.amount))
+                .chain(
+                    accounting
+                        .reservations()
+                        .filter(|reservation| reservation.client == client)
+                        .map(|reservation| reservation.amount - reservation.filled),
+                )
+                .chain(
+                    accounting
+                        .pending_deposits()
+                        .filter(|deposit| deposit.client == client)
+                        .map(|deposit| deposit.amount),
+                )
+                .sum::<Amount>();
+
+            if account.held - replicated > accounting.config.amount_epsilon {
+                return Err(Error::UnreplicatedHeldFunds {
+                    client,
+                    held: account.held,
+                    replicated,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Stop replicating and return the replicated state to continue processing as the leader;
+    /// See `Standby::check_promotion`.
+    pub fn promote(self) -> Accounting {
+        self.accounting
+    }
+}
@@ -0,0 +1,296 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use crate::*;
+
+/// Number of text lines per statement page;
+const LINES_PER_PAGE: usize = 64;
+
+#[derive(Debug, Clone, PartialEq)]
+/// A deposit or withdrawal listed on a statement;
+pub struct StatementLine {
+    pub transaction: Transaction,
+    pub state: TxState,
+    /// Why the transaction was ignored, if it was; Without `AccountingConfig::track_rejected`, or
+    /// after restoring a snapshot, the reason is `rejected`.
+    pub ignored: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Account statement of a client for a period: balances, transactions, dispute history and period
+/// totals; `Display` renders it as text, and `write_pdf` as a PDF document.
+pub struct Statement {
+    pub account: Account,
+    /// Start of the period, inclusive; `None` from the first transaction
+    pub from: Option<Timestamp>,
+    /// End of the period, exclusive; `None` until the last transaction
+    pub to: Option<Timestamp>,
+    /// Deposits and withdrawals of the period, ordered by timestamp then transaction id
+    pub lines: Vec<StatementLine>,
+    /// Deposits of the period, excluding ignored ones
+    pub deposits: Amount,
+    /// Withdrawals of the period, excluding ignored ones
+    pub withdrawals: Amount,
+    /// Deposits of the period that were charged back
+    pub charged_back: Amount,
+}
+
+impl Statement {
+    /// Write the statement as a PDF document, one page per `LINES_PER_PAGE` text lines;
+    pub fn write_pdf<W: Write>(&self, writer: W) -> Result<(), Error> {
+        let text = self.to_string();
+        let lines: Vec<&str> = text.lines().collect();
+        write_text_pdf(writer, &lines)
+    }
+}
+
+impl fmt::Display for Statement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let account = &self.account;
+        let date = |at: Option<Timestamp>| at.map(format_date).unwrap_or_else(|| "-".to_string());
+
+        writeln!(f, "Account statement, client {}", account.client)?;
+        match (self.from, self.to) {
+            (None, None) => writeln!(f, "Period: all activity")?,
+            (from, to) => writeln!(f, "Period: {} until {}", date(from), date(to))?,
+        }
+        match (account.locked, account.lock_reason) {
+            (true, Some(reason)) => writeln!(f, "Status: locked ({})", reason)?,
+            (true, None) => writeln!(f, "Status: locked")?,
+            (false, _) => writeln!(f, "Status: active")?,
+        }
+
+        writeln!(f)?;
+        writeln!(f, "Balances")?;
+        writeln!(f, "  Available  {:>20}", format_amount(account.available))?;
+        writeln!(f, "  Held       {:>20}", format_amount(account.held))?;
+        writeln!(f, "  Total      {:>20}", format_amount(account.total))?;
+
+        writeln!(f)?;
+        writeln!(f, "Transactions ({})", self.lines.len())?;
+        for line in self.lines.iter() {
+            let tx = &line.transaction;
+            writeln!(
+                f,
+                "  {:<10}  tx {:<10} {:<10} {:>20}  {}",
+                date(tx.timestamp),
+                tx.tx,
+                tx.r#type.as_str(),
+                tx.amount.map(format_amount).unwrap_or_default(),
+                match line.ignored.as_ref() {
+                    Some(reason) => format!("ignored: {}", reason),
+                    None => line.state.to_string(),
+                }
+            )?;
+        }
+
+        let disputed: Vec<&StatementLine> = self
+            .lines
+            .iter()
+            .filter(|line| !matches!(line.state, TxState::Normal | TxState::Rejected))
+            .collect();
+        writeln!(f)?;
+        writeln!(f, "Dispute history ({})", disputed.len())?;
+        for line in disputed {
+            let tx = &line.transaction;
+            writeln!(
+                f,
+                "  {:<10}  tx {:<10} {:>20}  {}",
+                date(tx.timestamp),
+                tx.tx,
+                tx.amount.map(format_amount).unwrap_or_default(),
+                line.state
+            )?;
+        }
+
+        writeln!(f)?;
+        writeln!(f, "Period totals")?;
+        writeln!(f, "  Deposits      {:>20}", format_amount(self.deposits))?;
+        writeln!(f, "  Withdrawals   {:>20}", format_amount(self.withdrawals))?;
+        writeln!(
+            f,
+            "  Charged back  {:>20}",
+            format_amount(self.charged_back)
+        )?;
+
+        Ok(())
+    }
+}
+
+impl Accounting {
+    /// Returns the statement of a client for the period between `from` (inclusive) and `to`
+    /// (exclusive), judged by transaction timestamps; Transactions without a timestamp are only
+    /// listed without bounds. Returns `None` if the client has no account.
+    pub fn statement(
+        &self,
+        client: ClientId,
+        from: Option<Timestamp>,
+        to: Option<Timestamp>,
+    ) -> Option<Statement> {
+        let account = self.get_account(client)?.clone();
+        let within = |timestamp: Option<Timestamp>| match (timestamp, from, to) {
+            (_, None, None) => true,
+            (None, _, _) => false,
+            (Some(at), from, to) => {
+                from.is_none_or(|from| at >= from) && to.is_none_or(|to| at < to)
+            }
+        };
+
+        // The first rejection of a deposit or withdrawal id is the recorded one, later ones are duplicates;
+        let mut reasons: HashMap<TxId, &str> = HashMap::new();
+        for rejected in self.rejected.iter() {
+            if matches!(rejected.r#type, TxType::Deposit | TxType::Withdrawal) {
+                reasons.entry(rejected.tx).or_insert(&rejected.reason);
+            }
+        }
+
+        let mut lines: Vec<StatementLine> = self
+            .client_transactions(client)
+            .filter(|tx| within(tx.timestamp))
+            .map(|tx| {
+                let state = self.tx_state(tx.tx).unwrap_or_default();
+                let ignored = (state == TxState::Rejected)
+                    .then(|| reasons.get(&tx.tx).unwrap_or(&"rejected").to_string());
+
+                StatementLine {
+                    transaction: tx.clone(),
+                    state,
+                    ignored,
+                }
+            })
+            .collect();
+        lines.sort_by_key(|line| (line.transaction.timestamp, line.transaction.tx));
+
+        let total = |include: &dyn Fn(&StatementLine) -> bool| -> Amount {
+            lines
+                .iter()
+                .filter(|line| line.ignored.is_none() && include(line))
+                .filter_map(|line| line.transaction.amount)
+                .sum()
+        };
+        let deposits = total(&|line| line.transaction.r#type == TxType::Deposit);
+        let withdrawals = total(&|line| line.transaction.r#type == TxType::Withdrawal);
+        let charged_back = total(&|line| line.state == TxState::ChargedBack);
+
+        Some(Statement {
+            account,
+            from,
+            to,
+            lines,
+            deposits,
+            withdrawals,
+            charged_back,
+        })
+    }
+
+    /// Write the statement of a client for a period to a PDF file; See `statement`.
+    /// Requires the `pdf` feature.
+    pub fn write_statement_pdf_file(
+        &self,
+        client: ClientId,
+        from: Option<Timestamp>,
+        to: Option<Timestamp>,
+        file_path: PathBuf,
+    ) -> Result<(), Error> {
+        let statement = self
+            .statement(client, from, to)
+            .ok_or_else(|| Error::InvalidArgument(format!("no account for client {}", client)))?;
+
+        let mut writer = BufWriter::new(File::create(file_path)?);
+        statement.write_pdf(&mut writer)?;
+        writer.flush()?;
+
+        Ok(())
+    }
+}
+
+/// Write lines of text as a minimal PDF document in the built-in Courier font, so columns stay
+/// aligned; Characters outside printable ASCII are replaced with `?`.
+fn write_text_pdf<W: Write>(mut writer: W, lines: &[&str]) -> Result<(), Error> {
+    let pages: Vec<&[&str]> = lines.chunks(LINES_PER_PAGE).collect();
+    let pages = if pages.is_empty() {
+        vec![&[][..]]
+    } else {
+        pages
+    };
+
+    // Objects 1 to 3 are the catalog, page tree and font; Each page adds a page and a content object;
+    let page_ids: Vec<usize> = (0..pages.len()).map(|page| 4 + 2 * page).collect();
+    let mut objects = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            page_ids
+                .iter()
+                .map(|id| format!("{} 0 R", id))
+                .collect::<Vec<String>>()
+                .join(" "),
+            pages.len()
+        ),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Courier >>".to_string(),
+    ];
+
+    for (page, id) in pages.iter().zip(page_ids.iter()) {
+        let mut content = String::from("BT /F1 9 Tf 11 TL 40 800 Td\n");
+        for line in page.iter() {
+            content.push_str(&format!("({}) Tj T*\n", escape_pdf_text(line)));
+        }
+        content.push_str("ET");
+
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 595 842] \
+             /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+            id + 1
+        ));
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{}\nendstream",
+            content.len(),
+            content
+        ));
+    }
+
+    let mut pdf = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.push_str(&format!("{} 0 obj\n{}\nendobj\n", i + 1, object));
+    }
+
+    let xref = pdf.len();
+    pdf.push_str(&format!(
+        "xref\n0 {}\n0000000000 65535 f \n",
+        objects.len() + 1
+    ));
+    for offset in offsets {
+        pdf.push_str(&format!("{:010} 00000 n \n", offset));
+    }
+    pdf.push_str(&format!(
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+    ));
+
+    writer.write_all(pdf.as_bytes())?;
+
+    Ok(())
+}
+
+/// Escape a line for a PDF string literal;
+fn escape_pdf_text(line: &str) -> String {
+    let mut escaped = String::with_capacity(line.len());
+    for c in line.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            ' '..='~' => escaped.push(c),
+            _ => escaped.push('?'),
+        }
+    }
+
+    escaped
+}
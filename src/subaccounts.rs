@@ -0,0 +1,118 @@
+#[cfg(feature = "io")]
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+/// Balances of a named sub-account, as written by `write_subaccounts_csv_file`.
+pub struct SubAccountBalance {
+    #[serde(rename = "client")]
+    pub client: ClientId,
+    #[serde(rename = "subaccount")]
+    pub subaccount: SubAccountId,
+    #[serde(rename = "available")]
+    pub available: Amount,
+    #[serde(rename = "held")]
+    pub held: Amount,
+    #[serde(rename = "total")]
+    pub total: Amount,
+    #[serde(rename = "locked")]
+    pub locked: bool,
+}
+
+impl Accounting {
+    /// Returns the sub-account targeted by a transaction: named by deposits and withdrawals, and
+    /// inherited from the referenced transaction by disputes, resolves and chargebacks;
+    pub(crate) fn target_subaccount(&self, tx: &Transaction) -> Option<SubAccountId> {
+        if matches!(tx.r#type, TxType::Deposit | TxType::Withdrawal) {
+            tx.subaccount.clone()
+        } else {
+            self.transactions
+                .get(&tx.tx)
+                .and_then(|recorded| recorded.transaction.subaccount.clone())
+        }
+    }
+
+    /// Process a transaction against its sub-account, or against the client's funds not allocated to
+    /// any sub-account, and apply the balance changes to the client's rollup account;
+    pub(crate) fn dispatch_subaccount(&mut self, account: &mut Account, tx: &Transaction) {
+        let name = self.target_subaccount(tx);
+        let mut subaccounts = self.subaccounts.remove(&tx.client).unwrap_or_default();
+
+        let mut target = match &name {
+            Some(name) => subaccounts
+                .get(name)
+                .cloned()
+                .unwrap_or_else(|| Account::new(tx.client)),
+            None => {
+                let mut unallocated = Account::new(tx.client);
+                unallocated.available = account.available;
+                unallocated.held = account.held;
+                unallocated.total = account.total;
+
+                for subaccount in subaccounts.values() {
+                    unallocated.available -= subaccount.available;
+                    unallocated.held -= subaccount.held;
+                    unallocated.total -= subaccount.total;
+                }
+                unallocated
+            }
+        };
+
+        let before = target.clone();
+        self.dispatch(&mut target, tx);
+        let delta = before.diff(&target);
+
+        account.available += delta.available;
+        account.held += delta.held;
+        account.total += delta.total;
+        if let Some(locked) = delta.locked {
+            account.locked |= locked;
+        }
+        if tx.r#type == TxType::Withdrawal && delta.total < Amount::ZERO {
+            account.stats.observe_withdrawal(-delta.total);
+        }
+
+        if let Some(name) = name {
+            subaccounts.insert(name, target);
+        }
+        if !subaccounts.is_empty() {
+            self.subaccounts.insert(tx.client, subaccounts);
+        }
+    }
+
+    /// Iterate over the named sub-accounts of a client, ordered by name;
+    pub fn subaccounts(&self, client: ClientId) -> impl Iterator<Item = (&SubAccountId, &Account)> {
+        self.subaccounts.get(&client).into_iter().flatten()
+    }
+
+    /// Write the balances of every named sub-account to a csv file, ordered by client and name;
+    /// The accounts export holds the rollup of each client's sub-accounts.
+    #[cfg(feature = "io")]
+    pub fn write_subaccounts_csv_file(&self, file_path: PathBuf) -> Result<(), Error> {
+        let mut sink = CsvFileSink::create(file_path, self.config.output_durability)?;
+
+        let mut clients: Vec<&ClientId> = self.subaccounts.keys().collect();
+        clients.sort_unstable();
+
+        for client in clients {
+            for (name, subaccount) in self.subaccounts(*client) {
+                let mut subaccount = subaccount.clone();
+                subaccount.round_balances();
+
+                sink.write(SubAccountBalance {
+                    client: *client,
+                    subaccount: name.clone(),
+                    available: subaccount.available,
+                    held: subaccount.held,
+                    total: subaccount.total,
+                    locked: subaccount.locked,
+                })?;
+            }
+        }
+
+        sink.close()
+    }
+}
@@ -1,23 +1,35 @@
+// Most tests process transactions for their effect on the accounts, not their outcome;
+#![allow(unused_must_use)]
+
 use std::path::PathBuf;
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rust_decimal_macros::dec;
 
-use crate::{Accounting, Error, Transaction, TxType};
+use crate::*;
 
 #[test]
 fn test_account_deposit() -> Result<(), Error> {
     let mut accounting = Accounting::init();
 
     let client = 1;
-    let deposit_amount = 100.0;
+    let deposit_amount = dec!(100.0);
 
     accounting.process_transaction(Transaction {
         client,
         tx: 1,
         r#type: TxType::Deposit,
         amount: Some(deposit_amount),
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
     });
 
     // Assert the account is created when making a deposit;
-    assert_eq!(accounting.get_account(client).is_some(), true);
+    assert!(accounting.get_account(client).is_some());
 
     // Assert both the account total and available balance are set;
     assert_eq!(
@@ -37,14 +49,18 @@ fn test_account_withdrawal() -> Result<(), Error> {
     let mut accounting = Accounting::init();
 
     let client = 1;
-    let deposit_amount = 100.0;
-    let withdrawal_amount = 40.0;
+    let deposit_amount = dec!(100.0);
+    let withdrawal_amount = dec!(40.0);
 
     accounting.process_transaction(Transaction {
         client,
         tx: 1,
         r#type: TxType::Deposit,
         amount: Some(deposit_amount),
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
     });
 
     accounting.process_transaction(Transaction {
@@ -52,6 +68,10 @@ fn test_account_withdrawal() -> Result<(), Error> {
         tx: 2,
         r#type: TxType::Withdrawal,
         amount: Some(withdrawal_amount),
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
     });
 
     // Ensure account total is reduced by amount withdrawn
@@ -73,13 +93,17 @@ fn test_account_dispute() -> Result<(), Error> {
     let mut accounting = Accounting::init();
 
     let client = 1;
-    let deposit_amount = 100.0;
+    let deposit_amount = dec!(100.0);
 
     accounting.process_transaction(Transaction {
         client,
         tx: 1,
         r#type: TxType::Deposit,
         amount: Some(deposit_amount),
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
     });
 
     accounting.process_transaction(Transaction {
@@ -87,6 +111,10 @@ fn test_account_dispute() -> Result<(), Error> {
         tx: 1,
         r#type: TxType::Dispute,
         amount: None,
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
     });
 
     assert_eq!(
@@ -96,7 +124,7 @@ fn test_account_dispute() -> Result<(), Error> {
 
     assert_eq!(
         accounting.get_account(client).map(|a| a.available),
-        Some(0.0)
+        Some(dec!(0.0))
     );
 
     assert_eq!(
@@ -112,13 +140,17 @@ fn test_account_resolution() -> Result<(), Error> {
     let mut accounting = Accounting::init();
 
     let client = 1;
-    let deposit_amount = 100.0;
+    let deposit_amount = dec!(100.0);
 
     accounting.process_transaction(Transaction {
         client,
         tx: 1,
         r#type: TxType::Deposit,
         amount: Some(deposit_amount),
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
     });
 
     accounting.process_transaction(Transaction {
@@ -126,6 +158,10 @@ fn test_account_resolution() -> Result<(), Error> {
         tx: 1,
         r#type: TxType::Dispute,
         amount: None,
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
     });
 
     // Ensure account total available is reduced by disputed tx amount
@@ -136,7 +172,7 @@ fn test_account_resolution() -> Result<(), Error> {
 
     assert_eq!(
         accounting.get_account(client).map(|a| a.available),
-        Some(0.0)
+        Some(dec!(0.0))
     );
 
     assert_eq!(
@@ -149,6 +185,10 @@ fn test_account_resolution() -> Result<(), Error> {
         tx: 1,
         r#type: TxType::Resolve,
         amount: None,
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
     });
 
     // Ensure account total available is available after resolution;
@@ -162,7 +202,10 @@ fn test_account_resolution() -> Result<(), Error> {
         Some(deposit_amount)
     );
 
-    assert_eq!(accounting.get_account(client).map(|a| a.held), Some(0.0));
+    assert_eq!(
+        accounting.get_account(client).map(|a| a.held),
+        Some(dec!(0.0))
+    );
 
     Ok(())
 }
@@ -172,13 +215,17 @@ fn test_account_chargeback() -> Result<(), Error> {
     let mut accounting = Accounting::init();
 
     let client = 1;
-    let deposit_amount = 100.0;
+    let deposit_amount = dec!(100.0);
 
     accounting.process_transaction(Transaction {
         client,
         tx: 1,
         r#type: TxType::Deposit,
         amount: Some(deposit_amount),
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
     });
 
     accounting.process_transaction(Transaction {
@@ -186,6 +233,10 @@ fn test_account_chargeback() -> Result<(), Error> {
         tx: 1,
         r#type: TxType::Dispute,
         amount: None,
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
     });
 
     // Ensure account total available is reduced by disputed tx amount
@@ -196,7 +247,7 @@ fn test_account_chargeback() -> Result<(), Error> {
 
     assert_eq!(
         accounting.get_account(client).map(|a| a.available),
-        Some(0.0)
+        Some(dec!(0.0))
     );
 
     assert_eq!(
@@ -211,19 +262,29 @@ fn test_account_chargeback() -> Result<(), Error> {
         tx: 1,
         r#type: TxType::Chargeback,
         amount: None,
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
     });
 
     // Ensure account total available is available after resolution;
-    assert_eq!(accounting.get_account(client).map(|a| a.total), Some(0.0));
+    assert_eq!(
+        accounting.get_account(client).map(|a| a.total),
+        Some(dec!(0.0))
+    );
 
     // Ensure available funds are reduced by funds withdrawn after charge back;
     assert_eq!(
         accounting.get_account(client).map(|a| a.available),
-        Some(0.0)
+        Some(dec!(0.0))
     );
 
     // Ensure amount held after charge back is 0.0
-    assert_eq!(accounting.get_account(client).map(|a| a.held), Some(0.0));
+    assert_eq!(
+        accounting.get_account(client).map(|a| a.held),
+        Some(dec!(0.0))
+    );
 
     // Ensure account is locked;
     assert_eq!(accounting.get_account(client).map(|a| a.locked), Some(true));
@@ -271,3 +332,5658 @@ fn test_write_csv_dummy_transactions() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[test]
+fn test_account_peak_and_drawdown_stats() -> Result<(), Error> {
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        withdrawal_dispute_policy: WithdrawalDisputePolicy::Credit,
+        ..Default::default()
+    });
+
+    let client = 1;
+
+    accounting.process_transaction(Transaction {
+        client,
+        tx: 1,
+        r#type: TxType::Deposit,
+        amount: Some(dec!(100.0)),
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    });
+
+    accounting.process_transaction(Transaction {
+        client,
+        tx: 2,
+        r#type: TxType::Withdrawal,
+        amount: Some(dec!(60.0)),
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    });
+
+    accounting.process_transaction(Transaction {
+        client,
+        tx: 3,
+        r#type: TxType::Withdrawal,
+        amount: Some(dec!(10.0)),
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    });
+
+    accounting.process_transaction(Transaction {
+        client,
+        tx: 3,
+        r#type: TxType::Dispute,
+        amount: None,
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    });
+
+    let stats = accounting.get_account(client).map(|a| a.stats.clone());
+
+    // Ensure the peak total is the balance before withdrawals;
+    assert_eq!(stats.as_ref().map(|s| s.peak_total), Some(dec!(100.0)));
+
+    // Ensure the largest single withdrawal is tracked;
+    assert_eq!(
+        stats.as_ref().map(|s| s.largest_withdrawal),
+        Some(dec!(60.0))
+    );
+
+    // Ensure the drawdown is the drop from the peak total;
+    assert_eq!(stats.as_ref().map(|s| s.max_drawdown), Some(dec!(70.0)));
+
+    // Ensure the peak held reflects the disputed withdrawal;
+    assert_eq!(stats.as_ref().map(|s| s.peak_held), Some(dec!(10.0)));
+
+    Ok(())
+}
+
+#[test]
+fn test_daily_balance_series() -> Result<(), Error> {
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        track_daily_balances: true,
+        ..Default::default()
+    });
+
+    let client = 1;
+
+    // 2021-04-20 10:00:00 UTC
+    let day_one = 1_618_912_800;
+    let day_two = day_one + SECONDS_PER_DAY;
+
+    accounting.process_transaction(Transaction {
+        client,
+        tx: 1,
+        r#type: TxType::Deposit,
+        amount: Some(dec!(100.0)),
+        timestamp: Some(day_one),
+        subaccount: None,
+        category: None,
+        source: None,
+    });
+
+    accounting.process_transaction(Transaction {
+        client,
+        tx: 2,
+        r#type: TxType::Deposit,
+        amount: Some(dec!(50.0)),
+        timestamp: Some(day_one + 60),
+        subaccount: None,
+        category: None,
+        source: None,
+    });
+
+    accounting.process_transaction(Transaction {
+        client,
+        tx: 3,
+        r#type: TxType::Withdrawal,
+        amount: Some(dec!(25.0)),
+        timestamp: Some(day_two),
+        subaccount: None,
+        category: None,
+        source: None,
+    });
+
+    let file_path = std::env::temp_dir().join("tx_sim_daily_balances.csv");
+    accounting.write_daily_balances_csv_file(file_path.clone())?;
+
+    let output = std::fs::read_to_string(&file_path)?;
+    std::fs::remove_file(&file_path)?;
+
+    // Ensure only the end-of-day balance is written for each day;
+    assert_eq!(
+        output,
+        "client,date,available,held,total\n\
+         1,2021-04-20,150.0,0.0,150.0\n\
+         1,2021-04-21,125.0,0.0,125.0\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_client_id_remapper_enricher() -> Result<(), Error> {
+    let dir = std::env::temp_dir();
+    let map_path = dir.join("tx_sim_client_map.csv");
+    let transactions_path = dir.join("tx_sim_enrich_transactions.csv");
+
+    std::fs::write(&map_path, "old_client,new_client\n7,1\n")?;
+    std::fs::write(
+        &transactions_path,
+        "type,client,tx,amount\ndeposit,7,1,10.0\ndeposit,2,2,5.0\n",
+    )?;
+
+    let mut accounting = Accounting::init();
+    accounting.add_enricher(Box::new(ClientIdRemapper::from_csv_file(map_path.clone())?));
+    accounting.read_transactions_csv_file(transactions_path.clone())?;
+
+    std::fs::remove_file(&map_path)?;
+    std::fs::remove_file(&transactions_path)?;
+
+    // Ensure the legacy client id is mapped to the new client id;
+    assert!(accounting.get_account(7).is_none());
+    assert_eq!(accounting.get_account(1).map(|a| a.total), Some(dec!(10.0)));
+
+    // Ensure unmapped client ids are unchanged;
+    assert_eq!(accounting.get_account(2).map(|a| a.total), Some(dec!(5.0)));
+
+    Ok(())
+}
+
+#[test]
+fn test_remap_clients_merges_accounts() -> Result<(), Error> {
+    let mut accounting = Accounting::init();
+
+    accounting.process_transaction(Transaction {
+        client: 1,
+        tx: 1,
+        r#type: TxType::Deposit,
+        amount: Some(dec!(100.0)),
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    });
+
+    accounting.process_transaction(Transaction {
+        client: 2,
+        tx: 2,
+        r#type: TxType::Deposit,
+        amount: Some(dec!(50.0)),
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    });
+
+    accounting.remap_clients(vec![(2, 1)].into_iter().collect())?;
+
+    // Ensure the remapped account is merged into the existing account;
+    assert!(accounting.get_account(2).is_none());
+    assert_eq!(
+        accounting.get_account(1).map(|a| a.total),
+        Some(dec!(150.0))
+    );
+
+    // Ensure the transaction history index follows the remap;
+    let history: Vec<TxId> = accounting.client_transactions(1).map(|tx| tx.tx).collect();
+    assert_eq!(history, vec![1, 2]);
+    assert!(accounting.client_transactions(2).next().is_none());
+
+    // Ensure the remap is applied to new transactions for the old client id;
+    accounting.process_transaction(Transaction {
+        client: 2,
+        tx: 3,
+        r#type: TxType::Withdrawal,
+        amount: Some(dec!(20.0)),
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    });
+
+    assert!(accounting.get_account(2).is_none());
+    assert_eq!(
+        accounting.get_account(1).map(|a| a.total),
+        Some(dec!(130.0))
+    );
+
+    // Ensure disputes against transactions of the old client id apply to the new account;
+    accounting.process_transaction(Transaction {
+        client: 1,
+        tx: 2,
+        r#type: TxType::Dispute,
+        amount: None,
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    });
+
+    assert_eq!(accounting.get_account(1).map(|a| a.held), Some(dec!(50.0)));
+
+    Ok(())
+}
+
+#[test]
+fn test_remap_clients_moves_client_state() -> Result<(), Error> {
+    let tx = |r#type, client, tx, amount: Option<Amount>| Transaction {
+        r#type,
+        client,
+        tx,
+        amount,
+        timestamp: Some(SECONDS_PER_DAY),
+        subaccount: None,
+        category: None,
+        source: None,
+    };
+
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        track_daily_balances: true,
+        ..Default::default()
+    });
+
+    accounting.try_process_transaction(tx(TxType::Deposit, 1, 1, Some(dec!(100.0))))?;
+    accounting.try_process_transaction(Transaction {
+        subaccount: Some("savings".to_string()),
+        ..tx(TxType::Deposit, 1, 2, Some(dec!(20.0)))
+    })?;
+    accounting.try_process_transaction(tx(TxType::Deposit, 2, 3, Some(dec!(50.0))))?;
+    accounting.try_process_transaction(tx(TxType::Dispute, 1, 1, None))?;
+    accounting.try_process_transaction(tx(TxType::Reserve, 2, 4, Some(dec!(10.0))))?;
+    accounting.set_reserve(1, dec!(5.0))?;
+    accounting.set_attribute(1, "tier", "gold");
+    accounting.flag_account(1);
+
+    // Ensure a chain resolves to the final client id, whatever the iteration order;
+    accounting.remap_clients(vec![(1, 2), (2, 3)].into_iter().collect())?;
+
+    assert!(accounting.get_account(1).is_none());
+    assert!(accounting.get_account(2).is_none());
+    assert_eq!(
+        accounting.get_account(3).map(|a| a.total),
+        Some(dec!(170.0))
+    );
+
+    let disputes: Vec<(TxId, ClientId)> = accounting
+        .disputes()
+        .map(|dispute| (dispute.tx, dispute.client))
+        .collect();
+    assert_eq!(disputes, vec![(1, 3)]);
+
+    let reservations: Vec<(TxId, ClientId)> = accounting
+        .reservations()
+        .map(|reservation| (reservation.tx, reservation.client))
+        .collect();
+    assert_eq!(reservations, vec![(4, 3)]);
+
+    let subaccounts: Vec<(&SubAccountId, ClientId)> = accounting
+        .subaccounts(3)
+        .map(|(name, account)| (name, account.client))
+        .collect();
+    assert_eq!(subaccounts, vec![(&"savings".to_string(), 3)]);
+    assert_eq!(accounting.subaccounts(1).count(), 0);
+
+    let daily: Vec<(ClientId, Amount)> = accounting
+        .daily_balances
+        .values()
+        .map(|balance| (balance.client, balance.total))
+        .collect();
+    assert_eq!(daily, vec![(3, dec!(170.0))]);
+
+    assert_eq!(accounting.reserve_floor(3), dec!(5.0));
+    assert_eq!(accounting.attribute(3, "tier"), Some("gold"));
+    assert_eq!(accounting.flagged_accounts().collect::<Vec<_>>(), vec![&3]);
+
+    // Ensure the dispute is resolved against the merged account;
+    accounting.try_process_transaction(tx(TxType::Resolve, 3, 1, None))?;
+    assert_eq!(accounting.get_account(3).map(|a| a.held), Some(dec!(10.0)));
+
+    // Ensure cyclic mappings are refused before anything is moved;
+    let mut cyclic = Accounting::init();
+    cyclic.try_process_transaction(tx(TxType::Deposit, 1, 1, Some(dec!(10.0))))?;
+    assert!(matches!(
+        cyclic.remap_clients(vec![(1, 2), (2, 1)].into_iter().collect()),
+        Err(Error::InvalidArgument(_))
+    ));
+    assert_eq!(cyclic.get_account(1).map(|a| a.total), Some(dec!(10.0)));
+
+    Ok(())
+}
+
+#[test]
+fn test_strict_mode_validations() -> Result<(), Error> {
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        strictness: Strictness::Strict,
+        ..Default::default()
+    });
+
+    accounting.try_process_transaction(Transaction {
+        client: 1,
+        tx: 1,
+        r#type: TxType::Deposit,
+        amount: Some(dec!(100.0)),
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    })?;
+
+    // Ensure duplicate transaction ids are rejected;
+    assert!(matches!(
+        accounting.try_process_transaction(Transaction {
+            client: 1,
+            tx: 1,
+            r#type: TxType::Deposit,
+            amount: Some(dec!(100.0)),
+            timestamp: None,
+            subaccount: None,
+            category: None,
+            source: None,
+        }),
+        Err(Error::DuplicateTx(1))
+    ));
+
+    // Ensure deposits without an amount are rejected;
+    assert!(matches!(
+        accounting.try_process_transaction(Transaction {
+            client: 1,
+            tx: 2,
+            r#type: TxType::Deposit,
+            amount: None,
+            timestamp: None,
+            subaccount: None,
+            category: None,
+            source: None,
+        }),
+        Err(Error::MissingAmount(2))
+    ));
+
+    // Ensure disputes of another client's transaction are rejected;
+    assert!(matches!(
+        accounting.try_process_transaction(Transaction {
+            client: 2,
+            tx: 1,
+            r#type: TxType::Dispute,
+            amount: None,
+            timestamp: None,
+            subaccount: None,
+            category: None,
+            source: None,
+        }),
+        Err(Error::ClientMismatch { client: 2, tx: 1 })
+    ));
+
+    // Ensure unknown transaction types are rejected;
+    assert!(matches!(
+        accounting.try_process_transaction(Transaction {
+            client: 1,
+            tx: 3,
+            r#type: TxType::Unknown,
+            amount: None,
+            timestamp: None,
+            subaccount: None,
+            category: None,
+            source: None,
+        }),
+        Err(Error::UnknownTxType(3))
+    ));
+
+    // Ensure rejected transactions did not modify the account;
+    assert_eq!(
+        accounting.get_account(1).map(|a| a.total),
+        Some(dec!(100.0))
+    );
+    assert_eq!(accounting.get_account(1).map(|a| a.held), Some(dec!(0.0)));
+
+    Ok(())
+}
+
+#[test]
+fn test_write_gzip_csv_dummy_transactions() -> Result<(), Error> {
+    let file_path = std::env::temp_dir().join("tx_sim_transactions.csv.gz");
+
+    let transactions = Accounting::generate_dummy_transactions(100, 10)?;
+    Accounting::write_transactions_gzip_csv_file(transactions, file_path.clone())?;
+
+    // Ensure the compressed file decodes back into every transaction;
+    let decoder = flate2::read::GzDecoder::new(std::fs::File::open(&file_path)?);
+    let rows = csv::Reader::from_reader(decoder)
+        .deserialize::<Transaction>()
+        .collect::<Result<Vec<Transaction>, csv::Error>>()?;
+
+    std::fs::remove_file(&file_path)?;
+
+    assert_eq!(rows.len(), 100);
+
+    Ok(())
+}
+
+#[cfg(feature = "parquet")]
+#[test]
+fn test_write_parquet_dummy_transactions() -> Result<(), Error> {
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    use parquet::record::Field;
+    use std::convert::TryInto;
+
+    let file_path = std::env::temp_dir().join("tx_sim_transactions.parquet");
+
+    let mut transactions = Accounting::generate_dummy_transactions(100, 10)?;
+    transactions[0].amount = Some(dec!(0.1234));
+    transactions[0].subaccount = Some("savings".to_string());
+    transactions[0].source = Some("bank.csv".to_string());
+    transactions[1].category = Some("payroll".to_string());
+    Accounting::write_transactions_parquet_file(transactions, file_path.clone())?;
+
+    let reader = SerializedFileReader::new(std::fs::File::open(&file_path)?)?;
+    let num_rows = reader.metadata().file_metadata().num_rows();
+    let mut rows = reader.get_row_iter(None)?;
+    let first = rows.next().unwrap()?;
+    let second = rows.next().unwrap()?;
+
+    std::fs::remove_file(&file_path)?;
+
+    assert_eq!(num_rows, 100);
+
+    // Ensure amounts are stored as exact decimals;
+    let amount = first
+        .get_column_iter()
+        .find(|(name, _)| name.as_str() == "amount")
+        .map(|(_, field)| field.clone());
+    match amount {
+        Some(Field::Decimal(decimal)) => {
+            assert_eq!(decimal.scale(), 4);
+            assert_eq!(
+                i128::from_be_bytes(decimal.data().try_into().unwrap()),
+                1234
+            );
+        }
+        other => panic!("unexpected amount {:?}", other),
+    }
+
+    // Ensure the sub-account, category and source columns are kept, with nulls for missing values;
+    let strings = |row: &parquet::record::Row| -> Vec<(String, Field)> {
+        row.get_column_iter()
+            .filter(|(name, _)| ["subaccount", "category", "source"].contains(&name.as_str()))
+            .map(|(name, field)| (name.clone(), field.clone()))
+            .collect()
+    };
+    assert_eq!(
+        strings(&first),
+        vec![
+            ("subaccount".to_string(), Field::Str("savings".to_string())),
+            ("category".to_string(), Field::Null),
+            ("source".to_string(), Field::Str("bank.csv".to_string())),
+        ]
+    );
+    assert_eq!(
+        strings(&second)[1],
+        ("category".to_string(), Field::Str("payroll".to_string()))
+    );
+
+    Ok(())
+}
+
+#[cfg(feature = "parquet")]
+#[test]
+fn test_write_daily_balances_parquet_file() -> Result<(), Error> {
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    use parquet::record::Field;
+    use std::convert::TryInto;
+
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        track_daily_balances: true,
+        ..Default::default()
+    });
+
+    // 2021-04-20 10:00:00 UTC
+    let day_one = 1_618_912_800;
+    for (tx, amount, timestamp) in [
+        (1, dec!(100.0), day_one),
+        (2, dec!(0.1234), day_one + SECONDS_PER_DAY),
+    ] {
+        accounting.process_transaction(Transaction {
+            client: 1,
+            tx,
+            r#type: TxType::Deposit,
+            amount: Some(amount),
+            timestamp: Some(timestamp),
+            subaccount: None,
+            category: None,
+            source: None,
+        });
+    }
+
+    let file_path = std::env::temp_dir().join("tx_sim_daily_balances.parquet");
+    accounting.write_daily_balances_parquet_file(file_path.clone())?;
+
+    let reader = SerializedFileReader::new(std::fs::File::open(&file_path)?)?;
+    let rows = reader.get_row_iter(None)?.collect::<Result<Vec<_>, _>>()?;
+
+    std::fs::remove_file(&file_path)?;
+
+    assert_eq!(rows.len(), 2);
+
+    // Ensure dates are days since the epoch and balances exact decimals;
+    let field = |name: &str| {
+        rows[1]
+            .get_column_iter()
+            .find(|(column, _)| column.as_str() == name)
+            .map(|(_, field)| field.clone())
+    };
+    assert_eq!(
+        field("date"),
+        Some(Field::Date((day_one / SECONDS_PER_DAY + 1) as i32))
+    );
+    match field("total") {
+        Some(Field::Decimal(decimal)) => assert_eq!(
+            i128::from_be_bytes(decimal.data().try_into().unwrap()),
+            1_001_234
+        ),
+        other => panic!("unexpected total {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "arrow")]
+#[test]
+fn test_record_batches() -> Result<(), Error> {
+    use arrow_array::{Array, Decimal128Array};
+
+    let mut accounting = Accounting::init();
+    for tx in Accounting::generate_dummy_transactions(100, 10)? {
+        accounting.process_transaction(tx);
+    }
+
+    let batch = accounting.to_record_batch()?;
+    assert_eq!(batch.num_rows(), accounting.accounts().count());
+    assert_eq!(batch.schema().field(3).name(), "total");
+
+    // Ensure balances are exported as exact decimals;
+    let totals = batch
+        .column(3)
+        .as_any()
+        .downcast_ref::<Decimal128Array>()
+        .unwrap();
+    assert_eq!(totals.scale(), 4);
+    let sum: Amount = accounting.accounts().map(|a| a.total).sum();
+    assert_eq!(
+        Decimal::from_i128_with_scale(totals.iter().flatten().sum::<i128>(), 4),
+        sum
+    );
+
+    let batch = accounting.transactions_to_record_batch()?;
+    assert_eq!(batch.num_rows(), accounting.transactions().count());
+    assert_eq!(batch.column(4).null_count(), batch.num_rows());
+
+    // Ensure the dense batch numbers the tx ids sequentially;
+    let dense = accounting.dense_transactions_to_record_batch(&accounting.dense_tx_ids())?;
+    #[cfg(not(feature = "tx-id-u64"))]
+    type TxIdArray = arrow_array::UInt32Array;
+    #[cfg(feature = "tx-id-u64")]
+    type TxIdArray = arrow_array::UInt64Array;
+    let ids = dense
+        .column(2)
+        .as_any()
+        .downcast_ref::<TxIdArray>()
+        .unwrap();
+    assert!(ids.values().iter().copied().eq(1..=ids.len() as TxId));
+
+    Ok(())
+}
+
+#[test]
+fn test_generate_adversarial_rows() -> Result<(), Error> {
+    let rows = Accounting::generate_adversarial_rows(70, 10);
+
+    // Ensure every adversarial case is produced;
+    for case in AdversarialCase::ALL.iter() {
+        assert!(rows.iter().any(|row| row.case == *case));
+    }
+
+    // Ensure malformed rows fail to parse while all other rows parse;
+    for row in rows.iter() {
+        let data = format!("type,client,tx,amount\n{}\n", row.line);
+        let parsed = csv::Reader::from_reader(data.as_bytes())
+            .deserialize::<Transaction>()
+            .collect::<Result<Vec<Transaction>, csv::Error>>();
+
+        assert_eq!(parsed.is_err(), row.case.is_malformed(), "{:?}", row);
+    }
+
+    // Ensure every collision reuses the id of a recorded deposit;
+    let mut accounting = Accounting::init();
+    let mut collisions = 0;
+    for row in rows.iter() {
+        let data = format!("type,client,tx,amount\n{}\n", row.line);
+        let tx = match csv::Reader::from_reader(data.as_bytes())
+            .deserialize::<Transaction>()
+            .next()
+        {
+            Some(Ok(tx)) => tx,
+            _ => continue,
+        };
+
+        if row.case == AdversarialCase::TxIdCollision {
+            if let Err(Error::DuplicateTx(_)) = accounting.validate_strict(&tx) {
+                collisions += 1;
+            }
+        }
+        let _ = accounting.process_transaction(tx);
+    }
+    assert_eq!(collisions, 70 / AdversarialCase::ALL.len());
+
+    // Ensure lenient processing of the adversarial file skips malformed rows without failing;
+    let file_path = std::env::temp_dir().join("tx_sim_adversarial.csv");
+    Accounting::write_adversarial_csv_file(&rows, file_path.clone())?;
+
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        strictness: Strictness::Lenient,
+        ..Default::default()
+    });
+    let result = accounting.read_transactions_csv_file(file_path.clone());
+
+    std::fs::remove_file(&file_path)?;
+
+    assert!(result.is_ok());
+
+    Ok(())
+}
+
+/// Number of random values serialized in each round-trip property test;
+const ROUND_TRIP_ITERATIONS: usize = 1000;
+
+/// Serialize values to csv and deserialize them back;
+fn csv_round_trip<T>(values: &[T]) -> Result<Vec<T>, Error>
+where
+    T: serde::Serialize + serde::de::DeserializeOwned,
+{
+    let mut wtr = csv::Writer::from_writer(vec![]);
+    for value in values {
+        wtr.serialize(value)?;
+    }
+    let data = wtr.into_inner().map_err(Box::new)?;
+
+    Ok(csv::Reader::from_reader(data.as_slice())
+        .deserialize::<T>()
+        .collect::<Result<Vec<T>, csv::Error>>()?)
+}
+
+#[test]
+fn test_transaction_round_trip() -> Result<(), Error> {
+    let mut rng = StdRng::seed_from_u64(699);
+
+    let transactions: Vec<Transaction> = (0..ROUND_TRIP_ITERATIONS)
+        .map(|_| random_transaction(&mut rng))
+        .collect();
+
+    // Ensure csv round trips are loss-free;
+    assert_eq!(csv_round_trip(&transactions)?, transactions);
+
+    // Ensure json round trips are loss-free;
+    for tx in transactions.iter() {
+        let json = serde_json::to_string(tx).expect("serialize transaction");
+        let parsed: Transaction = serde_json::from_str(&json).expect("deserialize transaction");
+        assert_eq!(&parsed, tx);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_account_round_trip() -> Result<(), Error> {
+    let mut rng = StdRng::seed_from_u64(699);
+
+    let accounts: Vec<Account> = (0..ROUND_TRIP_ITERATIONS)
+        .map(|_| random_account(&mut rng))
+        .collect();
+
+    // Ensure csv round trips are loss-free;
+    assert_eq!(csv_round_trip(&accounts)?, accounts);
+
+    // Ensure json round trips are loss-free;
+    for account in accounts.iter() {
+        let json = serde_json::to_string(account).expect("serialize account");
+        let parsed: Account = serde_json::from_str(&json).expect("deserialize account");
+        assert_eq!(&parsed, account);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_mock_clock_stamps_transactions() -> Result<(), Error> {
+    let clock = MockClock::new(1_618_912_800);
+
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        track_daily_balances: true,
+        stamp_missing_timestamps: true,
+        ..Default::default()
+    });
+    accounting.set_clock(Arc::new(clock.clone()));
+
+    assert_eq!(accounting.now(), 1_618_912_800);
+
+    accounting.process_transaction(Transaction {
+        client: 1,
+        tx: 1,
+        r#type: TxType::Deposit,
+        amount: Some(dec!(100.0)),
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    });
+
+    // Ensure the clock can be advanced after it is handed to the processor;
+    clock.advance(SECONDS_PER_DAY);
+
+    accounting.process_transaction(Transaction {
+        client: 1,
+        tx: 2,
+        r#type: TxType::Deposit,
+        amount: Some(dec!(50.0)),
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    });
+
+    // Ensure both transactions were stamped on separate days;
+    assert_eq!(accounting.now(), 1_618_912_800 + SECONDS_PER_DAY);
+
+    let file_path = std::env::temp_dir().join("tx_sim_clock_daily_balances.csv");
+    accounting.write_daily_balances_csv_file(file_path.clone())?;
+
+    let output = std::fs::read_to_string(&file_path)?;
+    std::fs::remove_file(&file_path)?;
+
+    assert_eq!(output.lines().count(), 3);
+
+    Ok(())
+}
+
+#[test]
+fn test_precision_policy() -> Result<(), Error> {
+    // Ensure amounts within four decimal places are unchanged;
+    assert_eq!(
+        PrecisionPolicy::Reject.apply(dec!(1.2345)),
+        Some(dec!(1.2345))
+    );
+
+    assert_eq!(PrecisionPolicy::Reject.apply(dec!(1.23456)), None);
+    assert_eq!(
+        PrecisionPolicy::Truncate.apply(dec!(1.23459)),
+        Some(dec!(1.2345))
+    );
+    assert_eq!(
+        PrecisionPolicy::RoundHalfEven.apply(dec!(1.23456)),
+        Some(dec!(1.2346))
+    );
+
+    // Ensure ties are rounded to the nearest even digit;
+    assert_eq!(
+        PrecisionPolicy::RoundHalfEven.apply(dec!(1.23445)),
+        Some(dec!(1.2344))
+    );
+    assert_eq!(
+        PrecisionPolicy::RoundHalfEven.apply(dec!(1.23435)),
+        Some(dec!(1.2344))
+    );
+    assert_eq!(
+        PrecisionPolicy::RoundHalfEven.apply(dec!(-1.23435)),
+        Some(dec!(-1.2344))
+    );
+
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        precision_policy: PrecisionPolicy::Reject,
+        ..Default::default()
+    });
+
+    accounting.process_transaction(Transaction {
+        client: 1,
+        tx: 1,
+        r#type: TxType::Deposit,
+        amount: Some(dec!(100.12345678)),
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    });
+
+    // Ensure the rejected transaction is reported and not processed;
+    assert!(accounting.get_account(1).is_none());
+    assert_eq!(accounting.precision_adjustments().len(), 1);
+    assert_eq!(accounting.precision_adjustments()[0].adjusted, None);
+
+    Ok(())
+}
+
+#[test]
+fn test_tx_type_display_from_str() -> Result<(), Error> {
+    // Ensure every transaction type round trips through its csv name;
+    for r#type in TxType::ALL.iter() {
+        assert_eq!(r#type.to_string().parse::<TxType>()?, *r#type);
+    }
+
+    assert_eq!("withdrawal".parse::<TxType>()?, TxType::Withdrawal);
+    assert!(matches!(
+        "depositt".parse::<TxType>(),
+        Err(Error::InvalidTxType(_))
+    ));
+
+    // Every type moving funds into or out of the account, and none only moving them to or from held;
+    let moving: Vec<TxType> = TxType::ALL
+        .iter()
+        .copied()
+        .filter(TxType::is_funds_movement)
+        .collect();
+    assert_eq!(
+        moving,
+        vec![
+            TxType::Deposit,
+            TxType::Withdrawal,
+            TxType::Fill,
+            TxType::Reversal,
+            TxType::Adjustment
+        ]
+    );
+    assert!(!TxType::Unknown.is_funds_movement());
+    assert_eq!(
+        CustomTxType::new("fee").map(|fee| TxType::Custom(fee).is_funds_movement()),
+        Some(false)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_account_diff_and_approx_eq() -> Result<(), Error> {
+    let mut account = Account::new(1);
+    account.available = dec!(100.0);
+    account.total = dec!(100.0);
+
+    let mut other = account.clone();
+    other.available = dec!(99.99999999);
+    other.total = dec!(99.99999999);
+
+    // Ensure dust is within tolerance;
+    assert!(account.approx_eq(&other, dec!(0.0001)));
+    assert!(!account.approx_eq(&other, dec!(0.0)));
+
+    other.held = dec!(10.0);
+    other.total = dec!(110.0);
+    other.locked = true;
+
+    let delta = account.diff(&other);
+
+    assert_eq!(delta.held, dec!(10.0));
+    assert_eq!(delta.total, dec!(10.0));
+    assert_eq!(delta.locked, Some(true));
+    assert!(!delta.is_empty(dec!(0.0001)));
+
+    Ok(())
+}
+
+#[test]
+fn test_error_codes() -> Result<(), Error> {
+    let error = Error::DuplicateTx(1);
+    assert_eq!(error.code(), "E2003");
+    assert_eq!(error.kind(), ErrorKind::Transaction);
+
+    let error = "depositt".parse::<TxType>().unwrap_err();
+    assert_eq!(error.code(), "E1003");
+    assert_eq!(error.kind(), ErrorKind::Parse);
+
+    let error = Error::from(std::io::Error::from(std::io::ErrorKind::NotFound));
+    assert_eq!(error.code(), "E3001");
+    assert_eq!(error.kind(), ErrorKind::Io);
+
+    Ok(())
+}
+
+#[test]
+fn test_csv_file_sink_durability() -> Result<(), Error> {
+    assert_eq!("close".parse::<Durability>()?, Durability::FsyncOnClose);
+    assert_eq!("100".parse::<Durability>()?, Durability::FsyncEvery(100));
+    assert!("0".parse::<Durability>().is_err());
+
+    let file_path = std::env::temp_dir().join("tx_sim_sink.csv");
+    let mut sink = CsvFileSink::create(file_path.clone(), Durability::FsyncEvery(2))?;
+
+    for account in [Account::new(1), Account::new(2), Account::new(3)].iter() {
+        sink.write(account)?;
+    }
+
+    // Ensure records are synced to the file every two records, before the sink is closed;
+    let synced = std::fs::read_to_string(&file_path)?;
+    assert_eq!(synced.lines().count(), 3);
+
+    sink.close()?;
+
+    let output = std::fs::read_to_string(&file_path)?;
+    std::fs::remove_file(&file_path)?;
+
+    assert_eq!(output.lines().count(), 4);
+
+    Ok(())
+}
+
+#[test]
+fn test_journal_rotation_and_compaction() -> Result<(), Error> {
+    let dir = std::env::temp_dir().join("tx_sim_journal_rotation");
+    let snapshot_path = dir.join("snap.bin");
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let clock = MockClock::new(1_618_912_800);
+    let mut accounting = Accounting::init();
+    accounting.set_clock(Arc::new(clock.clone()));
+
+    let journal = Journal::open(
+        dir.clone(),
+        JournalConfig {
+            max_segment_bytes: 72,
+            max_segment_age: Some(SECONDS_PER_DAY),
+            ..Default::default()
+        },
+        accounting.now(),
+    )?;
+    accounting.attach_journal(journal)?;
+
+    for tx in 1..=4 {
+        accounting.try_process_transaction(Transaction {
+            client: 1,
+            tx,
+            r#type: TxType::Deposit,
+            amount: Some(dec!(10.0)),
+            timestamp: None,
+            subaccount: None,
+            category: None,
+            source: None,
+        })?;
+    }
+
+    // Ensure a segment older than the maximum age is rotated;
+    clock.advance(SECONDS_PER_DAY);
+    accounting.try_process_transaction(Transaction {
+        client: 1,
+        tx: 1,
+        r#type: TxType::Dispute,
+        amount: None,
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    })?;
+
+    let journal = accounting.detach_journal().expect("journal is attached");
+    let segments = journal.segments().to_vec();
+    drop(journal);
+
+    // Ensure each size-limited segment holds the transactions that fit, and the index is persisted;
+    assert_eq!(
+        segments.iter().map(|s| s.records).collect::<Vec<u64>>(),
+        vec![2, 2, 1]
+    );
+    assert_eq!(segments[0].file, "journal.0001.log");
+    assert_eq!(Journal::read_index(&dir)?.len(), 3);
+
+    // Ensure compaction collapses the closed segments into a snapshot matching the processed state;
+    let summary = Journal::compact(&dir, snapshot_path.clone(), None, false, false)?;
+    assert_eq!(summary.transactions, 4);
+
+    let mut restored = Accounting::init();
+    restored.read_snapshot_file(snapshot_path.clone())?;
+
+    assert_eq!(restored.get_account(1).map(|a| a.total), Some(dec!(40.0)));
+    assert_eq!(restored.get_account(1).map(|a| a.held), Some(dec!(0.0)));
+    assert_eq!(Journal::read_index(&dir)?.len(), 1);
+    assert!(!dir.join("journal.0001.log").exists());
+
+    // Ensure compacting the open segment applies the remaining dispute onto the new snapshot;
+    let summary = Journal::compact(&dir, snapshot_path.clone(), None, true, false)?;
+    assert_eq!(summary.transactions, 1);
+
+    restored.read_snapshot_file(snapshot_path)?;
+
+    assert_eq!(restored.get_account(1).map(|a| a.held), Some(dec!(10.0)));
+    assert!(Journal::read_index(&dir)?.is_empty());
+
+    std::fs::remove_dir_all(&dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_journal_compaction_replays_config_and_operator_actions() -> Result<(), Error> {
+    let dir = std::env::temp_dir().join("tx_sim_journal_replay");
+    let snapshot_path = dir.join("snap.bin");
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let config = AccountingConfig {
+        max_open_disputes: Some(1),
+        quarantine_flagged: true,
+        ..Default::default()
+    };
+    let clock = MockClock::new(1_618_912_800);
+    let mut accounting = Accounting::with_config(config.clone());
+    accounting.set_clock(Arc::new(clock.clone()));
+    accounting.attach_journal(Journal::open(
+        dir.clone(),
+        JournalConfig::default(),
+        accounting.now(),
+    )?)?;
+
+    // Replayed transactions without a timestamp would take the time of the compaction;
+    let tx = |r#type, client, tx, amount| Transaction {
+        client,
+        tx,
+        r#type,
+        amount,
+        timestamp: Some(1_618_912_800 + tx as Timestamp),
+        subaccount: None,
+        category: None,
+        source: None,
+    };
+
+    for (client, id, amount) in [(1, 1, dec!(100)), (1, 2, dec!(50)), (2, 3, dec!(80))] {
+        accounting.try_process_transaction(tx(TxType::Deposit, client, id, Some(amount)))?;
+    }
+    for (client, id, amount) in [(3, 4, dec!(30)), (4, 5, dec!(20))] {
+        accounting.try_process_transaction(tx(TxType::Deposit, client, id, Some(amount)))?;
+    }
+
+    // Exceeding the dispute cap ignores the dispute, and flagging quarantines the later deposit;
+    accounting.try_process_transaction(tx(TxType::Dispute, 1, 1, None))?;
+    accounting.try_process_transaction(tx(TxType::Dispute, 1, 2, None))?;
+    accounting.flag_account(1);
+    accounting.try_process_transaction(tx(TxType::Deposit, 1, 6, Some(dec!(5))))?;
+    assert_eq!(accounting.get_account(1).map(|a| a.held), Some(dec!(100)));
+
+    clock.advance(60);
+    let hold = accounting.place_hold(2, dec!(30), "review")?;
+    accounting.place_hold(2, dec!(10), "aml")?;
+    accounting.try_process_transaction(tx(TxType::Withdrawal, 2, 7, Some(dec!(15))))?;
+    accounting.release_hold(hold)?;
+
+    // Ensure a reloaded config is recorded with the segment it applies to;
+    clock.advance(60);
+    accounting.reload_config(AccountingConfig {
+        max_open_disputes: Some(2),
+        ..config
+    })?;
+    accounting.close_account(3, ClosureDisposition::Transfer(4))?;
+    accounting.lock_account(4, true);
+    accounting.try_process_transaction(tx(TxType::Dispute, 2, 3, None))?;
+
+    clock.advance(60);
+    assert_eq!(accounting.release_quarantined(1).len(), 1);
+    accounting.flag_account(2);
+
+    drop(accounting.detach_journal());
+    assert_eq!(Journal::read_index(&dir)?.len(), 2);
+
+    let summary = Journal::compact(&dir, snapshot_path.clone(), None, true, false)?;
+    assert_eq!(summary.segments, 2);
+
+    let mut restored = Accounting::init();
+    restored.read_snapshot_file(snapshot_path)?;
+
+    // Ensure the compacted snapshot matches the live state;
+    let (live, compacted) = (accounting.snapshot(), restored.snapshot());
+    let accounts = |snapshot: &Snapshot| {
+        let mut accounts = snapshot.accounts.clone();
+        accounts.sort_by_key(|(account, _)| account.client);
+        accounts
+    };
+    assert_eq!(accounts(&compacted), accounts(&live));
+    assert_eq!(compacted.holds, live.holds);
+    assert_eq!(compacted.disputes, live.disputes);
+    assert_eq!(compacted.closures, live.closures);
+    assert_eq!(compacted.lock_events, live.lock_events);
+    assert_eq!(compacted.flagged_accounts, vec![2]);
+    assert_eq!(compacted.flagged_accounts, live.flagged_accounts);
+    assert!(std::fs::read_dir(&dir)?
+        .filter_map(|entry| entry.ok())
+        .all(|entry| !entry.file_name().to_string_lossy().ends_with(".actions")));
+
+    // Ensure segments recorded without a configuration are refused rather than replayed with the
+    // default one;
+    let mut journal = Journal::open(dir.clone(), JournalConfig::default(), 0)?;
+    journal.append(&tx(TxType::Deposit, 5, 8, Some(dec!(1))), 0)?;
+    drop(journal);
+
+    let error = Journal::compact(&dir, dir.join("snap.bin"), None, true, false).unwrap_err();
+    assert!(matches!(error, Error::InvalidArgument(_)));
+
+    std::fs::remove_dir_all(&dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_change_stream_replica() -> Result<(), Error> {
+    let file_path = std::env::temp_dir().join("tx_sim_change_stream.csv");
+
+    let mut accounting = Accounting::init();
+    accounting.attach_change_stream(ChangeStream::create_file(file_path.clone())?);
+
+    for tx in Accounting::generate_dummy_transactions(500, 10)? {
+        accounting.process_transaction(tx);
+    }
+    accounting.lock_account(1, false);
+
+    drop(accounting.detach_change_stream());
+
+    // Ensure a replica applying the stream matches the primary;
+    let mut replica = Accounting::init();
+    let mut last_seq = 0;
+
+    for event in ChangeStream::read_events(std::fs::File::open(&file_path)?) {
+        let event = event?;
+        assert_eq!(event.seq, last_seq + 1);
+        last_seq = event.seq;
+
+        replica.apply_account_event(&event);
+    }
+
+    std::fs::remove_file(&file_path)?;
+
+    for account in accounting.accounts() {
+        let replicated = replica.get_account(account.client).expect("replicated");
+        assert!(account.approx_eq(replicated, dec!(0.0)));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_standby_replication_and_promotion() -> Result<(), Error> {
+    let tx = |r#type, tx, amount| Transaction {
+        client: 1,
+        tx,
+        r#type,
+        amount,
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    };
+
+    // Stream the leader's changes, with a deposit under an open dispute;
+    let file_path = std::env::temp_dir().join("tx_sim_standby_stream.csv");
+    let mut leader = Accounting::init();
+    leader.set_clock(Arc::new(MockClock::new(1_618_912_800)));
+    leader.attach_change_stream(ChangeStream::create_file(file_path.clone())?);
+    leader.process_transaction(tx(TxType::Deposit, 1, Some(dec!(100.0))));
+    leader.process_transaction(tx(TxType::Deposit, 2, Some(dec!(50.0))));
+    leader.process_transaction(tx(TxType::Dispute, 2, None));
+    drop(leader.detach_change_stream());
+
+    let clock = MockClock::new(1_618_912_803);
+    let mut replica = Accounting::init();
+    replica.set_clock(Arc::new(clock.clone()));
+
+    let mut standby = Standby::new(replica);
+    let events = ChangeStream::read_events(std::fs::File::open(&file_path)?)
+        .collect::<Result<Vec<AccountEvent>, Error>>()?;
+    std::fs::remove_file(&file_path)?;
+
+    for event in events.iter() {
+        standby.apply(event)?;
+    }
+
+    // Ensure replayed events are ignored and gaps are rejected;
+    standby.apply(&events[0])?;
+    assert!(matches!(
+        standby.apply(&AccountEvent {
+            seq: 5,
+            ..events[0].clone()
+        }),
+        Err(Error::ReplicationGap {
+            expected: 4,
+            received: 5
+        })
+    ));
+
+    // Ensure lag is measured in seconds behind the leader, and idle time from the last applied event;
+    clock.advance(5);
+    assert_eq!(
+        standby.status(),
+        StandbyStatus {
+            stream: events[0].stream,
+            last_seq: 3,
+            lag: Some(3),
+            idle: Some(5),
+            accounts: 1
+        }
+    );
+
+    // Ensure the promoted state can continue processing, including disputes of replicated transactions;
+    let mut leader = standby.promote();
+    assert_eq!(
+        leader.get_dispute(2).map(|dispute| dispute.amount),
+        Some(dec!(50.0))
+    );
+    leader.process_transaction(tx(TxType::Withdrawal, 3, Some(dec!(40.0))));
+    assert!(matches!(
+        leader.process_transaction(tx(TxType::Deposit, 1, Some(dec!(1.0)))),
+        Err(TransactionError::DuplicateTx)
+    ));
+    for (r#type, id) in [
+        (TxType::Resolve, 2),
+        (TxType::Dispute, 1),
+        (TxType::Chargeback, 1),
+    ] {
+        assert!(leader.process_transaction(tx(r#type, id, None)).is_ok());
+    }
+
+    let account = leader.get_account(1).cloned().unwrap();
+    assert_eq!((account.total, account.held), (dec!(10.0), dec!(0.0)));
+    assert!(account.locked);
+
+    Ok(())
+}
+
+#[test]
+fn test_standby_restarts_sequence_on_new_stream() -> Result<(), Error> {
+    let event = |stream, seq, available| AccountEvent {
+        seq,
+        client: 1,
+        available,
+        total: available,
+        stream: Some(stream),
+        ..Default::default()
+    };
+
+    let mut standby = Standby::default();
+    standby.apply(&event(7, 1, dec!(1.0)))?;
+    standby.apply(&event(7, 2, dec!(2.0)))?;
+
+    // Ensure the first event of a reconnected leader is applied rather than ignored as replayed;
+    standby.apply(&event(8, 1, dec!(3.0)))?;
+    assert_eq!(
+        (standby.status().stream, standby.status().last_seq),
+        (Some(8), 1)
+    );
+    assert_eq!(
+        standby.accounting().get_account(1).map(|a| a.available),
+        Some(dec!(3.0))
+    );
+
+    // Ensure gaps are still rejected within the new stream;
+    assert!(matches!(
+        standby.apply(&event(8, 3, dec!(4.0))),
+        Err(Error::ReplicationGap {
+            expected: 2,
+            received: 3
+        })
+    ));
+
+    // Ensure streams get distinct ids;
+    assert_ne!(
+        ChangeStream::new(Box::new(std::io::sink())).stream_id(),
+        ChangeStream::new(Box::new(std::io::sink())).stream_id()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_standby_replicates_locks_and_closures_and_checks_promotion() -> Result<(), Error> {
+    let tx = |client, tx, amount| Transaction {
+        client,
+        tx,
+        r#type: TxType::Deposit,
+        amount: Some(amount),
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    };
+
+    let file_path = std::env::temp_dir().join("tx_sim_standby_promotion_stream.csv");
+    let mut leader = Accounting::init();
+    leader.set_clock(Arc::new(MockClock::new(1_618_912_800)));
+    leader.attach_change_stream(ChangeStream::create_file(file_path.clone())?);
+    leader.process_transaction(tx(1, 1, dec!(10.0)));
+    leader.process_transaction(tx(2, 2, dec!(10.0)));
+    leader.process_transaction(tx(3, 3, dec!(10.0)));
+    leader.lock_account_with_reason(1, true, LockReason::Rule, "velocity rule");
+    leader.close_account(2, ClosureDisposition::Transfer(3))?;
+    leader.place_hold(3, dec!(4.0), "review")?;
+    drop(leader.detach_change_stream());
+
+    let mut standby = Standby::default();
+    for event in ChangeStream::read_events(std::fs::File::open(&file_path)?) {
+        standby.apply(&event?)?;
+    }
+    std::fs::remove_file(&file_path)?;
+
+    let replica = standby.accounting();
+    assert_eq!(
+        replica.get_account(1).and_then(|a| a.lock_reason),
+        Some(LockReason::Rule)
+    );
+    assert_eq!(
+        replica.closures().cloned().collect::<Vec<_>>(),
+        leader.closures().cloned().collect::<Vec<_>>()
+    );
+
+    // Ensure promotion is refused while the unreplicated hold keeps funds held;
+    assert!(matches!(
+        standby.check_promotion(),
+        Err(Error::UnreplicatedHeldFunds {
+            client: 3,
+            held,
+            replicated
+        }) if held == dec!(4.0) && replicated == dec!(0.0)
+    ));
+    assert_eq!(
+        standby.check_promotion().map_err(|e| e.code()),
+        Err("E3008")
+    );
+
+    // Ensure promotion is allowed once the hold is released by the reconnected leader;
+    let hold = leader.client_holds(3).next().map(|hold| hold.id).unwrap();
+    leader.attach_change_stream(ChangeStream::create_file(file_path.clone())?);
+    leader.release_hold(hold)?;
+    drop(leader.detach_change_stream());
+
+    for event in ChangeStream::read_events(std::fs::File::open(&file_path)?) {
+        standby.apply(&event?)?;
+    }
+    std::fs::remove_file(&file_path)?;
+
+    assert!(standby.check_promotion().is_ok());
+    assert_eq!(
+        standby
+            .promote()
+            .get_account(3)
+            .map(|a| (a.available, a.held)),
+        Some((dec!(20.0), dec!(0.0)))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_place_and_release_hold() -> Result<(), Error> {
+    let mut accounting = Accounting::init();
+
+    accounting.process_transaction(Transaction {
+        client: 1,
+        tx: 1,
+        r#type: TxType::Deposit,
+        amount: Some(dec!(100.0)),
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    });
+
+    let hold = accounting.place_hold(1, dec!(30.0), "suspected fraud")?;
+
+    assert_eq!(
+        accounting.get_account(1).map(|a| a.available),
+        Some(dec!(70.0))
+    );
+    assert_eq!(accounting.get_account(1).map(|a| a.held), Some(dec!(30.0)));
+    assert_eq!(accounting.client_holds(1).count(), 1);
+
+    // Ensure held funds cannot be withdrawn;
+    accounting.process_transaction(Transaction {
+        client: 1,
+        tx: 2,
+        r#type: TxType::Withdrawal,
+        amount: Some(dec!(80.0)),
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    });
+    assert_eq!(
+        accounting.get_account(1).map(|a| a.total),
+        Some(dec!(100.0))
+    );
+
+    // Ensure holds exceeding the available funds are rejected;
+    assert!(matches!(
+        accounting.place_hold(1, dec!(80.0), "too large"),
+        Err(Error::InsufficientFunds { client: 1, .. })
+    ));
+
+    // Ensure a hold is kept when the held funds no longer cover it;
+    let mut account = accounting.get_account(1).cloned().unwrap();
+    account.held = dec!(20.0);
+    accounting.insert_account(account);
+    assert!(matches!(
+        accounting.release_hold(hold),
+        Err(Error::InsufficientHeldFunds { client: 1, .. })
+    ));
+    assert_eq!(accounting.get_account(1).map(|a| a.held), Some(dec!(20.0)));
+    assert_eq!(accounting.client_holds(1).count(), 1);
+
+    let mut account = accounting.get_account(1).cloned().unwrap();
+    account.held = dec!(30.0);
+    accounting.insert_account(account);
+
+    let released = accounting.release_hold(hold)?;
+    assert_eq!(released.reason, "suspected fraud");
+
+    assert_eq!(
+        accounting.get_account(1).map(|a| a.available),
+        Some(dec!(100.0))
+    );
+    assert_eq!(accounting.get_account(1).map(|a| a.held), Some(dec!(0.0)));
+    assert!(matches!(
+        accounting.release_hold(hold),
+        Err(Error::UnknownHold(_))
+    ));
+
+    // Ensure a hold follows its client when remapped, and is released from the new account;
+    let hold = accounting.place_hold(1, dec!(30.0), "remapped")?;
+    accounting.remap_clients(vec![(1, 2)].into_iter().collect())?;
+    assert_eq!(accounting.client_holds(1).count(), 0);
+    assert_eq!(
+        accounting
+            .client_holds(2)
+            .map(|hold| hold.id)
+            .collect::<Vec<_>>(),
+        vec![hold]
+    );
+
+    let released = accounting.release_hold(hold)?;
+    assert_eq!(released.client, 2);
+    assert_eq!(accounting.get_account(2).map(|a| a.held), Some(dec!(0.0)));
+    assert_eq!(
+        accounting.get_account(2).map(|a| a.available),
+        Some(dec!(100.0))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_latency_histograms() {
+    let mut histogram = LatencyHistogram::default();
+    histogram.record(std::time::Duration::from_micros(3));
+    histogram.record(std::time::Duration::from_micros(40));
+    histogram.record(std::time::Duration::from_secs(2));
+
+    assert_eq!(histogram.count(), 3);
+    assert_eq!(
+        histogram.percentile(0.3),
+        std::time::Duration::from_micros(5)
+    );
+    assert_eq!(
+        histogram.percentile(0.6),
+        std::time::Duration::from_micros(50)
+    );
+    // Ensure the overflow bucket reports the largest recorded latency;
+    assert_eq!(histogram.percentile(1.0), std::time::Duration::from_secs(2));
+
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        slow_transaction_threshold: Some(std::time::Duration::from_secs(0)),
+        ..Default::default()
+    });
+
+    for tx in 1..=3 {
+        accounting.process_transaction(Transaction {
+            client: 1,
+            tx,
+            r#type: TxType::Deposit,
+            amount: Some(dec!(1.0)),
+            timestamp: None,
+            subaccount: None,
+            category: None,
+            source: None,
+        });
+    }
+
+    let histograms = accounting.latency_histograms();
+    assert_eq!(histograms.get(&TxType::Deposit).map(|h| h.count()), Some(3));
+    assert!(histograms.get(&TxType::Withdrawal).is_none());
+}
+
+#[test]
+fn test_amount_epsilon() {
+    let deposit = |tx, amount| Transaction {
+        client: 1,
+        tx,
+        r#type: TxType::Deposit,
+        amount: Some(amount),
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    };
+    let withdrawal = Transaction {
+        client: 1,
+        tx: 4,
+        r#type: TxType::Withdrawal,
+        amount: Some(dec!(0.8)),
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    };
+
+    // Ensure 0.7 + 0.1 covers 0.8 exactly with the default (zero) tolerance;
+    let mut accounting = Accounting::init();
+    accounting.process_transaction(deposit(1, dec!(0.7)));
+    accounting.process_transaction(deposit(2, dec!(0.1)));
+    accounting.process_transaction(withdrawal.clone());
+    assert_eq!(
+        accounting.get_account(1).map(|a| a.available),
+        Some(dec!(0.0))
+    );
+    assert_eq!(accounting.get_account(1).map(|a| a.total), Some(dec!(0.0)));
+
+    // Ensure a configured tolerance accepts a shortfall and clears the dust;
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        amount_epsilon: dec!(0.000001),
+        ..Default::default()
+    });
+    accounting.process_transaction(deposit(1, dec!(0.7999999)));
+    accounting.process_transaction(withdrawal);
+    assert_eq!(
+        accounting.get_account(1).map(|a| a.available),
+        Some(dec!(0.0))
+    );
+}
+
+#[test]
+fn test_process_transaction_outcomes() {
+    let tx = |r#type, tx, amount| Transaction {
+        client: 1,
+        tx,
+        r#type,
+        amount,
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    };
+    let mut accounting = Accounting::init();
+
+    assert_eq!(
+        accounting.process_transaction(tx(TxType::Deposit, 1, Some(dec!(10.0)))),
+        Ok(TxOutcome::Applied)
+    );
+    assert_eq!(
+        accounting.process_transaction(tx(TxType::Withdrawal, 2, Some(dec!(15.0)))),
+        Err(TransactionError::InsufficientFunds {
+            available: dec!(10.0),
+            amount: dec!(15.0)
+        })
+    );
+    assert_eq!(
+        accounting.process_transaction(tx(TxType::Withdrawal, 3, None)),
+        Err(TransactionError::MissingAmount)
+    );
+    assert_eq!(
+        accounting.process_transaction(tx(TxType::Dispute, 99, None)),
+        Err(TransactionError::UnknownTx)
+    );
+    assert_eq!(
+        accounting.process_transaction(tx(TxType::Reserve, 1, Some(dec!(1.0)))),
+        Err(TransactionError::DuplicateTx)
+    );
+
+    // Ensure a dispute of a recorded transaction without an amount is not reported as applied;
+    assert_eq!(
+        accounting.can_apply(&tx(TxType::Dispute, 3, None)),
+        Err(TransactionError::MissingAmount)
+    );
+    assert_eq!(
+        accounting.process_transaction(tx(TxType::Dispute, 3, None)),
+        Err(TransactionError::MissingAmount)
+    );
+
+    accounting.process_transaction(tx(TxType::Dispute, 1, None));
+    assert_eq!(
+        accounting.process_transaction(tx(TxType::Chargeback, 1, None)),
+        Ok(TxOutcome::Applied)
+    );
+    assert_eq!(
+        accounting.process_transaction(tx(TxType::Deposit, 4, Some(dec!(1.0)))),
+        Err(TransactionError::AccountLocked)
+    );
+
+    // Ensure deposits subject to a clearing delay are reported as pending;
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        clearing_delay: Some(60),
+        ..Default::default()
+    });
+    assert_eq!(
+        accounting.process_transaction(tx(TxType::Deposit, 1, Some(dec!(10.0)))),
+        Ok(TxOutcome::Pending)
+    );
+}
+
+#[test]
+fn test_exact_decimal_amounts() -> Result<(), Error> {
+    let mut accounting = Accounting::init();
+    let file_path = std::env::temp_dir().join("tx_sim_exact_amounts.csv");
+
+    // Ten deposits of 0.1, each disputed and resolved, then one charged back;
+    let mut rows = String::from("type,client,tx,amount\n");
+    for tx in 1..=10 {
+        rows.push_str(&format!("deposit,1,{},0.1\n", tx));
+        rows.push_str(&format!("dispute,1,{},\nresolve,1,{},\n", tx, tx));
+    }
+    rows.push_str("dispute,1,10,\nchargeback,1,10,\n");
+    std::fs::write(&file_path, rows)?;
+    accounting.read_transactions_csv_file(file_path.clone())?;
+    std::fs::remove_file(file_path)?;
+
+    // Ensure the balances are exact rather than within floating-point tolerance;
+    let account = accounting.get_account(1).cloned().unwrap();
+    assert_eq!(
+        (account.available, account.held, account.total),
+        (dec!(0.9), dec!(0.0), dec!(0.9))
+    );
+
+    let mut output = Vec::new();
+    accounting.write_accounts_csv(&mut output)?;
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "client,available,held,total,locked\n1,0.9,0.0,0.9,true\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_invariant_checks() -> Result<(), Error> {
+    let deposit = |client, tx| Transaction {
+        client,
+        tx,
+        r#type: TxType::Deposit,
+        amount: Some(dec!(10.0)),
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    };
+
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        invariant_check_interval: Some(2),
+        ..Default::default()
+    });
+
+    accounting.try_process_transaction(deposit(1, 1))?;
+    accounting.try_process_transaction(deposit(2, 2))?;
+
+    // Corrupt the account balances;
+    let mut account = accounting.get_account(1).cloned().unwrap();
+    account.total += dec!(5.0);
+    accounting.insert_account(account);
+
+    // Ensure the violation is only detected once the next check is due;
+    accounting.try_process_transaction(deposit(1, 3))?;
+
+    match accounting.try_process_transaction(deposit(2, 4)) {
+        Err(Error::InvariantViolation(violation)) => {
+            assert_eq!(violation.account.client, 1);
+            assert_eq!(violation.tx.map(|tx| tx.tx), Some(4));
+            assert_eq!(
+                violation.history.iter().map(|tx| tx.tx).collect::<Vec<_>>(),
+                vec![1, 3]
+            );
+        }
+        other => panic!("expected an invariant violation, got {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_disputes_export_import() -> Result<(), Error> {
+    let deposit = Transaction {
+        client: 1,
+        tx: 1,
+        r#type: TxType::Deposit,
+        amount: Some(dec!(25.0)),
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    };
+
+    let mut accounting = Accounting::init();
+    accounting.process_transaction(deposit.clone());
+    accounting.process_transaction(Transaction {
+        client: 1,
+        tx: 1,
+        r#type: TxType::Dispute,
+        amount: None,
+        timestamp: Some(1_000),
+        subaccount: None,
+        category: None,
+        source: None,
+    });
+
+    let file_path = std::env::temp_dir().join("tx_sim_disputes.csv");
+    accounting.write_disputes_csv_file(file_path.clone())?;
+
+    let exported = std::fs::read_to_string(&file_path)?;
+    assert_eq!(
+        exported,
+        "tx,client,amount,opened_at,resolution\n1,1,25.0,1000,\n"
+    );
+
+    // Ensure importing the open disputes restores them, and importing twice does not hold twice;
+    let mut restored = Accounting::init();
+    restored.process_transaction(deposit);
+    assert_eq!(restored.read_disputes_csv_file(file_path.clone())?, 1);
+    assert_eq!(restored.read_disputes_csv_file(file_path.clone())?, 0);
+    assert_eq!(restored.get_account(1).map(|a| a.held), Some(dec!(25.0)));
+    assert_eq!(restored.get_dispute(1).map(|d| d.opened_at), Some(1_000));
+
+    // Ensure resolutions decided externally are applied;
+    std::fs::write(&file_path, exported.replace(",\n", ",chargeback\n"))?;
+    assert_eq!(restored.read_disputes_csv_file(file_path.clone())?, 1);
+    assert_eq!(restored.get_account(1).map(|a| a.locked), Some(true));
+    assert_eq!(restored.disputes().count(), 0);
+
+    std::fs::remove_file(file_path)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_submit_acknowledgements() -> Result<(), Error> {
+    let file_path = std::env::temp_dir().join("tx_sim_acks.csv");
+    let _ = std::fs::remove_file(&file_path);
+
+    let deposit = |tx| Transaction {
+        client: 1,
+        tx,
+        r#type: TxType::Deposit,
+        amount: Some(dec!(10.0)),
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    };
+
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        strictness: Strictness::Strict,
+        ..Default::default()
+    });
+    accounting.open_ack_log(file_path.clone())?;
+
+    let ack = accounting.submit("a".to_string(), deposit(1))?;
+    assert_eq!(ack.status, AckStatus::Accepted);
+
+    // Ensure a retried submission is not processed twice;
+    assert_eq!(accounting.submit("a".to_string(), deposit(1))?, ack);
+    assert_eq!(accounting.get_account(1).map(|a| a.total), Some(dec!(10.0)));
+
+    let rejected = accounting.submit("b".to_string(), deposit(1))?;
+    assert_eq!(rejected.status, AckStatus::Rejected);
+    assert_eq!(rejected.error.as_deref(), Some("E2003"));
+
+    // Ensure acknowledgements survive a restart;
+    let mut restarted = Accounting::init();
+    restarted.open_ack_log(file_path.clone())?;
+    assert_eq!(restarted.get_ack("a"), Some(&ack));
+    assert_eq!(restarted.submit("b".to_string(), deposit(2))?, rejected);
+    assert!(restarted.get_account(1).is_none());
+
+    std::fs::remove_file(file_path)?;
+
+    // Ensure ignored transactions are not acknowledged as accepted;
+    let withdrawal = |tx, amount| Transaction {
+        r#type: TxType::Withdrawal,
+        amount: Some(amount),
+        ..deposit(tx)
+    };
+    let ignored = accounting.submit("c".to_string(), withdrawal(3, dec!(100.0)))?;
+    assert_eq!(ignored.status, AckStatus::Ignored);
+    assert!(ignored.message.is_some());
+
+    // Ensure refusals of an open circuit breaker are not acknowledged, so they can be retried;
+    let mut halted = Accounting::with_config(AccountingConfig {
+        circuit_breaker: Some(CircuitBreaker {
+            max_outflow: Some(dec!(5.0)),
+            ..Default::default()
+        }),
+        ..Default::default()
+    });
+    halted.submit("a".to_string(), deposit(1))?;
+    let tripped = halted.submit("b".to_string(), withdrawal(2, dec!(6.0)))?;
+    assert_eq!(tripped.status, AckStatus::Accepted);
+    assert!(matches!(
+        halted.submit("c".to_string(), deposit(3)),
+        Err(Error::CircuitOpen(_))
+    ));
+    assert!(halted.get_ack("c").is_none());
+
+    halted.reset_circuit_breaker();
+    assert_eq!(
+        halted.submit("c".to_string(), deposit(3))?.status,
+        AckStatus::Accepted
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_fair_scheduler() -> Result<(), Error> {
+    let deposit = |client, tx| Transaction {
+        client,
+        tx,
+        r#type: TxType::Deposit,
+        amount: Some(dec!(1.0)),
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    };
+
+    let mut scheduler = FairScheduler::new(2);
+    assert!(scheduler.enqueue(deposit(1, 1)).is_none());
+    assert!(scheduler.enqueue(deposit(1, 2)).is_none());
+    assert_eq!(scheduler.enqueue(deposit(1, 3)), Some(deposit(1, 3)));
+    assert!(scheduler.enqueue(deposit(2, 4)).is_none());
+
+    // Ensure clients are drained round-robin, preserving each client's order;
+    let order: Vec<TxId> = std::iter::from_fn(|| scheduler.next_transaction())
+        .map(|tx| tx.tx)
+        .collect();
+    assert_eq!(order, vec![1, 4, 2]);
+
+    assert_eq!(scheduler.overflows().get(&1), Some(&1));
+    assert!(scheduler.is_empty());
+
+    let mut accounting = Accounting::init();
+    assert!(scheduler.enqueue(deposit(3, 5)).is_none());
+    assert_eq!(scheduler.drain_into(&mut accounting)?, 1);
+    assert_eq!(accounting.get_account(3).map(|a| a.total), Some(dec!(1.0)));
+
+    // Ensure a flooding client applies backpressure instead of losing rows, counted in the metrics;
+    let file_path = std::env::temp_dir().join("tx_sim_fair_scheduler.csv");
+    let mut rows = String::from("type,client,tx,amount\n");
+    for tx in 10..20 {
+        rows.push_str(&format!("deposit,4,{},1.0\n", tx));
+    }
+    std::fs::write(&file_path, rows)?;
+
+    let prometheus = PrometheusMetrics::new();
+    accounting.set_metrics_sink(Arc::new(prometheus.clone()));
+    let mut scheduler = FairScheduler::new(3);
+    accounting.read_transactions_csv_file_fair(file_path.clone(), &mut scheduler, 100)?;
+    std::fs::remove_file(file_path)?;
+
+    assert_eq!(accounting.get_account(4).map(|a| a.total), Some(dec!(10.0)));
+    assert_eq!(scheduler.overflows().get(&4), Some(&3));
+    assert!(prometheus
+        .render()
+        .contains("tx_sim_scheduler_overflows_total{client=\"4\"} 3"));
+
+    Ok(())
+}
+
+#[test]
+fn test_amount_analytics() {
+    let mut accounting = Accounting::init();
+
+    for (tx, amount) in [dec!(5.0), dec!(6.0), dec!(7.0), dec!(6.5), dec!(500.0)]
+        .iter()
+        .enumerate()
+    {
+        accounting.process_transaction(Transaction {
+            client: 1,
+            tx: tx as TxId + 1,
+            r#type: TxType::Deposit,
+            amount: Some(*amount),
+            timestamp: None,
+            subaccount: None,
+            category: None,
+            source: None,
+        });
+    }
+
+    let histogram = accounting.amount_histogram();
+    assert_eq!(
+        histogram
+            .iter()
+            .map(|bucket| (bucket.min, bucket.max, bucket.count))
+            .collect::<Vec<_>>(),
+        vec![(1., 10., 4), (100., 1000., 1)]
+    );
+
+    // Median is 6.5 and MAD is 0.5; Only the 500.0 deposit deviates by more than 3 MADs;
+    let anomalies = accounting.amount_anomalies(DEFAULT_ANOMALY_THRESHOLD);
+    assert_eq!(anomalies.len(), 1);
+    assert_eq!(anomalies[0].tx, 5);
+    assert_eq!(anomalies[0].median, 6.5);
+    assert_eq!(anomalies[0].mad, 0.5);
+}
+
+#[test]
+fn test_debit_adjustments() -> Result<(), Error> {
+    let mut accounting = Accounting::init();
+    accounting.process_transaction(Transaction {
+        client: 1,
+        tx: 1,
+        r#type: TxType::Deposit,
+        amount: Some(dec!(10.0)),
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    });
+
+    let fee = accounting.debit_adjustment(1, dec!(4.0), "monthly fee", false)?;
+    assert_eq!(fee.mode, DebitMode::Full);
+
+    // Ensure the debit is capped at the available funds;
+    let capped = accounting.debit_adjustment(1, dec!(8.0), "correction", false)?;
+    assert_eq!(
+        (capped.mode, capped.debited),
+        (DebitMode::Capped, dec!(6.0))
+    );
+    assert_eq!(
+        accounting.get_account(1).map(|a| a.available),
+        Some(dec!(0.0))
+    );
+
+    let overridden = accounting.debit_adjustment(1, dec!(2.0), "correction", true)?;
+    assert_eq!(overridden.mode, DebitMode::Override);
+    assert_eq!(
+        accounting.get_account(1).map(|a| a.available),
+        Some(dec!(-2.0))
+    );
+    assert_eq!(accounting.get_account(1).map(|a| a.total), Some(dec!(-2.0)));
+
+    assert_eq!(accounting.debit_adjustments().len(), 3);
+    assert!(accounting
+        .debit_adjustment(1, dec!(0.0), "noop", false)
+        .is_err());
+
+    // Ensure the audit trail survives a snapshot;
+    let snapshot = accounting.snapshot();
+    accounting.restore_snapshot(snapshot);
+    assert_eq!(accounting.debit_adjustments().len(), 3);
+    assert_eq!(accounting.debit_adjustments()[1].requested, dec!(8.0));
+
+    // Ensure locked accounts are not debited;
+    accounting.lock_account(1, true);
+    assert!(accounting
+        .debit_adjustment(1, dec!(1.0), "correction", true)
+        .is_err());
+    assert_eq!(accounting.get_account(1).map(|a| a.total), Some(dec!(-2.0)));
+    assert_eq!(accounting.debit_adjustments().len(), 3);
+
+    // Ensure adjustments are journaled, and replaying the journal debits the same amounts;
+    let dir = std::env::temp_dir().join("tx_sim_debit_adjustments");
+    let _ = std::fs::remove_dir_all(&dir);
+    let mut accounting = Accounting::init();
+    let journal = Journal::open(dir.clone(), JournalConfig::default(), accounting.now())?;
+    accounting.attach_journal(journal)?;
+    accounting.deposit(2, dec!(5.0))?;
+    accounting.debit_adjustment(2, dec!(7.0), "correction", false)?;
+    drop(accounting.detach_journal());
+
+    let mut replayed = Accounting::init();
+    for segment in Journal::read_index(&dir)? {
+        for tx in Journal::read_segment(&dir, &segment)? {
+            replayed.try_process_transaction(tx)?;
+        }
+    }
+    std::fs::remove_dir_all(&dir)?;
+
+    assert_eq!(replayed.get_account(2).map(|a| a.total), Some(dec!(0.0)));
+    assert_eq!(replayed.debit_adjustments()[0].debited, dec!(5.0));
+    assert_eq!(replayed.debit_adjustments()[0].reason, "correction");
+
+    Ok(())
+}
+
+#[test]
+fn test_restore_snapshot_resets_state() -> Result<(), Error> {
+    let tx = |r#type, tx, amount: Amount| Transaction {
+        r#type,
+        client: 1,
+        tx,
+        amount: Some(amount),
+        timestamp: Some(1_000),
+        subaccount: None,
+        category: Some("groceries".to_string()),
+        source: None,
+    };
+
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        dedup: Some(DedupConfig {
+            window: 60,
+            ..Default::default()
+        }),
+        quarantine_flagged: true,
+        ..Default::default()
+    });
+    accounting.set_budget(Budget {
+        client: 1,
+        category: "groceries".to_string(),
+        limit: dec!(30.0),
+        period: 30 * SECONDS_PER_DAY,
+    });
+    accounting.set_reserve(1, dec!(50.0))?;
+    accounting.try_process_transaction(tx(TxType::Deposit, 1, dec!(100.0)))?;
+    accounting.try_process_transaction(tx(TxType::Withdrawal, 2, dec!(30.0)))?;
+    assert_eq!(accounting.budget_spent(1, "groceries", 1_000), dec!(30.0));
+    accounting.flag_account(1);
+    accounting.try_process_transaction(tx(TxType::Deposit, 3, dec!(10.0)))?;
+    assert_eq!(accounting.quarantined_transactions().len(), 1);
+    accounting.clear_account_flag(1);
+    accounting.submit("receipt-1".to_string(), tx(TxType::Deposit, 4, dec!(5.0)))?;
+    assert!(!accounting.audit_log().is_empty());
+
+    accounting.restore_snapshot(Snapshot::default());
+
+    // Ensure nothing of the cleared state is left to reject or deduplicate later transactions;
+    assert!(accounting.quarantined_transactions().is_empty());
+    assert!(accounting.audit_log().is_empty());
+    assert!(accounting.get_ack("receipt-1").is_none());
+    assert_eq!(accounting.reserve_floor(1), Amount::ZERO);
+    assert_eq!(accounting.budget_spent(1, "groceries", 1_000), Amount::ZERO);
+
+    assert_eq!(
+        accounting.process_transaction(tx(TxType::Deposit, 1, dec!(100.0))),
+        Ok(TxOutcome::Applied)
+    );
+    assert_eq!(
+        accounting.process_transaction(tx(TxType::Withdrawal, 5, dec!(80.0))),
+        Ok(TxOutcome::Applied)
+    );
+    assert!(accounting.content_duplicates().is_empty());
+    assert_eq!(accounting.get_account(1).map(|a| a.total), Some(dec!(20.0)));
+
+    // Ensure the configuration is kept;
+    assert!(accounting.config().quarantine_flagged);
+
+    Ok(())
+}
+
+#[test]
+fn test_content_dedup() {
+    let deposit = |client, tx, timestamp| Transaction {
+        client,
+        tx,
+        r#type: TxType::Deposit,
+        amount: Some(dec!(10.0)),
+        timestamp: Some(timestamp),
+        subaccount: None,
+        category: None,
+        source: None,
+    };
+
+    let mut client_windows = std::collections::HashMap::new();
+    client_windows.insert(2, 0);
+
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        dedup: Some(DedupConfig {
+            window: 60,
+            client_windows,
+        }),
+        ..Default::default()
+    });
+
+    accounting.process_transaction(deposit(1, 1, 1_000));
+    accounting.process_transaction(deposit(1, 2, 1_030));
+    // Ensure content outside the window is applied;
+    accounting.process_transaction(deposit(1, 3, 1_200));
+    // Ensure clients with deduplication disabled are applied;
+    accounting.process_transaction(deposit(2, 4, 1_000));
+    accounting.process_transaction(deposit(2, 5, 1_000));
+
+    assert_eq!(accounting.get_account(1).map(|a| a.total), Some(dec!(20.0)));
+    assert_eq!(accounting.get_account(2).map(|a| a.total), Some(dec!(20.0)));
+
+    let duplicates = accounting.content_duplicates();
+    assert_eq!(duplicates.len(), 1);
+    assert_eq!((duplicates[0].tx, duplicates[0].original_tx), (2, 1));
+
+    // Ensure an ignored withdrawal does not block the same withdrawal resent once funds arrived;
+    let withdrawal = |tx| Transaction {
+        r#type: TxType::Withdrawal,
+        amount: Some(dec!(50.0)),
+        ..deposit(1, tx, 2_000)
+    };
+    assert!(accounting.process_transaction(withdrawal(6)).is_err());
+    for tx in 7..=9 {
+        accounting.process_transaction(Transaction {
+            amount: Some(Amount::from(tx * 10)),
+            ..deposit(1, tx, 2_000)
+        });
+    }
+    assert_eq!(
+        accounting.process_transaction(withdrawal(10)),
+        Ok(TxOutcome::Applied)
+    );
+}
+
+#[test]
+fn test_max_open_disputes() {
+    let run = |policy| {
+        let mut accounting = Accounting::with_config(AccountingConfig {
+            max_open_disputes: Some(1),
+            dispute_cap_policy: policy,
+            ..Default::default()
+        });
+
+        for tx in 1..=2 {
+            accounting.process_transaction(Transaction {
+                client: 1,
+                tx,
+                r#type: TxType::Deposit,
+                amount: Some(dec!(10.0)),
+                timestamp: None,
+                subaccount: None,
+                category: None,
+                source: None,
+            });
+        }
+        for tx in 1..=2 {
+            accounting.process_transaction(Transaction {
+                client: 1,
+                tx,
+                r#type: TxType::Dispute,
+                amount: None,
+                timestamp: None,
+                subaccount: None,
+                category: None,
+                source: None,
+            });
+        }
+
+        accounting
+    };
+
+    let rejected = run(DisputeCapPolicy::Reject);
+    assert_eq!(rejected.open_disputes(1), 1);
+    assert_eq!(rejected.get_account(1).map(|a| a.held), Some(dec!(10.0)));
+    assert_eq!(rejected.audit_log()[0].event, "dispute_cap_rejected");
+    assert_eq!(rejected.flagged_accounts().count(), 0);
+
+    let flagged = run(DisputeCapPolicy::Flag);
+    assert_eq!(flagged.open_disputes(1), 2);
+    assert_eq!(flagged.audit_log()[0].event, "dispute_cap_flagged");
+    assert_eq!(flagged.flagged_accounts().collect::<Vec<_>>(), vec![&1]);
+}
+
+#[test]
+fn test_decision_trace() {
+    let mut txs = std::collections::HashSet::new();
+    txs.insert(2);
+    txs.insert(3);
+
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        trace: Some(TraceFilter {
+            txs,
+            ..Default::default()
+        }),
+        ..Default::default()
+    });
+
+    for (tx, r#type) in [(1, TxType::Deposit), (2, TxType::Withdrawal)].iter() {
+        accounting.process_transaction(Transaction {
+            client: 1,
+            tx: *tx,
+            r#type: *r#type,
+            amount: Some(dec!(50.0)),
+            timestamp: None,
+            subaccount: None,
+            category: None,
+            source: None,
+        });
+    }
+    accounting.process_transaction(Transaction {
+        client: 1,
+        tx: 3,
+        r#type: TxType::Withdrawal,
+        amount: Some(dec!(80.0)),
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    });
+
+    let stages: Vec<&str> = accounting
+        .trace_steps()
+        .iter()
+        .map(|step| step.stage.as_str())
+        .collect();
+    assert_eq!(
+        stages,
+        vec!["received", "dispatch", "result", "received", "dispatch", "ignored", "result"]
+    );
+    assert_eq!(
+        accounting.trace_steps()[2].detail,
+        "available 50 -> 0, held 0 -> 0, total 50 -> 0, locked false -> false"
+    );
+    assert_eq!(accounting.trace_steps()[5].detail, "insufficient funds");
+}
+
+#[test]
+fn test_open_readonly() -> Result<(), Error> {
+    let mut accounting = Accounting::init();
+    for client in 1..=2 {
+        accounting.process_transaction(Transaction {
+            client,
+            tx: client as TxId,
+            r#type: TxType::Deposit,
+            amount: Some(dec!(12.5)),
+            timestamp: None,
+            subaccount: None,
+            category: None,
+            source: None,
+        });
+    }
+    accounting.process_transaction(Transaction {
+        client: 2,
+        tx: 2,
+        r#type: TxType::Dispute,
+        amount: None,
+        timestamp: Some(7),
+        subaccount: None,
+        category: None,
+        source: None,
+    });
+
+    let file_path = std::env::temp_dir().join("tx_sim_readonly.bin");
+    accounting.write_snapshot_file(file_path.clone())?;
+
+    let view = Accounting::open_readonly(file_path.clone())?;
+    assert_eq!(view.get_account(2).map(|a| a.held), Some(dec!(12.5)));
+    assert_eq!(view.disputes(Some(1)).count(), 0);
+    assert_eq!(view.disputes(Some(2)).count(), 1);
+
+    let mut out = vec![];
+    view.write_accounts_csv(&mut out, Some(1))?;
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "client,available,held,total,locked\n1,12.5,0.0,12.5,false\n"
+    );
+
+    std::fs::remove_file(file_path)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_prune() -> Result<(), Error> {
+    let clock = MockClock::new(1_000);
+    let mut accounting = Accounting::init();
+    accounting.set_clock(Arc::new(clock.clone()));
+
+    for (client, r#type, tx) in [
+        (1, TxType::Deposit, 1),
+        (1, TxType::Withdrawal, 2),
+        (2, TxType::Deposit, 3),
+    ]
+    .iter()
+    {
+        accounting.process_transaction(Transaction {
+            client: *client,
+            tx: *tx,
+            r#type: *r#type,
+            amount: Some(dec!(5.0)),
+            timestamp: None,
+            subaccount: None,
+            category: None,
+            source: None,
+        });
+    }
+
+    let archive = std::env::temp_dir().join("tx_sim_pruned.csv");
+    let _ = std::fs::remove_file(&archive);
+    let policy = PrunePolicy {
+        inactive_for: 60,
+        archive: Some(archive.clone()),
+    };
+
+    // Ensure recently active accounts are kept;
+    assert!(accounting.prune(&policy)?.is_empty());
+
+    clock.advance(60);
+    let pruned = accounting.prune(&policy)?;
+    assert_eq!(pruned.iter().map(|a| a.client).collect::<Vec<_>>(), vec![1]);
+    assert!(accounting.get_account(1).is_none());
+    assert!(accounting.get_account(2).is_some());
+
+    assert_eq!(
+        std::fs::read_to_string(&archive)?,
+        "client,available,held,total,locked\n1,0.0,0.0,0.0,false\n"
+    );
+    std::fs::remove_file(archive)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_tx_id_allocation() -> Result<(), Error> {
+    let mut accounting = Accounting::init();
+    accounting.process_transaction(Transaction {
+        client: 1,
+        tx: 10,
+        r#type: TxType::Deposit,
+        amount: Some(dec!(5.0)),
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    });
+
+    // Ensure default ids follow the largest recorded id;
+    assert_eq!(accounting.deposit(1, dec!(5.0))?, 11);
+    assert_eq!(accounting.withdraw(1, dec!(2.5))?, 12);
+    assert_eq!(accounting.get_account(1).map(|a| a.total), Some(dec!(7.5)));
+
+    // Ensure ids already in use are skipped;
+    accounting.set_id_allocator(Arc::new(CounterAllocator::new(10)));
+    assert_eq!(accounting.deposit(2, dec!(1.0))?, 13);
+
+    let node = SnowflakeAllocator::new(3);
+    assert_eq!(node.allocate(), 3 << SNOWFLAKE_SEQUENCE_BITS);
+    assert_eq!(node.allocate(), (3 << SNOWFLAKE_SEQUENCE_BITS) | 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_subaccounts() -> Result<(), Error> {
+    let file_path = std::env::temp_dir().join("tx_sim_subaccounts.csv");
+    std::fs::write(
+        &file_path,
+        "type,client,tx,amount\n\
+         deposit,1:savings,1,100.0\n\
+         deposit,1,2,50.0\n\
+         withdrawal,1,3,80.0\n\
+         withdrawal,1:savings,4,80.0\n\
+         dispute,1,1,\n\
+         deposit,1:checking,5,10.0\n",
+    )?;
+
+    let mut accounting = Accounting::init();
+    accounting.read_transactions_csv_file(file_path.clone())?;
+
+    let balances: Vec<(&str, Amount)> = accounting
+        .subaccounts(1)
+        .map(|(name, account)| (name.as_str(), account.available))
+        .collect();
+    assert_eq!(
+        balances,
+        vec![("checking", dec!(10.0)), ("savings", dec!(20.0))]
+    );
+
+    // Ensure the rollup includes the unallocated funds, and the dispute of the savings deposit
+    // was checked against the savings balance;
+    let account = accounting.get_account(1).unwrap();
+    assert_eq!(
+        (account.available, account.held, account.total),
+        (dec!(80.0), dec!(0.0), dec!(80.0))
+    );
+
+    // Ensure the sub-account round-trips through the client column;
+    Accounting::write_transactions_csv_file(
+        accounting.transactions().cloned().collect(),
+        file_path.clone(),
+    )?;
+    assert!(std::fs::read_to_string(&file_path)?.contains("deposit,1:savings,1,100.0"));
+
+    std::fs::remove_file(file_path)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_category_budgets() -> Result<(), Error> {
+    let file_path = std::env::temp_dir().join("tx_sim_budgets.csv");
+    std::fs::write(
+        &file_path,
+        "client,category,limit,period\n1,groceries,50.0,86400\n",
+    )?;
+
+    let run = |policy| -> Result<Accounting, Error> {
+        let mut accounting = Accounting::with_config(AccountingConfig {
+            budget_policy: policy,
+            ..Default::default()
+        });
+        accounting.read_budgets_csv_file(file_path.clone())?;
+        accounting.process_transaction(Transaction {
+            client: 1,
+            tx: 1,
+            r#type: TxType::Deposit,
+            amount: Some(dec!(200.0)),
+            timestamp: None,
+            subaccount: None,
+            category: None,
+            source: None,
+        });
+
+        for (tx, timestamp) in [(2, 100), (3, 200), (4, SECONDS_PER_DAY)].iter() {
+            accounting.process_transaction(Transaction {
+                client: 1,
+                tx: *tx,
+                r#type: TxType::Withdrawal,
+                amount: Some(dec!(30.0)),
+                timestamp: Some(*timestamp),
+                subaccount: None,
+                category: Some("groceries".to_string()),
+                source: None,
+            });
+        }
+
+        Ok(accounting)
+    };
+
+    // Ensure the second withdrawal of the day is rejected, and the budget resets the next day;
+    let rejected = run(BudgetPolicy::Reject)?;
+    assert_eq!(rejected.get_account(1).map(|a| a.total), Some(dec!(140.0)));
+    assert_eq!(
+        rejected.budget_spent(1, "groceries", SECONDS_PER_DAY),
+        dec!(30.0)
+    );
+    assert_eq!(rejected.audit_log()[0].event, "budget_rejected");
+
+    let flagged = run(BudgetPolicy::Flag)?;
+    assert_eq!(flagged.get_account(1).map(|a| a.total), Some(dec!(110.0)));
+    assert_eq!(flagged.flagged_accounts().count(), 1);
+
+    // Ensure a withdrawal ignored below the reserve floor is not counted against the budget;
+    let mut floored = Accounting::init();
+    floored.read_budgets_csv_file(file_path.clone())?;
+    floored.set_reserve(1, dec!(20.0))?;
+    for (r#type, tx, amount) in [
+        (TxType::Deposit, 1, dec!(40.0)),
+        (TxType::Withdrawal, 2, dec!(30.0)),
+    ] {
+        floored.process_transaction(Transaction {
+            client: 1,
+            tx,
+            r#type,
+            amount: Some(amount),
+            timestamp: Some(100),
+            subaccount: None,
+            category: Some("groceries".to_string()),
+            source: None,
+        });
+    }
+    assert_eq!(floored.tx_state(2), Some(TxState::Rejected));
+    assert_eq!(floored.budget_spent(1, "groceries", 100), dec!(0));
+    assert!(floored.audit_log().is_empty());
+
+    std::fs::remove_file(file_path)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_clearing_delay() {
+    let tx = |r#type, tx, amount, timestamp| Transaction {
+        client: 1,
+        tx,
+        r#type,
+        amount: Some(amount),
+        timestamp: Some(timestamp),
+        subaccount: None,
+        category: None,
+        source: None,
+    };
+
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        clearing_delay: Some(3 * SECONDS_PER_DAY),
+        ..Default::default()
+    });
+
+    accounting.process_transaction(tx(TxType::Deposit, 1, dec!(100.0), 0));
+    assert_eq!(accounting.get_account(1).map(|a| a.held), Some(dec!(100.0)));
+
+    // Ensure pending deposits cannot be withdrawn;
+    accounting.process_transaction(tx(TxType::Withdrawal, 2, dec!(10.0), SECONDS_PER_DAY));
+    assert_eq!(
+        accounting.get_account(1).map(|a| a.total),
+        Some(dec!(100.0))
+    );
+    assert_eq!(accounting.pending_deposits().count(), 1);
+
+    accounting.process_transaction(tx(TxType::Withdrawal, 3, dec!(10.0), 3 * SECONDS_PER_DAY));
+    let account = accounting.get_account(1).unwrap();
+    assert_eq!(
+        (account.available, account.held, account.total),
+        (dec!(90.0), dec!(0.0), dec!(90.0))
+    );
+    assert_eq!(accounting.pending_deposits().count(), 0);
+}
+
+#[test]
+fn test_lock_events() {
+    let tx = |r#type, tx, amount| Transaction {
+        client: 1,
+        tx,
+        r#type,
+        amount,
+        timestamp: Some(10),
+        subaccount: None,
+        category: None,
+        source: None,
+    };
+
+    let mut accounting = Accounting::init();
+    accounting.process_transaction(tx(TxType::Deposit, 1, Some(dec!(5.0))));
+    accounting.process_transaction(tx(TxType::Dispute, 1, None));
+    accounting.process_transaction(tx(TxType::Chargeback, 1, None));
+
+    let account = accounting.get_account(1).unwrap();
+    assert_eq!(account.lock_reason, Some(LockReason::Chargeback));
+    assert_eq!(
+        ExtendedAccount::from(account).lock_reason,
+        Some(LockReason::Chargeback)
+    );
+
+    accounting.lock_account(1, false);
+    accounting.lock_account_with_reason(2, true, LockReason::Rule, "velocity");
+
+    let events = accounting.lock_events();
+    assert_eq!(events.len(), 3);
+    assert_eq!(
+        (events[0].reason, events[0].tx, events[0].at),
+        (LockReason::Chargeback, Some(1), 10)
+    );
+    assert_eq!(
+        (events[1].locked, events[1].reason),
+        (false, LockReason::Manual)
+    );
+    assert_eq!(events[2].detail, "velocity");
+    assert_eq!(accounting.get_account(1).unwrap().lock_reason, None);
+
+    let mut restored = Accounting::init();
+    restored.restore_snapshot(accounting.snapshot());
+    assert_eq!(
+        restored.get_account(2).unwrap().lock_reason,
+        Some(LockReason::Rule)
+    );
+}
+
+#[test]
+fn test_read_snapshot() -> Result<(), Error> {
+    let deposit = |client, tx| Transaction {
+        client,
+        tx,
+        r#type: TxType::Deposit,
+        amount: Some(dec!(1.0)),
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    };
+
+    let mut accounting = Accounting::init();
+    accounting.process_transaction(deposit(1, 1));
+    accounting.process_transaction(deposit(2, 2));
+
+    let view = accounting.read_snapshot();
+
+    // Keep processing while the view is exported from another thread;
+    let export = std::thread::spawn(move || {
+        let mut out = Vec::new();
+        view.write_accounts_csv(&mut out).map(|_| (view, out))
+    });
+    accounting.process_transaction(deposit(1, 3));
+    accounting.process_transaction(deposit(3, 4));
+
+    let (view, out) = export.join().unwrap()?;
+    assert_eq!(view.processed, 2);
+    assert_eq!(view.len(), 2);
+    assert_eq!(view.get_account(1).map(|a| a.total), Some(dec!(1.0)));
+    assert_eq!(String::from_utf8(out).unwrap().lines().count(), 3);
+    assert_eq!(accounting.get_account(1).map(|a| a.total), Some(dec!(2.0)));
+    assert_eq!(accounting.processed(), 4);
+
+    Ok(())
+}
+
+#[test]
+fn test_custom_tx_handler() -> Result<(), Error> {
+    let mut accounting = Accounting::init();
+    let bonus = accounting.register_tx_type("bonus")?;
+    assert_eq!(accounting.register_tx_type("bonus")?, bonus);
+    assert_eq!(accounting.tx_type("bonus")?, bonus);
+    assert_eq!(bonus.to_string(), "bonus");
+    assert!(accounting.register_tx_type("deposit").is_err());
+    assert!(accounting
+        .register_tx_type(&"x".repeat(CUSTOM_TX_TYPE_MAX_LEN + 1))
+        .is_err());
+    accounting.register_tx_type("rebate")?;
+
+    let file_path = std::env::temp_dir().join("tx_sim_custom_types.csv");
+    std::fs::write(
+        &file_path,
+        "type,client,tx,amount\ndeposit,1,1,1.0\nbonus,1,2,0.5\nrebate,1,3,1.0\n",
+    )?;
+
+    // Ensure custom types are registered per instance, so other instances skip them as unknown;
+    let mut other = Accounting::init();
+    assert!(other.tx_type("bonus").is_err());
+    other.read_transactions_csv_file(file_path.clone())?;
+    assert_eq!(other.unknown_tx_types().get("bonus"), Some(&1));
+    assert_eq!(other.get_account(1).unwrap().total, dec!(1.0));
+
+    accounting.register_handler(
+        bonus,
+        |_: &mut Accounting, account: &mut Account, tx: &Transaction| {
+            let amount = tx.amount.unwrap_or_default();
+            account.available += amount;
+            account.total += amount;
+        },
+    );
+    accounting.read_transactions_csv_file(file_path.clone())?;
+
+    std::fs::remove_file(file_path)?;
+
+    // Ensure the type without a handler is skipped, and the bonus is credited;
+    let account = accounting.get_account(1).unwrap();
+    assert_eq!((account.available, account.total), (dec!(1.5), dec!(1.5)));
+
+    let mut tx = accounting.transactions().next().cloned().unwrap();
+    tx.r#type = bonus;
+    assert!(accounting.validate_strict(&tx).is_ok());
+    assert!(Accounting::init().validate_strict(&tx).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_reservations() {
+    let tx = |r#type, tx, amount| Transaction {
+        client: 1,
+        tx,
+        r#type,
+        amount,
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    };
+    let balances = |accounting: &Accounting| {
+        let account = accounting.get_account(1).unwrap();
+        (account.available, account.held, account.total)
+    };
+
+    let mut accounting = Accounting::init();
+    accounting.process_transaction(tx(TxType::Deposit, 1, Some(dec!(100.0))));
+    accounting.process_transaction(tx(TxType::Reserve, 2, Some(dec!(40.0))));
+    accounting.process_transaction(tx(TxType::Reserve, 3, Some(dec!(70.0))));
+    assert_eq!(balances(&accounting), (dec!(60.0), dec!(40.0), dec!(100.0)));
+
+    // Ensure reserved funds cannot be withdrawn;
+    accounting.process_transaction(tx(TxType::Withdrawal, 4, Some(dec!(70.0))));
+    assert_eq!(balances(&accounting), (dec!(60.0), dec!(40.0), dec!(100.0)));
+
+    accounting.process_transaction(tx(TxType::Fill, 2, Some(dec!(15.0))));
+    assert_eq!(balances(&accounting), (dec!(60.0), dec!(25.0), dec!(85.0)));
+    assert_eq!(
+        accounting.get_reservation(2).map(|r| (r.amount, r.filled)),
+        Some((dec!(25.0), dec!(15.0)))
+    );
+
+    // Ensure over-releasing is ignored, and the remainder is released by default;
+    accounting.process_transaction(tx(TxType::Release, 2, Some(dec!(30.0))));
+    accounting.process_transaction(tx(TxType::Release, 2, None));
+    assert_eq!(balances(&accounting), (dec!(85.0), dec!(0.0), dec!(85.0)));
+    assert_eq!(accounting.reservations().count(), 0);
+    assert_eq!(accounting.disputes().count(), 0);
+}
+
+#[test]
+fn test_scenario_runner() -> Result<(), Error> {
+    let mut base = Accounting::init();
+    for client in 1..=10 {
+        base.process_transaction(Transaction {
+            client,
+            tx: client as TxId,
+            r#type: TxType::Deposit,
+            amount: Some(dec!(1_000.0)),
+            timestamp: None,
+            subaccount: None,
+            category: None,
+            source: None,
+        });
+    }
+
+    let profile = GeneratorProfile {
+        transactions: 500,
+        clients: 10,
+        ..Default::default()
+    };
+    let mut runner = ScenarioRunner::new(base.snapshot());
+    runner.add_scenario(Scenario::new("baseline", profile.clone()));
+    runner.add_scenario(Scenario {
+        withdrawal_fee: dec!(1.0),
+        dispute_multiplier: 5.0,
+        ..Scenario::new("surge", profile)
+    });
+
+    let summaries = runner.run()?;
+    assert_eq!(summaries.len(), 2);
+    assert_eq!(summaries[0].transactions, 500);
+    assert_eq!(summaries[0].fees, dec!(0.0));
+    assert!(summaries[1].fees > dec!(0.0));
+
+    // Ensure runs are reproducible and leave the base ledger untouched;
+    assert_eq!(runner.run()?, summaries);
+    assert_eq!(base.get_account(1).map(|a| a.total), Some(dec!(1_000.0)));
+    assert_eq!(runner.base_accounting().accounts().count(), 10);
+
+    Ok(())
+}
+
+#[test]
+fn test_monte_carlo() -> Result<(), Error> {
+    let runner = ScenarioRunner::new(Snapshot::default());
+    let scenario = Scenario {
+        seed: 42,
+        ..Scenario::new(
+            "stress",
+            GeneratorProfile {
+                transactions: 200,
+                clients: 5,
+                ..Default::default()
+            },
+        )
+    };
+
+    let report = runner.monte_carlo(&scenario, 20)?;
+    assert_eq!(report.runs.len(), 20);
+    assert_eq!(report, runner.monte_carlo(&scenario, 20)?);
+
+    let distributions = report.distributions();
+    let total = distributions.iter().find(|d| d.metric == "total").unwrap();
+    assert!(total.min <= total.p5 && total.p5 <= total.p50);
+    assert!(total.p50 <= total.p95 && total.p95 <= total.max);
+    assert!(total.min < total.max);
+
+    let values = vec![4.0, 1.0, 3.0, 2.0];
+    let distribution = MetricDistribution::from_values("x", values);
+    assert_eq!(
+        (
+            distribution.min,
+            distribution.mean,
+            distribution.p50,
+            distribution.max
+        ),
+        (1.0, 2.5, 2.0, 4.0)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_account_report() {
+    let tx = |r#type, tx, amount, timestamp| Transaction {
+        client: 7,
+        tx,
+        r#type,
+        amount,
+        timestamp,
+        subaccount: None,
+        category: None,
+        source: None,
+    };
+
+    let mut accounting = Accounting::init();
+    accounting.process_transaction(tx(TxType::Deposit, 1, Some(dec!(1_234_567.5)), Some(0)));
+    accounting.process_transaction(tx(
+        TxType::Deposit,
+        2,
+        Some(dec!(10.0)),
+        Some(SECONDS_PER_DAY),
+    ));
+    accounting.process_transaction(tx(
+        TxType::Withdrawal,
+        3,
+        Some(dec!(0.5)),
+        Some(SECONDS_PER_DAY),
+    ));
+    accounting.process_transaction(tx(TxType::Dispute, 2, None, Some(SECONDS_PER_DAY)));
+
+    assert!(accounting.account_report(8, 5).is_none());
+
+    let report = accounting.account_report(7, 2).unwrap();
+    assert_eq!(report.disputes.len(), 1);
+    assert_eq!(
+        report.recent.iter().map(|tx| tx.tx).collect::<Vec<_>>(),
+        vec![3, 2]
+    );
+
+    let printed = report.to_string();
+    assert!(printed.starts_with("Client 7\n"));
+    assert!(printed.contains("  Available        1,234,567.0000\n"));
+    assert!(printed.contains(&format!(
+        "  tx {:<10} {:>20}  opened 1970-01-02\n",
+        2, "10.0000"
+    )));
+
+    assert_eq!(format_amount(dec!(-1234.56789)), "-1,234.5679");
+    assert_eq!(format_amount(dec!(-0.00001)), "0.0000");
+    assert_eq!(format_amount(dec!(999.0)), "999.0000");
+}
+
+#[test]
+fn test_prometheus_metrics() {
+    let tx = |r#type, tx, amount| Transaction {
+        client: 1,
+        tx,
+        r#type,
+        amount,
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    };
+
+    let prometheus = PrometheusMetrics::new();
+    let mut accounting = Accounting::init();
+    accounting.set_metrics_sink(Arc::new(prometheus.clone()));
+
+    accounting.process_transaction(tx(TxType::Deposit, 1, Some(dec!(5.0))));
+    accounting.process_transaction(tx(TxType::Withdrawal, 2, Some(dec!(50.0))));
+    accounting.process_transaction(tx(TxType::Dispute, 1, None));
+    accounting.process_transaction(tx(TxType::Chargeback, 1, None));
+
+    let rendered = prometheus.render();
+    let lines: Vec<&str> = rendered.lines().collect();
+    for expected in [
+        "# TYPE tx_sim_transactions_total counter",
+        "tx_sim_transactions_total{type=\"deposit\"} 1",
+        "tx_sim_transactions_ignored_total{type=\"withdrawal\"} 1",
+        "tx_sim_accounts_locked_total{reason=\"chargeback\"} 1",
+        "tx_sim_transaction_duration_seconds_bucket{type=\"deposit\",le=\"+Inf\"} 1",
+        "tx_sim_transaction_duration_seconds_count{type=\"dispute\"} 1",
+        "tx_sim_accounts 1",
+        "tx_sim_open_disputes 0",
+    ]
+    .iter()
+    {
+        assert!(lines.contains(expected), "missing {}", expected);
+    }
+}
+
+#[cfg(feature = "otlp")]
+#[test]
+fn test_otlp_export() -> Result<(), Error> {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    // Accept one export request and respond like a collector;
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let endpoint = format!("http://{}/collector/", listener.local_addr()?);
+    let collector = std::thread::spawn(move || -> std::io::Result<String> {
+        let (mut conn, _) = listener.accept()?;
+        let mut request = Vec::new();
+        let mut buf = [0; 4096];
+        while !String::from_utf8_lossy(&request).contains("\"resourceSpans\"")
+            || !request.ends_with(b"}")
+        {
+            let n = conn.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            request.extend_from_slice(&buf[..n]);
+        }
+        conn.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n{}")?;
+        Ok(String::from_utf8_lossy(&request).into_owned())
+    });
+
+    let trace_id = "4bf92f3577b34da6a3ce929d0e0e4736";
+    let exporter = OtlpExporter::new(OtlpConfig {
+        endpoint,
+        batch_size: 3,
+        traceparent: Some(format!("00-{}-00f067aa0ba902b7-01", trace_id)),
+        ..Default::default()
+    })?;
+    assert!(exporter
+        .traceparent()
+        .starts_with(&format!("00-{}-", trace_id)));
+
+    let mut accounting = Accounting::init();
+    accounting.attach_otlp_exporter(exporter);
+    for tx in Accounting::generate_dummy_transactions(7, 3)? {
+        accounting.process_transaction(tx);
+    }
+    accounting.detach_otlp_exporter().unwrap().finish()?;
+
+    let request = collector.join().unwrap()?;
+    assert!(request.starts_with("POST /collector/v1/traces HTTP/1.1"));
+
+    let body: serde_json::Value =
+        serde_json::from_str(&request[request.find("\r\n\r\n").unwrap() + 4..]).unwrap();
+    let spans = body["resourceSpans"][0]["scopeSpans"][0]["spans"]
+        .as_array()
+        .unwrap();
+
+    // Ensure three batch spans of 3, 3 and 1 transactions, and a root span joining the upstream trace;
+    assert_eq!(spans.len(), 4);
+    assert!(spans.iter().all(|span| span["traceId"] == trace_id));
+    assert_eq!(spans[2]["attributes"][1]["value"]["intValue"], "1");
+    assert_eq!(spans[3]["name"], "tx-sim.process");
+    assert_eq!(spans[3]["parentSpanId"], "00f067aa0ba902b7");
+    assert_eq!(spans[0]["parentSpanId"], spans[3]["spanId"]);
+
+    assert!(OtlpExporter::new(OtlpConfig {
+        traceparent: Some("00-invalid".to_string()),
+        ..Default::default()
+    })
+    .is_err());
+    for endpoint in ["https://collector:4318", "http://", "http:///v1"] {
+        assert!(OtlpExporter::new(OtlpConfig {
+            endpoint: endpoint.to_string(),
+            ..Default::default()
+        })
+        .is_err());
+    }
+
+    // Ensure spans are capped while the collector is unreachable, and the final export fails;
+    let unreachable = TcpListener::bind("127.0.0.1:0")?.local_addr()?;
+    let exporter = OtlpExporter::new(OtlpConfig {
+        endpoint: format!("http://{}", unreachable),
+        batch_size: 1,
+        timeout: std::time::Duration::from_millis(200),
+        ..Default::default()
+    })?;
+
+    let mut accounting = Accounting::init();
+    accounting.attach_otlp_exporter(exporter);
+    for tx in
+        Accounting::generate_dummy_transactions((OTLP_MAX_SPANS + OTLP_EXPORT_SPANS) as TxId, 3)?
+    {
+        accounting.process_transaction(tx);
+    }
+    let exporter = accounting.detach_otlp_exporter().unwrap();
+    assert_eq!(exporter.dropped_spans(), OTLP_EXPORT_SPANS as u64);
+    assert!(exporter.finish().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_preload_accounts_csv() -> Result<(), Error> {
+    let file_path = std::env::temp_dir().join("tx_sim_preload_accounts.csv");
+    std::fs::write(
+        &file_path,
+        "client,available,held,total,locked,peak_total,lock_reason\n\
+         1,10.0,2.5,12.5,false,20.0,\n\
+         2,0.0,0.0,0.0,true,5.0,chargeback\n",
+    )?;
+
+    let mut accounting = Accounting::init();
+    assert_eq!(accounting.preload_accounts_csv(file_path.clone())?, 2);
+
+    let tx = |client, tx| Transaction {
+        client,
+        tx,
+        r#type: TxType::Deposit,
+        amount: Some(dec!(1.0)),
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    };
+    accounting.process_transaction(tx(1, 1));
+    accounting.process_transaction(tx(2, 2));
+
+    // Ensure held funds are carried over and locked accounts stay locked;
+    let account = accounting.get_account(1).unwrap();
+    assert_eq!(
+        (account.available, account.held, account.total),
+        (dec!(11.0), dec!(2.5), dec!(13.5))
+    );
+    let account = accounting.get_account(2).unwrap();
+    assert_eq!((account.total, account.locked), (dec!(0.0), true));
+    assert_eq!(account.lock_reason, Some(LockReason::Chargeback));
+
+    // Ensure inconsistent or repeated accounts are rejected without loading any account;
+    std::fs::write(
+        &file_path,
+        "client,available,held,total,locked\n3,1.0,0.0,1.0,false\n1,1.0,0.0,1.0,false\n",
+    )?;
+    assert!(accounting.preload_accounts_csv(file_path.clone()).is_err());
+    assert!(accounting.get_account(3).is_none());
+
+    std::fs::write(
+        &file_path,
+        "client,available,held,total,locked\n4,1.0,1.0,1.0,false\n",
+    )?;
+    assert!(Accounting::init()
+        .preload_accounts_csv(file_path.clone())
+        .is_err());
+
+    std::fs::remove_file(file_path)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_diagnose_csv_file() -> Result<(), Error> {
+    let file_path = std::env::temp_dir().join("tx_sim_diagnose.csv");
+    std::fs::write(
+        &file_path,
+        "kind; client_id; tx; value\n\
+         deposit; 1; 1; 2.5\n\
+         withdrawal; 1; 2; 1.0\n\
+         deposit; x; 3; 1.0\n",
+    )?;
+
+    let diagnosis = diagnose_csv_file(file_path.clone(), DEFAULT_DIAGNOSE_SAMPLE)?;
+    assert_eq!(diagnosis.format.delimiter, b';');
+    assert!(diagnosis.format.trim);
+    assert!(diagnosis.is_parseable());
+    assert_eq!(
+        diagnosis
+            .columns
+            .iter()
+            .map(|column| (column.column, column.kind))
+            .collect::<Vec<_>>(),
+        vec![
+            (Some("type"), ColumnKind::Text),
+            (Some("client"), ColumnKind::Text),
+            (Some("tx"), ColumnKind::Integer),
+            (Some("amount"), ColumnKind::Decimal),
+        ]
+    );
+    assert_eq!((diagnosis.rows_sampled, diagnosis.rows_failed), (3, 1));
+    assert_eq!(
+        diagnosis.suggested_options(),
+        vec![
+            "--delimiter ';'",
+            "--trim",
+            "--map 'type=kind'",
+            "--map 'client=client_id'",
+            "--map 'amount=value'",
+            "--lenient",
+        ]
+    );
+
+    // Ensure the suggested format parses the file;
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        input: diagnosis.format,
+        strictness: Strictness::Lenient,
+        ..Default::default()
+    });
+    accounting.read_transactions_csv_file(file_path.clone())?;
+    assert_eq!(accounting.get_account(1).unwrap().total, dec!(1.5));
+
+    // Ensure decimal-comma amounts are detected and read, instead of suggesting to skip every row;
+    std::fs::write(
+        &file_path,
+        "type;client;tx;amount\n\
+         deposit;1;1;1.234,5\n\
+         withdrawal;1;2;0,25\n",
+    )?;
+    let diagnosis = diagnose_csv_file(file_path.clone(), DEFAULT_DIAGNOSE_SAMPLE)?;
+    assert_eq!(diagnosis.columns[3].kind, ColumnKind::Decimal);
+    assert_eq!((diagnosis.rows_sampled, diagnosis.rows_failed), (2, 0));
+    assert_eq!(
+        diagnosis.suggested_options(),
+        vec!["--delimiter ';'", "--decimal-comma"]
+    );
+
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        input: diagnosis.format,
+        ..Default::default()
+    });
+    accounting.read_transactions_csv_file(file_path.clone())?;
+    assert_eq!(accounting.get_account(1).unwrap().total, dec!(1234.25));
+
+    // Ensure files without a header row are reported;
+    std::fs::write(&file_path, "deposit,1,1,2.5\n")?;
+    assert!(!diagnose_csv_file(file_path.clone(), 10)?.is_parseable());
+
+    std::fs::remove_file(file_path)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_blank_and_comment_lines() -> Result<(), Error> {
+    let file_path = std::env::temp_dir().join("tx_sim_commented.csv");
+    std::fs::write(
+        &file_path,
+        "type,client,tx,amount\n\
+         # opening balance\n\
+         deposit,1,1,5.0\n\
+         \x20\x20\n\
+         withdrawal,1,2,2.0\n",
+    )?;
+
+    // Ensure comment and whitespace-only lines fail by default;
+    let mut accounting = Accounting::init();
+    assert!(accounting
+        .read_transactions_csv_file(file_path.clone())
+        .is_err());
+
+    // Ensure they are skipped in lenient mode;
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        strictness: Strictness::Lenient,
+        ..Default::default()
+    });
+    accounting.read_transactions_csv_file(file_path.clone())?;
+    assert_eq!(accounting.get_account(1).unwrap().total, dec!(3.0));
+
+    // Ensure rows with missing fields still fail when blank lines are skipped;
+    std::fs::write(&file_path, "type,client,tx,amount\n\ndeposit,1\n")?;
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        input: InputFormat {
+            comment: Some(b'#'),
+            skip_blank_lines: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+    let error = accounting
+        .read_transactions_csv_file(file_path.clone())
+        .unwrap_err();
+    assert_eq!(error.code(), "E1005");
+
+    std::fs::remove_file(file_path)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_dispute_amounts() {
+    let tx = |r#type, tx, amount| Transaction {
+        client: 1,
+        tx,
+        r#type,
+        amount,
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    };
+
+    // Ensure amounts on dispute rows are ignored by default;
+    let mut accounting = Accounting::init();
+    accounting.process_transaction(tx(TxType::Deposit, 1, Some(dec!(10.0))));
+    accounting.process_transaction(tx(TxType::Dispute, 1, Some(dec!(4.0))));
+    let account = accounting.get_account(1).unwrap();
+    assert_eq!((account.available, account.held), (dec!(0.0), dec!(10.0)));
+
+    // Ensure rows with amounts are ignored with the reject policy;
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        dispute_amount_policy: DisputeAmountPolicy::Reject,
+        ..Default::default()
+    });
+    accounting.process_transaction(tx(TxType::Deposit, 1, Some(dec!(10.0))));
+    accounting.process_transaction(tx(TxType::Dispute, 1, Some(dec!(4.0))));
+    assert_eq!(accounting.get_account(1).unwrap().held, dec!(0.0));
+    accounting.process_transaction(tx(TxType::Dispute, 1, None));
+    accounting.process_transaction(tx(TxType::Chargeback, 1, Some(dec!(10.0))));
+    assert!(!accounting.get_account(1).unwrap().locked);
+
+    // Ensure partial disputes hold and charge back only the amount of the dispute row;
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        partial_disputes: true,
+        ..Default::default()
+    });
+    accounting.process_transaction(tx(TxType::Deposit, 1, Some(dec!(10.0))));
+    accounting.process_transaction(tx(TxType::Dispute, 1, Some(dec!(12.0))));
+    assert_eq!(accounting.get_account(1).unwrap().held, dec!(0.0));
+    accounting.process_transaction(tx(TxType::Dispute, 1, Some(dec!(4.0))));
+    let account = accounting.get_account(1).unwrap();
+    assert_eq!((account.available, account.held), (dec!(6.0), dec!(4.0)));
+    accounting.process_transaction(tx(TxType::Chargeback, 1, None));
+    let account = accounting.get_account(1).unwrap();
+    assert_eq!(
+        (
+            account.available,
+            account.held,
+            account.total,
+            account.locked
+        ),
+        (dec!(6.0), dec!(0.0), dec!(6.0), true)
+    );
+}
+
+#[test]
+fn test_partition() -> Result<(), Error> {
+    assert_eq!(
+        "3/16".parse::<Partition>()?,
+        Partition::Bucket {
+            index: 3,
+            count: 16
+        }
+    );
+    assert_eq!(
+        "1-9".parse::<Partition>()?,
+        Partition::Range { first: 1, last: 9 }
+    );
+    assert!("0/4".parse::<Partition>().is_err());
+    assert!("5/4".parse::<Partition>().is_err());
+    assert!("9-1".parse::<Partition>().is_err());
+
+    // Ensure every client belongs to exactly one bucket;
+    for client in 0..1000 {
+        let buckets = (1..=4)
+            .filter(|index| {
+                Partition::Bucket {
+                    index: *index,
+                    count: 4,
+                }
+                .contains(client)
+            })
+            .count();
+        assert_eq!(buckets, 1);
+    }
+
+    let file_path = std::env::temp_dir().join("tx_sim_partition.csv");
+    std::fs::write(
+        &file_path,
+        "type,client,tx,amount\n\
+         deposit,1,1,1.0\n\
+         deposit,2,2,2.0\n\
+         deposit,3,3,3.0\n\
+         dispute,3,3,\n\
+         deposit,3:savings,4,4.0\n",
+    )?;
+
+    // Ensure the partitions together process every client once, including sub-account rows;
+    let mut clients = Vec::new();
+    for index in 1..=2 {
+        let mut accounting = Accounting::with_config(AccountingConfig {
+            partition: Some(Partition::Bucket { index, count: 2 }),
+            ..Default::default()
+        });
+        accounting.read_transactions_csv_file(file_path.clone())?;
+        for account in accounting.accounts() {
+            assert_eq!(client_bucket(account.client, 2), index);
+            clients.push(account.client);
+        }
+    }
+    clients.sort_unstable();
+    assert_eq!(clients, vec![1, 2, 3]);
+
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        partition: Some(Partition::Range { first: 3, last: 3 }),
+        ..Default::default()
+    });
+    accounting.read_transactions_csv_file(file_path.clone())?;
+    assert_eq!(accounting.accounts().count(), 1);
+    assert_eq!(accounting.get_account(3).unwrap().held, dec!(3.0));
+
+    std::fs::remove_file(file_path)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_merge_accounts_csv_files() -> Result<(), Error> {
+    let dir = std::env::temp_dir();
+    let inputs = vec![
+        dir.join("tx_sim_merge_1.csv"),
+        dir.join("tx_sim_merge_2.csv"),
+    ];
+    let output = dir.join("tx_sim_merge.csv");
+    std::fs::write(
+        &inputs[0],
+        "client,available,held,total,locked\n3,3.0,0.0,3.0,false\n1,1.0,0.0,1.0,false\n",
+    )?;
+    std::fs::write(
+        &inputs[1],
+        "client,available,held,total,locked\n2,0.0,2.0,2.0,true\n",
+    )?;
+
+    // Ensure the merged export is ordered by client;
+    assert_eq!(
+        merge_accounts_csv_files(&inputs, output.clone(), Durability::default())?,
+        3
+    );
+    assert_eq!(
+        std::fs::read_to_string(&output)?,
+        "client,available,held,total,locked\n\
+         1,1.0,0.0,1.0,false\n\
+         2,0.0,2.0,2.0,true\n\
+         3,3.0,0.0,3.0,false\n"
+    );
+
+    // Ensure clients appearing in more than one input are rejected;
+    std::fs::write(
+        &inputs[1],
+        "client,available,held,total,locked\n1,0.0,2.0,2.0,true\n",
+    )?;
+    assert!(merge_accounts_csv_files(&inputs, output.clone(), Durability::default()).is_err());
+
+    // Ensure inputs with different columns are rejected;
+    std::fs::write(&inputs[1], "client,available,held,total\n2,0.0,2.0,2.0\n")?;
+    assert!(merge_accounts_csv_files(&inputs, output.clone(), Durability::default()).is_err());
+
+    for path in inputs.iter().chain(std::iter::once(&output)) {
+        std::fs::remove_file(path)?;
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_close_account() -> Result<(), Error> {
+    let tx = |r#type, client, tx, amount| Transaction {
+        client,
+        tx,
+        r#type,
+        amount,
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    };
+
+    let mut accounting = Accounting::init();
+    accounting.process_transaction(tx(TxType::Deposit, 1, 1, Some(dec!(10.0))));
+    accounting.process_transaction(tx(TxType::Deposit, 2, 2, Some(dec!(5.0))));
+    accounting.process_transaction(tx(TxType::Deposit, 3, 3, Some(dec!(2.0))));
+    accounting.process_transaction(tx(TxType::Dispute, 1, 1, None));
+
+    // Ensure closure is blocked while disputes are open;
+    assert!(accounting
+        .close_account(1, ClosureDisposition::WriteOff)
+        .is_err());
+    accounting.process_transaction(tx(TxType::Resolve, 1, 1, None));
+
+    // Ensure locked accounts are neither closed nor credited;
+    accounting.lock_account(2, true);
+    assert!(accounting
+        .close_account(2, ClosureDisposition::WriteOff)
+        .is_err());
+    assert!(accounting
+        .close_account(1, ClosureDisposition::Transfer(2))
+        .is_err());
+    accounting.lock_account(2, false);
+
+    // Ensure residual funds are transferred to the designated account;
+    let closure = accounting.close_account(1, ClosureDisposition::Transfer(2))?;
+    assert_eq!(
+        (closure.residual, closure.transferred_to),
+        (dec!(10.0), Some(2))
+    );
+    assert_eq!(accounting.get_account(1).unwrap().total, dec!(0.0));
+    assert_eq!(accounting.get_account(2).unwrap().available, dec!(15.0));
+
+    // Ensure written off funds are recorded as a debit adjustment;
+    accounting.close_account(3, ClosureDisposition::WriteOff)?;
+    assert_eq!(accounting.get_account(3).unwrap().total, dec!(0.0));
+    let debited = |accounting: &Accounting| {
+        accounting
+            .debit_adjustments()
+            .iter()
+            .map(|adjustment| (adjustment.client, adjustment.debited))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        debited(&accounting),
+        vec![(1, dec!(10.0)), (2, dec!(-10.0)), (3, dec!(2.0))]
+    );
+
+    // Ensure closed accounts are excluded from later processing, also after a snapshot is restored;
+    let mut restored = Accounting::init();
+    restored.restore_snapshot(accounting.snapshot());
+    for accounting in [&mut accounting, &mut restored] {
+        accounting.process_transaction(tx(TxType::Deposit, 1, 4, Some(dec!(1.0))));
+        assert_eq!(accounting.get_account(1).unwrap().total, dec!(0.0));
+        assert!(accounting.is_closed(1));
+        assert!(accounting
+            .close_account(1, ClosureDisposition::WriteOff)
+            .is_err());
+        assert!(accounting
+            .close_account(2, ClosureDisposition::Transfer(1))
+            .is_err());
+    }
+
+    // Ensure the closures are journaled, so compacting the journal moves the same funds;
+    let dir = std::env::temp_dir().join("tx_sim_close_account");
+    let _ = std::fs::remove_dir_all(&dir);
+    let mut accounting = Accounting::init();
+    let journal = Journal::open(dir.clone(), JournalConfig::default(), accounting.now())?;
+    accounting.attach_journal(journal)?;
+    accounting.try_process_transaction(tx(TxType::Deposit, 1, 1, Some(dec!(10.0))))?;
+    accounting.try_process_transaction(tx(TxType::Deposit, 3, 2, Some(dec!(2.0))))?;
+    accounting.close_account(1, ClosureDisposition::Transfer(2))?;
+    accounting.close_account(3, ClosureDisposition::WriteOff)?;
+    drop(accounting.detach_journal());
+
+    let snapshot_path = dir.join("snap.bin");
+    Journal::compact(&dir, snapshot_path.clone(), None, true, false)?;
+    let mut replayed = Accounting::init();
+    replayed.read_snapshot_file(snapshot_path)?;
+    std::fs::remove_dir_all(&dir)?;
+
+    for client in 1..=3 {
+        assert_eq!(
+            replayed.get_account(client).map(|account| account.total),
+            accounting.get_account(client).map(|account| account.total)
+        );
+        assert_eq!(replayed.is_closed(client), accounting.is_closed(client));
+    }
+    assert_eq!(debited(&replayed), debited(&accounting));
+
+    Ok(())
+}
+
+#[test]
+fn test_anonymize_csv_file() -> Result<(), Error> {
+    let dir = std::env::temp_dir();
+    let input = dir.join("tx_sim_anonymize_in.csv");
+    let output = dir.join("tx_sim_anonymize_out.csv");
+    std::fs::write(
+        &input,
+        "type,client,tx,amount\n\
+         deposit,1,1,100.0\n\
+         deposit,1,2,100.0\n\
+         deposit,2,3,40.0\n\
+         dispute,1,1,\n\
+         deposit,x,4\n",
+    )?;
+
+    let anonymizer = Anonymizer {
+        seed: 7,
+        ..Default::default()
+    };
+    assert_eq!(
+        anonymizer.anonymize_csv_file(input.clone(), output.clone())?,
+        5
+    );
+
+    let anonymized = std::fs::read_to_string(&output)?;
+    let rows: Vec<Vec<&str>> = anonymized
+        .lines()
+        .skip(1)
+        .map(|line| line.split(',').collect())
+        .collect();
+
+    // Ensure clients are rewritten consistently and structure is preserved;
+    assert_eq!(rows[0][1], rows[1][1]);
+    assert_eq!(rows[0][1], rows[3][1]);
+    assert_ne!(rows[0][1], rows[2][1]);
+    assert_eq!(
+        rows.iter().map(|row| (row[0], row[2])).collect::<Vec<_>>(),
+        vec![
+            ("deposit", "1"),
+            ("deposit", "2"),
+            ("deposit", "3"),
+            ("dispute", "1"),
+            ("deposit", "4")
+        ]
+    );
+
+    // Ensure amounts are perturbed within the tolerance, and duplicates remain duplicates;
+    let amount: f64 = rows[0][3].parse().unwrap();
+    assert!((95.0..=105.0).contains(&amount));
+    assert_eq!(rows[0][3], rows[1][3]);
+    assert_eq!(rows[3][3], "");
+
+    // Ensure malformed rows are copied unchanged;
+    assert_eq!(rows[4], vec!["deposit", "x", "4"]);
+
+    // Ensure the same seed produces the same output;
+    anonymizer.anonymize_csv_file(input.clone(), output.clone())?;
+    assert_eq!(std::fs::read_to_string(&output)?, anonymized);
+
+    std::fs::remove_file(input)?;
+    std::fs::remove_file(output)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_can_apply() {
+    let tx = |r#type, tx, amount| Transaction {
+        client: 1,
+        tx,
+        r#type,
+        amount,
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    };
+
+    let mut accounting = Accounting::init();
+    accounting.process_transaction(tx(TxType::Deposit, 1, Some(dec!(10.0))));
+
+    // Ensure the outcome of each check matches processing, and that no state is changed;
+    let withdrawal = tx(TxType::Withdrawal, 2, Some(dec!(15.0)));
+    assert_eq!(
+        accounting.can_apply(&withdrawal),
+        Err(TransactionError::InsufficientFunds {
+            available: dec!(10.0),
+            amount: dec!(15.0)
+        })
+    );
+    assert_eq!(
+        accounting.can_apply(&tx(TxType::Withdrawal, 2, None)),
+        Err(TransactionError::MissingAmount)
+    );
+    assert_eq!(
+        accounting.can_apply(&tx(TxType::Resolve, 1, None)),
+        Err(TransactionError::InvalidStateTransition {
+            state: TxState::Normal,
+            r#type: TxType::Resolve
+        })
+    );
+    assert_eq!(
+        accounting.can_apply(&tx(TxType::Dispute, 9, None)),
+        Err(TransactionError::UnknownTx)
+    );
+    assert_eq!(
+        accounting.can_apply(&tx(TxType::Release, 9, None)),
+        Err(TransactionError::UnknownReservation)
+    );
+
+    let withdrawal = tx(TxType::Withdrawal, 2, Some(dec!(5.0)));
+    assert_eq!(accounting.can_apply(&withdrawal), Ok(()));
+    assert_eq!(accounting.get_account(1).unwrap().available, dec!(10.0));
+
+    accounting.process_transaction(tx(TxType::Dispute, 1, None));
+    assert_eq!(
+        accounting.can_apply(&tx(TxType::Chargeback, 1, None)),
+        Ok(())
+    );
+    accounting.process_transaction(tx(TxType::Chargeback, 1, None));
+    assert_eq!(
+        accounting.can_apply(&tx(TxType::Deposit, 3, Some(dec!(1.0)))),
+        Err(TransactionError::AccountLocked)
+    );
+
+    // Ensure transactions of flagged clients report the quarantine that would divert them;
+    let mut quarantining = Accounting::with_config(AccountingConfig {
+        quarantine_flagged: true,
+        ..Default::default()
+    });
+    quarantining.process_transaction(tx(TxType::Deposit, 1, Some(dec!(10.0))));
+    quarantining.flag_account(1);
+    let withdrawal = tx(TxType::Withdrawal, 2, Some(dec!(5.0)));
+    assert_eq!(
+        quarantining.can_apply(&withdrawal),
+        Err(TransactionError::Quarantined)
+    );
+    assert_eq!(
+        quarantining.process_transaction(withdrawal),
+        Err(TransactionError::Quarantined)
+    );
+    assert_eq!(quarantining.quarantined_transactions().len(), 1);
+
+    // Ensure an open circuit breaker is reported, without tripping or feeding it further;
+    let mut breaking = Accounting::with_config(AccountingConfig {
+        circuit_breaker: Some(CircuitBreaker {
+            max_outflow: Some(dec!(5.0)),
+            action: BreakerAction::Quarantine,
+            ..Default::default()
+        }),
+        ..Default::default()
+    });
+    breaking.process_transaction(tx(TxType::Deposit, 1, Some(dec!(10.0))));
+    let withdrawal = tx(TxType::Withdrawal, 2, Some(dec!(6.0)));
+    assert_eq!(breaking.can_apply(&withdrawal), Ok(()));
+    assert!(breaking.circuit_breaker_trip().is_none());
+    assert_eq!(
+        breaking.process_transaction(withdrawal),
+        Ok(TxOutcome::Applied)
+    );
+
+    let deposit = tx(TxType::Deposit, 3, Some(dec!(1.0)));
+    assert_eq!(
+        breaking.can_apply(&deposit),
+        Err(TransactionError::CircuitOpen)
+    );
+    assert_eq!(
+        breaking.process_transaction(deposit),
+        Err(TransactionError::CircuitOpen)
+    );
+}
+
+#[test]
+fn test_held_aging() -> Result<(), Error> {
+    let start = 1_618_912_800;
+    let clock = MockClock::new(start);
+    let mut accounting = Accounting::init();
+    accounting.set_clock(Arc::new(clock.clone()));
+
+    let tx = |r#type, tx, amount, timestamp| Transaction {
+        client: 1,
+        tx,
+        r#type,
+        amount,
+        timestamp,
+        subaccount: None,
+        category: None,
+        source: None,
+    };
+    accounting.process_transaction(tx(TxType::Deposit, 1, Some(dec!(10.0)), None));
+    accounting.process_transaction(tx(TxType::Deposit, 2, Some(dec!(5.0)), None));
+    accounting.process_transaction(tx(TxType::Dispute, 1, None, Some(start)));
+
+    clock.advance(20 * SECONDS_PER_DAY);
+    let hold = accounting.place_hold(1, dec!(2.0), "review")?;
+    clock.advance(12 * SECONDS_PER_DAY);
+
+    let aging = accounting.held_aging(clock.now());
+    assert_eq!(
+        aging
+            .iter()
+            .map(|entry| (entry.kind, entry.id, entry.age_days, entry.bucket))
+            .collect::<Vec<_>>(),
+        vec![
+            (HeldKind::Dispute, 1, 32, AgingBucket::OverMonth),
+            (HeldKind::Hold, hold, 12, AgingBucket::UpToMonth),
+        ]
+    );
+    assert_eq!(AgingBucket::of(7), AgingBucket::UpToWeek);
+    assert_eq!(AgingBucket::of(8), AgingBucket::UpToMonth);
+
+    Ok(())
+}
+
+#[test]
+fn test_notifications() {
+    let day = 1_618_912_800;
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        notifications: Some(NotificationConfig {
+            large_withdrawal: Some(dec!(100.0)),
+        }),
+        ..Default::default()
+    });
+
+    let tx = |r#type, client, tx, amount, timestamp| Transaction {
+        client,
+        tx,
+        r#type,
+        amount,
+        timestamp: Some(timestamp),
+        subaccount: None,
+        category: None,
+        source: None,
+    };
+    accounting.process_transaction(tx(TxType::Deposit, 1, 1, Some(dec!(500.0)), day));
+    accounting.process_transaction(tx(TxType::Withdrawal, 1, 2, Some(dec!(150.0)), day));
+    accounting.process_transaction(tx(TxType::Withdrawal, 1, 3, Some(dec!(120.0)), day + 60));
+    accounting.process_transaction(tx(TxType::Withdrawal, 1, 4, Some(dec!(20.0)), day + 60));
+    accounting.process_transaction(tx(
+        TxType::Withdrawal,
+        1,
+        5,
+        Some(dec!(110.0)),
+        day + SECONDS_PER_DAY,
+    ));
+    accounting.process_transaction(tx(TxType::Deposit, 2, 6, Some(dec!(10.0)), day));
+    accounting.process_transaction(tx(TxType::Dispute, 2, 6, None, day));
+    accounting.process_transaction(tx(TxType::Chargeback, 2, 6, None, day));
+
+    // Ensure notifications are deduplicated per client, kind and day;
+    assert_eq!(
+        accounting
+            .notifications()
+            .iter()
+            .map(|notification| (notification.client, notification.kind, notification.tx))
+            .collect::<Vec<_>>(),
+        vec![
+            (1, NotificationKind::LargeWithdrawal, Some(2)),
+            (1, NotificationKind::LargeWithdrawal, Some(5)),
+            (2, NotificationKind::Lock, Some(6)),
+            (2, NotificationKind::Chargeback, Some(6)),
+        ]
+    );
+
+    // Ensure no notifications are recorded by default;
+    let mut accounting = Accounting::init();
+    accounting.process_transaction(tx(TxType::Deposit, 2, 6, Some(dec!(10.0)), day));
+    accounting.process_transaction(tx(TxType::Dispute, 2, 6, None, day));
+    accounting.process_transaction(tx(TxType::Chargeback, 2, 6, None, day));
+    assert!(accounting.notifications().is_empty());
+}
+
+#[test]
+fn test_interest_accrual() {
+    let day = 1_618_876_800;
+    let deposit = |client, tx, amount, timestamp| Transaction {
+        client,
+        tx,
+        r#type: TxType::Deposit,
+        amount: Some(amount),
+        timestamp: Some(timestamp),
+        subaccount: None,
+        category: None,
+        source: None,
+    };
+
+    // Ensure interest compounds daily, posted as tagged deposits;
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        interest: Some(InterestConfig {
+            daily_rate: dec!(0.01),
+            compound_days: 1,
+        }),
+        ..Default::default()
+    });
+    accounting.process_transaction(deposit(1, 1, dec!(100.0), day));
+    accounting.process_transaction(deposit(1, 2, dec!(1.0), day + 2 * SECONDS_PER_DAY));
+    let account = accounting.get_account(1).unwrap();
+    assert_eq!(account.available, dec!(103.01));
+    assert_eq!(accounting.interest_postings().len(), 2);
+    let posting = &accounting.interest_postings()[0];
+    assert_eq!(posting.category.as_deref(), Some(INTEREST_CATEGORY));
+    assert_eq!(posting.amount, Some(dec!(1.0)));
+    assert_eq!(posting.timestamp, Some(day + SECONDS_PER_DAY));
+
+    // Ensure interest is posted once per compounding period;
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        interest: Some(InterestConfig {
+            daily_rate: dec!(0.01),
+            compound_days: 30,
+        }),
+        ..Default::default()
+    });
+    accounting.process_transaction(deposit(1, 1, dec!(100.0), day));
+    accounting.accrue_all_interest(day + 10 * SECONDS_PER_DAY);
+    assert!(accounting.interest_postings().is_empty());
+    accounting.accrue_all_interest(day + 40 * SECONDS_PER_DAY);
+    assert_eq!(accounting.interest_postings().len(), 1);
+    assert!(accounting.get_account(1).unwrap().available > dec!(100.0));
+}
+
+#[test]
+fn test_write_accounts_csv() -> Result<(), Error> {
+    let mut accounting = Accounting::init();
+    accounting.process_transaction(Transaction {
+        r#type: TxType::Deposit,
+        client: 1,
+        tx: 1,
+        amount: Some(dec!(1.23456)),
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    });
+
+    let mut output = Vec::new();
+    accounting.write_accounts_csv(&mut output)?;
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "client,available,held,total,locked\n1,1.2346,0.0,1.2346,false\n"
+    );
+
+    // Ensure the file writer produces the same table;
+    let file_path = std::env::temp_dir().join("tx_sim_write_accounts_csv.csv");
+    accounting.write_accounts_csv_file(file_path.clone())?;
+    assert_eq!(
+        std::fs::read_to_string(&file_path)?,
+        "client,available,held,total,locked\n1,1.2346,0.0,1.2346,false\n"
+    );
+    std::fs::remove_file(&file_path)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_write_accounts_csv_to_writers_and_files() -> Result<(), Error> {
+    let mut accounting = Accounting::init();
+    for tx in Accounting::generate_dummy_transactions(200, 5)? {
+        accounting.process_transaction(tx);
+    }
+    accounting.lock_account(3, true);
+
+    // Ensure a file receives the same table as any other writer, replacing a longer existing file;
+    let mut output = Vec::new();
+    accounting.write_accounts_csv(&mut output)?;
+    let output = String::from_utf8(output).unwrap();
+
+    let file_path = std::env::temp_dir().join("tx_sim_write_accounts_csv_file.csv");
+    std::fs::write(&file_path, output.repeat(2))?;
+    accounting.write_accounts_csv_file(file_path.clone())?;
+    let written = std::fs::read_to_string(&file_path)?;
+    std::fs::remove_file(&file_path)?;
+
+    assert_eq!(written, output);
+    assert_eq!(written.lines().count(), accounting.accounts().count() + 1);
+    assert!(written
+        .lines()
+        .any(|line| line.starts_with("3,") && line.ends_with(",true")));
+
+    // Ensure write errors are returned rather than dropped;
+    struct Failing;
+    impl std::io::Write for Failing {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::BrokenPipe,
+                "closed",
+            ))
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    assert!(accounting.write_accounts_csv(Failing).is_err());
+    assert!(accounting
+        .write_accounts_csv_file(std::env::temp_dir().join("tx_sim_missing_dir/accounts.csv"))
+        .is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_decoded_reader() -> Result<(), Error> {
+    use std::io::Read;
+
+    /// Reader returning a single byte per read, splitting every code unit across reads;
+    struct ByteReader(std::vec::IntoIter<u8>);
+
+    impl Read for ByteReader {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            match self.0.next() {
+                Some(byte) if !buf.is_empty() => {
+                    buf[0] = byte;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    let text = "type,client,tx,amount,timestamp,category\ndeposit,1,1,2.5,,caf\u{e9} \u{1f600}\n";
+    let decode = |bytes: Vec<u8>, encoding| -> Result<String, Error> {
+        let mut decoded = String::new();
+        DecodedReader::new(ByteReader(bytes.into_iter()), encoding).read_to_string(&mut decoded)?;
+        Ok(decoded)
+    };
+
+    let utf16le: Vec<u8> = vec![0xFF, 0xFE]
+        .into_iter()
+        .chain(text.encode_utf16().flat_map(u16::to_le_bytes))
+        .collect();
+    let utf16be: Vec<u8> = text.encode_utf16().flat_map(u16::to_be_bytes).collect();
+    let utf8_bom: Vec<u8> = vec![0xEF, 0xBB, 0xBF]
+        .into_iter()
+        .chain(text.bytes())
+        .collect();
+
+    // Ensure encodings are detected from byte order marks and NUL bytes, and decoded transparently;
+    assert_eq!(decode(utf16le.clone(), None)?, text);
+    assert_eq!(decode(utf16be, None)?, text);
+    assert_eq!(decode(utf8_bom, None)?, text);
+    assert_eq!(decode(text.as_bytes().to_vec(), None)?, text);
+    assert_eq!(
+        decode(vec![b'c', b'a', b'f', 0xE9], Some(Encoding::Latin1))?,
+        "caf\u{e9}"
+    );
+
+    // Ensure decoded files are processed;
+    let file_path = std::env::temp_dir().join("tx_sim_utf16.csv");
+    std::fs::write(&file_path, utf16le)?;
+    let mut accounting = Accounting::init();
+    accounting.read_transactions_csv_file(file_path.clone())?;
+    assert_eq!(accounting.get_account(1).unwrap().total, dec!(2.5));
+    std::fs::remove_file(file_path)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_resource_report() {
+    let mut accounting = Accounting::init();
+    for tx in 1..=3 {
+        accounting.process_transaction(Transaction {
+            r#type: TxType::Deposit,
+            client: tx as ClientId,
+            tx,
+            amount: Some(dec!(10)),
+            timestamp: None,
+            subaccount: None,
+            category: None,
+            source: None,
+        });
+    }
+
+    let report = accounting.resource_report();
+    assert_eq!(report.transactions_processed, 3);
+    assert_eq!(report.transactions_retained, 3);
+    assert_eq!(report.accounts, 3);
+    assert!(report.estimated_bytes() > 0);
+    // Ensure empty collections are omitted;
+    assert!(report.collections.iter().all(|usage| usage.entries > 0));
+
+    #[cfg(target_os = "linux")]
+    assert!(report.peak_rss.is_some());
+
+    assert_eq!(format_bytes(512), "512 B");
+    assert_eq!(format_bytes(1536), "1.5 KiB");
+    assert_eq!(format_bytes(3 * 1024 * 1024), "3.0 MiB");
+}
+
+#[test]
+fn test_tx_state_transitions() {
+    let tx = |r#type, tx| Transaction {
+        client: 1,
+        tx,
+        r#type,
+        amount: None,
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    };
+
+    let mut accounting = Accounting::init();
+    accounting.process_transaction(Transaction {
+        amount: Some(dec!(10)),
+        ..tx(TxType::Deposit, 1)
+    });
+    accounting.process_transaction(Transaction {
+        amount: Some(dec!(5)),
+        ..tx(TxType::Deposit, 2)
+    });
+    assert_eq!(accounting.tx_state(1), Some(TxState::Normal));
+
+    // Ensure a resolve or chargeback without a prior dispute is rejected and holds nothing;
+    assert_eq!(
+        accounting.process_transaction(tx(TxType::Resolve, 1)),
+        Err(TransactionError::InvalidStateTransition {
+            state: TxState::Normal,
+            r#type: TxType::Resolve
+        })
+    );
+    assert_eq!(
+        accounting.process_transaction(tx(TxType::Chargeback, 1)),
+        Err(TransactionError::InvalidStateTransition {
+            state: TxState::Normal,
+            r#type: TxType::Chargeback
+        })
+    );
+
+    // Ensure a resolved transaction can be disputed again;
+    accounting.process_transaction(tx(TxType::Dispute, 1));
+    assert_eq!(accounting.tx_state(1), Some(TxState::Disputed));
+    accounting.process_transaction(tx(TxType::Resolve, 1));
+    assert_eq!(accounting.tx_state(1), Some(TxState::Resolved));
+    assert_eq!(
+        accounting.process_transaction(tx(TxType::Resolve, 1)),
+        Err(TransactionError::InvalidStateTransition {
+            state: TxState::Resolved,
+            r#type: TxType::Resolve
+        })
+    );
+    accounting.process_transaction(tx(TxType::Dispute, 1));
+    accounting.process_transaction(tx(TxType::Chargeback, 1));
+    assert_eq!(accounting.tx_state(1), Some(TxState::ChargedBack));
+
+    // Ensure a charged back transaction cannot be charged back again, even with other funds held;
+    accounting.lock_account(1, false);
+    accounting.process_transaction(tx(TxType::Dispute, 2));
+    assert_eq!(
+        accounting.process_transaction(tx(TxType::Chargeback, 1)),
+        Err(TransactionError::InvalidStateTransition {
+            state: TxState::ChargedBack,
+            r#type: TxType::Chargeback
+        })
+    );
+    let account = accounting.get_account(1).unwrap();
+    assert_eq!(
+        (account.available, account.held, account.total),
+        (dec!(0), dec!(5), dec!(5))
+    );
+
+    // Ensure the state survives a snapshot round-trip;
+    let mut restored = Accounting::init();
+    restored.restore_snapshot(accounting.snapshot());
+    assert_eq!(restored.tx_state(1), Some(TxState::ChargedBack));
+    assert_eq!(restored.tx_state(2), Some(TxState::Disputed));
+}
+
+#[test]
+fn test_double_dispute() -> Result<(), Error> {
+    let tx = |r#type, amount| Transaction {
+        client: 1,
+        tx: 1,
+        r#type,
+        amount,
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    };
+
+    let mut accounting = Accounting::init();
+    accounting.process_transaction(tx(TxType::Deposit, Some(dec!(10))));
+    accounting.process_transaction(Transaction {
+        tx: 2,
+        ..tx(TxType::Deposit, Some(dec!(10)))
+    });
+
+    // Ensure the second dispute is ignored and the amount is only held once;
+    assert_eq!(
+        accounting.process_transaction(tx(TxType::Dispute, None)),
+        Ok(TxOutcome::Applied)
+    );
+    assert_eq!(
+        accounting.can_apply(&tx(TxType::Dispute, None)),
+        Err(TransactionError::AlreadyDisputed)
+    );
+    assert_eq!(
+        accounting.process_transaction(tx(TxType::Dispute, None)),
+        Err(TransactionError::AlreadyDisputed)
+    );
+    let account = accounting.get_account(1).unwrap();
+    assert_eq!((account.available, account.held), (dec!(10), dec!(10)));
+
+    // Ensure a single resolve releases the dispute;
+    accounting.process_transaction(tx(TxType::Resolve, None));
+    let account = accounting.get_account(1).unwrap();
+    assert_eq!((account.available, account.held), (dec!(20), dec!(0)));
+
+    // Ensure repeated disputes are reported as errors in strict mode;
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        strictness: Strictness::Strict,
+        ..Default::default()
+    });
+    accounting.try_process_transaction(tx(TxType::Deposit, Some(dec!(10))))?;
+    accounting.try_process_transaction(tx(TxType::Dispute, None))?;
+    let err = accounting
+        .try_process_transaction(tx(TxType::Dispute, None))
+        .unwrap_err();
+    assert_eq!(err.code(), "E2008");
+
+    Ok(())
+}
+
+#[test]
+fn test_config_validation() {
+    assert!(AccountingConfig::default().validate().is_ok());
+
+    let field = |config: AccountingConfig| match config.validate() {
+        Err(Error::InvalidConfig { field, .. }) => Some(field),
+        _ => None,
+    };
+
+    // Ensure nonsensical limits are reported with the offending field;
+    assert_eq!(
+        field(AccountingConfig {
+            amount_epsilon: dec!(-0.1),
+            ..Default::default()
+        }),
+        Some("amount_epsilon")
+    );
+    assert_eq!(
+        field(AccountingConfig {
+            max_open_disputes: Some(0),
+            ..Default::default()
+        }),
+        Some("max_open_disputes")
+    );
+    assert_eq!(
+        field(AccountingConfig {
+            interest: Some(InterestConfig {
+                daily_rate: dec!(0.01),
+                compound_days: 0,
+            }),
+            ..Default::default()
+        }),
+        Some("interest.compound_days")
+    );
+
+    // Ensure conflicting options are reported;
+    assert_eq!(
+        field(AccountingConfig {
+            dispute_cap_policy: DisputeCapPolicy::Flag,
+            ..Default::default()
+        }),
+        Some("dispute_cap_policy")
+    );
+    let mut config = AccountingConfig::default();
+    config.input.comment = Some(b',');
+    assert_eq!(field(config), Some("input.comment"));
+
+    let error = AccountingConfig {
+        max_open_disputes: Some(0),
+        ..Default::default()
+    }
+    .validate()
+    .unwrap_err();
+    assert_eq!(error.code(), "E1006");
+    assert!(error
+        .to_string()
+        .starts_with("Invalid config `max_open_disputes`"));
+}
+
+#[test]
+fn test_prelude() {
+    use crate::prelude::*;
+
+    let mut accounting = Accounting::with_config(AccountingConfig::default());
+    let outcome = accounting.process_transaction(Transaction {
+        r#type: TxType::Deposit,
+        client: 1,
+        tx: 1,
+        amount: Some(Decimal::new(25, 1)),
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    });
+    assert_eq!(outcome, Ok(TxOutcome::Applied));
+    assert_eq!(accounting.tx_state(1), Some(TxState::Normal));
+    let account: &Account = accounting.get_account(1).unwrap();
+    assert_eq!(account.total, dec!(2.5));
+}
+
+#[cfg(feature = "internals")]
+#[test]
+fn test_internals() {
+    use crate::internals::AccountingInternals;
+
+    // Ensure custom handlers can reuse the low-level helpers, e.g. to credit a bonus as a deposit;
+    let mut accounting = Accounting::init();
+    let bonus = accounting.register_tx_type("internals_bonus").unwrap();
+    accounting.register_handler(
+        bonus,
+        |accounting: &mut Accounting, account: &mut Account, tx: &Transaction| {
+            AccountingInternals::process_deposit(accounting, account, tx)
+        },
+    );
+    accounting.process_transaction(Transaction {
+        r#type: bonus,
+        client: 1,
+        tx: 1,
+        amount: Some(dec!(5)),
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    });
+    assert_eq!(accounting.get_account(1).unwrap().available, dec!(5));
+}
+
+#[test]
+fn test_duplicate_tx_ids() -> Result<(), Error> {
+    let tx = |r#type, client, tx, amount| Transaction {
+        client,
+        tx,
+        r#type,
+        amount,
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    };
+
+    let mut accounting = Accounting::init();
+    accounting.process_transaction(tx(TxType::Deposit, 1, 1, Some(dec!(10))));
+
+    // Ensure a reused id is rejected instead of replacing the recorded transaction;
+    assert_eq!(
+        accounting.can_apply(&tx(TxType::Deposit, 2, 1, Some(dec!(99)))),
+        Err(TransactionError::DuplicateTx)
+    );
+    assert_eq!(
+        accounting.process_transaction(tx(TxType::Deposit, 2, 1, Some(dec!(99)))),
+        Err(TransactionError::DuplicateTx)
+    );
+    assert!(accounting.get_account(2).unwrap().total.is_zero());
+
+    // Ensure disputes still reference the first record;
+    accounting.process_transaction(tx(TxType::Dispute, 1, 1, None));
+    assert_eq!(accounting.get_account(1).unwrap().held, dec!(10));
+
+    assert_eq!(
+        accounting.duplicate_tx_ids(),
+        &[DuplicateTxId {
+            r#type: TxType::Deposit,
+            client: 2,
+            tx: 1,
+            amount: Some(dec!(99)),
+            original_type: TxType::Deposit,
+            original_client: 1,
+        }][..]
+    );
+
+    let file_path = std::env::temp_dir().join("tx_sim_duplicate_ids.csv");
+    accounting.write_duplicate_tx_ids_csv_file(file_path.clone())?;
+    assert_eq!(
+        std::fs::read_to_string(&file_path)?,
+        "type,client,tx,amount,original_type,original_client\ndeposit,2,1,99,deposit,1\n"
+    );
+    std::fs::remove_file(file_path)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_network_adjustments() -> Result<(), Error> {
+    let mut accounting = Accounting::init();
+    for (client, tx) in [(1, 1), (2, 2)].iter().copied() {
+        accounting.process_transaction(Transaction {
+            client,
+            tx,
+            r#type: TxType::Deposit,
+            amount: Some(dec!(10)),
+            timestamp: None,
+            subaccount: None,
+            category: None,
+            source: None,
+        });
+    }
+
+    let file_path = std::env::temp_dir().join("tx_sim_network_adjustments.csv");
+    std::fs::write(
+        &file_path,
+        "tx, action, amount\n1, dispute,\n1, chargeback,\n2, dispute,\n2, resolve,\n1, reversal, 4\n9, dispute,\n2, reversal,\n",
+    )?;
+
+    // Ensure unknown transactions and invalid reversals are skipped;
+    assert_eq!(
+        accounting.read_network_adjustments_csv_file(file_path.clone())?,
+        5
+    );
+    std::fs::remove_file(file_path)?;
+
+    // Ensure the reversal credits the client back, and the account stays locked;
+    let account = accounting.get_account(1).unwrap();
+    assert_eq!((account.available, account.total), (dec!(4), dec!(4)));
+    assert!(account.locked);
+    assert_eq!(accounting.tx_state(1), Some(TxState::Resolved));
+
+    let account = accounting.get_account(2).unwrap();
+    assert_eq!((account.available, account.held), (dec!(10), dec!(0)));
+    assert!(accounting.reverse_chargeback(2, None).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_reversal_capped_at_charged_back_amount() -> Result<(), Error> {
+    let tx = |r#type, amount| Transaction {
+        client: 1,
+        tx: 1,
+        r#type,
+        amount,
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    };
+    let dir = std::env::temp_dir().join("tx_sim_reversal_journal");
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        partial_disputes: true,
+        ..Default::default()
+    });
+    accounting.attach_journal(Journal::open(dir.clone(), JournalConfig::default(), 0)?)?;
+    accounting.process_transaction(tx(TxType::Deposit, Some(dec!(10))));
+    accounting.process_transaction(tx(TxType::Dispute, Some(dec!(4))));
+    accounting.process_transaction(tx(TxType::Chargeback, None));
+
+    // Ensure the reversal is capped at the partial amount charged back, also after a snapshot;
+    let mut restored = Accounting::init();
+    restored.restore_snapshot(accounting.snapshot());
+    assert!(restored.reverse_chargeback(1, Some(dec!(5))).is_err());
+    assert!(accounting.reverse_chargeback(1, Some(dec!(5))).is_err());
+    assert_eq!(accounting.reverse_chargeback(1, None)?, dec!(4));
+
+    let account = accounting.get_account(1).unwrap();
+    assert_eq!((account.available, account.total), (dec!(10), dec!(10)));
+    assert!(account.locked);
+    assert_eq!(
+        accounting.can_apply(&tx(TxType::Reversal, None)),
+        Err(TransactionError::InvalidStateTransition {
+            state: TxState::Resolved,
+            r#type: TxType::Reversal
+        })
+    );
+
+    // Ensure the applied reversal is journaled;
+    let mut journal = accounting.detach_journal().unwrap();
+    journal.flush()?;
+    let segment = journal.segments()[0].clone();
+    let journaled = Journal::read_segment(&dir, &segment)?;
+    assert_eq!(journaled.last().map(|tx| tx.r#type), Some(TxType::Reversal));
+    drop(journal);
+    std::fs::remove_dir_all(&dir)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_non_positive_amounts() {
+    let tx = |r#type, tx, amount| Transaction {
+        client: 1,
+        tx,
+        r#type,
+        amount: Some(amount),
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    };
+
+    let mut accounting = Accounting::init();
+    accounting.process_transaction(tx(TxType::Deposit, 1, dec!(10)));
+
+    // Ensure negative and zero amounts are ignored and cannot drive balances negative;
+    assert_eq!(
+        accounting.process_transaction(tx(TxType::Deposit, 2, dec!(-50))),
+        Err(TransactionError::InvalidAmount(dec!(-50)))
+    );
+    assert_eq!(
+        accounting.process_transaction(tx(TxType::Withdrawal, 3, dec!(-5))),
+        Err(TransactionError::InvalidAmount(dec!(-5)))
+    );
+    assert_eq!(
+        accounting.can_apply(&tx(TxType::Withdrawal, 4, dec!(0))),
+        Err(TransactionError::InvalidAmount(dec!(0)))
+    );
+    assert_eq!(
+        accounting.process_transaction(tx(TxType::Withdrawal, 4, dec!(0))),
+        Err(TransactionError::InvalidAmount(dec!(0)))
+    );
+    assert_eq!(accounting.tx_state(2), None);
+    let account = accounting.get_account(1).unwrap();
+    assert_eq!((account.available, account.total), (dec!(10), dec!(10)));
+
+    // Ensure strict mode reports them as errors;
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        strictness: Strictness::Strict,
+        ..Default::default()
+    });
+    let err = accounting
+        .try_process_transaction(tx(TxType::Deposit, 1, dec!(-1)))
+        .unwrap_err();
+    assert!(matches!(err, Error::InvalidAmount { tx: 1, .. }));
+    assert_eq!(err.code(), "E2009");
+}
+
+#[test]
+fn test_cross_client_disputes() {
+    let tx = |r#type, client, amount| Transaction {
+        client,
+        tx: 1,
+        r#type,
+        amount,
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    };
+
+    let mut accounting = Accounting::init();
+    accounting.process_transaction(tx(TxType::Deposit, 1, Some(dec!(10))));
+    accounting.process_transaction(Transaction {
+        tx: 2,
+        ..tx(TxType::Deposit, 2, Some(dec!(10)))
+    });
+
+    // Ensure a dispute of another client's transaction leaves both accounts untouched;
+    assert_eq!(
+        accounting.can_apply(&tx(TxType::Dispute, 2, None)),
+        Err(TransactionError::ClientMismatch(1))
+    );
+    assert_eq!(
+        accounting.process_transaction(tx(TxType::Dispute, 2, None)),
+        Err(TransactionError::ClientMismatch(1))
+    );
+    for client in 1..=2 {
+        let account = accounting.get_account(client).unwrap();
+        assert_eq!((account.available, account.held), (dec!(10), dec!(0)));
+    }
+    assert_eq!(accounting.tx_state(1), Some(TxState::Normal));
+
+    // Ensure resolves and chargebacks of another client's dispute are ignored;
+    accounting.process_transaction(tx(TxType::Dispute, 1, None));
+    assert_eq!(
+        accounting.process_transaction(tx(TxType::Chargeback, 2, None)),
+        Err(TransactionError::ClientMismatch(1))
+    );
+    assert_eq!(
+        accounting.process_transaction(tx(TxType::Resolve, 2, None)),
+        Err(TransactionError::ClientMismatch(1))
+    );
+    assert!(!accounting.get_account(2).unwrap().locked);
+    assert_eq!(accounting.get_account(1).unwrap().held, dec!(10));
+}
+
+#[test]
+fn test_scenario_script() -> Result<(), Error> {
+    let script = ScenarioScript::from_reader(
+        "# clearing is driven by the script clock\n\
+         deposit, 1, 1, 10.0\n\
+         expect-balance, 1, 0, 10.0, 10.0\n\
+         advance-clock, 100\n\
+         deposit, 1, 2, 1.0\n\
+         expect-balance, 1, 10.0, 1.0, 11.0\n\
+         expect-locked, 1, true\n"
+            .as_bytes(),
+    )?;
+    assert_eq!(script.steps.len(), 6);
+    assert_eq!(script.steps[2], (4, ScriptStep::AdvanceClock(100)));
+
+    let report = script.run(AccountingConfig {
+        clearing_delay: Some(100),
+        ..Default::default()
+    });
+    assert_eq!((report.steps, report.expectations), (6, 3));
+    assert!(!report.passed());
+    assert_eq!(
+        report.failures,
+        vec![ScriptFailure {
+            line: 7,
+            message: "expected client 1 locked true, got false".to_string(),
+        }]
+    );
+
+    // Ensure invalid rows name the line;
+    let err =
+        ScenarioScript::from_reader("deposit, 1, 1, 1.0\nexpect-locked, 1, maybe\n".as_bytes())
+            .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Invalid argument: line 2: invalid locked 'maybe'"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_input_limits() -> Result<(), Error> {
+    let file_path = std::env::temp_dir().join("tx_sim_input_limits.csv");
+    std::fs::write(
+        &file_path,
+        "type,client,tx,amount\ndeposit,1,1,1.0\ndeposit,1,2,1.0\ndeposit,1,3,1.0\n",
+    )?;
+
+    // Ensure processing aborts, even in lenient mode, once the row limit is exceeded;
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        strictness: Strictness::Lenient,
+        max_rows: Some(2),
+        ..Default::default()
+    });
+    let err = accounting
+        .read_transactions_csv_file(file_path.clone())
+        .unwrap_err();
+    assert_eq!(err.code(), "E1007");
+    assert_eq!(err.to_string(), "Input exceeds the row count limit of 2");
+    assert_eq!(accounting.get_account(1).unwrap().total, dec!(2));
+
+    // Ensure oversized files are refused before any row is read;
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        max_file_size: Some(16),
+        ..Default::default()
+    });
+    assert!(matches!(
+        accounting.read_transactions_csv_file(file_path.clone()),
+        Err(Error::InputLimitExceeded {
+            limit: "file size",
+            max: 16
+        })
+    ));
+    assert!(accounting.get_account(1).is_none());
+
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        max_rows: Some(3),
+        max_file_size: Some(1024),
+        ..Default::default()
+    });
+    accounting.read_transactions_csv_file(file_path.clone())?;
+    assert_eq!(accounting.get_account(1).unwrap().total, dec!(3));
+    std::fs::remove_file(file_path)?;
+
+    assert_eq!(parse_bytes("512"), Some(512));
+    assert_eq!(parse_bytes("64K"), Some(64 * 1024));
+    assert_eq!(parse_bytes("2g"), Some(2 << 30));
+    assert_eq!(parse_bytes("ten"), None);
+
+    Ok(())
+}
+
+#[test]
+fn test_withdrawal_disputes() {
+    let tx = |r#type, tx, amount| Transaction {
+        client: 1,
+        tx,
+        r#type,
+        amount,
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    };
+    let balances = |accounting: &Accounting| {
+        let account = accounting.get_account(1).unwrap();
+        (
+            account.available,
+            account.held,
+            account.total,
+            account.locked,
+        )
+    };
+
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        withdrawal_dispute_policy: WithdrawalDisputePolicy::Credit,
+        ..Default::default()
+    });
+    accounting.process_transaction(tx(TxType::Deposit, 1, Some(dec!(100))));
+    accounting.process_transaction(tx(TxType::Withdrawal, 2, Some(dec!(80))));
+    accounting.process_transaction(tx(TxType::Withdrawal, 3, Some(dec!(10))));
+
+    // Ensure a disputed withdrawal is credited back into held funds, even beyond available funds;
+    assert_eq!(
+        accounting.process_transaction(tx(TxType::Dispute, 2, None)),
+        Ok(TxOutcome::Applied)
+    );
+    assert_eq!(balances(&accounting), (dec!(10), dec!(80), dec!(90), false));
+
+    // Ensure a resolve removes the credit, as the withdrawal stands;
+    accounting.process_transaction(tx(TxType::Resolve, 2, None));
+    assert_eq!(balances(&accounting), (dec!(10), dec!(0), dec!(10), false));
+
+    // Ensure a chargeback returns the withdrawal to the client's available funds;
+    accounting.process_transaction(tx(TxType::Dispute, 3, None));
+    assert_eq!(balances(&accounting), (dec!(10), dec!(10), dec!(20), false));
+    accounting.process_transaction(tx(TxType::Chargeback, 3, None));
+    assert_eq!(balances(&accounting), (dec!(20), dec!(0), dec!(20), true));
+
+    // Ensure the reversal of a withdrawal chargeback debits the returned funds;
+    accounting.reverse_chargeback(3, None).unwrap();
+    assert_eq!(balances(&accounting), (dec!(10), dec!(0), dec!(10), true));
+
+    // Ensure a withdrawal ignored for insufficient funds is recorded as rejected, so a dispute and
+    // chargeback cannot credit money that never left the account;
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        withdrawal_dispute_policy: WithdrawalDisputePolicy::Credit,
+        ..Default::default()
+    });
+    accounting.process_transaction(tx(TxType::Deposit, 1, Some(dec!(5))));
+    accounting.process_transaction(tx(TxType::Withdrawal, 2, Some(dec!(100))));
+    assert_eq!(accounting.tx_state(2), Some(TxState::Rejected));
+    assert_eq!(
+        accounting.process_transaction(tx(TxType::Dispute, 2, None)),
+        Err(TransactionError::InvalidStateTransition {
+            state: TxState::Rejected,
+            r#type: TxType::Dispute
+        })
+    );
+    accounting.process_transaction(tx(TxType::Chargeback, 2, None));
+    assert_eq!(balances(&accounting), (dec!(5), dec!(0), dec!(5), false));
+
+    // Ensure withdrawal disputes are ignored by default;
+    let mut accounting = Accounting::init();
+    accounting.process_transaction(tx(TxType::Deposit, 1, Some(dec!(100))));
+    accounting.process_transaction(tx(TxType::Withdrawal, 2, Some(dec!(80))));
+    assert_eq!(
+        accounting.can_apply(&tx(TxType::Dispute, 2, None)),
+        Err(TransactionError::WithdrawalDispute)
+    );
+    assert_eq!(
+        accounting.process_transaction(tx(TxType::Dispute, 2, None)),
+        Err(TransactionError::WithdrawalDispute)
+    );
+    assert_eq!(balances(&accounting), (dec!(20), dec!(0), dec!(20), false));
+    assert_eq!(
+        "credit".parse::<WithdrawalDisputePolicy>().unwrap(),
+        WithdrawalDisputePolicy::Credit
+    );
+}
+
+#[test]
+fn test_reserve_floors() {
+    let tx = |r#type, tx, amount| Transaction {
+        client: 1,
+        tx,
+        r#type,
+        amount,
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    };
+
+    let mut accounting = Accounting::init();
+    accounting.set_reserve(1, dec!(50)).unwrap();
+    assert_eq!(accounting.reserve_floor(1), dec!(50));
+    assert_eq!(accounting.reserve_floor(2), dec!(0));
+    assert!(accounting.set_reserve(1, dec!(-1)).is_err());
+
+    accounting.process_transaction(tx(TxType::Deposit, 1, Some(dec!(100))));
+
+    // Ensure withdrawals may not take available funds below the reserve floor;
+    let rejection = TransactionError::BelowReserveFloor {
+        available: dec!(100),
+        floor: dec!(50),
+        amount: dec!(60),
+    };
+    assert_eq!(
+        accounting.can_apply(&tx(TxType::Withdrawal, 2, Some(dec!(60)))),
+        Err(rejection.clone())
+    );
+    assert_eq!(
+        accounting.process_transaction(tx(TxType::Withdrawal, 2, Some(dec!(60)))),
+        Err(rejection)
+    );
+    assert_eq!(
+        accounting.process_transaction(tx(TxType::Withdrawal, 3, Some(dec!(50)))),
+        Ok(TxOutcome::Applied)
+    );
+    assert_eq!(accounting.get_account(1).unwrap().available, dec!(50));
+
+    // Ensure removing the floor releases the reserve;
+    accounting.set_reserve(1, dec!(0)).unwrap();
+    assert_eq!(accounting.reserve_floors().count(), 0);
+    assert_eq!(
+        accounting.process_transaction(tx(TxType::Withdrawal, 4, Some(dec!(50)))),
+        Ok(TxOutcome::Applied)
+    );
+}
+
+#[test]
+fn test_dispute_funds_policy() {
+    let tx = |r#type, tx, amount| Transaction {
+        client: 1,
+        tx,
+        r#type,
+        amount,
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    };
+    let run = |policy| {
+        let mut accounting = Accounting::with_config(AccountingConfig {
+            dispute_funds_policy: policy,
+            ..Default::default()
+        });
+        accounting.process_transaction(tx(TxType::Deposit, 1, Some(dec!(100))));
+        accounting.process_transaction(tx(TxType::Withdrawal, 2, Some(dec!(80))));
+        accounting
+    };
+
+    // Ensure disputes exceeding the available funds are ignored by default;
+    let mut accounting = run(DisputeFundsPolicy::Ignore);
+    assert_eq!(
+        accounting.process_transaction(tx(TxType::Dispute, 1, None)),
+        Err(TransactionError::InsufficientFunds {
+            available: dec!(20),
+            amount: dec!(100)
+        })
+    );
+    assert_eq!(accounting.get_account(1).unwrap().held, dec!(0));
+
+    // Ensure the hold policy holds the disputed amount, letting available funds go negative;
+    let mut accounting = run(DisputeFundsPolicy::Hold);
+    assert_eq!(accounting.can_apply(&tx(TxType::Dispute, 1, None)), Ok(()));
+    assert_eq!(
+        accounting.process_transaction(tx(TxType::Dispute, 1, None)),
+        Ok(TxOutcome::Applied)
+    );
+    let account = accounting.get_account(1).unwrap();
+    assert_eq!(
+        (account.available, account.held, account.total),
+        (dec!(-80), dec!(100), dec!(20))
+    );
+    assert!(accounting.check_invariants().is_ok());
+
+    accounting.process_transaction(tx(TxType::Chargeback, 1, None));
+    let account = accounting.get_account(1).unwrap();
+    assert_eq!(
+        (
+            account.available,
+            account.held,
+            account.total,
+            account.locked
+        ),
+        (dec!(-80), dec!(0), dec!(-80), true)
+    );
+    assert_eq!(
+        "hold".parse::<DisputeFundsPolicy>().unwrap(),
+        DisputeFundsPolicy::Hold
+    );
+}
+
+#[test]
+fn test_dispute_expiry() {
+    let tx = |r#type, tx, amount, day: Timestamp| Transaction {
+        client: 1,
+        tx,
+        r#type,
+        amount,
+        timestamp: Some(day * SECONDS_PER_DAY),
+        subaccount: None,
+        category: None,
+        source: None,
+    };
+    let run = |action| {
+        let mut accounting = Accounting::with_config(AccountingConfig {
+            dispute_expiry: Some(DisputeExpiry { days: 7, action }),
+            ..Default::default()
+        });
+        accounting.process_transaction(tx(TxType::Deposit, 1, Some(dec!(100)), 0));
+        accounting.process_transaction(tx(TxType::Deposit, 2, Some(dec!(50)), 0));
+        accounting.process_transaction(tx(TxType::Dispute, 1, None, 1));
+        accounting.process_transaction(tx(TxType::Dispute, 2, None, 5));
+        accounting
+    };
+
+    // Ensure disputes open for the expiry period are resolved before a later transaction;
+    let mut accounting = run(DisputeExpiryAction::Resolve);
+    accounting.process_transaction(tx(TxType::Deposit, 3, Some(dec!(10)), 7));
+    assert_eq!(accounting.get_account(1).unwrap().held, dec!(150));
+    accounting.process_transaction(tx(TxType::Deposit, 4, Some(dec!(10)), 8));
+    let account = accounting.get_account(1).unwrap();
+    assert_eq!((account.available, account.held), (dec!(120), dec!(50)));
+    assert_eq!(accounting.tx_state(1), Some(TxState::Resolved));
+    assert_eq!(accounting.tx_state(2), Some(TxState::Disputed));
+
+    // Ensure the automatic action is marked in the audit log;
+    let events: Vec<_> = accounting
+        .audit_log()
+        .iter()
+        .map(|event| (event.event.as_str(), event.tx))
+        .collect();
+    assert_eq!(events, [("dispute_auto_resolved", Some(1))]);
+
+    // Ensure the chargeback action charges back and locks the account;
+    let mut accounting = run(DisputeExpiryAction::Chargeback);
+    assert_eq!(accounting.expire_disputes(12 * SECONDS_PER_DAY), 2);
+    let account = accounting.get_account(1).unwrap();
+    assert_eq!(
+        (account.total, account.held, account.locked),
+        (dec!(0), dec!(0), true)
+    );
+    assert_eq!(accounting.lock_events().len(), 1);
+    assert_eq!(accounting.audit_log()[0].event, "dispute_auto_charged_back");
+    assert_eq!(accounting.tx_state(2), Some(TxState::ChargedBack));
+
+    assert!(AccountingConfig {
+        dispute_expiry: Some(DisputeExpiry::default()),
+        ..Default::default()
+    }
+    .validate()
+    .is_err());
+}
+
+#[test]
+fn test_unknown_tx_types() -> Result<(), Error> {
+    let path = std::env::temp_dir().join("tx_sim_unknown_tx_types.csv");
+    std::fs::write(
+        &path,
+        "type,client,tx,amount\ndeposit,1,1,10.0\ndepositt,1,2,5.0\ndepositt,1,3,5.0\nwithdrawl,1,4,1.0\n",
+    )?;
+
+    // Ensure unknown types are counted and skipped, never aborting or panicking the run;
+    let mut accounting = Accounting::init();
+    accounting.read_transactions_csv_file(path.clone())?;
+    assert_eq!(accounting.get_account(1).unwrap().available, dec!(10));
+    let skipped: Vec<_> = accounting
+        .unknown_tx_types()
+        .iter()
+        .map(|(name, rows)| (name.as_str(), *rows))
+        .collect();
+    assert_eq!(skipped, [("depositt", 2), ("withdrawl", 1)]);
+
+    // Ensure strict mode fails on the first unknown type;
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        strictness: Strictness::Strict,
+        ..Default::default()
+    });
+    assert!(matches!(
+        accounting.read_transactions_csv_file(path),
+        Err(Error::InvalidTxType(name)) if name == "depositt"
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_account_attributes() {
+    let mut accounting = Accounting::init();
+    accounting.set_attribute(1, "vip", "true");
+    accounting.set_attribute(1, "tier", "gold");
+    accounting.set_attribute(2, "vip", "false");
+    assert_eq!(accounting.attribute(1, "vip"), Some("true"));
+    assert_eq!(accounting.attribute(3, "vip"), None);
+    assert_eq!(
+        accounting.account_attributes(1).collect::<Vec<_>>(),
+        [("tier", "gold"), ("vip", "true")]
+    );
+
+    // Ensure custom handlers can base their policy on attributes;
+    let bonus = accounting.register_tx_type("vip_bonus").unwrap();
+    accounting.register_handler(
+        bonus,
+        |accounting: &mut Accounting, account: &mut Account, tx: &Transaction| {
+            if accounting.attribute(account.client, "vip") == Some("true") {
+                let amount = tx.amount.unwrap_or_default();
+                account.available += amount;
+                account.total += amount;
+            }
+        },
+    );
+    for client in [1, 2].iter().copied() {
+        accounting.process_transaction(Transaction {
+            client,
+            tx: client as TxId,
+            r#type: bonus,
+            amount: Some(dec!(5)),
+            timestamp: None,
+            subaccount: None,
+            category: None,
+            source: None,
+        });
+    }
+    assert_eq!(accounting.get_account(1).unwrap().total, dec!(5));
+    assert_eq!(accounting.get_account(2).unwrap().total, dec!(0));
+
+    // Ensure attributes are kept in snapshots;
+    let mut restored = Accounting::init();
+    restored.restore_snapshot(accounting.snapshot());
+    assert_eq!(restored.attributes().count(), 3);
+    assert_eq!(restored.attribute(1, "tier"), Some("gold"));
+
+    assert_eq!(
+        restored.remove_attribute(2, "vip"),
+        Some("false".to_string())
+    );
+    assert_eq!(restored.account_attributes(2).count(), 0);
+}
+
+#[test]
+fn test_tx_receipts() {
+    let tx = |r#type, tx, amount| Transaction {
+        client: 1,
+        tx,
+        r#type,
+        amount,
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    };
+
+    let mut accounting = Accounting::init();
+    let receipt = accounting.apply(tx(TxType::Deposit, 1, Some(dec!(10))));
+    assert!(receipt.is_applied());
+    assert_eq!(
+        (receipt.status, receipt.available, receipt.total),
+        (TxStatus::Applied, dec!(10), dec!(10))
+    );
+
+    // Ensure ignored transactions report the rejection and the unchanged balances;
+    let receipt = accounting.apply(tx(TxType::Withdrawal, 2, Some(dec!(20))));
+    assert!(!receipt.is_applied());
+    assert_eq!(
+        receipt.status,
+        TxStatus::Ignored(TransactionError::InsufficientFunds {
+            available: dec!(10),
+            amount: dec!(20)
+        })
+    );
+    assert_eq!(receipt.available, dec!(10));
+
+    // Ensure the receipt reports the account becoming locked;
+    accounting.apply(tx(TxType::Dispute, 1, None));
+    let receipt = accounting.apply(tx(TxType::Chargeback, 1, None));
+    assert_eq!((receipt.total, receipt.locked), (dec!(0), true));
+    assert!(receipt.became_locked);
+    let receipt = accounting.apply(tx(TxType::Deposit, 3, Some(dec!(1))));
+    assert!(receipt.locked && !receipt.became_locked);
+
+    // Ensure strict validation errors are reported as rejected;
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        strictness: Strictness::Strict,
+        ..Default::default()
+    });
+    let receipt = accounting.apply(tx(TxType::Deposit, 1, None));
+    assert!(matches!(
+        receipt.status,
+        TxStatus::Rejected { code: "E2004", .. }
+    ));
+}
+
+#[test]
+fn test_rejected_transactions() {
+    let tx = |r#type, tx, amount| Transaction {
+        client: 1,
+        tx,
+        r#type,
+        amount,
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    };
+
+    // Ensure ignored transactions are only recorded when tracked;
+    let mut accounting = Accounting::init();
+    accounting.process_transaction(tx(TxType::Withdrawal, 1, Some(dec!(5))));
+    assert!(accounting.rejected_transactions().is_empty());
+
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        track_rejected: true,
+        ..Default::default()
+    });
+    accounting.process_transaction(tx(TxType::Deposit, 1, Some(dec!(10))));
+    accounting.process_transaction(tx(TxType::Withdrawal, 2, Some(dec!(50))));
+    accounting.process_transaction(tx(TxType::Dispute, 9, None));
+
+    let rejected: Vec<_> = accounting
+        .rejected_transactions()
+        .iter()
+        .map(|rejected| (rejected.tx, rejected.reason.as_str()))
+        .collect();
+    assert_eq!(
+        rejected,
+        [
+            (2, "insufficient funds: 10 available to cover 50"),
+            (9, "unknown transaction")
+        ]
+    );
+}
+
+#[test]
+fn test_transaction_sources() -> Result<(), Error> {
+    let tx = |r#type, tx, amount, source: Option<&str>| Transaction {
+        client: 1,
+        tx,
+        r#type,
+        amount,
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: source.map(str::to_string),
+    };
+
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        max_open_disputes: Some(1),
+        ..Default::default()
+    });
+    accounting.process_transaction(tx(TxType::Deposit, 1, Some(dec!(10)), Some("partner-a")));
+    accounting.process_transaction(tx(TxType::Deposit, 2, Some(dec!(10)), Some("partner-b")));
+    accounting.process_transaction(tx(TxType::Withdrawal, 3, Some(dec!(50)), Some("partner-b")));
+    accounting.process_transaction(tx(TxType::Dispute, 1, None, Some("disputes")));
+    accounting.process_transaction(tx(TxType::Dispute, 2, None, Some("disputes")));
+    accounting.process_transaction(tx(TxType::Deposit, 4, Some(dec!(1)), None));
+
+    // Ensure transactions are aggregated per source;
+    let summaries: Vec<_> = accounting
+        .source_summaries()
+        .values()
+        .map(|summary| {
+            (
+                summary.source.as_str(),
+                summary.transactions,
+                summary.applied,
+                summary.ignored,
+            )
+        })
+        .collect();
+    assert_eq!(
+        summaries,
+        [
+            ("disputes", 2, 1, 1),
+            ("partner-a", 1, 1, 0),
+            ("partner-b", 2, 1, 1)
+        ]
+    );
+
+    // Ensure the source is retained in the audit log;
+    assert_eq!(accounting.audit_log()[0].event, "dispute_cap_rejected");
+    assert_eq!(
+        accounting.audit_log()[0].source.as_deref(),
+        Some("disputes")
+    );
+
+    // Ensure the source column is read from csv;
+    let transactions = "type,client,tx,amount,source\ndeposit,1,1,2.5,topic-1\n";
+    let row = csv::Reader::from_reader(transactions.as_bytes())
+        .deserialize::<Transaction>()
+        .next()
+        .unwrap()?;
+    assert_eq!(row.source.as_deref(), Some("topic-1"));
+
+    Ok(())
+}
+
+#[test]
+fn test_file_locking() -> Result<(), Error> {
+    let dir = std::env::temp_dir().join("tx_sim_file_locking");
+    let _ = std::fs::remove_dir_all(&dir);
+
+    // Ensure a second journal in the same directory is refused while the first is open;
+    let journal = Journal::open(dir.clone(), JournalConfig::default(), 0)?;
+    let error = Journal::open(dir.clone(), JournalConfig::default(), 0).unwrap_err();
+    assert!(matches!(&error, Error::AlreadyInUse(path) if path.ends_with(JOURNAL_LOCK_FILE)));
+    assert_eq!(error.code(), "E3007");
+
+    let forced = JournalConfig {
+        force: true,
+        ..Default::default()
+    };
+    drop(Journal::open(dir.clone(), forced, 0)?);
+
+    // Ensure the journal is not compacted while a process has it open, unless forced;
+    let snapshot_path = dir.join("snap.bin");
+    let error =
+        Journal::compact(&dir, snapshot_path.clone(), Default::default(), true, false).unwrap_err();
+    assert!(matches!(&error, Error::AlreadyInUse(path) if path.ends_with(JOURNAL_LOCK_FILE)));
+    Journal::compact(&dir, snapshot_path.clone(), Default::default(), false, true)?;
+
+    drop(journal);
+    drop(Journal::open(dir.clone(), JournalConfig::default(), 0)?);
+
+    // Ensure snapshots are not written while another process holds their lock;
+    let lock = FileLock::acquire(Snapshot::lock_path(&snapshot_path))?;
+    assert!(matches!(
+        Accounting::init().write_snapshot_file(snapshot_path.clone()),
+        Err(Error::AlreadyInUse(_))
+    ));
+    assert!(matches!(
+        Journal::compact(
+            &dir,
+            snapshot_path.clone(),
+            Default::default(),
+            false,
+            false
+        ),
+        Err(Error::AlreadyInUse(_))
+    ));
+
+    drop(lock);
+    Accounting::init().write_snapshot_file(snapshot_path)?;
+
+    Ok(())
+}
+
+#[test]
+fn test_read_transactions_from_reader() -> Result<(), Error> {
+    let data = "type,client,tx,amount\ndeposit,1,1,10.0\nwithdrawal,1,2,4.0\ndeposit,2,3,1.5\n";
+
+    let mut accounting = Accounting::init();
+    accounting.read_transactions_csv(data.as_bytes())?;
+
+    assert_eq!(accounting.get_account(1).unwrap().available, dec!(6.0));
+    assert_eq!(accounting.get_account(2).unwrap().available, dec!(1.5));
+
+    // Ensure the file reader matches the reader over the same content;
+    let file_path = std::env::temp_dir().join("tx_sim_read_transactions_from_reader.csv");
+    std::fs::write(&file_path, data)?;
+    let mut from_file = Accounting::init();
+    from_file.read_transactions_csv_file(file_path)?;
+    assert_eq!(from_file.get_account(1), accounting.get_account(1));
+
+    // Ensure the input limits apply to readers;
+    let mut limited = Accounting::with_config(AccountingConfig {
+        max_rows: Some(2),
+        ..Default::default()
+    });
+    assert!(matches!(
+        limited.read_transactions_csv(data.as_bytes()),
+        Err(Error::InputLimitExceeded { .. })
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_client_id_width() -> Result<(), Error> {
+    let data = "type,client,tx,amount\ndeposit,70000,1,10.0\ndeposit,1,2,1.0\n";
+
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        strictness: Strictness::Lenient,
+        ..Default::default()
+    });
+    accounting.read_transactions_csv(data.as_bytes())?;
+    assert_eq!(accounting.get_account(1).unwrap().available, dec!(1.0));
+
+    // Ensure client ids beyond u16::MAX are only accepted by the wider `ClientId` features;
+    let wide = "70000".parse::<ClientId>().ok();
+    assert_eq!(wide.is_some(), ClientId::BITS > 16);
+    assert_eq!(
+        wide.and_then(|client| accounting.get_account(client))
+            .map(|account| account.available),
+        wide.map(|_| dec!(10.0))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_tx_id_width() -> Result<(), Error> {
+    let data = "type,client,tx,amount\ndeposit,1,1,1.0\ndeposit,1,5000000000,10.0\n";
+
+    let mut accounting = Accounting::init();
+    let result = accounting.read_transactions_csv(data.as_bytes());
+
+    // Ensure tx ids beyond u32::MAX are reported as out of range, unless built with `tx-id-u64`;
+    if TxId::BITS == 32 {
+        let error = result.unwrap_err();
+        assert_eq!(error.code(), "E1008");
+        assert!(matches!(
+            error,
+            Error::IdOutOfRange {
+                line: 3,
+                column: "tx",
+                max: 4294967295,
+                ..
+            }
+        ));
+        assert_eq!(accounting.get_account(1).unwrap().available, dec!(1.0));
+    } else {
+        result?;
+        assert_eq!(accounting.get_account(1).unwrap().available, dec!(11.0));
+    }
+
+    // Ensure client ids are validated against their width as well;
+    let data = "type,client,tx,amount\ndeposit,99999999999999999999:savings,1,1.0\n";
+    assert!(matches!(
+        Accounting::init().read_transactions_csv(data.as_bytes()),
+        Err(Error::IdOutOfRange {
+            column: "client",
+            ..
+        })
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_account_activity_export() -> Result<(), Error> {
+    let mut accounting = Accounting::init();
+    for (tx, client, r#type, amount, timestamp) in [
+        (1, 1, TxType::Deposit, Some(dec!(5.0)), 100),
+        (2, 1, TxType::Withdrawal, Some(dec!(9.0)), 200),
+        (3, 2, TxType::Deposit, Some(dec!(1.0)), 150),
+        (4, 1, TxType::Deposit, Some(dec!(1.0)), 300),
+    ] {
+        accounting.process_transaction(Transaction {
+            r#type,
+            client,
+            tx,
+            amount,
+            timestamp: Some(timestamp),
+            subaccount: None,
+            category: None,
+            source: None,
+        });
+    }
+
+    // Ensure ignored transactions are counted as activity;
+    assert_eq!(accounting.get_account(1).unwrap().stats.transactions, 3);
+    assert_eq!(accounting.last_activity(1), Some(300));
+
+    let mut output = Vec::new();
+    accounting.write_accounts_extended_csv(&mut output)?;
+    let rows: Vec<ExtendedAccount> = csv::Reader::from_reader(output.as_slice())
+        .deserialize()
+        .collect::<Result<_, _>>()?;
+    let activity: std::collections::BTreeMap<ClientId, (Option<Timestamp>, u64)> = rows
+        .iter()
+        .map(|row| (row.client, (row.last_activity, row.transactions)))
+        .collect();
+    assert_eq!(activity[&1], (Some(300), 3));
+    assert_eq!(activity[&2], (Some(150), 1));
+
+    // Ensure the counts are kept in snapshots;
+    let mut restored = Accounting::init();
+    restored.restore_snapshot(accounting.snapshot());
+    assert_eq!(restored.get_account(1).unwrap().stats.transactions, 3);
+
+    Ok(())
+}
+
+#[test]
+fn test_circuit_breaker() -> Result<(), Error> {
+    let tx = |r#type, tx, amount: Amount, timestamp| Transaction {
+        r#type,
+        client: 1,
+        tx,
+        amount: Some(amount),
+        timestamp: Some(timestamp),
+        subaccount: None,
+        category: None,
+        source: None,
+    };
+
+    // Ensure the breaker halts once too many of the recent transactions are ignored;
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        circuit_breaker: Some(CircuitBreaker {
+            max_rejection_rate: Some(0.5),
+            rejection_window: 4,
+            ..Default::default()
+        }),
+        ..Default::default()
+    });
+    accounting.try_process_transaction(tx(TxType::Deposit, 1, dec!(5.0), 0))?;
+    accounting.try_process_transaction(tx(TxType::Withdrawal, 2, dec!(9.0), 1))?;
+    accounting.try_process_transaction(tx(TxType::Withdrawal, 3, dec!(9.0), 2))?;
+    let error = accounting
+        .try_process_transaction(tx(TxType::Withdrawal, 4, dec!(9.0), 3))
+        .unwrap_err();
+    assert_eq!(error.code(), "E2011");
+    assert_eq!(accounting.circuit_breaker_trip().unwrap().tx, 4);
+    assert!(accounting
+        .audit_log()
+        .iter()
+        .any(|event| event.event == "circuit_breaker_tripped"));
+
+    assert!(matches!(
+        accounting.try_process_transaction(tx(TxType::Deposit, 5, dec!(1.0), 4)),
+        Err(Error::CircuitOpen(_))
+    ));
+    assert_eq!(
+        accounting.process_transaction(tx(TxType::Deposit, 6, dec!(1.0), 5)),
+        Err(TransactionError::CircuitOpen)
+    );
+    assert_eq!(accounting.get_account(1).unwrap().available, dec!(5.0));
+
+    accounting.reset_circuit_breaker();
+    accounting.try_process_transaction(tx(TxType::Deposit, 7, dec!(1.0), 6))?;
+    assert_eq!(accounting.get_account(1).unwrap().available, dec!(6.0));
+
+    // Ensure outflows are bounded per window, and later transactions quarantined once exceeded;
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        circuit_breaker: Some(CircuitBreaker {
+            max_outflow: Some(dec!(10.0)),
+            outflow_window: 3600,
+            action: BreakerAction::Quarantine,
+            ..Default::default()
+        }),
+        ..Default::default()
+    });
+    accounting.try_process_transaction(tx(TxType::Deposit, 1, dec!(100.0), 0))?;
+    accounting.try_process_transaction(tx(TxType::Withdrawal, 2, dec!(6.0), 10))?;
+    accounting.try_process_transaction(tx(TxType::Withdrawal, 3, dec!(6.0), 4000))?;
+    assert!(accounting.circuit_breaker_trip().is_none());
+
+    accounting.try_process_transaction(tx(TxType::Withdrawal, 4, dec!(6.0), 4010))?;
+    assert_eq!(accounting.circuit_breaker_trip().unwrap().tx, 4);
+
+    assert_eq!(
+        accounting.try_process(tx(TxType::Withdrawal, 5, dec!(6.0), 4020))?,
+        Err(TransactionError::CircuitOpen)
+    );
+    assert_eq!(accounting.get_account(1).unwrap().available, dec!(82.0));
+    assert_eq!(accounting.quarantined_transactions().len(), 1);
+    assert_eq!(accounting.quarantined_transactions()[0].tx, 5);
+
+    Ok(())
+}
+
+#[test]
+fn test_transaction_lookup() {
+    let mut accounting = Accounting::init();
+    for (r#type, tx, amount) in [
+        (TxType::Deposit, 1, Some(dec!(10.0))),
+        (TxType::Deposit, 2, Some(dec!(4.0))),
+        (TxType::Dispute, 1, None),
+        (TxType::Withdrawal, 3, Some(dec!(100.0))),
+    ] {
+        accounting.process_transaction(Transaction {
+            r#type,
+            client: 1,
+            tx,
+            amount,
+            timestamp: None,
+            subaccount: None,
+            category: None,
+            source: None,
+        });
+    }
+
+    // Ensure the disputed deposit can be looked up, while disputes are not recorded themselves;
+    let deposit = accounting.get_transaction(1).unwrap();
+    assert_eq!(
+        (deposit.r#type, deposit.amount),
+        (TxType::Deposit, Some(dec!(10.0)))
+    );
+    assert_eq!(accounting.tx_state(1), Some(TxState::Disputed));
+    assert_eq!(
+        accounting.get_transaction(3).map(|tx| tx.r#type),
+        Some(TxType::Withdrawal)
+    );
+    assert!(accounting.get_transaction(4).is_none());
+
+    let mut ids: Vec<TxId> = accounting.transactions().map(|tx| tx.tx).collect();
+    ids.sort_unstable();
+    assert_eq!(ids, vec![1, 2, 3]);
+}
+
+#[test]
+fn test_client_transactions() {
+    let mut accounting = Accounting::init();
+    for (client, tx, r#type) in [
+        (1, 1, TxType::Deposit),
+        (2, 2, TxType::Deposit),
+        (1, 3, TxType::Withdrawal),
+        (1, 1, TxType::Dispute),
+        (2, 1, TxType::Deposit),
+        (1, 4, TxType::Deposit),
+    ] {
+        accounting.process_transaction(Transaction {
+            r#type,
+            client,
+            tx,
+            amount: Some(dec!(1.0)).filter(|_| r#type != TxType::Dispute),
+            timestamp: None,
+            subaccount: None,
+            category: None,
+            source: None,
+        });
+    }
+
+    // Ensure each client's history is listed in order, without disputes or duplicate ids;
+    let history = |accounting: &Accounting, client| -> Vec<TxId> {
+        accounting
+            .client_transactions(client)
+            .map(|tx| tx.tx)
+            .collect()
+    };
+    assert_eq!(history(&accounting, 1), vec![1, 3, 4]);
+    assert_eq!(history(&accounting, 2), vec![2]);
+    assert!(history(&accounting, 3).is_empty());
+
+    // Ensure the index is rebuilt from snapshots;
+    let mut restored = Accounting::init();
+    restored.restore_snapshot(accounting.snapshot());
+    let mut ids = history(&restored, 1);
+    ids.sort_unstable();
+    assert_eq!(ids, vec![1, 3, 4]);
+}
+
+#[test]
+fn test_quarantine_flagged_accounts() {
+    let tx = |client, r#type, tx, amount: Amount| Transaction {
+        r#type,
+        client,
+        tx,
+        amount: Some(amount),
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    };
+
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        quarantine_flagged: true,
+        ..Default::default()
+    });
+    accounting.flag_account(2);
+
+    // Ensure the review flag survives a snapshot;
+    let snapshot = accounting.snapshot();
+    accounting.restore_snapshot(snapshot);
+    assert_eq!(accounting.flagged_accounts().collect::<Vec<_>>(), vec![&2]);
+
+    // Ensure transactions of flagged clients are kept in the quarantine instead of applied;
+    accounting
+        .process_transaction(tx(1, TxType::Deposit, 1, dec!(5.0)))
+        .unwrap();
+    assert_eq!(
+        accounting.process_transaction(tx(2, TxType::Deposit, 2, dec!(7.0))),
+        Err(TransactionError::Quarantined)
+    );
+    assert_eq!(
+        accounting.process_transaction(tx(2, TxType::Withdrawal, 3, dec!(2.0))),
+        Err(TransactionError::Quarantined)
+    );
+    assert!(accounting.get_account(2).is_none());
+    assert_eq!(accounting.quarantined_transactions().len(), 2);
+
+    // Ensure a released client's transactions are applied in the order received;
+    let receipts = accounting.release_quarantined(2);
+    assert!(receipts.iter().all(TxReceipt::is_applied));
+    assert_eq!(accounting.get_account(2).unwrap().available, dec!(5.0));
+    assert!(accounting.quarantined_transactions().is_empty());
+    assert!(accounting.flagged_accounts().next().is_none());
+    assert!(accounting
+        .audit_log()
+        .iter()
+        .any(|event| event.event == "quarantine_released"));
+
+    accounting
+        .process_transaction(tx(2, TxType::Deposit, 4, dec!(1.0)))
+        .unwrap();
+    assert_eq!(accounting.get_account(2).unwrap().available, dec!(6.0));
+}
+
+#[test]
+fn test_config_reload() -> Result<(), Error> {
+    let tx = |r#type, tx, amount| Transaction {
+        r#type,
+        client: 1,
+        tx,
+        amount,
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    };
+
+    let mut accounting = Accounting::init();
+    accounting.try_process_transaction(tx(TxType::Deposit, 1, Some(dec!(5.0))))?;
+    accounting.try_process_transaction(tx(TxType::Deposit, 2, Some(dec!(5.0))))?;
+
+    // Ensure an invalid configuration is rejected before the swap;
+    let invalid = AccountingConfig {
+        max_open_disputes: Some(0),
+        ..Default::default()
+    };
+    assert_eq!(
+        accounting.reload_config(invalid).unwrap_err().code(),
+        "E1006"
+    );
+    assert_eq!(accounting.config().max_open_disputes, None);
+
+    // Ensure a reload keeps the state, applies to later transactions and is audited;
+    let changed = accounting.reload_config(AccountingConfig {
+        max_open_disputes: Some(1),
+        ..Default::default()
+    })?;
+    assert_eq!(changed, vec!["max_open_disputes"]);
+    assert_eq!(accounting.get_account(1).unwrap().available, dec!(10.0));
+
+    let event = accounting.audit_log().last().unwrap();
+    assert_eq!(event.event, "config_reloaded");
+    assert_eq!(event.detail, "changed max_open_disputes");
+    assert_eq!(event.client, None);
+
+    accounting.try_process_transaction(tx(TxType::Dispute, 1, None))?;
+    assert_eq!(
+        accounting.process_transaction(tx(TxType::Dispute, 2, None)),
+        Err(TransactionError::TooManyOpenDisputes)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_process_transactions_batch() {
+    let tx = |r#type, tx, amount| Transaction {
+        r#type,
+        client: 1,
+        tx,
+        amount,
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    };
+
+    let mut accounting = Accounting::init();
+    let result = accounting.process_transactions(vec![
+        tx(TxType::Deposit, 1, Some(dec!(5.0))),
+        tx(TxType::Withdrawal, 2, Some(dec!(2.0))),
+        tx(TxType::Withdrawal, 3, Some(dec!(9.0))),
+        tx(TxType::Unknown, 4, Some(dec!(1.0))),
+        tx(TxType::Resolve, 1, None),
+    ]);
+
+    assert_eq!(
+        result,
+        BatchResult {
+            applied: 2,
+            rejected: 2,
+            unknown: 1,
+        }
+    );
+    assert_eq!(result.total(), 5);
+    assert_eq!(accounting.get_account(1).unwrap().available, dec!(3.0));
+}
+
+#[test]
+fn test_dense_tx_ids() -> Result<(), Error> {
+    let mut accounting = Accounting::init();
+    for (tx, amount) in [(900, dec!(1.0)), (17, dec!(2.0)), (40_000, dec!(3.0))] {
+        accounting.try_process_transaction(Transaction {
+            r#type: TxType::Deposit,
+            client: 1,
+            tx,
+            amount: Some(amount),
+            timestamp: None,
+            subaccount: None,
+            category: None,
+            source: None,
+        })?;
+    }
+
+    // Ensure sparse ids are mapped to dense ids in ascending id order;
+    let dense = accounting.dense_tx_ids();
+    assert_eq!(dense.len(), 3);
+    assert_eq!(dense.get(17), Some(1));
+    assert_eq!(dense.get(900), Some(2));
+    assert_eq!(dense.get(40_000), Some(3));
+    assert_eq!(dense.get(5), None);
+
+    let file_path = std::env::temp_dir().join("tx_sim_dense_transactions.csv");
+    let mapping_path = std::env::temp_dir().join("tx_sim_dense_tx_ids.csv");
+    accounting
+        .write_recorded_transactions_csv_file(file_path.clone(), Some(mapping_path.clone()))?;
+
+    assert_eq!(
+        std::fs::read_to_string(&file_path)?,
+        "type,client,tx,amount,timestamp,category,source\n\
+         deposit,1,1,2.0,,,\n\
+         deposit,1,2,1.0,,,\n\
+         deposit,1,3,3.0,,,\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(&mapping_path)?,
+        "tx,dense_tx\n17,1\n900,2\n40000,3\n"
+    );
+
+    // Ensure the original ids are kept without a mapping;
+    accounting.write_recorded_transactions_csv_file(file_path.clone(), None)?;
+    assert!(std::fs::read_to_string(&file_path)?.contains("deposit,1,40000,3.0,,,"));
+
+    Ok(())
+}
+
+#[test]
+fn test_csv_rows() -> Result<(), Error> {
+    let file_path = std::env::temp_dir().join("tx_sim_csv_rows.csv");
+    std::fs::write(
+        &file_path,
+        "type,client,tx,amount\ndeposit,1,1,5.0\ndepositt,1,2,1.0\ndeposit,2,3,2.0\nwithdrawal,1,4,x\n",
+    )?;
+
+    // Ensure rows are parsed without being processed, interleaving a filter before processing;
+    let mut accounting = Accounting::init();
+    let mut errors = Vec::new();
+    for row in accounting.csv_rows(file_path.clone())? {
+        match row {
+            Ok(tx) if tx.client == 1 => {
+                accounting.process_transaction(tx).unwrap();
+            }
+            Ok(_) => {}
+            Err(e) => errors.push((e.line, e.code())),
+        }
+    }
+
+    std::fs::remove_file(&file_path)?;
+
+    assert_eq!(accounting.get_account(1).unwrap().available, dec!(5.0));
+    assert!(accounting.get_account(2).is_none());
+    assert_eq!(errors, vec![(3, "E1003"), (5, "E1001")]);
+
+    Ok(())
+}
+
+#[cfg(feature = "pdf")]
+#[test]
+fn test_account_statement() -> Result<(), Error> {
+    let mut accounting = Accounting::with_config(AccountingConfig {
+        track_rejected: true,
+        ..Default::default()
+    });
+    for (r#type, tx, amount, timestamp) in [
+        (TxType::Deposit, 1, Some(dec!(100.0)), 1_609_459_200),
+        (TxType::Deposit, 2, Some(dec!(50.0)), 1_609_545_600),
+        (TxType::Withdrawal, 3, Some(dec!(500.0)), 1_609_632_000),
+        (TxType::Dispute, 2, None, 1_609_718_400),
+        (TxType::Chargeback, 2, None, 1_609_804_800),
+        (TxType::Withdrawal, 4, Some(dec!(20.0)), 1_612_137_600),
+    ] {
+        accounting.process_transaction(Transaction {
+            r#type,
+            client: 7,
+            tx,
+            amount,
+            timestamp: Some(timestamp),
+            subaccount: None,
+            category: None,
+            source: None,
+        });
+    }
+
+    // Ensure dispute states and ignored transactions are reflected in the lines and totals;
+    let statement = accounting.statement(7, None, None).unwrap();
+    assert_eq!(statement.lines.len(), 3);
+    assert_eq!(statement.lines[1].state, TxState::ChargedBack);
+    assert!(statement.lines[2].ignored.is_some());
+    assert_eq!(statement.deposits, dec!(150.0));
+    assert_eq!(statement.withdrawals, dec!(0.0));
+    assert_eq!(statement.charged_back, dec!(50.0));
+    assert!(statement.to_string().contains("Dispute history (1)"));
+
+    // Ensure ignored transactions are still excluded from the totals after a snapshot;
+    let mut restored = Accounting::init();
+    restored.restore_snapshot(accounting.snapshot());
+    let restored = restored.statement(7, None, None).unwrap();
+    assert_eq!(restored.lines[2].ignored.as_deref(), Some("rejected"));
+    assert_eq!(restored.withdrawals, dec!(0.0));
+
+    // Ensure the period bounds are inclusive and exclusive;
+    let period = accounting
+        .statement(7, Some(1_609_545_600), Some(1_609_632_000))
+        .unwrap();
+    assert_eq!(period.lines.len(), 1);
+    assert_eq!(period.lines[0].transaction.tx, 2);
+    assert!(accounting.statement(8, None, None).is_none());
+
+    let mut pdf = Vec::new();
+    statement.write_pdf(&mut pdf)?;
+    let pdf = String::from_utf8(pdf).unwrap();
+    assert!(pdf.starts_with("%PDF-1.4\n"));
+    assert!(pdf.contains("(Account statement, client 7) Tj"));
+    assert!(pdf.ends_with("%%EOF\n"));
+
+    Ok(())
+}
@@ -0,0 +1,83 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use csv::{Reader, Writer};
+
+use crate::*;
+
+/// Distinguishes fixtures created by the same process;
+static NEXT_FIXTURE: AtomicUsize = AtomicUsize::new(0);
+
+/// Test-support harness: a temporary directory of fixture files, removed when dropped;
+/// Used by the crate's CLI integration tests, and reusable by crates extending the CLI.
+#[derive(Debug)]
+pub struct TestFixture {
+    dir: PathBuf,
+}
+
+impl TestFixture {
+    /// Create an empty fixture directory under the system temporary directory;
+    pub fn new(name: &str) -> Result<Self, Error> {
+        let dir = std::env::temp_dir().join(format!(
+            "tx_sim_{}_{}_{}",
+            name,
+            process::id(),
+            NEXT_FIXTURE.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&dir)?;
+
+        Ok(TestFixture { dir })
+    }
+
+    /// Returns the path of a file in the fixture directory;
+    pub fn path(&self, file_name: &str) -> PathBuf {
+        self.dir.join(file_name)
+    }
+
+    /// Write a fixture file, returning its path;
+    pub fn write(&self, file_name: &str, contents: &str) -> Result<PathBuf, Error> {
+        let path = self.path(file_name);
+        fs::write(&path, contents)?;
+        Ok(path)
+    }
+
+    /// Write transactions as a csv fixture file, returning its path;
+    pub fn write_transactions(
+        &self,
+        file_name: &str,
+        transactions: &[Transaction],
+    ) -> Result<PathBuf, Error> {
+        let path = self.path(file_name);
+        let mut wtr = Writer::from_path(&path)?;
+
+        for tx in transactions {
+            wtr.serialize(tx)?;
+        }
+
+        wtr.flush()?;
+        Ok(path)
+    }
+
+    /// Read a file written to the fixture directory, e.g. a report;
+    pub fn read(&self, file_name: &str) -> Result<String, Error> {
+        Ok(fs::read_to_string(self.path(file_name))?)
+    }
+}
+
+impl Drop for TestFixture {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Parse the accounts csv written by the CLI, ordered by client id;
+pub fn parse_accounts_csv(data: &[u8]) -> Result<Vec<Account>, Error> {
+    let mut accounts = Reader::from_reader(data)
+        .deserialize()
+        .collect::<Result<Vec<Account>, csv::Error>>()?;
+    accounts.sort_by_key(|account| account.client);
+
+    Ok(accounts)
+}
@@ -0,0 +1,98 @@
+use std::collections::HashSet;
+#[cfg(feature = "io")]
+use std::io::Write;
+#[cfg(feature = "io")]
+use std::path::PathBuf;
+
+#[cfg(feature = "io")]
+use csv::Writer;
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+#[derive(Debug, Default, Clone, PartialEq)]
+/// Selects the transactions whose processing decisions are traced, by transaction id or client;
+/// Disputes, resolves and chargebacks match the id of the transaction they reference.
+pub struct TraceFilter {
+    pub txs: HashSet<TxId>,
+    pub clients: HashSet<ClientId>,
+}
+
+impl TraceFilter {
+    /// Returns true if the transaction is selected;
+    pub fn matches(&self, tx: &Transaction) -> bool {
+        self.txs.contains(&tx.tx) || self.clients.contains(&tx.client)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+/// A single decision taken while processing a traced transaction.
+pub struct TraceStep {
+    #[serde(rename = "tx")]
+    pub tx: TxId,
+    #[serde(rename = "client")]
+    pub client: ClientId,
+    #[serde(rename = "type")]
+    pub r#type: TxType,
+    /// Processing stage, e.g. `validate`, `precision`, `dispatch` or `result`
+    #[serde(rename = "stage")]
+    pub stage: String,
+    #[serde(rename = "detail")]
+    pub detail: String,
+}
+
+impl Accounting {
+    /// Record a processing decision if the transaction is traced; The detail is only formatted for
+    /// traced transactions.
+    pub(crate) fn trace<F: FnOnce() -> String>(
+        &mut self,
+        tx: &Transaction,
+        stage: &str,
+        detail: F,
+    ) {
+        let traced = match self.config.trace.as_ref() {
+            Some(filter) => filter.matches(tx),
+            None => false,
+        };
+
+        if traced {
+            self.trace_steps.push(TraceStep {
+                tx: tx.tx,
+                client: tx.client,
+                r#type: tx.r#type,
+                stage: stage.to_string(),
+                detail: detail(),
+            });
+        }
+    }
+
+    /// Returns the recorded decision trace, in processing order; See `AccountingConfig::trace`.
+    pub fn trace_steps(&self) -> &[TraceStep] {
+        &self.trace_steps
+    }
+
+    /// Write the decision trace as csv to any writer, e.g. stderr;
+    #[cfg(feature = "io")]
+    pub fn write_trace_csv<W: Write>(&self, writer: W) -> Result<(), Error> {
+        let mut wtr = Writer::from_writer(writer);
+
+        for step in self.trace_steps.iter() {
+            wtr.serialize(step)?;
+        }
+
+        wtr.flush()?;
+        Ok(())
+    }
+
+    /// Write the decision trace to a csv file;
+    #[cfg(feature = "io")]
+    pub fn write_trace_csv_file(&self, file_path: PathBuf) -> Result<(), Error> {
+        let mut sink = CsvFileSink::create(file_path, self.config.output_durability)?;
+
+        for step in self.trace_steps.iter() {
+            sink.write(step)?;
+        }
+
+        sink.close()
+    }
+}
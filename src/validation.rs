@@ -0,0 +1,184 @@
+use thiserror::Error as ThisError;
+
+use crate::*;
+
+/// Outcome of a transaction applied by `Accounting::process_transaction`;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxOutcome {
+    /// The transaction was applied to the account;
+    Applied,
+    /// The deposit was applied to the held funds until its clearing delay has passed;
+    Pending,
+}
+
+/// Reason a transaction would be ignored or rejected by `Accounting::process_transaction`;
+/// Returned as the error of `process_transaction` and `can_apply`.
+#[derive(ThisError, Debug, Clone, PartialEq)]
+pub enum TransactionError {
+    #[error("account is locked")]
+    AccountLocked,
+    #[error("account is closed")]
+    AccountClosed,
+    #[error("no handler for transaction type '{0}'")]
+    UnknownTxType(TxType),
+    #[error("missing amount")]
+    MissingAmount,
+    #[error("invalid amount {0}")]
+    InvalidAmount(Amount),
+    #[error("amount {0} exceeds {AMOUNT_DECIMAL_PLACES} decimal places")]
+    ExcessPrecision(Amount),
+    #[error("duplicate transaction id")]
+    DuplicateTx,
+    #[error("insufficient funds: {available} available to cover {amount}")]
+    InsufficientFunds { available: Amount, amount: Amount },
+    #[error("insufficient held funds: {held} held to cover {amount}")]
+    InsufficientHeldFunds { held: Amount, amount: Amount },
+    #[error("withdrawal of {amount} would take {available} available below the reserve floor of {floor}")]
+    BelowReserveFloor {
+        available: Amount,
+        floor: Amount,
+        amount: Amount,
+    },
+    #[error("exceeds category budget")]
+    BudgetExceeded,
+    #[error("unknown transaction")]
+    UnknownTx,
+    #[error("transaction belongs to client {0}")]
+    ClientMismatch(ClientId),
+    #[error("too many open disputes")]
+    TooManyOpenDisputes,
+    #[error("transaction is already under dispute")]
+    AlreadyDisputed,
+    #[error("withdrawal disputes are disabled")]
+    WithdrawalDispute,
+    #[error("unexpected amount {0}")]
+    UnexpectedAmount(Amount),
+    #[error("unknown reservation")]
+    UnknownReservation,
+    #[error("duplicate content of an earlier transaction")]
+    DuplicateContent,
+    #[error("cannot apply {r#type} to a transaction that is {state}")]
+    InvalidStateTransition { state: TxState, r#type: TxType },
+    #[error("circuit breaker is open")]
+    CircuitOpen,
+    #[error("client is flagged for review")]
+    Quarantined,
+}
+
+impl Accounting {
+    /// Record why the transaction being processed is ignored, returned by `process_transaction`;
+    pub(crate) fn ignore<F: FnOnce() -> String>(
+        &mut self,
+        tx: &Transaction,
+        rejection: TransactionError,
+        detail: F,
+    ) {
+        self.rejection = Some(rejection);
+        self.trace(tx, "ignored", detail);
+    }
+
+    /// Check whether a transaction would be applied against the current state, without applying it,
+    /// e.g. to pre-validate withdrawal forms against the live engine; The transaction is processed by
+    /// `process_transaction` against a scratch copy of the state it can touch, so every check,
+    /// including quarantine, the circuit breaker and content deduplication, is the one applied when
+    /// it is processed. Enrichers are not evaluated, and custom handlers only see the client's state.
+    pub fn can_apply(&self, tx: &Transaction) -> Result<(), TransactionError> {
+        self.dry_run_scope(tx)
+            .process_transaction(tx.clone())
+            .map(|_| ())
+    }
+
+    /// Returns a scratch processor holding a copy of the state a transaction can read or change: the
+    /// client's account, sub-accounts, disputes, holds, pending deposits, closure, floor, budgets,
+    /// attributes, flag and interest accrual, and the transaction, reservation and dispute it
+    /// references, with the configuration, handlers, clock and circuit breaker state;
+    fn dry_run_scope(&self, tx: &Transaction) -> Accounting {
+        let client = self.client_remapper.map(tx.client);
+        let mut scope = Accounting::with_config(AccountingConfig {
+            trace: None,
+            track_rejected: false,
+            invariant_check_interval: None,
+            slow_transaction_threshold: None,
+            ..self.config.clone()
+        });
+        scope.client_remapper = self.client_remapper.clone();
+        scope.clock = self.clock.clone();
+        scope.handlers = self.handlers.clone();
+        scope.breaker = self.breaker.clone();
+
+        if let Some(account) = self.get_account(client) {
+            scope.accounts.insert(client, account.clone());
+        }
+        if let Some(subaccounts) = self.subaccounts.get(&client) {
+            scope.subaccounts.insert(client, subaccounts.clone());
+        }
+
+        let disputes = self
+            .disputes
+            .values()
+            .filter(|dispute| dispute.client == client || dispute.tx == tx.tx);
+        for dispute in disputes {
+            scope.disputes.insert(dispute.tx, dispute.clone());
+        }
+
+        // The referenced transaction, and those of the client's open disputes;
+        let referenced = scope.disputes.keys().copied().chain(Some(tx.tx));
+        for id in referenced.collect::<Vec<TxId>>() {
+            if let Some(recorded) = self.transactions.get(&id) {
+                scope.transactions.insert(id, recorded.clone());
+            }
+        }
+
+        if let Some(reservation) = self.reservations.get(&tx.tx) {
+            scope.reservations.insert(tx.tx, reservation.clone());
+        }
+        for hold in self.client_holds(client) {
+            scope.holds.insert(hold.id, hold.clone());
+        }
+        for (key, deposit) in self.pending_deposits.iter() {
+            if deposit.client == client {
+                scope.pending_deposits.insert(*key, deposit.clone());
+            }
+        }
+
+        if let Some(closure) = self.closures.get(&client) {
+            scope.closures.insert(client, closure.clone());
+        }
+        if let Some(floor) = self.reserve_floors.get(&client) {
+            scope.reserve_floors.insert(client, *floor);
+        }
+        for (key, budget) in self.budgets.iter() {
+            if key.0 == client {
+                scope.budgets.insert(key.clone(), budget.clone());
+            }
+        }
+        for (key, spending) in self.budget_spending.iter() {
+            if key.0 == client {
+                scope.budget_spending.insert(key.clone(), *spending);
+            }
+        }
+        if let Some(attributes) = self.attributes.get(&client) {
+            scope.attributes.insert(client, attributes.clone());
+        }
+        if self.flagged_accounts.contains(&client) {
+            scope.flagged_accounts.insert(client);
+        }
+        if let Some(at) = self.last_activity.get(&client) {
+            scope.last_activity.insert(client, *at);
+        }
+        if let Some(accrual) = self.interest_accrual.get(&client) {
+            scope.interest_accrual.insert(client, *accrual);
+        }
+
+        // Content is compared after the client id is remapped;
+        let hash = content_hash(&Transaction {
+            client,
+            ..tx.clone()
+        });
+        if let Some(seen) = self.content_hashes.get(&hash) {
+            scope.content_hashes.insert(hash, *seen);
+        }
+
+        scope
+    }
+}
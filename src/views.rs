@@ -0,0 +1,101 @@
+use std::collections::BTreeMap;
+#[cfg(feature = "io")]
+use std::io::Write;
+#[cfg(feature = "io")]
+use std::path::PathBuf;
+use std::sync::Arc;
+
+#[cfg(feature = "io")]
+use csv::Writer;
+
+use crate::*;
+
+#[derive(Debug, Clone)]
+/// Consistent point-in-time view of all accounts, taken with `Accounting::read_snapshot`;
+/// The view is immutable and cheap to clone, so it can be exported from another thread while the
+/// processor continues with new transactions.
+pub struct ReadSnapshot {
+    /// Number of transactions processed when the view was taken
+    pub processed: u64,
+    /// Clock time at which the view was taken
+    pub taken_at: Timestamp,
+    accounts: Arc<BTreeMap<ClientId, Account>>,
+}
+
+impl ReadSnapshot {
+    /// Returns the account of a client as of the view, if any;
+    pub fn get_account(&self, client: ClientId) -> Option<&Account> {
+        self.accounts.get(&client)
+    }
+
+    /// Iterate over the accounts as of the view, ordered by client id;
+    pub fn accounts(&self) -> impl Iterator<Item = &Account> {
+        self.accounts.values()
+    }
+
+    /// Returns the number of accounts in the view;
+    pub fn len(&self) -> usize {
+        self.accounts.len()
+    }
+
+    /// Returns true if the view holds no accounts;
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+
+    /// Write the accounts as csv to any writer, with rounded balances;
+    #[cfg(feature = "io")]
+    pub fn write_accounts_csv<W: Write>(&self, writer: W) -> Result<(), Error> {
+        let mut wtr = Writer::from_writer(writer);
+
+        for account in self.accounts() {
+            let mut account = account.clone();
+            account.round_balances();
+            wtr.serialize(account)?;
+        }
+
+        wtr.flush()?;
+        Ok(())
+    }
+
+    /// Write the accounts to a csv file, with rounded balances;
+    #[cfg(feature = "io")]
+    pub fn write_accounts_csv_file(
+        &self,
+        file_path: PathBuf,
+        durability: Durability,
+    ) -> Result<(), Error> {
+        let mut sink = CsvFileSink::create(file_path, durability)?;
+
+        for account in self.accounts() {
+            let mut account = account.clone();
+            account.round_balances();
+            sink.write(account)?;
+        }
+
+        sink.close()
+    }
+}
+
+impl Accounting {
+    /// Take a consistent point-in-time view of all accounts;
+    /// Only the in-memory accounts are copied, so intake is paused for the copy rather than for
+    /// writing the accounts out. Later transactions are not visible through the view.
+    pub fn read_snapshot(&self) -> ReadSnapshot {
+        ReadSnapshot {
+            processed: self.processed,
+            taken_at: self.now(),
+            accounts: Arc::new(
+                self.accounts
+                    .iter()
+                    .map(|(client, account)| (*client, account.clone()))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Returns the number of transactions passed to `process_transaction`;
+    pub fn processed(&self) -> u64 {
+        self.processed
+    }
+}
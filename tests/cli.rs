@@ -0,0 +1,482 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use rust_decimal_macros::dec;
+use std::path::PathBuf;
+use tx_sim::*;
+
+/// Returns the path of a file in `tests/fixtures`;
+fn fixture(file_name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(file_name)
+}
+
+fn tx_sim() -> Command {
+    Command::cargo_bin("tx-sim").unwrap()
+}
+
+#[test]
+fn test_cli_writes_accounts() -> Result<(), Error> {
+    let output = tx_sim().arg(fixture("chargeback.csv")).output()?;
+    assert!(output.status.success());
+
+    let accounts = parse_accounts_csv(&output.stdout)?;
+    assert_eq!(accounts.len(), 2);
+    assert_eq!(
+        (accounts[0].available, accounts[0].total, accounts[0].locked),
+        (dec!(7.5), dec!(7.5), false)
+    );
+
+    // Ensure the deposit after the chargeback is ignored on the locked account;
+    assert_eq!(
+        (accounts[1].available, accounts[1].total, accounts[1].locked),
+        (dec!(0.0), dec!(0.0), true)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_reports_errors() {
+    tx_sim()
+        .arg("missing.csv")
+        .assert()
+        .code(1)
+        .stdout("")
+        .stderr(predicate::str::starts_with("error[E3001]"));
+
+    tx_sim()
+        .args(["--epsilon", "tiny"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "error[E1004]: Invalid argument: invalid --epsilon",
+        ));
+
+    // Ensure typo'd options and conflicting settings are reported instead of silently accepted;
+    tx_sim()
+        .args(["--stirct", "transactions.csv"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("unknown option '--stirct'"));
+
+    // Ensure every subcommand rejects unknown options rather than taking them as paths;
+    for args in [
+        vec!["merge", "--outptu", "accounts.csv"],
+        vec!["anonymize", "--sed", "in.csv", "out.csv"],
+        vec!["diagnose", "--sampel", "transactions.csv"],
+        vec!["account", "1", "--lats", "transactions.csv"],
+        vec!["compact", "--snapshto", "snap.bin"],
+        vec!["scenario", "run", "--verbose"],
+    ] {
+        tx_sim()
+            .args(&args)
+            .assert()
+            .code(1)
+            .stderr(predicate::str::contains(format!(
+                "error[E1004]: Invalid argument: unknown option '{}'",
+                args[1..].iter().find(|arg| arg.starts_with("--")).unwrap()
+            )));
+    }
+
+    tx_sim()
+        .args(["--dispute-cap-policy", "flag", "transactions.csv"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::starts_with(
+            "error[E1006]: Invalid config `dispute_cap_policy`",
+        ));
+}
+
+#[test]
+fn test_cli_writes_reports() -> Result<(), Error> {
+    let fixture_dir = TestFixture::new("cli_reports")?;
+    let lock_events = fixture_dir.path("locks.csv");
+
+    tx_sim()
+        .arg("-v")
+        .arg("--lock-events")
+        .arg(&lock_events)
+        .arg(fixture("chargeback.csv"))
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "[WARN] Ignored Deposit tx 4 for client 2: account is locked",
+        ));
+
+    // Ensure warnings are left out by default, and `-vv` adds per-row decisions;
+    tx_sim()
+        .arg(fixture("chargeback.csv"))
+        .assert()
+        .success()
+        .stderr("");
+
+    tx_sim()
+        .arg("-vv")
+        .arg(fixture("chargeback.csv"))
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("[DEBUG] Processed"));
+
+    // Rows without a timestamp are stamped with the processing time;
+    let lock_events = fixture_dir.read("locks.csv")?;
+    let lines: Vec<&str> = lock_events.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0], "at,client,locked,reason,tx,detail");
+    assert!(lines[1].ends_with(",2,true,chargeback,2,chargeback tx 2"));
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_writes_rejected() -> Result<(), Error> {
+    let fixture_dir = TestFixture::new("cli_rejected")?;
+    let rejected = fixture_dir.path("rejected.csv");
+
+    tx_sim()
+        .arg("--rejected")
+        .arg(&rejected)
+        .arg(fixture("chargeback.csv"))
+        .assert()
+        .success();
+
+    assert_eq!(
+        fixture_dir.read("rejected.csv")?,
+        "type,client,tx,amount,reason,source\ndeposit,2,4,1.0,account is locked,\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_trace_to_stderr() -> Result<(), Error> {
+    let fixture_dir = TestFixture::new("cli_trace")?;
+    let transactions = fixture_dir.write_transactions(
+        "transactions.csv",
+        &[Transaction {
+            r#type: TxType::Withdrawal,
+            client: 3,
+            tx: 9,
+            amount: Some(dec!(1.0)),
+            timestamp: None,
+            subaccount: None,
+            category: None,
+            source: None,
+        }],
+    )?;
+
+    tx_sim()
+        .args(["--trace-tx", "9"])
+        .arg(transactions)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("3,0.0,0.0,0.0,false"))
+        .stderr(predicate::str::contains(
+            "9,3,withdrawal,ignored,insufficient funds",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_runs_scenario_scripts() {
+    tx_sim()
+        .args(["scenario", "run"])
+        .arg(fixture("scenario.csv"))
+        .assert()
+        .success()
+        .stdout(predicate::str::ends_with(
+            "scenario.csv: 12 steps, 5 expectations, 0 failed\n",
+        ));
+}
+
+#[test]
+fn test_cli_input_limits() {
+    tx_sim()
+        .args(["--max-rows", "2"])
+        .arg(fixture("chargeback.csv"))
+        .assert()
+        .code(1)
+        .stderr(predicate::str::starts_with(
+            "error[E1007]: Input exceeds the row count limit of 2",
+        ));
+
+    tx_sim()
+        .args(["--max-file-size", "1K"])
+        .arg(fixture("chargeback.csv"))
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_cli_skips_unknown_types() -> Result<(), Error> {
+    // Ensure rows of unknown transaction types are counted and skipped instead of aborting the run;
+    let output = tx_sim().arg(fixture("unknown_types.csv")).output()?;
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "warning: skipped rows of unknown transaction types: 'depositt' (1 rows), 'unknown' (1 rows)\n"
+    );
+
+    let accounts = parse_accounts_csv(&output.stdout)?;
+    assert_eq!(accounts[0].available, dec!(7.5));
+
+    tx_sim()
+        .arg("--strict")
+        .arg(fixture("unknown_types.csv"))
+        .assert()
+        .code(1)
+        .stderr(predicate::str::starts_with("error[E1003]"));
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_writes_source_summary() -> Result<(), Error> {
+    let fixture_dir = TestFixture::new("cli_sources")?;
+    let summary = fixture_dir.path("sources.csv");
+    let rejected = fixture_dir.path("rejected.csv");
+
+    tx_sim()
+        .arg("--source-summary")
+        .arg(&summary)
+        .arg("--rejected")
+        .arg(&rejected)
+        .arg(fixture("chargeback.csv"))
+        .assert()
+        .success();
+
+    // Ensure rows are tagged with the file name they were read from;
+    assert_eq!(
+        fixture_dir.read("sources.csv")?,
+        "source,transactions,applied,ignored\nchargeback.csv,6,5,1\n"
+    );
+    assert!(fixture_dir
+        .read("rejected.csv")?
+        .ends_with(",account is locked,chargeback.csv\n"));
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_circuit_breaker() -> Result<(), Error> {
+    let fixture_dir = TestFixture::new("cli_circuit_breaker")?;
+    let transactions = fixture_dir.write(
+        "transactions.csv",
+        "type,client,tx,amount\ndeposit,1,1,5.0\nwithdrawal,1,2,9.0\nwithdrawal,1,3,9.0\ndeposit,1,4,1.0\n",
+    )?;
+
+    // Ensure a tripped breaker halts the run without writing the accounts;
+    tx_sim()
+        .args(["--breaker-rejection-rate", "0.5", "--breaker-window", "2"])
+        .arg(&transactions)
+        .assert()
+        .code(1)
+        .stdout("")
+        .stderr(predicate::str::contains("error[E2011]"));
+
+    let quarantine = fixture_dir.path("quarantine.csv");
+    tx_sim()
+        .args(["--breaker-rejection-rate", "0.5", "--breaker-window", "2"])
+        .args(["--breaker-action", "quarantine", "--quarantine"])
+        .arg(&quarantine)
+        .arg(&transactions)
+        .assert()
+        .success()
+        .stdout("client,available,held,total,locked\n1,5.0,0.0,5.0,false\n")
+        .stderr(predicate::str::contains("1 transactions quarantined"));
+
+    assert_eq!(
+        fixture_dir.read("quarantine.csv")?,
+        "type,client,tx,amount,timestamp,category,source\ndeposit,1,4,1.0,,,\n"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_release_quarantined() -> Result<(), Error> {
+    let fixture_dir = TestFixture::new("cli_release_quarantined")?;
+    let transactions = fixture_dir.write(
+        "transactions.csv",
+        "type,client,tx,amount\ndeposit,1,1,5.0\ndeposit,2,2,3.0\ndeposit,3,3,4.0\n",
+    )?;
+    let snapshot = fixture_dir.path("snap.bin");
+    let quarantine = fixture_dir.path("quarantine.csv");
+    let journal = fixture_dir.path("journal");
+
+    // Ensure transactions of flagged clients are quarantined instead of applied;
+    tx_sim()
+        .args(["--quarantine-flagged", "--flag", "2", "--flag", "3"])
+        .arg("--quarantine")
+        .arg(&quarantine)
+        .arg("--journal")
+        .arg(&journal)
+        .arg(&transactions)
+        .assert()
+        .success()
+        .stdout("client,available,held,total,locked\n1,5.0,0.0,5.0,false\n");
+
+    tx_sim()
+        .args(["compact", "--all", "--snapshot"])
+        .arg(&snapshot)
+        .arg("--journal")
+        .arg(&journal)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("(1 transactions)"));
+
+    // Ensure released transactions are applied onto the snapshot and the rest kept;
+    tx_sim()
+        .args(["release", "--client", "2", "--snapshot"])
+        .arg(&snapshot)
+        .arg("--quarantine")
+        .arg(&quarantine)
+        .assert()
+        .success()
+        .stdout("Released 1 transactions of client 2 (1 applied)\n");
+
+    assert_eq!(
+        fixture_dir.read("quarantine.csv")?,
+        "type,client,tx,amount,timestamp,category,source\ndeposit,3,3,4.0,,,\n"
+    );
+
+    tx_sim()
+        .args(["inspect", "--client", "2", "--snapshot"])
+        .arg(&snapshot)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2,3.0,0.0,3.0,false"));
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_standby_reloads_after_promotion() -> Result<(), Error> {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::process::Stdio;
+    use std::thread;
+    use std::time::Duration;
+
+    let free_addr = || -> Result<String, Error> {
+        Ok(TcpListener::bind("127.0.0.1:0")?.local_addr()?.to_string())
+    };
+    let (listen, admin) = (free_addr()?, free_addr()?);
+
+    let mut standby = std::process::Command::new(assert_cmd::cargo::cargo_bin("tx-sim"))
+        .args(["standby", "--listen", &listen, "--admin", &admin])
+        .arg(fixture("chargeback.csv"))
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    // Send one admin command per connection, waiting for the endpoint to come up;
+    let command = |line: &str| -> Result<String, Error> {
+        let mut conn = (0..50)
+            .find_map(|_| {
+                TcpStream::connect(&admin)
+                    .map_err(|_| thread::sleep(Duration::from_millis(100)))
+                    .ok()
+            })
+            .expect("admin endpoint");
+        writeln!(conn, "{}", line)?;
+        let mut reply = String::new();
+        BufReader::new(conn).read_line(&mut reply)?;
+        Ok(reply.trim().to_string())
+    };
+
+    assert_eq!(command("promote")?, "promoted");
+    assert_eq!(command("promote")?, "already promoted");
+    assert_eq!(
+        command("reload --max-open-disputes 3")?,
+        "reloaded max_open_disputes"
+    );
+    assert_eq!(command("status")?, "promoted accounts=2");
+
+    // Ensure the leader processes transactions sent to the listen address;
+    let mut producer = TcpStream::connect(&listen)?;
+    write!(producer, "type,client,tx,amount\ndeposit,9,100,5.0\n")?;
+    producer.shutdown(std::net::Shutdown::Write)?;
+    let mut reply = String::new();
+    BufReader::new(producer).read_line(&mut reply)?;
+    assert_eq!(reply.trim(), "processed");
+    assert_eq!(command("status")?, "promoted accounts=3");
+
+    assert_eq!(command("shutdown")?, "shutdown");
+
+    let mut stdout = Vec::new();
+    standby.stdout.take().unwrap().read_to_end(&mut stdout)?;
+    assert!(standby.wait()?.success());
+    assert_eq!(parse_accounts_csv(&stdout)?.len(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn test_cli_standby_keeps_serving_after_failed_commands() -> Result<(), Error> {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::process::Stdio;
+    use std::thread;
+    use std::time::Duration;
+
+    let free_addr = || -> Result<String, Error> {
+        Ok(TcpListener::bind("127.0.0.1:0")?.local_addr()?.to_string())
+    };
+    let (listen, admin) = (free_addr()?, free_addr()?);
+
+    let mut standby = std::process::Command::new(assert_cmd::cargo::cargo_bin("tx-sim"))
+        .args(["standby", "--listen", &listen, "--admin", &admin])
+        .arg(fixture("missing.csv"))
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let connect = || {
+        (0..50)
+            .find_map(|_| {
+                TcpStream::connect(&admin)
+                    .map_err(|_| thread::sleep(Duration::from_millis(100)))
+                    .ok()
+            })
+            .expect("admin endpoint")
+    };
+    let command = |line: &str| -> Result<String, Error> {
+        let mut conn = connect();
+        writeln!(conn, "{}", line)?;
+        let mut reply = String::new();
+        BufReader::new(conn).read_line(&mut reply)?;
+        Ok(reply.trim().to_string())
+    };
+
+    // Ensure connections dropped before sending a command do not stop the endpoint;
+    drop(connect());
+    drop(connect());
+
+    // Ensure a broken change stream is dropped, and logged by the subcommand;
+    let mut leader = TcpStream::connect(&listen)?;
+    write!(
+        leader,
+        "seq,tx,client,available,held,total,locked\n5,,1,1.0,0.0,1.0,false\n"
+    )?;
+    leader.shutdown(std::net::Shutdown::Write)?;
+    leader.read_to_end(&mut Vec::new())?;
+
+    // Ensure a failed promotion keeps the node replicating;
+    assert!(command("promote")?.starts_with("error[E3001]"));
+    assert_eq!(
+        command("status")?,
+        "stream=- last_seq=0 lag=- idle=- accounts=0"
+    );
+    assert_eq!(command("shutdown")?, "shutdown");
+
+    let mut stderr = String::new();
+    standby.stderr.take().unwrap().read_to_string(&mut stderr)?;
+    assert!(standby.wait()?.success());
+    assert!(stderr.contains(
+        "[ERROR] Stopped replicating change stream: Replication gap: expected event 1, received 5"
+    ));
+
+    Ok(())
+}
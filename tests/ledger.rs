@@ -0,0 +1,100 @@
+//! Tests of the `Ledger` façade that only use the core, so they also run without default
+//! features, i.e. without the `io` feature: `cargo test --no-default-features`.
+use rust_decimal_macros::dec;
+use tx_sim::prelude::*;
+use tx_sim::{AccountView, DisputeCapPolicy, Ledger};
+
+fn tx(r#type: TxType, client: ClientId, tx: TxId, amount: Option<Amount>) -> Transaction {
+    Transaction {
+        client,
+        tx,
+        r#type,
+        amount,
+        timestamp: None,
+        subaccount: None,
+        category: None,
+        source: None,
+    }
+}
+
+#[test]
+fn test_ledger() {
+    let transactions = [
+        tx(TxType::Deposit, 1, 1, Some(dec!(10))),
+        tx(TxType::Deposit, 2, 2, Some(dec!(5.5))),
+        tx(TxType::Withdrawal, 1, 3, Some(dec!(2.5))),
+        tx(TxType::Dispute, 2, 2, None),
+    ];
+
+    let mut ledger = Ledger::default();
+    let mut replica = Ledger::default();
+    let empty = ledger.state_hash();
+    for transaction in transactions.iter() {
+        assert_eq!(ledger.apply(transaction.clone()), Ok(TxOutcome::Applied));
+    }
+
+    // Ensure replicas applying the same transactions agree, and diverge on any other transaction;
+    for transaction in transactions.iter() {
+        assert!(replica.apply(transaction.clone()).is_ok());
+    }
+    assert_ne!(ledger.state_hash(), empty);
+    assert_eq!(ledger.state_hash(), replica.state_hash());
+    assert!(replica.apply(tx(TxType::Resolve, 2, 2, None)).is_ok());
+    assert_ne!(ledger.state_hash(), replica.state_hash());
+
+    assert_eq!(
+        ledger.account(2),
+        Some(AccountView {
+            client: 2,
+            available: dec!(0),
+            held: dec!(5.5),
+            total: dec!(5.5),
+            locked: false
+        })
+    );
+    assert_eq!(ledger.account(3), None);
+    assert_eq!(
+        ledger.apply(tx(TxType::Withdrawal, 1, 5, Some(dec!(100)))),
+        Err(TransactionError::InsufficientFunds {
+            available: dec!(7.5),
+            amount: dec!(100)
+        })
+    );
+}
+
+#[test]
+fn test_ledger_time_and_config() {
+    let mut ledger = Ledger::new(AccountingConfig {
+        max_open_disputes: Some(1),
+        dispute_cap_policy: DisputeCapPolicy::Reject,
+        ..Default::default()
+    });
+    for id in [1, 2] {
+        assert!(ledger
+            .apply(tx(TxType::Deposit, 1, id, Some(dec!(10))))
+            .is_ok());
+    }
+
+    // Ensure time only moves with `set_time`, stamping disputes opened without a timestamp;
+    ledger.set_time(1_618_912_800);
+    assert_eq!(
+        ledger.apply(tx(TxType::Dispute, 1, 1, None)),
+        Ok(TxOutcome::Applied)
+    );
+    assert_eq!(
+        ledger
+            .accounting()
+            .get_dispute(1)
+            .map(|dispute| dispute.opened_at),
+        Some(1_618_912_800)
+    );
+
+    // Ensure the configured policies apply;
+    assert!(ledger.apply(tx(TxType::Dispute, 1, 2, None)).is_err());
+    assert_eq!(
+        ledger
+            .account(1)
+            .map(|account| (account.held, account.total)),
+        Some((dec!(10), dec!(20)))
+    );
+}